@@ -0,0 +1,86 @@
+//! Tempo de cálculo de expressões já lidas, que é o que se repete em tabelas, gráficos e no
+//! `csv`: uma expressão com variável, chamadas de função do usuário, as expressões longas de
+//! `parser.rs` e `Expression::evaluate_range` contra um cálculo por vez.
+//!
+//! ```sh
+//! cd benches && cargo bench --bench evaluator
+//! ```
+
+use std::hint::black_box;
+
+use asdf_calc::{EvalContext, Expression, Function};
+use asdf_calc_benches::{nested_parentheses, operator_chain, SHORT, SIZES};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const INPUTS: usize = 1000;
+
+fn compiled(c: &mut Criterion) {
+    let expression = Expression::new(SHORT).unwrap();
+    c.bench_function("avaliacao/curta", |b| {
+        b.iter(|| black_box(&expression).evaluate())
+    });
+
+    let mut context = EvalContext::new();
+    context.set_variable("x", 0.5);
+    let expression = Expression::with_context("x^2 + 3*x - sin(x) / 2", &context).unwrap();
+    c.bench_function("avaliacao/variavel", |b| {
+        b.iter(|| black_box(&expression).evaluate_with(&context))
+    });
+
+    let hypot = Function::new(vec!["a".into(), "b".into()], "(a^2 + b^2)^0.5", &context).unwrap();
+    context.define_function("hypot", hypot);
+    let expression = Expression::with_context("hypot(x, 2) + hypot(3, x)", &context).unwrap();
+    c.bench_function("avaliacao/funcao", |b| {
+        b.iter(|| black_box(&expression).evaluate_with(&context))
+    });
+}
+
+fn long(c: &mut Criterion) {
+    let mut group = c.benchmark_group("avaliacao/parenteses");
+    for depth in SIZES {
+        let expression = Expression::new(&nested_parentheses(depth)).unwrap();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(depth),
+            &expression,
+            |b, expression| b.iter(|| black_box(expression).evaluate()),
+        );
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("avaliacao/operadores");
+    for operators in SIZES {
+        let expression = Expression::new(&operator_chain(operators)).unwrap();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(operators),
+            &expression,
+            |b, expression| b.iter(|| black_box(expression).evaluate()),
+        );
+    }
+    group.finish();
+}
+
+fn range(c: &mut Criterion) {
+    let mut context = EvalContext::new();
+    context.set_variable("x", 0.0);
+    let expression = Expression::with_context("x^2 + 3*x - sin(x) / 2", &context).unwrap();
+    let inputs: Vec<f64> = (0..INPUTS).map(|i| i as f64 / 10.0).collect();
+
+    let mut group = c.benchmark_group("avaliacao/intervalo");
+    group.throughput(Throughput::Elements(INPUTS as u64));
+    group.bench_function("evaluate_range", |b| {
+        b.iter(|| expression.evaluate_range(&context, "x", black_box(&inputs)))
+    });
+    group.bench_function("um_por_vez", |b| {
+        let mut context = context.clone();
+        b.iter(|| {
+            for input in black_box(&inputs) {
+                context.set_variable("x", *input);
+                black_box(expression.evaluate_with(&context)).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, compiled, long, range);
+criterion_main!(benches);
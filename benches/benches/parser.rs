@@ -0,0 +1,43 @@
+//! Tempo de leitura (`Expression::new`) de uma expressão curta, de parênteses aninhados e de
+//! longas sequências de operadores.
+//!
+//! ```sh
+//! cd benches && cargo bench --bench parser
+//! ```
+
+use std::hint::black_box;
+
+use asdf_calc::Expression;
+use asdf_calc_benches::{nested_parentheses, operator_chain, SHORT, SIZES};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+fn short(c: &mut Criterion) {
+    c.bench_function("parse/curta", |b| {
+        b.iter(|| Expression::new(black_box(SHORT)).unwrap())
+    });
+}
+
+fn nested(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse/parenteses");
+    for depth in SIZES {
+        let text = nested_parentheses(depth);
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &text, |b, text| {
+            b.iter(|| Expression::new(black_box(text)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn chain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse/operadores");
+    for operators in SIZES {
+        let text = operator_chain(operators);
+        group.bench_with_input(BenchmarkId::from_parameter(operators), &text, |b, text| {
+            b.iter(|| Expression::new(black_box(text)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, short, nested, chain);
+criterion_main!(benches);
@@ -0,0 +1,26 @@
+//! Expressões usadas pelos benchmarks em `benches/`, geradas com o tamanho pedido para que o
+//! tempo possa ser comparado entre tamanhos diferentes.
+
+/// Tamanhos das expressões longas, até perto do limite padrão de aninhamento (256).
+pub const SIZES: [usize; 3] = [10, 100, 250];
+
+pub const SHORT: &str = "2 * (3 + 4) / 5 - 1";
+
+/// `((...(1)...))` com `depth` pares de parênteses.
+pub fn nested_parentheses(depth: usize) -> String {
+    format!("{}1{}", "(".repeat(depth), ")".repeat(depth))
+}
+
+/// `1 + 2 * 3 - 4 / 5 + ...` com `operators` operadores, alternando as precedências.
+pub fn operator_chain(operators: usize) -> String {
+    let mut text = String::from("1");
+    for (index, operator) in ["+", "*", "-", "/"]
+        .iter()
+        .cycle()
+        .take(operators)
+        .enumerate()
+    {
+        text.push_str(&format!(" {} {}", operator, index + 2));
+    }
+    text
+}
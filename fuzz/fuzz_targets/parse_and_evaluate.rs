@@ -0,0 +1,19 @@
+//! Alimenta `Expression::new` e `Expression::evaluate` com bytes arbitrários. Qualquer panic é
+//! tratado pelo libFuzzer como uma falha, então o alvo garante que a biblioteca nunca entra em
+//! panic, independente do input.
+//!
+//! ```sh
+//! cargo +nightly fuzz run parse_and_evaluate
+//! ```
+#![no_main]
+
+use asdf_calc::Expression;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // bytes que não são UTF-8 válido viram `U+FFFD`, que também precisa ser tratado pelo lexer
+    let text = String::from_utf8_lossy(data);
+    if let Ok(expression) = Expression::new(&text) {
+        let _ = expression.evaluate();
+    }
+});
@@ -0,0 +1,42 @@
+#ifndef ASDF_CALC_H
+#define ASDF_CALC_H
+
+/*
+ * Interface em C do asdf-calc, disponível ao compilar o crate com
+ *
+ *     cargo rustc --lib --release --features ffi --crate-type cdylib
+ *
+ * Exemplo de uso:
+ *
+ *     double result;
+ *     char *err;
+ *     if (asdf_calc_eval("3 + (3 + 5) * 6", &result, &err) != ASDF_CALC_OK) {
+ *         fprintf(stderr, "%s\n", err);
+ *         asdf_calc_free_string(err);
+ *     }
+ */
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define ASDF_CALC_OK 0
+#define ASDF_CALC_ERROR 1
+
+/*
+ * Calcula `expression` (UTF-8, terminada em '\0') e escreve o resultado em `out`.
+ *
+ * Em caso de erro retorna ASDF_CALC_ERROR e, se `err` não for nulo, escreve nele uma mensagem
+ * (um erro por linha) que deve ser liberada com `asdf_calc_free_string`. Em caso de sucesso
+ * `*err` recebe NULL.
+ */
+int asdf_calc_eval(const char *expression, double *out, char **err);
+
+/* Libera uma string alocada pela biblioteca. Aceita NULL. */
+void asdf_calc_free_string(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ASDF_CALC_H */
@@ -0,0 +1,91 @@
+//! Módulo Python com a mesma síntaxe e as mesmas regras de cálculo da calculadora.
+//!
+//! ```python
+//! import asdf_calc
+//!
+//! asdf_calc.evaluate("2+2")  # 4.0
+//!
+//! expression = asdf_calc.Expression("price * (1 + tax)", {"price": 10, "tax": 0.2})
+//! expression.evaluate()  # 12.0
+//! expression.variables = {"price": 20, "tax": 0.2}
+//! expression.evaluate()  # 24.0
+//! ```
+//!
+//! Compilado com `maturin develop` (ou `maturin build --release`) dentro desta pasta.
+
+use std::collections::HashMap;
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+fn context_from(variables: &HashMap<String, f64>) -> calc::EvalContext {
+    let mut context = calc::EvalContext::new();
+    for (name, value) in variables {
+        context.set_variable(name.as_str(), *value);
+    }
+    context
+}
+
+/// Os erros viram um único `ValueError`, com um erro por linha.
+fn to_py_err(errors: Vec<calc::Error>) -> PyErr {
+    PyValueError::new_err(
+        errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+/// Calcula a expressão de uma vez, com as variáveis opcionais de `variables`.
+#[pyfunction]
+#[pyo3(signature = (text, variables = None))]
+fn evaluate(text: &str, variables: Option<HashMap<String, f64>>) -> PyResult<f64> {
+    let context = context_from(&variables.unwrap_or_default());
+    let expression = calc::Expression::with_context(text, &context).map_err(to_py_err)?;
+    expression
+        .evaluate_with(&context)
+        .map_err(|error| to_py_err(vec![error]))
+}
+
+/// Expressão já compilada, que pode ser calculada várias vezes trocando o valor de `variables`.
+#[pyclass(name = "Expression")]
+struct PyExpression {
+    text: String,
+    expression: calc::Expression,
+    #[pyo3(get, set)]
+    variables: HashMap<String, f64>,
+}
+
+#[pymethods]
+impl PyExpression {
+    /// Todos os nomes usados em `text` precisam existir em `variables` (ou serem constantes).
+    #[new]
+    #[pyo3(signature = (text, variables = None))]
+    fn new(text: String, variables: Option<HashMap<String, f64>>) -> PyResult<Self> {
+        let variables = variables.unwrap_or_default();
+        let expression =
+            calc::Expression::with_context(&text, &context_from(&variables)).map_err(to_py_err)?;
+        Ok(PyExpression {
+            text,
+            expression,
+            variables,
+        })
+    }
+
+    fn evaluate(&self) -> PyResult<f64> {
+        self.expression
+            .evaluate_with(&context_from(&self.variables))
+            .map_err(|error| to_py_err(vec![error]))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Expression({:?})", self.text)
+    }
+}
+
+#[pymodule]
+fn asdf_calc(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(evaluate, module)?)?;
+    module.add_class::<PyExpression>()?;
+    Ok(())
+}
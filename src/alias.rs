@@ -0,0 +1,64 @@
+//! Apelidos do usuário (`alias q = exit`, `alias k = * 1000`), trocados pelo texto deles em cada
+//! linha antes de ela ser lida, como comando ou como expressão. Pensados para o `init.calc`,
+//! para que as manias de cada um fiquem a uma tecla de distância.
+
+use std::collections::BTreeMap;
+
+/// Troca cada palavra de `line` que é o nome de um apelido pelo texto dele, sem olhar de novo o
+/// texto colocado, para que um apelido que cita outro (ou a si mesmo) não entre em loop. Os
+/// trechos entre aspas (`prompt "..."`) e as linhas que definem ou removem apelidos ficam como
+/// estão. Os nomes não diferenciam maiúsculas, como os das variáveis.
+pub fn expand(line: &str, aliases: &BTreeMap<String, String>) -> String {
+    let first = line.split_whitespace().next().unwrap_or_default();
+    if aliases.is_empty() || ["alias", "unalias"].contains(&first.to_lowercase().as_str()) {
+        return line.to_string();
+    }
+
+    let mut output = String::with_capacity(line.len());
+    let mut quoted = false;
+    let mut chars = line.char_indices().peekable();
+    while let Some((start, char)) = chars.next() {
+        if char == '"' {
+            quoted = !quoted;
+        }
+        if quoted || !(char.is_alphanumeric() || char == '_') {
+            output.push(char);
+            continue;
+        }
+        let mut end = start + char.len_utf8();
+        while let Some((index, next)) =
+            chars.next_if(|(_, next)| next.is_alphanumeric() || *next == '_')
+        {
+            end = index + next.len_utf8();
+        }
+        let word = &line[start..end];
+        // `5k` e `x2` são uma palavra só, e números nunca são apelidos
+        let expansion = if char.is_ascii_digit() {
+            None
+        } else {
+            aliases.get(&word.to_lowercase())
+        };
+        output.push_str(expansion.map_or(word, String::as_str));
+    }
+    output
+}
+
+#[test]
+fn expand_should_replace_whole_words_once() {
+    let aliases: BTreeMap<String, String> = [("q", "exit"), ("k", "* 1000"), ("kk", "k * k")]
+        .into_iter()
+        .map(|(name, text)| (name.to_string(), text.to_string()))
+        .collect();
+    assert_eq!(expand("q", &aliases), "exit");
+    assert_eq!(expand("  Q  ", &aliases), "  exit  ");
+    assert_eq!(expand("5 k + 2", &aliases), "5 * 1000 + 2");
+    assert_eq!(expand("5k + kilo + k2", &aliases), "5k + kilo + k2");
+    assert_eq!(expand("kk", &aliases), "k * k");
+    assert_eq!(
+        expand("prompt \"k> \" k", &aliases),
+        "prompt \"k> \" * 1000"
+    );
+    assert_eq!(expand("alias k = * 1e6", &aliases), "alias k = * 1e6");
+    assert_eq!(expand("área k", &aliases), "área * 1000");
+    assert_eq!(expand("k", &BTreeMap::new()), "k");
+}
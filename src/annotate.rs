@@ -0,0 +1,66 @@
+//! `asdf-calc annotate <arquivo>`: escreve o resultado ao lado de cada linha do arquivo que for
+//! uma expressão, transformando anotações em documentos calculados.
+
+use std::{fs, io, path::Path};
+
+use crate::session::Session;
+
+/// Marca que separa a linha do resultado escrito por `annotate`.
+const MARKER: &str = " # = ";
+
+/// Adiciona `# = resultado` a cada linha que puder ser calculada, calculando todas numa mesma
+/// sessão. As outras linhas ficam como estão, e resultados de uma execução anterior são
+/// substituídos.
+pub fn annotate(text: &str, template: &Session) -> String {
+    let mut session = template.clone();
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let (content, newline) = match line.strip_suffix('\n') {
+            Some(content) => match content.strip_suffix('\r') {
+                Some(content) => (content, "\r\n"),
+                None => (content, "\n"),
+            },
+            None => (line, ""),
+        };
+        let expression = match content.find(MARKER) {
+            Some(marker) => &content[..marker],
+            None => content,
+        };
+
+        if expression.trim().is_empty() {
+            output.push_str(line);
+            continue;
+        }
+        match session.eval(expression.trim()) {
+            Ok(result) => output.push_str(&format!(
+                "{}{}{}{}",
+                expression.trim_end(),
+                MARKER,
+                result,
+                newline
+            )),
+            Err(_) => output.push_str(line),
+        }
+    }
+    output
+}
+
+/// Reescreve o arquivo com os resultados, passando por um arquivo temporário para que ele não
+/// fique pela metade caso algo dê errado.
+pub fn run(path: &Path, template: &Session) -> io::Result<()> {
+    let annotated = annotate(&fs::read_to_string(path)?, template);
+
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".asdf-calc-tmp");
+    fs::write(&temporary, annotated)?;
+    fs::rename(&temporary, path)
+}
+
+#[test]
+fn annotate_should_only_touch_expression_lines() {
+    let text = "Orçamento do mês\n\naluguel = 1500\naluguel * 12 # = 1\ntotal anual\n3 +";
+    assert_eq!(
+        annotate(text, &Session::default()),
+        "Orçamento do mês\n\naluguel = 1500 # = 1500\naluguel * 12 # = 18000\ntotal anual\n3 +"
+    );
+}
@@ -0,0 +1,81 @@
+//! Correções para os erros de digitação mais comuns, que a calculadora interativa oferece com
+//! "você quis dizer ...?" em vez de só mostrar o erro: operadores repetidos (`3 ++ 4`) e números
+//! com os milhares separados por espaço (`1 000 + 2`).
+
+/// Operadores que não fazem sentido repetidos. O `-` fica de fora, já que `3 -- 4` é `3 - (-4)`.
+const DOUBLED: [char; 4] = ['+', '*', '/', '^'];
+
+/// `text` corrigido, ou `None` se não há nada para corrigir. Os operadores repetidos viram um só,
+/// a não ser o `**`, que vira o `^` da potência, e os grupos de três dígitos separados por um
+/// espaço do número antes deles são juntados a ele (`1 000 000` é `1000000`).
+pub fn correct(text: &str) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let char = chars[i];
+        if DOUBLED.contains(&char) && chars.get(i + 1) == Some(&char) {
+            output.push(if char == '*' { '^' } else { char });
+            while chars.get(i) == Some(&char) {
+                i += 1;
+            }
+            continue;
+        }
+        // só o começo de um número, e não os dígitos de `x2` ou de `1.5`
+        if !char.is_ascii_digit() || (i > 0 && is_word_char(chars[i - 1])) {
+            output.push(char);
+            i += 1;
+            continue;
+        }
+        let start = i;
+        i = digits_end(&chars, i);
+        output.extend(&chars[start..i]);
+        if i - start > 3 {
+            continue;
+        }
+        while chars.get(i) == Some(&' ') {
+            let end = digits_end(&chars, i + 1);
+            // as casas decimais podem vir depois do último grupo
+            let glued = chars
+                .get(end)
+                .is_some_and(|char| char.is_alphanumeric() || *char == '_');
+            if end - (i + 1) != 3 || glued {
+                break;
+            }
+            output.extend(&chars[i + 1..end]);
+            i = end;
+        }
+    }
+    (output != text).then_some(output)
+}
+
+fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_' || char == '.'
+}
+
+/// A posição logo depois dos dígitos que começam em `start`.
+fn digits_end(chars: &[char], start: usize) -> usize {
+    let length = chars[start.min(chars.len())..]
+        .iter()
+        .take_while(|char| char.is_ascii_digit())
+        .count();
+    start + length
+}
+
+#[test]
+fn correct_should_fix_doubled_operators_and_split_numbers() {
+    let fixed = |text: &str| correct(text).unwrap_or_else(|| text.to_string());
+    assert_eq!(fixed("3 ++ 4"), "3 + 4");
+    assert_eq!(fixed("3 +++ 4 // 2"), "3 + 4 / 2");
+    assert_eq!(fixed("2 ** 10"), "2 ^ 10");
+    assert_eq!(fixed("1 000 + 2"), "1000 + 2");
+    assert_eq!(fixed("x = 12 345 678.5"), "x = 12345678.5");
+    assert_eq!(fixed("1 000 km"), "1000 km");
+
+    for text in [
+        "3 -- 4", "2 10", "1000 000", "x2 000", "1.5 000", "1 0000", "1 000a", "1  000",
+    ] {
+        assert_eq!(fixed(text), text, "{}", text);
+    }
+    assert_eq!(correct("1 + 2"), None);
+}
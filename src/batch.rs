@@ -0,0 +1,138 @@
+//! `--table [-n] [arquivo...]`: calcula as linhas dos arquivos (ou do stdin, sem nenhum) numa
+//! mesma sessão e escreve cada expressão ao lado do resultado, em colunas alinhadas, para que
+//! os resultados de um lote sejam fáceis de revisar e de comparar com `diff`.
+//!
+//! As linhas são lidas como as do `init.calc`: os comandos da sessão (`angle deg`) valem para as
+//! linhas seguintes sem aparecer na tabela, e as linhas vazias ou começando com `#` são
+//! ignoradas.
+
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::PathBuf,
+};
+
+use crate::session::Session;
+
+/// Uma linha calculada: onde ela está, o texto dela e o resultado ou os erros.
+#[derive(Debug, Clone, PartialEq)]
+struct Row {
+    location: String,
+    input: String,
+    result: Result<String, String>,
+}
+
+/// Calcula as linhas de `text`, com `source` (o arquivo, ou vazio) antes do número de cada uma.
+fn evaluate(text: &str, source: &str, session: &mut Session) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = session.expand_aliases(line);
+        let input = line.trim();
+        if input.is_empty() || input.starts_with('#') {
+            continue;
+        }
+        let result = match session.command(input) {
+            Some(Ok(_)) => continue,
+            Some(Err(message)) => Err(message),
+            None => session
+                .eval(input)
+                .map(|result| session.format_result(result))
+                .map_err(|errors| {
+                    let messages: Vec<String> =
+                        errors.iter().map(|error| error.to_string()).collect();
+                    messages.join("; ")
+                }),
+        };
+        rows.push(Row {
+            location: format!("{}{}", source, number + 1),
+            input: input.to_string(),
+            result,
+        });
+    }
+    rows
+}
+
+/// As linhas com as entradas alinhadas à esquerda e os resultados começando todos na mesma
+/// coluna, com os números das linhas antes delas se `numbers`.
+fn render(rows: &[Row], numbers: bool) -> String {
+    let width = |text: fn(&Row) -> &str| {
+        rows.iter()
+            .map(|row| text(row).chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let (location_width, input_width) = (width(|row| &row.location), width(|row| &row.input));
+    let mut output = String::new();
+    for row in rows {
+        if numbers {
+            output.push_str(&format!("{:>1$} | ", row.location, location_width));
+        }
+        let result = match &row.result {
+            Ok(result) => result.clone(),
+            Err(message) => format!("erro: {}", message),
+        };
+        output.push_str(&format!("{:<2$} | {}\n", row.input, result, input_width));
+    }
+    output
+}
+
+/// Retorna o código de saída do processo: 1 se algum arquivo não pôde ser lido ou alguma linha
+/// teve erro.
+pub fn run(files: &[PathBuf], numbers: bool, mut session: Session) -> i32 {
+    let mut rows = Vec::new();
+    let mut failed = false;
+    if files.is_empty() {
+        let mut text = String::new();
+        match io::stdin().read_to_string(&mut text) {
+            Ok(_) => rows.extend(evaluate(&text, "", &mut session)),
+            Err(error) => {
+                eprintln!("não foi possível ler input: {}", error);
+                failed = true;
+            }
+        }
+    }
+    for file in files {
+        // com mais de um arquivo, cada número de linha diz de qual ele é
+        let source = if files.len() > 1 {
+            format!("{}:", file.display())
+        } else {
+            String::new()
+        };
+        match fs::read_to_string(file) {
+            Ok(text) => rows.extend(evaluate(&text, &source, &mut session)),
+            Err(error) => {
+                eprintln!("{}: {}", file.display(), error);
+                failed = true;
+            }
+        }
+    }
+
+    let mut stdout = io::stdout().lock();
+    if let Err(error) = stdout.write_all(render(&rows, numbers).as_bytes()) {
+        eprintln!("não foi possível escrever o resultado: {}", error);
+        return 1;
+    }
+    i32::from(failed || rows.iter().any(|row| row.result.is_err()))
+}
+
+#[test]
+fn table_should_align_inputs_and_results() {
+    let mut session = Session::default();
+    let text = "# aluguel\nrent = 1500\n\nrent * 12\nangle deg\nsin(90)\n2 +\n";
+    let rows = evaluate(text, "", &mut session);
+    assert_eq!(
+        render(&rows, false),
+        "\
+rent = 1500 | 1500
+rent * 12   | 18000
+sin(90)     | 1
+2 +         | erro: coluna 3: síntaxe incorreta, operador sem um número à sua direita\n"
+    );
+    assert_eq!(
+        render(&rows[..2], true),
+        "2 | rent = 1500 | 1500\n4 | rent * 12   | 18000\n"
+    );
+
+    let rows = evaluate("1 km to m\n", "a.calc:", &mut session);
+    assert_eq!(render(&rows, true), "a.calc:1 | 1 km to m | 1000 m\n");
+}
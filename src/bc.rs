@@ -0,0 +1,342 @@
+//! Modo compatível com o `bc` (`--compat bc`), para que scripts que chamam o `bc` possam usar a
+//! calculadora sem reescrever as expressões.
+//!
+//! Só um subconjunto é aceito: comandos separados por `;` ou por linha, comentários (`/* */` e
+//! `#`), `scale=N`, `^`, `quit` e funções de um único `return` (`define f(x) { return x * 2 }`).
+//! Os cálculos continuam sendo feitos em `f64`, e o `scale` só corta as casas decimais do
+//! resultado mostrado, ao invés de valer para cada divisão como no `bc`.
+
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    mem,
+    path::PathBuf,
+};
+
+use asdf_calc::{EvalContext, Function, Statement};
+
+/// Casas decimais usadas com `-l`, como no `bc`. As funções da biblioteca matemática do `bc`
+/// (`s`, `c`, `a`, `l`, `e` e `j`) não estão disponíveis.
+pub const MATHLIB_SCALE: usize = 20;
+
+/// Junta as linhas do input em comandos, tirando os comentários e mantendo o corpo de uma
+/// função (entre `{` e `}`) num único comando, mesmo que ele ocupe várias linhas.
+#[derive(Debug, Default)]
+struct Splitter {
+    current: String,
+    depth: usize,
+    in_comment: bool,
+}
+
+impl Splitter {
+    /// Retorna os comandos completados pela linha.
+    fn push_line(&mut self, line: &str) -> Vec<String> {
+        let mut statements = Vec::new();
+        let mut chars = line.chars().peekable();
+        while let Some(char) = chars.next() {
+            if self.in_comment {
+                if char == '*' && chars.peek() == Some(&'/') {
+                    chars.next();
+                    self.in_comment = false;
+                }
+                continue;
+            }
+
+            match char {
+                '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    self.in_comment = true;
+                }
+                '#' => break,
+                '{' => {
+                    self.depth += 1;
+                    self.current.push(char);
+                }
+                '}' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    self.current.push(char);
+                }
+                ';' if self.depth == 0 => statements.push(mem::take(&mut self.current)),
+                char => self.current.push(char),
+            }
+        }
+
+        if self.depth == 0 && !self.in_comment {
+            statements.push(mem::take(&mut self.current));
+        } else {
+            self.current.push('\n');
+        }
+
+        statements.retain(|statement| !statement.trim().is_empty());
+        statements
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Print(String),
+    /// Atribuições e definições não mostram nada, como no `bc`.
+    Silent,
+    Quit,
+}
+
+#[derive(Debug, Default)]
+pub struct Bc {
+    pub context: EvalContext,
+    pub scale: usize,
+}
+
+impl Bc {
+    pub fn new(scale: usize, context: EvalContext) -> Self {
+        Bc { context, scale }
+    }
+
+    pub fn execute(&mut self, statement: &str) -> Result<Outcome, String> {
+        let statement = statement.trim();
+        if statement == "quit" {
+            return Ok(Outcome::Quit);
+        }
+        if statement == "scale" {
+            return Ok(Outcome::Print(self.scale.to_string()));
+        }
+        if let Some(value) = statement
+            .strip_prefix("scale")
+            .map(str::trim_start)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            let value = self.evaluate(value)?;
+            if !(0.0..=f64::from(u16::MAX)).contains(&value) {
+                return Err(format!("scale inválido: {}", value));
+            }
+            self.scale = value as usize;
+            return Ok(Outcome::Silent);
+        }
+        if let Some(definition) = statement.strip_prefix("define") {
+            self.define(definition)?;
+            return Ok(Outcome::Silent);
+        }
+
+        match Statement::new(statement, &self.context).map_err(join_errors)? {
+            statement @ Statement::Assignment(..) => {
+                statement
+                    .execute(&mut self.context)
+                    .map_err(|error| error.to_string())?;
+                Ok(Outcome::Silent)
+            }
+            statement => {
+                let result = statement
+                    .execute(&mut self.context)
+                    .map_err(|error| error.to_string())?;
+                format_number(result, self.scale).map(Outcome::Print)
+            }
+        }
+    }
+
+    fn evaluate(&mut self, text: &str) -> Result<f64, String> {
+        Statement::new(text, &self.context)
+            .map_err(join_errors)?
+            .execute(&mut self.context)
+            .map_err(|error| error.to_string())
+    }
+
+    /// Lê `nome(parâmetros) { return expressão }`, o que vem depois do `define`.
+    fn define(&mut self, definition: &str) -> Result<(), String> {
+        let invalid = || "definição de função inválida".to_string();
+
+        let open = definition.find('(').ok_or_else(invalid)?;
+        let close = definition.find(')').ok_or_else(invalid)?;
+        let name = definition[..open].trim();
+        let parameters: Vec<String> = definition[open + 1..close]
+            .split(',')
+            .map(str::trim)
+            .filter(|parameter| !parameter.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !is_name(name) || !parameters.iter().all(|parameter| is_name(parameter)) {
+            return Err(invalid());
+        }
+
+        let body = definition[close + 1..]
+            .trim()
+            .strip_prefix('{')
+            .and_then(|body| body.strip_suffix('}'))
+            .ok_or_else(invalid)?;
+        let statements: Vec<&str> = body
+            .split([';', '\n'])
+            .map(str::trim)
+            .filter(|statement| !statement.is_empty())
+            .collect();
+        let value = match statements.as_slice() {
+            [statement] => statement
+                .strip_prefix("return")
+                .filter(|rest| {
+                    !rest.starts_with(|char: char| char.is_alphanumeric() || char == '_')
+                })
+                .ok_or_else(|| {
+                    "só são aceitas funções com um único 'return' no corpo".to_string()
+                })?,
+            _ => return Err("só são aceitas funções com um único 'return' no corpo".to_string()),
+        };
+        // `return` sozinho retorna 0, como no `bc`
+        let value = if value.trim().is_empty() { "0" } else { value };
+
+        let function =
+            Function::new(parameters, value.trim(), &self.context).map_err(join_errors)?;
+        self.context.define_function(name, function);
+        Ok(())
+    }
+}
+
+fn is_name(text: &str) -> bool {
+    text.starts_with(|char: char| char.is_alphabetic() || char == '_')
+        && text
+            .chars()
+            .all(|char| char.is_alphanumeric() || char == '_')
+}
+
+fn join_errors(errors: Vec<asdf_calc::Error>) -> String {
+    errors
+        .iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escreve o número como o `bc`, cortando (sem arredondar) as casas decimais além de `scale` e
+/// sem o zero antes do ponto (`.5`).
+pub fn format_number(value: f64, scale: usize) -> Result<String, String> {
+    if !value.is_finite() {
+        return Err(format!("o resultado ({}) não é um número finito", value));
+    }
+
+    let mut text = value.to_string();
+    if let Some(point) = text.find('.') {
+        text.truncate(if scale == 0 { point } else { point + 1 + scale });
+        if text.contains('.') {
+            let trimmed = text.trim_end_matches('0').trim_end_matches('.').len();
+            text.truncate(trimmed);
+        }
+    }
+
+    if let Some(rest) = text.strip_prefix("0.") {
+        text = format!(".{}", rest);
+    } else if let Some(rest) = text.strip_prefix("-0.") {
+        text = format!("-.{}", rest);
+    }
+    if text == "-0" {
+        text = "0".to_string();
+    }
+    Ok(text)
+}
+
+/// Lê os `files` em ordem e depois o stdin, como o `bc`. Retorna o código de saída do processo.
+pub fn run(files: &[PathBuf], scale: usize, context: &EvalContext) -> i32 {
+    let mut bc = Bc::new(scale, context.clone());
+    let mut had_errors = false;
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    let mut run_lines = |source: &str, lines: &mut dyn Iterator<Item = io::Result<String>>| {
+        let mut splitter = Splitter::default();
+        for (number, line) in lines.enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(error) => {
+                    eprintln!("{}: não foi possível ler: {}", source, error);
+                    had_errors = true;
+                    return false;
+                }
+            };
+
+            for statement in splitter.push_line(&line) {
+                match bc.execute(&statement) {
+                    Ok(Outcome::Print(result)) => {
+                        let _ = writeln!(stdout, "{}", result);
+                    }
+                    Ok(Outcome::Silent) => {}
+                    Ok(Outcome::Quit) => return false,
+                    Err(message) => {
+                        had_errors = true;
+                        for message in message.lines() {
+                            eprintln!("{} {}: {}", source, number + 1, message);
+                        }
+                    }
+                }
+            }
+        }
+        true
+    };
+
+    for file in files {
+        let text = match fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("{}: não foi possível ler: {}", file.display(), error);
+                return 1;
+            }
+        };
+        let source = file.display().to_string();
+        if !run_lines(&source, &mut text.lines().map(|line| Ok(line.to_string()))) {
+            return if had_errors { 1 } else { 0 };
+        }
+    }
+    run_lines("(stdin)", &mut io::stdin().lock().lines());
+
+    if had_errors {
+        1
+    } else {
+        0
+    }
+}
+
+#[test]
+fn splitter_should_join_multiline_definitions_and_skip_comments() {
+    let mut splitter = Splitter::default();
+    assert_eq!(
+        splitter.push_line("scale=4; 10/3 /* divisão */"),
+        vec!["scale=4".to_string(), " 10/3 ".to_string()]
+    );
+    assert_eq!(splitter.push_line("define f(x) {"), Vec::<String>::new());
+    assert_eq!(
+        splitter.push_line("  return x * 2 # dobro"),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        splitter.push_line("}"),
+        vec!["define f(x) {\n  return x * 2 \n}".to_string()]
+    );
+}
+
+#[test]
+fn bc_should_run_scripts_written_for_bc() {
+    let mut bc = Bc::new(0, EvalContext::new());
+    assert_eq!(bc.execute("7 / 2"), Ok(Outcome::Print("3".to_string())));
+    assert_eq!(bc.execute("scale = 4"), Ok(Outcome::Silent));
+    assert_eq!(
+        bc.execute("10 / 3"),
+        Ok(Outcome::Print("3.3333".to_string()))
+    );
+    assert_eq!(bc.execute("2 ^ 10"), Ok(Outcome::Print("1024".to_string())));
+    assert_eq!(bc.execute("x = 1 / 4"), Ok(Outcome::Silent));
+    assert_eq!(
+        bc.execute("define f(a, b) {\n return (a * b + x)\n}"),
+        Ok(Outcome::Silent)
+    );
+    assert_eq!(
+        bc.execute("f(2, -3)"),
+        Ok(Outcome::Print("-5.75".to_string()))
+    );
+    assert_eq!(bc.execute("scale"), Ok(Outcome::Print("4".to_string())));
+    assert!(bc.execute("define g(x) { x = 2; return x }").is_err());
+    assert!(bc.execute("1 / 0").is_err());
+    assert_eq!(bc.execute("quit"), Ok(Outcome::Quit));
+}
+
+#[test]
+fn format_number_should_truncate_like_bc() {
+    assert_eq!(format_number(0.57, 2), Ok(".57".to_string()));
+    assert_eq!(format_number(-2.0 / 3.0, 3), Ok("-.666".to_string()));
+    assert_eq!(format_number(-0.0001, 2), Ok("0".to_string()));
+    assert_eq!(format_number(1.5, 0), Ok("1".to_string()));
+    assert_eq!(format_number(42.0, 5), Ok("42".to_string()));
+}
@@ -0,0 +1,73 @@
+//! `asdf-calc --bench N <expressão>`: repete a leitura e o cálculo de uma expressão, mostrando
+//! o menor, o médio e o maior tempo de cada etapa, para comparar implementações do avaliador.
+
+use std::time::Duration;
+
+use crate::{
+    repl::format_duration,
+    session::{Session, Timing},
+};
+
+/// Menor, média e maior duração.
+fn summarize(durations: &[Duration]) -> (Duration, Duration, Duration) {
+    let min = durations.iter().min().copied().unwrap_or_default();
+    let max = durations.iter().max().copied().unwrap_or_default();
+    let mean = durations.iter().sum::<Duration>() / durations.len().max(1) as u32;
+    (min, mean, max)
+}
+
+/// Calcula `expression` `iterations` vezes, cada uma numa cópia de `template` para que uma
+/// atribuição não mude as seguintes, e monta o relatório.
+pub fn bench(expression: &str, iterations: u32, template: &Session) -> Result<String, String> {
+    let mut timings: Vec<Timing> = Vec::with_capacity(iterations as usize);
+    let mut result = f64::NAN;
+    for _ in 0..iterations {
+        let mut session = template.clone();
+        result = session.eval(expression).map_err(|errors| {
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+        timings.extend(session.last_timing);
+    }
+
+    let parse: Vec<Duration> = timings.iter().map(|timing| timing.parse).collect();
+    let evaluation: Vec<Duration> = timings.iter().map(|timing| timing.evaluation).collect();
+    let total: Vec<Duration> = timings
+        .iter()
+        .map(|timing| timing.parse + timing.evaluation)
+        .collect();
+
+    let mut report = format!("{} = {} ({} repetições)\n", expression, result, iterations);
+    report.push_str(&format!(
+        "{:<8} {:>12} {:>12} {:>12}\n",
+        "", "mín", "média", "máx"
+    ));
+    for (name, durations) in [
+        ("leitura", &parse),
+        ("cálculo", &evaluation),
+        ("total", &total),
+    ] {
+        let (min, mean, max) = summarize(durations);
+        report.push_str(&format!(
+            "{:<8} {:>12} {:>12} {:>12}\n",
+            name,
+            format_duration(min),
+            format_duration(mean),
+            format_duration(max)
+        ));
+    }
+    Ok(report)
+}
+
+#[test]
+fn bench_should_report_every_stage() {
+    let report = bench("2 ^ 10", 5, &Session::default()).unwrap();
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines[0], "2 ^ 10 = 1024 (5 repetições)");
+    assert!(lines[2].starts_with("leitura"));
+    assert!(lines[4].starts_with("total"));
+    assert!(bench("2 +", 5, &Session::default()).is_err());
+}
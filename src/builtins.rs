@@ -0,0 +1,1068 @@
+//! Funções que já vêm definidas em toda expressão. Uma função do contexto com o mesmo nome
+//! esconde a embutida, assim como as variáveis escondem as constantes.
+
+use crate::{
+    context::{AngleMode, Summation, WordSize},
+    expression::{approx_equal, from_bool},
+    geo,
+    math::{self, Libm},
+    units::{Dimension, DATA_RATE},
+};
+
+/// Quantidade de argumentos que uma função embutida aceita.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exactly(usize),
+    /// Funções variádicas, como `min(...)`.
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(self, count: usize) -> bool {
+        match self {
+            Arity::Exactly(expected) => count == expected,
+            Arity::AtLeast(minimum) => count >= minimum,
+        }
+    }
+}
+
+/// Que unidades uma função embutida aceita nos argumentos, e qual o resultado tem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Units {
+    /// Só números sem unidade (`sin`, `popcount`).
+    None,
+    /// Os `n` primeiros argumentos (todos, nas variádicas) têm a mesma unidade, que é a do
+    /// resultado, e os outros não têm unidade (`round(x, casas)`).
+    Kept(usize),
+    /// Como `Kept`, mas o resultado é um número sem unidade (`sign`, `approx`).
+    Compared(usize),
+    /// Cada argumento tem exatamente a sua dimensão, e o resultado tem a segunda
+    /// (`transfer_time(bytes, bytes/s)` é um tempo).
+    Fixed(&'static [Dimension], Dimension),
+}
+
+/// Como uma função embutida usa o modo de ângulo do contexto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Angle {
+    None,
+    /// O primeiro argumento é um ângulo (`sin`).
+    Argument,
+    /// O resultado é um ângulo (`atan`).
+    Result,
+}
+
+#[derive(Debug)]
+pub struct Builtin {
+    name: &'static str,
+    /// A função em si sempre trabalha em radianos, e a conversão para o modo do contexto é feita
+    /// por `call`.
+    angle: Angle,
+    arity: Arity,
+    /// A função recebe o tamanho da palavra do contexto, em bits, depois dos argumentos.
+    word_size: bool,
+    units: Units,
+    /// Recebe os argumentos já conferidos com `arity`, e retorna `None` se eles estiverem fora do
+    /// domínio da função.
+    function: fn(&[f64], &Libm, Summation) -> Option<f64>,
+}
+
+impl Builtin {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    pub fn angle(&self) -> Angle {
+        self.angle
+    }
+
+    pub(crate) fn units(&self) -> Units {
+        self.units
+    }
+
+    pub(crate) fn call(
+        &self,
+        arguments: &[f64],
+        angle_mode: AngleMode,
+        word_size: WordSize,
+        libm: &Libm,
+        summation: Summation,
+    ) -> Option<f64> {
+        if self.word_size {
+            let mut arguments = arguments.to_vec();
+            arguments.push(f64::from(word_size.bits()));
+            return (self.function)(&arguments, libm, summation);
+        }
+        match self.angle {
+            Angle::None => (self.function)(arguments, libm, summation),
+            Angle::Argument => {
+                let mut arguments = arguments.to_vec();
+                arguments[0] = angle_mode.to_radians(arguments[0]);
+                (self.function)(&arguments, libm, summation)
+            }
+            Angle::Result => (self.function)(arguments, libm, summation)
+                .map(|radians| angle_mode.from_radians(radians)),
+        }
+    }
+}
+
+/// Cada função embutida tem um único nome, então ele basta para compará-las.
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+pub const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "min",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(usize::MAX),
+        arity: Arity::AtLeast(1),
+        function: |values, _, _| Some(fold_propagating_nan(values, f64::min)),
+    },
+    Builtin {
+        name: "max",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(usize::MAX),
+        arity: Arity::AtLeast(1),
+        function: |values, _, _| Some(fold_propagating_nan(values, f64::max)),
+    },
+    Builtin {
+        name: "sum",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(usize::MAX),
+        arity: Arity::AtLeast(1),
+        function: |values, _, summation| Some(summation.sum(values)),
+    },
+    Builtin {
+        name: "mean",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(usize::MAX),
+        arity: Arity::AtLeast(1),
+        function: |values, _, summation| Some(summation.sum(values) / values.len() as f64),
+    },
+    Builtin {
+        name: "clamp",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(3),
+        arity: Arity::Exactly(3),
+        function: |values, _, _| {
+            let [x, low, high] = [values[0], values[1], values[2]];
+            // `f64::clamp` entraria em pânico com limites invertidos ou `NaN`
+            if low.is_nan() || high.is_nan() || low > high {
+                None
+            } else if x.is_nan() {
+                Some(x)
+            } else {
+                Some(x.max(low).min(high))
+            }
+        },
+    },
+    Builtin {
+        name: "approx",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Compared(2),
+        arity: Arity::Exactly(3),
+        function: |values, _, _| {
+            let [a, b, tolerance] = [values[0], values[1], values[2]];
+            // com uma tolerância negativa nada seria igual, nem um número a ele mesmo
+            if tolerance.is_nan() || tolerance < 0.0 {
+                None
+            } else {
+                Some(from_bool(approx_equal(a, b, tolerance)))
+            }
+        },
+    },
+    Builtin {
+        name: "equal",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Compared(2),
+        arity: Arity::Exactly(3),
+        function: |values, _, _| {
+            let [a, b, epsilon] = [values[0], values[1], values[2]];
+            // como em `approx`, nada seria igual com uma tolerância negativa
+            if epsilon.is_nan() || epsilon < 0.0 {
+                None
+            } else {
+                Some(from_bool((a - b).abs() <= epsilon))
+            }
+        },
+    },
+    Builtin {
+        name: "lerp",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(2),
+        arity: Arity::Exactly(3),
+        function: |values, _, _| {
+            let [a, b, t] = [values[0], values[1], values[2]];
+            Some(a + (b - a) * t)
+        },
+    },
+    Builtin {
+        name: "rotl",
+        angle: Angle::None,
+        word_size: true,
+        units: Units::None,
+        arity: Arity::Exactly(2),
+        function: |values, _, _| rotate(values, true),
+    },
+    Builtin {
+        name: "rotr",
+        angle: Angle::None,
+        word_size: true,
+        units: Units::None,
+        arity: Arity::Exactly(2),
+        function: |values, _, _| rotate(values, false),
+    },
+    Builtin {
+        name: "popcount",
+        angle: Angle::None,
+        word_size: true,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, _, _| word(values[0], values[1]).map(|(x, _)| f64::from(x.count_ones())),
+    },
+    Builtin {
+        name: "clz",
+        angle: Angle::None,
+        word_size: true,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, _, _| {
+            let (x, word_size) = word(values[0], values[1])?;
+            Some(f64::from(x.leading_zeros() - (64 - word_size.bits())))
+        },
+    },
+    Builtin {
+        name: "ctz",
+        angle: Angle::None,
+        word_size: true,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, _, _| {
+            let (x, word_size) = word(values[0], values[1])?;
+            Some(f64::from(x.trailing_zeros().min(word_size.bits())))
+        },
+    },
+    Builtin {
+        name: "signed",
+        angle: Angle::None,
+        word_size: true,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, _, _| {
+            let (x, word_size) = word(values[0], values[1])?;
+            let sign_bit = 1u64 << (word_size.bits() - 1);
+            Some(if x & sign_bit == 0 {
+                x as f64
+            } else {
+                -(((!x & word_size.mask()) + 1) as f64)
+            })
+        },
+    },
+    Builtin {
+        name: "unsigned",
+        angle: Angle::None,
+        word_size: true,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, _, _| word(values[0], values[1]).map(|(x, _)| x as f64),
+    },
+    Builtin {
+        name: "parity",
+        angle: Angle::None,
+        word_size: true,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, _, _| {
+            word(values[0], values[1]).map(|(x, _)| f64::from(x.count_ones() % 2))
+        },
+    },
+    Builtin {
+        name: "crc32",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::AtLeast(1),
+        function: |values, _, _| crc32(values),
+    },
+    Builtin {
+        name: "ulp",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(1),
+        arity: Arity::Exactly(1),
+        function: |values, _, _| Some(ulp(values[0])),
+    },
+    Builtin {
+        name: "float_distance",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Compared(2),
+        arity: Arity::Exactly(2),
+        function: |values, _, _| {
+            let [a, b] = [values[0], values[1]];
+            if a.is_nan() || b.is_nan() {
+                return Some(f64::NAN);
+            }
+            Some(ordered_bits(a).abs_diff(ordered_bits(b)) as f64)
+        },
+    },
+    Builtin {
+        name: "round",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(1),
+        arity: Arity::Exactly(2),
+        function: |values, libm, _| round_to(values[0], values[1], libm),
+    },
+    Builtin {
+        name: "trunc",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(1),
+        arity: Arity::Exactly(1),
+        function: |values, _, _| Some(math::trunc(values[0])),
+    },
+    Builtin {
+        name: "sign",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Compared(1),
+        arity: Arity::Exactly(1),
+        function: |values, _, _| {
+            let x = values[0];
+            // `f64::signum` seria 1 para o zero
+            Some(if x > 0.0 {
+                1.0
+            } else if x < 0.0 {
+                -1.0
+            } else {
+                x
+            })
+        },
+    },
+    Builtin {
+        name: "frac",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(1),
+        arity: Arity::Exactly(1),
+        function: |values, _, _| Some(values[0] - math::trunc(values[0])),
+    },
+    Builtin {
+        name: "sinh",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| Some((libm.sinh)(values[0])),
+    },
+    Builtin {
+        name: "cosh",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| Some((libm.cosh)(values[0])),
+    },
+    Builtin {
+        name: "tanh",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| Some((libm.tanh)(values[0])),
+    },
+    Builtin {
+        name: "asinh",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| Some((libm.asinh)(values[0])),
+    },
+    Builtin {
+        name: "acosh",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| {
+            // `NaN` passa adiante, como nas outras funções
+            if values[0] < 1.0 {
+                None
+            } else {
+                Some((libm.acosh)(values[0]))
+            }
+        },
+    },
+    Builtin {
+        name: "atanh",
+        // em -1 e 1 a função vai para o infinito
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| {
+            if values[0].abs() >= 1.0 {
+                None
+            } else {
+                Some((libm.atanh)(values[0]))
+            }
+        },
+    },
+    Builtin {
+        name: "sin",
+        angle: Angle::Argument,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| Some((libm.sin)(values[0])),
+    },
+    Builtin {
+        name: "cos",
+        angle: Angle::Argument,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| Some((libm.cos)(values[0])),
+    },
+    Builtin {
+        name: "tan",
+        angle: Angle::Argument,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| Some((libm.tan)(values[0])),
+    },
+    Builtin {
+        name: "asin",
+        angle: Angle::Result,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| {
+            if values[0].abs() > 1.0 {
+                None
+            } else {
+                Some((libm.asin)(values[0]))
+            }
+        },
+    },
+    Builtin {
+        name: "acos",
+        angle: Angle::Result,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| {
+            if values[0].abs() > 1.0 {
+                None
+            } else {
+                Some((libm.acos)(values[0]))
+            }
+        },
+    },
+    Builtin {
+        name: "atan",
+        angle: Angle::Result,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| Some((libm.atan)(values[0])),
+    },
+    // `atan(y / x)` não sabe em que quadrante o ponto está, já que `-y / -x` é igual a `y / x`
+    Builtin {
+        name: "atan2",
+        angle: Angle::Result,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(2),
+        function: |values, libm, _| Some((libm.atan2)(values[0], values[1])),
+    },
+    Builtin {
+        name: "gamma",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| gamma_domain(values[0]).then(|| (libm.gamma)(values[0])),
+    },
+    Builtin {
+        name: "lgamma",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| gamma_domain(values[0]).then(|| (libm.lgamma)(values[0])),
+    },
+    Builtin {
+        name: "erf",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| Some((libm.erf)(values[0])),
+    },
+    Builtin {
+        name: "erfc",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, libm, _| Some((libm.erfc)(values[0])),
+    },
+    // `money.`: contas do dia a dia, com as porcentagens em pontos percentuais (`15` é 15%)
+    Builtin {
+        name: "money.split",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(1),
+        arity: Arity::Exactly(2),
+        function: |values, _, _| {
+            let [total, people] = [values[0], values[1]];
+            (math::trunc(people) == people && people >= 1.0).then(|| total / people)
+        },
+    },
+    Builtin {
+        name: "money.tip",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(1),
+        arity: Arity::Exactly(2),
+        function: |values, _, _| percentage(values[0], values[1]),
+    },
+    Builtin {
+        name: "money.with_tax",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(1),
+        arity: Arity::Exactly(2),
+        function: |values, _, _| percentage(values[0], values[1]).map(|tax| values[0] + tax),
+    },
+    // temperaturas, sem unidade porque as escalas Celsius e Fahrenheit não começam no zero
+    // absoluto, e as unidades só sabem multiplicar; abaixo do zero absoluto não há resultado
+    Builtin {
+        name: "c_to_f",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, _, _| (values[0] >= -273.15).then(|| values[0] * 1.8 + 32.0),
+    },
+    Builtin {
+        name: "f_to_c",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, _, _| (values[0] >= -459.67).then(|| (values[0] - 32.0) / 1.8),
+    },
+    Builtin {
+        name: "k_to_c",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(1),
+        function: |values, _, _| (values[0] >= 0.0).then(|| values[0] - 273.15),
+    },
+    // distâncias entre latitudes e longitudes, sempre em graus como nos mapas
+    Builtin {
+        name: "haversine",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Fixed(&[Dimension::NONE; 4], Dimension::LENGTH),
+        arity: Arity::Exactly(4),
+        function: |values, libm, _| {
+            geo::haversine([values[0], values[1]], [values[2], values[3]], libm)
+        },
+    },
+    Builtin {
+        name: "vincenty",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Fixed(&[Dimension::NONE; 4], Dimension::LENGTH),
+        arity: Arity::Exactly(4),
+        function: |values, libm, _| {
+            geo::vincenty([values[0], values[1]], [values[2], values[3]], libm)
+        },
+    },
+    // telas: `diag(largura, altura)` e a densidade de pixels com a diagonal em polegadas
+    Builtin {
+        name: "diag",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(2),
+        arity: Arity::Exactly(2),
+        function: |values, libm, _| Some(diagonal(values[0], values[1], libm)),
+    },
+    Builtin {
+        name: "ppi",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::None,
+        arity: Arity::Exactly(3),
+        function: |values, libm, _| {
+            let [width, height, inches] = [values[0], values[1], values[2]];
+            (inches > 0.0).then(|| diagonal(width, height, libm) / inches)
+        },
+    },
+    // tamanhos em base64: `b64len` com os `=` do fim, como quase todo codificador faz, e
+    // `unb64len` é o tamanho máximo dos dados de um texto com eles; o tamanho exato sai do
+    // `unb64len_nopad` com os caracteres antes dos `=`
+    Builtin {
+        name: "b64len",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(1),
+        arity: Arity::Exactly(1),
+        function: |values, _, _| count(values[0]).map(|bytes| (4 * bytes.div_ceil(3)) as f64),
+    },
+    Builtin {
+        name: "b64len_nopad",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(1),
+        arity: Arity::Exactly(1),
+        function: |values, _, _| count(values[0]).map(|bytes| (4 * bytes).div_ceil(3) as f64),
+    },
+    Builtin {
+        name: "unb64len",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(1),
+        arity: Arity::Exactly(1),
+        function: |values, _, _| {
+            count(values[0])
+                .filter(|chars| chars % 4 == 0)
+                .map(|chars| (chars / 4 * 3) as f64)
+        },
+    },
+    Builtin {
+        name: "unb64len_nopad",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Kept(1),
+        arity: Arity::Exactly(1),
+        // um caractere a mais que um múltiplo de 4 não completa nem um byte
+        function: |values, _, _| {
+            count(values[0])
+                .filter(|chars| chars % 4 != 1)
+                .map(|chars| (chars * 3 / 4) as f64)
+        },
+    },
+    // quanto tempo leva para transferir um tamanho de dados a uma velocidade (`4 TB`, `1 Gbps`)
+    Builtin {
+        name: "transfer_time",
+        angle: Angle::None,
+        word_size: false,
+        units: Units::Fixed(&[Dimension::INFORMATION, DATA_RATE], Dimension::TIME),
+        arity: Arity::Exactly(2),
+        function: |values, _, _| {
+            let [size, rate] = [values[0], values[1]];
+            (size >= 0.0 && rate > 0.0).then(|| size / rate)
+        },
+    },
+];
+
+/// `pct` por cento de `total`, para as funções de `money.`, que não aceitam porcentagens
+/// negativas.
+/// `value` se ele for uma quantidade (de bytes, de caracteres) que dá para contar.
+fn count(value: f64) -> Option<u64> {
+    // acima de 2⁵³ os inteiros do `f64` já não são todos representáveis
+    (value >= 0.0 && math::trunc(value) == value && value < 9007199254740992.0)
+        .then_some(value as u64)
+}
+
+fn diagonal(width: f64, height: f64, libm: &Libm) -> f64 {
+    (libm.pow)(width * width + height * height, 0.5)
+}
+
+fn percentage(total: f64, pct: f64) -> Option<f64> {
+    (pct >= 0.0).then(|| total * pct / 100.0)
+}
+
+/// Lê o argumento de uma função de bits na palavra de `bits` bits.
+fn word(value: f64, bits: f64) -> Option<(u64, WordSize)> {
+    let word_size = WordSize::from_bits(bits as u32)?;
+    Some((word_size.to_bits(value)?, word_size))
+}
+
+/// `rotl(x, n)` e `rotr(x, n)`, girando os bits de `x` na palavra. `n` pode ser negativo, para
+/// girar para o outro lado, ou maior que a palavra.
+fn rotate(values: &[f64], left: bool) -> Option<f64> {
+    let (x, word_size) = word(values[0], values[2])?;
+    let n = values[1];
+    if math::trunc(n) != n || n.abs() > 1e15 {
+        return None;
+    }
+    let bits = i64::from(word_size.bits());
+    let n = if left { n as i64 } else { -(n as i64) };
+    let n = n.rem_euclid(bits) as u32;
+    // `x >> 64` não é permitido, e girar 0 bits não muda nada
+    if n == 0 {
+        return Some(x as f64);
+    }
+    let rotated = ((x << n) | (x >> (word_size.bits() - n))) & word_size.mask();
+    Some(rotated as f64)
+}
+
+/// Distância entre `|x|` e o próximo `f64` mais longe do zero (ou o anterior, no maior `f64`).
+fn ulp(x: f64) -> f64 {
+    let x = x.abs();
+    if !x.is_finite() {
+        x
+    } else if x == f64::MAX {
+        x - x.next_down()
+    } else {
+        x.next_up() - x
+    }
+}
+
+/// Posição de `x` na sequência de todos os `f64`, para que a diferença entre duas posições seja
+/// quantos `f64` existem entre eles (`0.0` e `-0.0` ficam na mesma).
+fn ordered_bits(x: f64) -> i64 {
+    let bits = x.to_bits() as i64;
+    if bits < 0 {
+        -(bits & i64::MAX)
+    } else {
+        bits
+    }
+}
+
+/// `crc32(...)`, o CRC-32 do zlib e do Ethernet dos bytes formados pelos argumentos. Cada
+/// argumento é um inteiro não negativo que vira os seus bytes, do mais significativo para o
+/// menos, sem os zeros à esquerda: `crc32(0x3132, 0x33)` é o CRC dos bytes `31 32 33`.
+fn crc32(values: &[f64]) -> Option<f64> {
+    let mut crc = u32::MAX;
+    for value in values {
+        let bytes = WordSize::U64
+            .to_bits(*value)
+            .filter(|_| *value >= 0.0)?
+            .to_be_bytes();
+        let first = bytes
+            .iter()
+            .position(|byte| *byte != 0)
+            .unwrap_or(bytes.len() - 1);
+        for byte in &bytes[first..] {
+            crc ^= u32::from(*byte);
+            for _ in 0..8 {
+                let mask = (crc & 1).wrapping_neg();
+                crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+            }
+        }
+    }
+    Some(f64::from(!crc))
+}
+
+pub(crate) fn builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+/// `round(x, n)`, arredondando para `n` casas decimais (ou para dezenas, centenas... com `n`
+/// negativo). `n` precisa ser inteiro.
+fn round_to(x: f64, digits: f64, libm: &Libm) -> Option<f64> {
+    if digits.is_nan() || math::trunc(digits) != digits {
+        return None;
+    }
+
+    // para `n` negativo, dividir por `10^-n` é mais preciso que multiplicar por `10^n`
+    let factor = (libm.pow)(10.0, digits.abs());
+    if digits >= 0.0 {
+        let scaled = x * factor;
+        // com tantas casas o número já não tem o que arredondar
+        if !scaled.is_finite() {
+            return Some(x);
+        }
+        Some(math::round(scaled) / factor)
+    } else if factor.is_infinite() {
+        Some(0.0)
+    } else {
+        Some(math::round(x / factor) * factor)
+    }
+}
+
+/// Γ(x) não é definida no zero e nos inteiros negativos, onde tem polos.
+fn gamma_domain(x: f64) -> bool {
+    !(x <= 0.0 && math::trunc(x) == x)
+}
+
+/// `f64::min` e `f64::max` ignoram `NaN`, mas um argumento inválido deveria invalidar o
+/// resultado, como nas operações.
+fn fold_propagating_nan(values: &[f64], fold: fn(f64, f64) -> f64) -> f64 {
+    values
+        .iter()
+        .copied()
+        .reduce(|acc, value| {
+            if acc.is_nan() || value.is_nan() {
+                f64::NAN
+            } else {
+                fold(acc, value)
+            }
+        })
+        .unwrap_or(f64::NAN)
+}
+
+#[test]
+fn builtins_should_compute_their_values() {
+    let call = |name: &str, arguments: &[f64]| {
+        builtin(name).unwrap().call(
+            arguments,
+            AngleMode::Radians,
+            WordSize::U64,
+            &math::SYSTEM,
+            Summation::default(),
+        )
+    };
+    assert_eq!(call("min", &[3.0, -1.0, 2.0]), Some(-1.0));
+    assert_eq!(call("max", &[3.0, -1.0, 2.0]), Some(3.0));
+    assert!(call("max", &[1.0, f64::NAN]).unwrap().is_nan());
+    assert_eq!(call("sum", &[0.1, 0.2, 0.3]), Some(0.6));
+    assert_eq!(call("mean", &[1.0, 2.0, 6.0]), Some(3.0));
+    assert_eq!(call("money.split", &[90.0, 4.0]), Some(22.5));
+    assert_eq!(call("money.split", &[90.0, 0.0]), None);
+    assert_eq!(call("money.split", &[90.0, 2.5]), None);
+    assert_eq!(call("money.tip", &[80.0, 15.0]), Some(12.0));
+    assert_eq!(call("money.tip", &[80.0, -15.0]), None);
+    assert_eq!(call("money.with_tax", &[200.0, 8.5]), Some(217.0));
+    assert_eq!(call("clamp", &[5.0, 0.0, 1.0]), Some(1.0));
+    assert_eq!(call("clamp", &[-5.0, 0.0, 1.0]), Some(0.0));
+    assert_eq!(call("clamp", &[0.5, 1.0, 0.0]), None);
+    assert_eq!(call("lerp", &[10.0, 20.0, 0.25]), Some(12.5));
+    assert_eq!(call("approx", &[100.0, 100.5, 0.01]), Some(1.0));
+    assert_eq!(call("approx", &[100.0, 102.0, 0.01]), Some(0.0));
+    assert_eq!(call("approx", &[0.0, 0.005, 0.01]), Some(1.0));
+    assert_eq!(call("approx", &[1.0, 1.0, -1.0]), None);
+    assert_eq!(call("equal", &[100.0, 100.5, 0.5]), Some(1.0));
+    assert_eq!(call("equal", &[0.001, 0.002, 1e-4]), Some(0.0));
+    assert_eq!(call("equal", &[1.0, 1.0, -1.0]), None);
+    assert_eq!(call("round", &[1.23456, 2.0]), Some(1.23));
+    assert_eq!(call("round", &[-2.5, 0.0]), Some(-3.0));
+    assert_eq!(call("round", &[1234.5, -2.0]), Some(1200.0));
+    assert_eq!(call("round", &[0.1, 400.0]), Some(0.1));
+    assert_eq!(call("round", &[1234.5, -400.0]), Some(0.0));
+    assert_eq!(call("round", &[1.0, 0.5]), None);
+    assert_eq!(call("trunc", &[-2.7]), Some(-2.0));
+    assert_eq!(call("sign", &[-0.1]), Some(-1.0));
+    assert_eq!(call("sign", &[0.0]), Some(0.0));
+    assert_eq!(call("frac", &[-1.25]), Some(-0.25));
+
+    assert_eq!(call("sinh", &[0.0]), Some(0.0));
+    assert_eq!(call("cosh", &[0.0]), Some(1.0));
+    assert_eq!(call("tanh", &[f64::INFINITY]), Some(1.0));
+    let x = call("asinh", &[call("sinh", &[1.5]).unwrap()]).unwrap();
+    assert!((x - 1.5).abs() < 1e-12);
+    assert_eq!(call("acosh", &[1.0]), Some(0.0));
+    assert_eq!(call("acosh", &[0.5]), None);
+    assert_eq!(call("atanh", &[0.0]), Some(0.0));
+    assert_eq!(call("atanh", &[1.0]), None);
+    assert_eq!(call("atanh", &[-2.0]), None);
+    assert!(call("acosh", &[f64::NAN]).unwrap().is_nan());
+    assert!(builtin("sqrt2").is_none());
+}
+
+#[test]
+fn bit_builtins_should_use_the_word_size() {
+    let call = |name: &str, arguments: &[f64], word_size| {
+        builtin(name).unwrap().call(
+            arguments,
+            AngleMode::Radians,
+            word_size,
+            &math::SYSTEM,
+            Summation::default(),
+        )
+    };
+    assert_eq!(
+        call("rotl", &[0x81 as f64, 1.0], WordSize::U8),
+        Some(0x03 as f64)
+    );
+    assert_eq!(
+        call("rotr", &[0x81 as f64, 1.0], WordSize::U8),
+        Some(0xc0 as f64)
+    );
+    assert_eq!(
+        call("rotl", &[1.0, -1.0], WordSize::U16),
+        Some(0x8000 as f64)
+    );
+    assert_eq!(call("rotl", &[5.0, 32.0], WordSize::U32), Some(5.0));
+    assert_eq!(call("rotl", &[-1.0, 3.0], WordSize::U8), Some(255.0));
+    assert_eq!(call("rotl", &[256.0, 1.0], WordSize::U8), None);
+    assert_eq!(call("rotl", &[1.0, 0.5], WordSize::U8), None);
+
+    assert_eq!(call("popcount", &[255.0], WordSize::U64), Some(8.0));
+    assert_eq!(call("popcount", &[-1.0], WordSize::U16), Some(16.0));
+    assert_eq!(call("clz", &[1.0], WordSize::U8), Some(7.0));
+    assert_eq!(call("clz", &[0.0], WordSize::U32), Some(32.0));
+    assert_eq!(call("clz", &[1.0], WordSize::U64), Some(63.0));
+    assert_eq!(call("ctz", &[8.0], WordSize::U8), Some(3.0));
+    assert_eq!(call("ctz", &[0.0], WordSize::U8), Some(8.0));
+    assert_eq!(call("ctz", &[0.5], WordSize::U8), None);
+
+    assert_eq!(call("signed", &[255.0], WordSize::U8), Some(-1.0));
+    assert_eq!(call("signed", &[128.0], WordSize::U8), Some(-128.0));
+    assert_eq!(call("signed", &[127.0], WordSize::U8), Some(127.0));
+    assert_eq!(call("signed", &[-5.0], WordSize::U8), Some(-5.0));
+    assert_eq!(call("signed", &[u64::MAX as f64], WordSize::U64), None);
+    assert_eq!(
+        call("signed", &[2f64.powi(63)], WordSize::U64),
+        Some(-(2f64.powi(63)))
+    );
+    assert_eq!(call("unsigned", &[-1.0], WordSize::U16), Some(65535.0));
+    assert_eq!(
+        call("unsigned", &[-1.0], WordSize::U64),
+        Some(u64::MAX as f64)
+    );
+    assert_eq!(call("unsigned", &[65536.0], WordSize::U16), None);
+
+    assert_eq!(call("parity", &[7.0], WordSize::U8), Some(1.0));
+    assert_eq!(call("parity", &[-1.0], WordSize::U8), Some(0.0));
+    assert_eq!(call("parity", &[0.0], WordSize::U8), Some(0.0));
+}
+
+#[test]
+fn float_builtins_should_measure_rounding_differences() {
+    let call = |name: &str, arguments: &[f64]| {
+        builtin(name).unwrap().call(
+            arguments,
+            AngleMode::Radians,
+            WordSize::U64,
+            &math::SYSTEM,
+            Summation::default(),
+        )
+    };
+    assert_eq!(call("ulp", &[1.0]), Some(f64::EPSILON));
+    assert_eq!(call("ulp", &[-1.0]), Some(f64::EPSILON));
+    assert_eq!(call("ulp", &[0.0]), Some(5e-324));
+    assert_eq!(call("ulp", &[f64::MAX]), Some(2f64.powi(971)));
+    assert_eq!(call("ulp", &[f64::INFINITY]), Some(f64::INFINITY));
+
+    assert_eq!(call("c_to_f", &[100.0]), Some(212.0));
+    assert_eq!(call("c_to_f", &[-40.0]), Some(-40.0));
+    assert_eq!(call("c_to_f", &[-300.0]), None);
+    assert_eq!(call("f_to_c", &[212.0]), Some(100.0));
+    assert_eq!(call("f_to_c", &[-500.0]), None);
+    assert_eq!(call("k_to_c", &[0.0]), Some(-273.15));
+    assert_eq!(call("k_to_c", &[-1.0]), None);
+    assert_eq!(call("diag", &[3.0, 4.0]), Some(5.0));
+    assert_eq!(
+        call("ppi", &[1920.0, 1080.0, 24.0]).map(math::round),
+        Some(92.0)
+    );
+    assert_eq!(call("ppi", &[1920.0, 1080.0, 0.0]), None);
+    assert_eq!(call("b64len", &[0.0]), Some(0.0));
+    assert_eq!(call("b64len", &[4.0]), Some(8.0));
+    assert_eq!(call("b64len", &[1.5]), None);
+    assert_eq!(call("b64len_nopad", &[4.0]), Some(6.0));
+    assert_eq!(call("unb64len", &[8.0]), Some(6.0));
+    assert_eq!(call("unb64len", &[6.0]), None);
+    assert_eq!(call("unb64len_nopad", &[6.0]), Some(4.0));
+    assert_eq!(call("unb64len_nopad", &[5.0]), None);
+    assert_eq!(call("transfer_time", &[4e12, 1.25e8]), Some(32000.0));
+    assert_eq!(call("transfer_time", &[1.0, 0.0]), None);
+    assert_eq!(call("float_distance", &[0.1 + 0.2, 0.3]), Some(1.0));
+    assert_eq!(call("float_distance", &[1.0, 1.0]), Some(0.0));
+    assert_eq!(call("float_distance", &[-0.0, 0.0]), Some(0.0));
+    assert_eq!(call("float_distance", &[-5e-324, 5e-324]), Some(2.0));
+    assert_eq!(call("float_distance", &[1.0, 2.0]), Some(2f64.powi(52)));
+    assert!(call("float_distance", &[1.0, f64::NAN]).unwrap().is_nan());
+}
+
+#[test]
+fn crc32_should_checksum_the_bytes_of_its_arguments() {
+    let crc32 = |arguments: &[f64]| {
+        builtin("crc32").unwrap().call(
+            arguments,
+            AngleMode::Radians,
+            WordSize::U64,
+            &math::SYSTEM,
+            Summation::default(),
+        )
+    };
+    let digits: Vec<f64> = (b'1'..=b'9').map(f64::from).collect();
+    assert_eq!(crc32(&digits), Some(0xcbf4_3926u32 as f64));
+    assert_eq!(
+        crc32(&[0x3132_3334 as f64, 0x35 as f64, 0x3637_3839 as f64]),
+        Some(0xcbf4_3926u32 as f64)
+    );
+    assert_eq!(crc32(&[0.0]), Some(0xd202_ef8du32 as f64));
+    assert_eq!(crc32(&[-1.0]), None);
+    assert_eq!(crc32(&[1.5]), None);
+}
+
+#[test]
+fn trigonometric_builtins_should_use_the_angle_mode() {
+    let call = |name: &str, arguments: &[f64], angle_mode| {
+        builtin(name)
+            .unwrap()
+            .call(
+                arguments,
+                angle_mode,
+                WordSize::U64,
+                &math::SYSTEM,
+                Summation::default(),
+            )
+            .unwrap()
+    };
+    assert_eq!(call("sin", &[90.0], AngleMode::Degrees), 1.0);
+    assert_eq!(call("cos", &[0.0], AngleMode::Radians), 1.0);
+    assert!((call("tan", &[45.0], AngleMode::Degrees) - 1.0).abs() < 1e-15);
+    assert_eq!(call("acos", &[-1.0], AngleMode::Degrees), 180.0);
+    assert_eq!(
+        builtin("asin").unwrap().call(
+            &[1.5],
+            AngleMode::Radians,
+            WordSize::U64,
+            &math::SYSTEM,
+            Summation::default()
+        ),
+        None
+    );
+
+    assert_eq!(call("atan2", &[1.0, 1.0], AngleMode::Degrees), 45.0);
+    assert_eq!(call("atan2", &[-1.0, -1.0], AngleMode::Degrees), -135.0);
+    assert_eq!(call("atan2", &[1.0, -1.0], AngleMode::Degrees), 135.0);
+    assert_eq!(call("atan", &[-1.0 / -1.0], AngleMode::Degrees), 45.0);
+    assert_eq!(
+        call("atan2", &[1.0, 0.0], AngleMode::Radians),
+        core::f64::consts::FRAC_PI_2
+    );
+}
+
+#[test]
+fn gamma_and_error_functions_should_be_available() {
+    let call = |name: &str, x: f64| {
+        builtin(name).unwrap().call(
+            &[x],
+            AngleMode::Radians,
+            WordSize::U64,
+            &math::SYSTEM,
+            Summation::default(),
+        )
+    };
+    assert_eq!(call("gamma", 5.0), Some(24.0));
+    assert!((call("gamma", 0.5).unwrap() - core::f64::consts::PI.sqrt()).abs() < 1e-15);
+    assert!((call("gamma", -0.5).unwrap() + 2.0 * core::f64::consts::PI.sqrt()).abs() < 1e-14);
+    assert_eq!(call("gamma", 0.0), None);
+    assert_eq!(call("gamma", -3.0), None);
+    assert!((call("lgamma", 101.0).unwrap() - 363.73937555556347).abs() < 1e-10);
+    assert_eq!(call("lgamma", -1.0), None);
+
+    assert_eq!(call("erf", 0.0), Some(0.0));
+    assert!((call("erf", 1.0).unwrap() - 0.8427007929497149).abs() < 1e-15);
+    assert_eq!(call("erfc", 0.0), Some(1.0));
+    // `1 - erf(10)` já seria 0
+    assert!((call("erfc", 10.0).unwrap() - 2.088487583762545e-45).abs() < 1e-58);
+}
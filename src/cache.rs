@@ -0,0 +1,164 @@
+//! Cache das linhas da calculadora interativa (`cache stats`, `cache clear`): a leitura de cada
+//! linha é guardada para que ela não precise ser lida de novo, e o resultado também, se a linha
+//! for uma expressão que não usa nenhum nome (`Expression::is_pure`).
+//!
+//! A leitura de uma linha depende dos nomes definidos (um nome desconhecido é um erro, e as
+//! funções do usuário são resolvidas na leitura) e de algumas configurações, então cada linha é
+//! guardada junto de uma impressão digital desse estado, e só é usada de novo se ela for a mesma.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use asdf_calc::{EvalContext, Statement, BASES};
+
+/// Quantas linhas ficam guardadas. Quando o cache enche ele é esvaziado, que é mais simples que
+/// descobrir qual linha descartar e raramente acontece numa sessão interativa.
+const CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone)]
+struct Entry {
+    statement: Arc<Statement>,
+    result: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Cache {
+    /// Pelo texto já preparado da linha e pela impressão digital do contexto.
+    entries: HashMap<(String, u64), Entry>,
+    pub parse_hits: usize,
+    pub parse_misses: usize,
+    pub result_hits: usize,
+}
+
+/// Linha procurada no cache, com o que foi encontrado dela.
+pub struct Lookup {
+    key: (String, u64),
+    pub statement: Option<Arc<Statement>>,
+    pub result: Option<f64>,
+}
+
+impl Cache {
+    pub fn lookup(&mut self, text: &str, context: &EvalContext) -> Lookup {
+        let key = (text.to_string(), fingerprint(context));
+        let entry = self.entries.get(&key);
+        match entry {
+            Some(entry) => {
+                self.parse_hits += 1;
+                if entry.result.is_some() {
+                    self.result_hits += 1;
+                }
+            }
+            None => self.parse_misses += 1,
+        }
+        Lookup {
+            statement: entry.map(|entry| entry.statement.clone()),
+            result: entry.and_then(|entry| entry.result),
+            key,
+        }
+    }
+
+    /// Guarda a leitura da linha procurada com `lookup`, e o resultado dela, que só é guardado
+    /// se ela for uma expressão pura.
+    pub fn insert(&mut self, lookup: Lookup, statement: Arc<Statement>, result: f64) {
+        let result = match statement.as_ref() {
+            Statement::Expression(expression) if expression.is_pure() => Some(result),
+            _ => None,
+        };
+        if self.entries.len() >= CAPACITY && !self.entries.contains_key(&lookup.key) {
+            self.entries.clear();
+        }
+        self.entries.insert(lookup.key, Entry { statement, result });
+    }
+
+    pub fn clear(&mut self) {
+        *self = Cache::default();
+    }
+
+    pub fn stats(&self) -> String {
+        let results = self
+            .entries
+            .values()
+            .filter(|entry| entry.result.is_some())
+            .count();
+        format!(
+            "cache: {} linhas guardadas, {} com o resultado; {} leituras e {} cálculos evitados, {} linhas lidas",
+            self.entries.len(),
+            results,
+            self.parse_hits,
+            self.result_hits,
+            self.parse_misses
+        )
+    }
+}
+
+/// Resumo de tudo no contexto que muda a leitura de uma linha, ou o resultado de uma expressão
+/// pura: os nomes definidos, as funções, os valores das constantes e as configurações.
+fn fingerprint(context: &EvalContext) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let mut variables: Vec<&str> = context.variables().map(|(name, _)| name).collect();
+    variables.sort_unstable();
+    variables.hash(&mut hasher);
+    let mut constants: Vec<(&str, u64)> = context
+        .constants()
+        .map(|(name, value)| (name, value.to_bits()))
+        .collect();
+    constants.sort_unstable();
+    constants.hash(&mut hasher);
+    // redefinir uma função cria outra, num endereço diferente enquanto a antiga estiver guardada
+    let mut functions: Vec<(&str, usize)> = context
+        .functions()
+        .map(|(name, function)| (name, function as *const _ as usize))
+        .collect();
+    functions.sort_unstable();
+    functions.hash(&mut hasher);
+    // as unidades ficam guardadas nos nós, como as funções
+    let units: Vec<(&str, u64, [i8; BASES])> = context
+        .units()
+        .map(|(name, unit)| (name, unit.factor.to_bits(), unit.dimension.exponents()))
+        .collect();
+    units.hash(&mut hasher);
+    format!("{:?}", context.angle_mode()).hash(&mut hasher);
+    context.word_size().bits().hash(&mut hasher);
+    context.epsilon().to_bits().hash(&mut hasher);
+    context.approx_tolerance().to_bits().hash(&mut hasher);
+    context.si_suffixes().hash(&mut hasher);
+    context.strict().hash(&mut hasher);
+    context.deterministic().hash(&mut hasher);
+    context.summation().name().hash(&mut hasher);
+    context.limits().max_nesting.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn cache_should_only_reuse_lines_read_in_the_same_context() {
+    let mut cache = Cache::default();
+    let mut context = EvalContext::new();
+    let lookup = cache.lookup("2 + 2", &context);
+    assert!(lookup.statement.is_none());
+    let statement = Arc::new(Statement::new("2 + 2", &context).unwrap());
+    cache.insert(lookup, statement, 4.0);
+
+    let lookup = cache.lookup("2 + 2", &context);
+    assert!(lookup.statement.is_some());
+    assert_eq!(lookup.result, Some(4.0));
+
+    context.set_variable("x", 1.0);
+    assert!(cache.lookup("2 + 2", &context).statement.is_none());
+    assert_eq!(
+        (cache.parse_hits, cache.parse_misses, cache.result_hits),
+        (1, 2, 1)
+    );
+
+    let lookup = cache.lookup("x + 1", &context);
+    let statement = Arc::new(Statement::new("x + 1", &context).unwrap());
+    cache.insert(lookup, statement, 2.0);
+    let lookup = cache.lookup("x + 1", &context);
+    assert!(lookup.statement.is_some());
+    assert_eq!(lookup.result, None);
+
+    cache.clear();
+    assert!(cache.stats().starts_with("cache: 0 linhas guardadas"));
+}
@@ -0,0 +1,189 @@
+//! Dias úteis para `workdays(início, fim)` e `add_workdays(data, dias)`: os dias que não caem
+//! no fim de semana (`weekend sat sun`, o padrão) nem são um dos feriados lidos com
+//! `holidays <arquivo>`, que tem uma data `aaaa-mm-dd` por linha.
+
+use std::{collections::BTreeSet, fs, path::Path};
+
+use crate::date::{Date, WEEKDAYS};
+
+/// Mais que isso `add_workdays` não procura por um dia útil, para que um calendário sem
+/// nenhum (`weekend` com os sete dias) não trave a sessão. São uns dez mil anos.
+const MAX_DAYS: i64 = 3_660_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calendar {
+    /// Se cada dia da semana, de segunda a domingo, é fim de semana.
+    pub weekend: [bool; 7],
+    pub holidays: BTreeSet<Date>,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Calendar {
+            weekend: [false, false, false, false, false, true, true],
+            holidays: BTreeSet::new(),
+        }
+    }
+}
+
+impl Calendar {
+    pub fn is_workday(&self, date: Date) -> bool {
+        !self.weekend[date.weekday()] && !self.holidays.contains(&date)
+    }
+
+    /// Os dias úteis de `start` até `end`, contando os dois, como o `NETWORKDAYS` das
+    /// planilhas. É negativo se `end` vier antes de `start`.
+    pub fn workdays(&self, start: Date, end: Date) -> i64 {
+        let (first, last, sign) = if start <= end {
+            (start, end, 1)
+        } else {
+            (end, start, -1)
+        };
+        // as semanas inteiras têm sempre os mesmos dias úteis, tirando os feriados
+        let days = last.0 - first.0 + 1;
+        let per_week = self.weekend.iter().filter(|weekend| !**weekend).count() as i64;
+        let mut count = days / 7 * per_week;
+        for day in first.0 + days / 7 * 7..=last.0 {
+            count += i64::from(!self.weekend[Date(day).weekday()]);
+        }
+        let holidays = self
+            .holidays
+            .range(first..=last)
+            .filter(|date| !self.weekend[date.weekday()])
+            .count() as i64;
+        sign * (count - holidays)
+    }
+
+    /// O dia útil `days` dias úteis depois de `start` (antes, se negativo), sem contar o próprio
+    /// `start`, como o `WORKDAY` das planilhas.
+    pub fn add_workdays(&self, start: Date, days: i64) -> Result<Date, String> {
+        if days.abs() > MAX_DAYS {
+            return Err(format!("no máximo {} dias úteis de cada vez", MAX_DAYS));
+        }
+        let step = days.signum();
+        let (mut date, mut remaining) = (start, days.abs());
+        let mut searched = 0;
+        while remaining > 0 {
+            date = Date(date.0 + step);
+            if self.is_workday(date) {
+                remaining -= 1;
+            }
+            searched += 1;
+            if searched > MAX_DAYS {
+                return Err("nenhum dia útil encontrado, confira o 'weekend'".to_string());
+            }
+        }
+        Ok(date)
+    }
+
+    /// Aplica o que vem depois de `weekend`: os dias separados por espaços ou vírgulas
+    /// (`sat sun`), ou `none` para nenhum.
+    pub fn set_weekend(&mut self, text: &str) -> Result<(), String> {
+        let mut weekend = [false; 7];
+        if text.trim() != "none" {
+            for name in text.split([' ', ',']).filter(|name| !name.is_empty()) {
+                let day = WEEKDAYS
+                    .iter()
+                    .position(|weekday| *weekday == name)
+                    .ok_or_else(|| {
+                        format!("dia desconhecido: {} (use {})", name, WEEKDAYS.join(", "))
+                    })?;
+                weekend[day] = true;
+            }
+        }
+        self.weekend = weekend;
+        Ok(())
+    }
+
+    pub fn describe_weekend(&self) -> String {
+        let days: Vec<&str> = WEEKDAYS
+            .iter()
+            .zip(self.weekend)
+            .filter(|(_, weekend)| *weekend)
+            .map(|(name, _)| *name)
+            .collect();
+        if days.is_empty() {
+            "none".to_string()
+        } else {
+            days.join(" ")
+        }
+    }
+}
+
+/// Os feriados de um arquivo com uma data por linha, ignorando as vazias e as começando com
+/// `#`.
+pub fn read_holidays(path: &Path) -> Result<BTreeSet<Date>, String> {
+    let text =
+        fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    parse_holidays(&text).map_err(|message| format!("{}:{}", path.display(), message))
+}
+
+fn parse_holidays(text: &str) -> Result<BTreeSet<Date>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            Date::parse(line).map_err(|message| format!("{}: {}", index + 1, message))
+        })
+        .collect()
+}
+
+#[test]
+fn workdays_should_skip_weekends_and_holidays() {
+    let date = |text: &str| Date::parse(text).unwrap();
+    let mut calendar = Calendar::default();
+    assert_eq!(
+        calendar.workdays(date("2024-01-01"), date("2024-03-01")),
+        45
+    );
+    assert_eq!(
+        calendar.workdays(date("2024-03-01"), date("2024-01-01")),
+        -45
+    );
+    assert_eq!(calendar.workdays(date("2024-01-06"), date("2024-01-07")), 0);
+    assert_eq!(calendar.workdays(date("2024-01-05"), date("2024-01-05")), 1);
+
+    calendar.holidays = parse_holidays("# feriados\n2024-01-01\n\n2024-01-06\n").unwrap();
+    assert_eq!(
+        calendar.workdays(date("2024-01-01"), date("2024-03-01")),
+        44
+    );
+    calendar.set_weekend("fri, sat").unwrap();
+    assert_eq!(calendar.describe_weekend(), "fri sat");
+    assert_eq!(calendar.workdays(date("2024-01-01"), date("2024-01-07")), 4);
+    assert!(calendar.set_weekend("sábado").is_err());
+    assert_eq!(
+        parse_holidays("2024-01-01\n2024-02-30"),
+        Err("2: data inválida '2024-02-30' (use aaaa-mm-dd)".to_string())
+    );
+}
+
+#[test]
+fn add_workdays_should_land_on_a_workday() {
+    let date = |text: &str| Date::parse(text).unwrap();
+    let mut calendar = Calendar::default();
+    assert_eq!(
+        calendar.add_workdays(date("2024-01-01"), 10),
+        Ok(date("2024-01-15"))
+    );
+    assert_eq!(
+        calendar.add_workdays(date("2024-01-06"), 1),
+        Ok(date("2024-01-08"))
+    );
+    assert_eq!(
+        calendar.add_workdays(date("2024-01-08"), -1),
+        Ok(date("2024-01-05"))
+    );
+    assert_eq!(
+        calendar.add_workdays(date("2024-01-06"), 0),
+        Ok(date("2024-01-06"))
+    );
+    calendar.holidays.insert(date("2024-01-08"));
+    assert_eq!(
+        calendar.add_workdays(date("2024-01-05"), 1),
+        Ok(date("2024-01-09"))
+    );
+    calendar.set_weekend("mon tue wed thu fri sat sun").unwrap();
+    assert!(calendar.add_workdays(date("2024-01-01"), 1).is_err());
+}
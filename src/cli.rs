@@ -0,0 +1,599 @@
+//! Leitura dos argumentos de linha de comando.
+
+use std::{path::PathBuf, time::Duration};
+
+use crate::diagnostic::ErrorFormat;
+
+pub const USAGE: &str = "\
+uso: asdf-calc [-D nome=valor]... [--env] [--no-si] [--strict] [--no-init]
+                 [--timeout <segundos>] [--error-format human|json] [--deterministic] [comando]
+
+opções:
+    -D nome=valor            define uma variável antes de qualquer linha ser calculada (o valor
+                             pode ser uma expressão usando as definições anteriores)
+    --env                    troca ${NOME} pelo valor numérico da variável de ambiente NOME
+    --no-si                  não aceita os sufixos do SI depois dos números (5k, 3.3M, 10u, 2n)
+    --strict                 só aceita a sintaxe sem atalhos: sem os sufixos do SI e sem
+                             multiplicação implícita (5 * km, e não 5 km)
+    --no-init                não executa o ~/.config/asdf-calc/init.calc antes da calculadora
+                             interativa e do daemon
+    --timeout <segundos>     interrompe os cálculos que demorarem mais que isso (na calculadora
+                             interativa, o Ctrl+C também interrompe o cálculo em andamento)
+    --error-format human|json
+                             como --one-shot, --raw e --check escrevem os erros: o texto de
+                             sempre, ou um objeto JSON por linha com o tipo, a mensagem, o
+                             trecho em bytes e uma sugestão de correção
+    --deterministic          calcula sin, ^, gamma... com implementações próprias, que dão os
+                             mesmos resultados, bit a bit, em qualquer sistema
+
+comandos:
+    (nenhum)                 abre a calculadora interativa
+    --one-shot [--copy]      lê uma linha do stdin, escreve só o resultado e sai (--copy
+                             também coloca o resultado na área de transferência)
+    --raw [-n] [expressão]   escreve só o valor do resultado, sem formatação, para uso em
+                             scripts (VAR=$(asdf-calc --raw '3*7')); sem a expressão ela é
+                             lida do stdin, e -n não escreve a quebra de linha no fim
+    --check [expressão]      confere a expressão (ou uma linha do stdin) sem calcular,
+                             escrevendo {\"valid\": ...} e os erros em JSON
+    --table [-n] [arquivo...] calcula as linhas dos arquivos (ou do stdin) e escreve cada uma ao
+                             lado do resultado, em colunas alinhadas (-n numera as linhas)
+    --compat bc [-l] [arquivo...]
+                             aceita um subconjunto da síntaxe do bc (scale, ^, define), lendo
+                             os arquivos e depois o stdin (-l começa com scale=20)
+    --bench <n> <expressão>  calcula a expressão n vezes, mostrando os tempos de cada etapa
+    --rpc                    fala JSON-RPC pelo stdin/stdout, uma mensagem por linha
+    daemon [--socket <path>] mantém uma sessão aberta num socket Unix
+    send [--socket <path>] <expressão>
+                             calcula a expressão na sessão do daemon
+    annotate <arquivo>       escreve '# = resultado' ao lado de cada linha que for uma expressão
+    test <arquivo>...        executa os scripts, conferindo as linhas 'assert a == b' e
+                             'assert_approx a, b, tolerância'
+    watch <arquivo>          calcula o arquivo de novo sempre que ele muda
+    csv <arquivo> --formula <expressão> [--column <nome>] [--jobs <n>]
+                             calcula a fórmula para cada linha do CSV, com as colunas como
+                             variáveis, e escreve o CSV com o resultado numa coluna nova
+                             (chamada 'result' se --column não for usado), dividindo as linhas
+                             entre n threads
+    replay [--interactive] <arquivo>
+                             mostra as linhas e os resultados de uma sessão salva com 'export
+                             json' (--interactive espera um Enter antes de cada resultado)
+    serve [--port <porta>]   expõe a calculadora como uma API JSON sobre HTTP (porta 8080)
+    help                     mostra esta mensagem";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Repl,
+    Serve {
+        port: u16,
+    },
+    Rpc,
+    OneShot {
+        copy: bool,
+    },
+    Check {
+        /// Vazia para ler a expressão do stdin.
+        expression: String,
+    },
+    Raw {
+        /// Vazia para ler a expressão do stdin.
+        expression: String,
+        newline: bool,
+    },
+    Table {
+        /// Vazio para ler as linhas do stdin.
+        files: Vec<PathBuf>,
+        numbers: bool,
+    },
+    Bc {
+        files: Vec<PathBuf>,
+        mathlib: bool,
+    },
+    Bench {
+        iterations: u32,
+        expression: String,
+    },
+    Daemon {
+        socket: Option<PathBuf>,
+    },
+    Send {
+        socket: Option<PathBuf>,
+        expression: String,
+    },
+    Watch {
+        file: PathBuf,
+    },
+    Annotate {
+        file: PathBuf,
+    },
+    Test {
+        files: Vec<PathBuf>,
+    },
+    Csv {
+        file: PathBuf,
+        formula: String,
+        column: String,
+        jobs: usize,
+    },
+    Replay {
+        file: PathBuf,
+        interactive: bool,
+    },
+    Help,
+}
+
+/// Opções que vêm antes do comando e valem para qualquer um deles.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GlobalOptions {
+    /// Nome e valor (ainda não calculado) de cada `-D nome=valor`.
+    pub definitions: Vec<(String, String)>,
+    /// `--env`
+    pub env: bool,
+    /// `--no-si`
+    pub no_si: bool,
+    /// `--strict`
+    pub strict: bool,
+    /// `--no-init`
+    pub no_init: bool,
+    pub timeout: Option<Duration>,
+    /// `--error-format`
+    pub error_format: ErrorFormat,
+    /// `--deterministic`
+    pub deterministic: bool,
+}
+
+/// Separa as opções globais (`-D nome=valor`, ou `-Dnome=valor`, `--env`, `--no-si`,
+/// `--strict`, `--no-init`, `--timeout`, `--error-format` e `--deterministic`) que vêm antes do
+/// comando, retornando elas e o resto dos argumentos.
+pub fn split_global_options<I>(args: I) -> Result<(GlobalOptions, Vec<String>), String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter().peekable();
+    let mut options = GlobalOptions::default();
+    while let Some(arg) = args.next_if(|arg| {
+        arg.starts_with("-D")
+            || arg == "--env"
+            || arg == "--no-si"
+            || arg == "--strict"
+            || arg == "--no-init"
+            || arg == "--timeout"
+            || arg == "--error-format"
+            || arg == "--deterministic"
+    }) {
+        if arg == "--env" {
+            options.env = true;
+            continue;
+        }
+        if arg == "--no-si" {
+            options.no_si = true;
+            continue;
+        }
+        if arg == "--strict" {
+            options.strict = true;
+            continue;
+        }
+        if arg == "--no-init" {
+            options.no_init = true;
+            continue;
+        }
+        if arg == "--deterministic" {
+            options.deterministic = true;
+            continue;
+        }
+        if arg == "--timeout" {
+            let value = value_of("--timeout", args.next())?;
+            // `try_from_secs_f64` também recusa os tempos grandes demais para um `Duration`
+            let timeout = value
+                .parse::<f64>()
+                .ok()
+                .filter(|seconds| *seconds > 0.0)
+                .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                .ok_or_else(|| format!("tempo limite inválido '{}'", value))?;
+            options.timeout = Some(timeout);
+            continue;
+        }
+        if arg == "--error-format" {
+            options.error_format = ErrorFormat::parse(&value_of("--error-format", args.next())?)?;
+            continue;
+        }
+
+        let definition = match arg.strip_prefix("-D") {
+            Some("") => value_of("-D", args.next())?,
+            Some(definition) => definition.to_string(),
+            None => unreachable!(),
+        };
+        let Some((name, value)) = definition.split_once('=') else {
+            return Err(format!(
+                "definição inválida '{}', use -D nome=valor",
+                definition
+            ));
+        };
+
+        let name = name.trim();
+        let is_name = name.starts_with(|char: char| char.is_alphabetic() || char == '_')
+            && name
+                .chars()
+                .all(|char| char.is_alphanumeric() || char == '_');
+        if !is_name {
+            return Err(format!("nome inválido '{}' em -D", name));
+        }
+        options
+            .definitions
+            .push((name.to_string(), value.trim().to_string()));
+    }
+
+    Ok((options, args.collect()))
+}
+
+fn value_of(flag: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("{} precisa de um valor", flag))
+}
+
+pub fn parse_args<I>(args: I) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter();
+    match args.next().as_deref() {
+        None => Ok(Command::Repl),
+        Some("help") | Some("--help") | Some("-h") => Ok(Command::Help),
+        Some("--rpc") => Ok(Command::Rpc),
+        Some("--one-shot") => {
+            let mut copy = false;
+            for arg in args {
+                match arg.as_str() {
+                    "--copy" => copy = true,
+                    arg => return Err(format!("argumento desconhecido '{}'", arg)),
+                }
+            }
+            Ok(Command::OneShot { copy })
+        }
+        Some("--raw") => {
+            let mut args = args.peekable();
+            let newline = args
+                .next_if(|arg| arg == "-n" || arg == "--no-newline")
+                .is_none();
+            Ok(Command::Raw {
+                expression: args.collect::<Vec<_>>().join(" "),
+                newline,
+            })
+        }
+        Some("--check") => Ok(Command::Check {
+            expression: args.collect::<Vec<_>>().join(" "),
+        }),
+        Some("--table") => {
+            let mut files = Vec::new();
+            let mut numbers = false;
+            for arg in args {
+                match arg.as_str() {
+                    "-n" | "--line-numbers" => numbers = true,
+                    _ => files.push(PathBuf::from(arg)),
+                }
+            }
+            Ok(Command::Table { files, numbers })
+        }
+        Some("--compat") => {
+            let dialect = value_of("--compat", args.next())?;
+            if dialect != "bc" {
+                return Err(format!(
+                    "modo de compatibilidade desconhecido '{}'",
+                    dialect
+                ));
+            }
+
+            let mut files = Vec::new();
+            let mut mathlib = false;
+            for arg in args {
+                match arg.as_str() {
+                    "-l" | "--mathlib" => mathlib = true,
+                    // o bc só usa -q para não mostrar o banner, que aqui nunca é mostrado
+                    "-q" | "--quiet" => {}
+                    _ => files.push(PathBuf::from(arg)),
+                }
+            }
+            Ok(Command::Bc { files, mathlib })
+        }
+        Some("--bench") => {
+            let value = value_of("--bench", args.next())?;
+            let iterations = value
+                .parse()
+                .ok()
+                .filter(|iterations| *iterations > 0)
+                .ok_or_else(|| format!("quantidade de repetições inválida '{}'", value))?;
+            let expression = args.collect::<Vec<_>>().join(" ");
+            if expression.is_empty() {
+                return Err("--bench precisa de uma expressão".to_string());
+            }
+            Ok(Command::Bench {
+                iterations,
+                expression,
+            })
+        }
+        Some("--copy") => Err("--copy só pode ser usado junto com --one-shot".to_string()),
+        Some(command @ ("watch" | "annotate")) => {
+            let file = PathBuf::from(value_of(command, args.next())?);
+            match args.next() {
+                Some(arg) => Err(format!("argumento desconhecido '{}'", arg)),
+                None if command == "watch" => Ok(Command::Watch { file }),
+                None => Ok(Command::Annotate { file }),
+            }
+        }
+        Some("test") => {
+            let files: Vec<PathBuf> = args.map(PathBuf::from).collect();
+            if files.is_empty() {
+                return Err("test precisa de pelo menos um arquivo".to_string());
+            }
+            Ok(Command::Test { files })
+        }
+        Some("csv") => {
+            let mut file = None;
+            let mut formula = None;
+            let mut column = "result".to_string();
+            let mut jobs = 1;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--formula" | "-f" => formula = Some(value_of(&arg, args.next())?),
+                    "--column" | "-c" => column = value_of(&arg, args.next())?,
+                    "--jobs" | "-j" => {
+                        let value = value_of(&arg, args.next())?;
+                        jobs =
+                            value.parse().ok().filter(|jobs| *jobs > 0).ok_or_else(|| {
+                                format!("quantidade de jobs inválida '{}'", value)
+                            })?;
+                    }
+                    _ if file.is_none() => file = Some(PathBuf::from(arg)),
+                    arg => return Err(format!("argumento desconhecido '{}'", arg)),
+                }
+            }
+            Ok(Command::Csv {
+                file: file.ok_or_else(|| "csv precisa de um arquivo".to_string())?,
+                formula: formula.ok_or_else(|| "csv precisa de uma --formula".to_string())?,
+                column,
+                jobs,
+            })
+        }
+        Some("replay") => {
+            let mut file = None;
+            let mut interactive = false;
+            for arg in args {
+                match arg.as_str() {
+                    "--interactive" | "-i" => interactive = true,
+                    _ if file.is_none() => file = Some(PathBuf::from(arg)),
+                    arg => return Err(format!("argumento desconhecido '{}'", arg)),
+                }
+            }
+            let file = file.ok_or_else(|| "replay precisa de um arquivo".to_string())?;
+            Ok(Command::Replay { file, interactive })
+        }
+        Some("serve") => {
+            let mut port = 8080;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--port" | "-p" => {
+                        let value = value_of(&arg, args.next())?;
+                        port = value
+                            .parse()
+                            .map_err(|_| format!("porta inválida '{}'", value))?;
+                    }
+                    arg => return Err(format!("argumento desconhecido '{}'", arg)),
+                }
+            }
+            Ok(Command::Serve { port })
+        }
+        Some("daemon") => {
+            let mut socket = None;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--socket" => socket = Some(PathBuf::from(value_of(&arg, args.next())?)),
+                    arg => return Err(format!("argumento desconhecido '{}'", arg)),
+                }
+            }
+            Ok(Command::Daemon { socket })
+        }
+        Some("send") => {
+            let mut socket = None;
+            let mut words = Vec::new();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--socket" => socket = Some(PathBuf::from(value_of(&arg, args.next())?)),
+                    _ => words.push(arg),
+                }
+            }
+            if words.is_empty() {
+                return Err("send precisa de uma expressão".to_string());
+            }
+            Ok(Command::Send {
+                socket,
+                expression: words.join(" "),
+            })
+        }
+        Some(arg) => Err(format!("comando desconhecido '{}'", arg)),
+    }
+}
+
+#[cfg(test)]
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn split_global_options_should_read_leading_options() {
+    assert_eq!(
+        split_global_options(args(&[
+            "-D",
+            "x=3",
+            "--env",
+            "--timeout",
+            "0.5",
+            "--no-si",
+            "--strict",
+            "--no-init",
+            "--error-format",
+            "json",
+            "--deterministic",
+            "-Drate = 0.05",
+            "--one-shot",
+            "-D"
+        ])),
+        Ok((
+            GlobalOptions {
+                definitions: vec![
+                    ("x".to_string(), "3".to_string()),
+                    ("rate".to_string(), "0.05".to_string())
+                ],
+                env: true,
+                no_si: true,
+                strict: true,
+                no_init: true,
+                timeout: Some(Duration::from_millis(500)),
+                error_format: ErrorFormat::Json,
+                deterministic: true,
+            },
+            args(&["--one-shot", "-D"])
+        ))
+    );
+    assert!(split_global_options(args(&["-D", "x"])).is_err());
+    assert!(split_global_options(args(&["-D", "2x=1"])).is_err());
+    assert!(split_global_options(args(&["-D"])).is_err());
+    assert!(split_global_options(args(&["--timeout", "-1"])).is_err());
+    assert_eq!(
+        split_global_options(args(&["--timeout", "1e300"])),
+        Err("tempo limite inválido '1e300'".to_string())
+    );
+    assert!(split_global_options(args(&["--timeout", "inf"])).is_err());
+    assert!(split_global_options(args(&["--error-format", "xml"])).is_err());
+}
+
+#[test]
+fn parse_args_should_read_commands() {
+    assert_eq!(parse_args(args(&[])), Ok(Command::Repl));
+    assert_eq!(parse_args(args(&["--rpc"])), Ok(Command::Rpc));
+    assert_eq!(
+        parse_args(args(&["serve"])),
+        Ok(Command::Serve { port: 8080 })
+    );
+    assert_eq!(
+        parse_args(args(&["serve", "--port", "9000"])),
+        Ok(Command::Serve { port: 9000 })
+    );
+    assert!(parse_args(args(&["serve", "--port", "abc"])).is_err());
+    assert_eq!(
+        parse_args(args(&["send", "--socket", "/tmp/s", "x", "*", "2"])),
+        Ok(Command::Send {
+            socket: Some(PathBuf::from("/tmp/s")),
+            expression: "x * 2".to_string()
+        })
+    );
+    assert!(parse_args(args(&["send"])).is_err());
+    assert_eq!(
+        parse_args(args(&["--one-shot"])),
+        Ok(Command::OneShot { copy: false })
+    );
+    assert_eq!(
+        parse_args(args(&["--one-shot", "--copy"])),
+        Ok(Command::OneShot { copy: true })
+    );
+    assert!(parse_args(args(&["--copy"])).is_err());
+    assert_eq!(
+        parse_args(args(&["--check", "2", "*", "x"])),
+        Ok(Command::Check {
+            expression: "2 * x".to_string()
+        })
+    );
+    assert_eq!(
+        parse_args(args(&["--raw", "3*7"])),
+        Ok(Command::Raw {
+            expression: "3*7".to_string(),
+            newline: true
+        })
+    );
+    assert_eq!(
+        parse_args(args(&["--raw", "-n", "-5", "+", "1"])),
+        Ok(Command::Raw {
+            expression: "-5 + 1".to_string(),
+            newline: false
+        })
+    );
+    assert_eq!(
+        parse_args(args(&["--compat", "bc", "-l", "script.bc"])),
+        Ok(Command::Bc {
+            files: vec![PathBuf::from("script.bc")],
+            mathlib: true
+        })
+    );
+    assert!(parse_args(args(&["--compat", "dc"])).is_err());
+    assert_eq!(
+        parse_args(args(&["--table", "-n", "a.calc", "b.calc"])),
+        Ok(Command::Table {
+            files: vec![PathBuf::from("a.calc"), PathBuf::from("b.calc")],
+            numbers: true
+        })
+    );
+    assert_eq!(
+        parse_args(args(&["--table"])),
+        Ok(Command::Table {
+            files: Vec::new(),
+            numbers: false
+        })
+    );
+    assert_eq!(
+        parse_args(args(&["--bench", "100", "2", "^", "10"])),
+        Ok(Command::Bench {
+            iterations: 100,
+            expression: "2 ^ 10".to_string()
+        })
+    );
+    assert!(parse_args(args(&["--bench", "0", "1"])).is_err());
+    assert!(parse_args(args(&["--bench", "10"])).is_err());
+    assert_eq!(
+        parse_args(args(&["watch", "budget.calc"])),
+        Ok(Command::Watch {
+            file: PathBuf::from("budget.calc")
+        })
+    );
+    assert!(parse_args(args(&["watch"])).is_err());
+    assert_eq!(
+        parse_args(args(&["test", "a.calc", "b.calc"])),
+        Ok(Command::Test {
+            files: vec![PathBuf::from("a.calc"), PathBuf::from("b.calc")]
+        })
+    );
+    assert!(parse_args(args(&["test"])).is_err());
+    assert_eq!(
+        parse_args(args(&["annotate", "notes.txt"])),
+        Ok(Command::Annotate {
+            file: PathBuf::from("notes.txt")
+        })
+    );
+    assert_eq!(
+        parse_args(args(&["replay", "session.json", "--interactive"])),
+        Ok(Command::Replay {
+            file: PathBuf::from("session.json"),
+            interactive: true
+        })
+    );
+    assert!(parse_args(args(&["replay", "--interactive"])).is_err());
+    assert!(parse_args(args(&["replay", "a.json", "b.json"])).is_err());
+    assert_eq!(
+        parse_args(args(&["csv", "data.csv", "--formula", "price * qty"])),
+        Ok(Command::Csv {
+            file: PathBuf::from("data.csv"),
+            formula: "price * qty".to_string(),
+            column: "result".to_string(),
+            jobs: 1
+        })
+    );
+    assert_eq!(
+        parse_args(args(&["csv", "-j", "4", "data.csv", "-f", "1"])),
+        Ok(Command::Csv {
+            file: PathBuf::from("data.csv"),
+            formula: "1".to_string(),
+            column: "result".to_string(),
+            jobs: 4
+        })
+    );
+    assert!(parse_args(args(&["csv", "data.csv", "-f", "1", "--jobs", "0"])).is_err());
+    assert!(parse_args(args(&["csv", "data.csv"])).is_err());
+    assert!(parse_args(args(&["csv", "--formula", "1"])).is_err());
+    assert!(parse_args(args(&["unknown"])).is_err());
+}
@@ -0,0 +1,116 @@
+//! Contas rápidas com cores RGB: `hex_to_rgb(#336699)`, `rgb_to_hex(51, 102, 153)`,
+//! `blend(#ff0000, #0000ff, 0.5)`, `lighten(#336699, 0.2)` e `darken(#336699, 0.2)`, todas com
+//! frações entre 0 e 1. Como `polar`, são comandos que ocupam a linha inteira, porque uma cor tem
+//! três componentes.
+
+/// Vermelho, verde e azul.
+pub type Rgb = [u8; 3];
+
+const BLACK: Rgb = [0, 0, 0];
+const WHITE: Rgb = [255, 255, 255];
+
+/// `#rrggbb` ou a forma curta `#rgb`, em que cada dígito é repetido (`#369` é `#336699`).
+pub fn parse_hex(text: &str) -> Result<Rgb, String> {
+    let invalid = || format!("cor inválida '{}' (use #rrggbb ou #rgb)", text);
+    let digits = text.strip_prefix('#').ok_or_else(invalid)?;
+    if !digits.chars().all(|char| char.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let digits = match digits.len() {
+        3 => digits.chars().flat_map(|char| [char, char]).collect(),
+        6 => digits.to_string(),
+        _ => return Err(invalid()),
+    };
+    let value = u32::from_str_radix(&digits, 16).map_err(|_| invalid())?;
+    Ok(from_u32(value))
+}
+
+/// A cor de um número como `0x336699`.
+pub fn from_value(value: f64) -> Result<Rgb, String> {
+    if !(0.0..=f64::from(0xffffff)).contains(&value) || value.fract() != 0.0 {
+        return Err(format!(
+            "cor inválida {} (precisa ser um inteiro entre 0 e 0xffffff)",
+            value
+        ));
+    }
+    Ok(from_u32(value as u32))
+}
+
+fn from_u32(value: u32) -> Rgb {
+    let [_, red, green, blue] = value.to_be_bytes();
+    [red, green, blue]
+}
+
+/// A cor dos três componentes, que precisam ser inteiros entre 0 e 255.
+pub fn from_components(red: f64, green: f64, blue: f64) -> Result<Rgb, String> {
+    let component = |value: f64| {
+        if (0.0..=255.0).contains(&value) && value.fract() == 0.0 {
+            Ok(value as u8)
+        } else {
+            Err(format!(
+                "componente de cor inválido {} (precisa ser um inteiro entre 0 e 255)",
+                value
+            ))
+        }
+    };
+    Ok([component(red)?, component(green)?, component(blue)?])
+}
+
+pub fn to_hex([red, green, blue]: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", red, green, blue)
+}
+
+/// A cor a uma fração `t` do caminho entre `from` e `to`, componente a componente.
+pub fn blend(from: Rgb, to: Rgb, t: f64) -> Result<Rgb, String> {
+    if !(0.0..=1.0).contains(&t) {
+        return Err(format!(
+            "a fração da mistura precisa estar entre 0 e 1: {}",
+            t
+        ));
+    }
+    let mut result = BLACK;
+    for ((result, from), to) in result.iter_mut().zip(from).zip(to) {
+        let (from, to) = (f64::from(from), f64::from(to));
+        *result = (from + (to - from) * t).round() as u8;
+    }
+    Ok(result)
+}
+
+/// A cor misturada com uma fração `t` de branco.
+pub fn lighten(color: Rgb, t: f64) -> Result<Rgb, String> {
+    blend(color, WHITE, t)
+}
+
+/// A cor misturada com uma fração `t` de preto.
+pub fn darken(color: Rgb, t: f64) -> Result<Rgb, String> {
+    blend(color, BLACK, t)
+}
+
+#[test]
+fn colors_should_convert_between_hex_and_rgb() {
+    assert_eq!(parse_hex("#336699"), Ok([51, 102, 153]));
+    assert_eq!(parse_hex("#369"), Ok([51, 102, 153]));
+    assert_eq!(parse_hex("#FFF"), Ok(WHITE));
+    assert!(parse_hex("336699").is_err());
+    assert!(parse_hex("#33669").is_err());
+    assert!(parse_hex("#+33669").is_err());
+    assert_eq!(to_hex([51, 102, 153]), "#336699");
+    assert_eq!(from_value(f64::from(0x336699)), Ok([51, 102, 153]));
+    assert!(from_value(-1.0).is_err());
+    assert!(from_components(256.0, 0.0, 0.0).is_err());
+    assert!(from_components(0.5, 0.0, 0.0).is_err());
+}
+
+#[test]
+fn colors_should_blend_component_by_component() {
+    assert_eq!(blend([255, 0, 0], [0, 0, 255], 0.5), Ok([128, 0, 128]));
+    assert_eq!(
+        lighten([51, 102, 153], 0.2).map(to_hex),
+        Ok("#5c85ad".to_string())
+    );
+    assert_eq!(darken([51, 102, 153], 1.0), Ok(BLACK));
+    assert!(blend(BLACK, WHITE, 1.5).is_err());
+    assert!(blend(BLACK, WHITE, 50.0).is_err());
+    assert!(lighten(BLACK, -0.1).is_err());
+    assert!(lighten(BLACK, 20.0).is_err());
+}
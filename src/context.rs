@@ -0,0 +1,643 @@
+use alloc::{collections::BTreeMap, string::String, sync::Arc};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    expression::{Function, APPROX_TOLERANCE},
+    math::{self, Libm},
+    portable::PORTABLE,
+    units::{self, Dimension, Unit},
+};
+
+/// Constantes que podem ser usadas pelo nome nas expressões.
+pub const CONSTANTS: &[(&str, f64)] = &[("pi", core::f64::consts::PI), ("e", core::f64::consts::E)];
+
+fn constant(name: &str) -> Option<f64> {
+    CONSTANTS
+        .iter()
+        .find(|(constant_name, _)| *constant_name == name)
+        .map(|(_, value)| *value)
+}
+
+/// Unidade dos ângulos nas expressões. Ângulos escritos em graus (`30°15'50"`) são convertidos
+/// para ela.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AngleMode {
+    #[default]
+    Radians,
+    Degrees,
+}
+
+impl AngleMode {
+    pub fn from_degrees(self, degrees: f64) -> f64 {
+        match self {
+            AngleMode::Radians => degrees.to_radians(),
+            AngleMode::Degrees => degrees,
+        }
+    }
+
+    pub fn to_degrees(self, angle: f64) -> f64 {
+        match self {
+            AngleMode::Radians => angle.to_degrees(),
+            AngleMode::Degrees => angle,
+        }
+    }
+
+    pub fn from_radians(self, radians: f64) -> f64 {
+        match self {
+            AngleMode::Radians => radians,
+            AngleMode::Degrees => radians.to_degrees(),
+        }
+    }
+
+    pub fn to_radians(self, angle: f64) -> f64 {
+        match self {
+            AngleMode::Radians => angle,
+            AngleMode::Degrees => angle.to_radians(),
+        }
+    }
+}
+
+/// Tamanho da palavra das funções de bits (`rotl`, `popcount`...), como os inteiros sem sinal do
+/// Rust. Começa em 64 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WordSize {
+    U8,
+    U16,
+    U32,
+    #[default]
+    U64,
+}
+
+impl WordSize {
+    pub fn bits(self) -> u32 {
+        match self {
+            WordSize::U8 => 8,
+            WordSize::U16 => 16,
+            WordSize::U32 => 32,
+            WordSize::U64 => 64,
+        }
+    }
+
+    pub fn from_bits(bits: u32) -> Option<WordSize> {
+        match bits {
+            8 => Some(WordSize::U8),
+            16 => Some(WordSize::U16),
+            32 => Some(WordSize::U32),
+            64 => Some(WordSize::U64),
+            _ => None,
+        }
+    }
+
+    /// Os bits de `value` na palavra, se ele for um inteiro que cabe nela com ou sem sinal
+    /// (`-1` é `0xff` em 8 bits).
+    pub fn to_bits(self, value: f64) -> Option<u64> {
+        let limit = (1u128 << self.bits()) as f64;
+        if math::trunc(value) != value || value >= limit || value < -limit / 2.0 {
+            return None;
+        }
+        let bits = if value < 0.0 {
+            value as i64 as u64
+        } else {
+            value as u64
+        };
+        Some(bits & self.mask())
+    }
+
+    /// Todos os bits da palavra ligados.
+    pub fn mask(self) -> u64 {
+        u64::MAX >> (64 - self.bits())
+    }
+}
+
+/// Como `sum(...)`, `mean(...)` e as outras funções que somam muitos números fazem a soma. Somar
+/// um a um perde um pouco da precisão a cada parcela, e o erro cresce com a quantidade delas;
+/// os outros algoritmos guardam ou evitam esse erro. Começa em `Neumaier`, e todos somam sempre
+/// na mesma ordem, dando os mesmos bits no modo determinístico.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Summation {
+    /// Da esquerda para a direita, sem correção.
+    Naive,
+    /// Soma compensada de Kahan, que guarda o que se perdeu em cada soma para corrigir a próxima.
+    Kahan,
+    /// Variante de Kahan–Babuška–Neumaier, que também acerta quando uma parcela é maior que a
+    /// soma até ali (`1 + 1e100 - 1e100`).
+    #[default]
+    Neumaier,
+    /// Soma em árvore, metade com metade, com um erro que cresce com o logaritmo da quantidade
+    /// de parcelas.
+    Pairwise,
+}
+
+impl Summation {
+    pub const ALL: [Summation; 4] = [
+        Summation::Naive,
+        Summation::Kahan,
+        Summation::Neumaier,
+        Summation::Pairwise,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Summation::Naive => "naive",
+            Summation::Kahan => "kahan",
+            Summation::Neumaier => "neumaier",
+            Summation::Pairwise => "pairwise",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Summation> {
+        Summation::ALL
+            .into_iter()
+            .find(|summation| summation.name() == name)
+    }
+
+    pub fn sum(self, values: &[f64]) -> f64 {
+        let naive = || values.iter().fold(0.0, |sum, value| sum + value);
+        let sum = match self {
+            Summation::Naive => return naive(),
+            Summation::Kahan => {
+                let (mut sum, mut compensation) = (0.0, 0.0);
+                for value in values {
+                    let corrected = value - compensation;
+                    let next = sum + corrected;
+                    compensation = (next - sum) - corrected;
+                    sum = next;
+                }
+                sum
+            }
+            Summation::Neumaier => {
+                let (mut sum, mut compensation) = (0.0f64, 0.0);
+                for value in values {
+                    let next = sum + value;
+                    compensation += if sum.abs() >= value.abs() {
+                        (sum - next) + value
+                    } else {
+                        (value - next) + sum
+                    };
+                    sum = next;
+                }
+                sum + compensation
+            }
+            Summation::Pairwise => pairwise_sum(values),
+        };
+        // com infinitos a compensação vira `NaN` (`inf - inf`), mas a soma simples já está certa
+        if sum.is_nan() {
+            naive()
+        } else {
+            sum
+        }
+    }
+}
+
+fn pairwise_sum(values: &[f64]) -> f64 {
+    // abaixo disso a árvore não muda quase nada no erro, e só deixa a soma mais lenta
+    if values.len() <= 8 {
+        return values.iter().fold(0.0, |sum, value| sum + value);
+    }
+    let (left, right) = values.split_at(values.len() / 2);
+    pairwise_sum(left) + pairwise_sum(right)
+}
+
+/// Flag compartilhada que, quando ligada, faz o cálculo em andamento parar com
+/// `Error::Interrupted` na próxima chamada de função. Pode ser ligada por outra thread (para um
+/// tempo limite) ou por um tratador de sinal (para o Ctrl+C), já que só usa uma operação
+/// atômica.
+#[derive(Debug, Clone, Default)]
+pub struct InterruptFlag(Arc<AtomicBool>);
+
+impl InterruptFlag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A flag não faz parte do valor de um `EvalContext`, então duas flags são sempre iguais.
+impl PartialEq for InterruptFlag {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// Limites que impedem que expressões muito aninhadas ou funções chamando funções demais
+/// estourem a pilha, retornando `Error::LimitExceeded` no lugar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Níveis de parênteses, sinais e potências encadeadas (`2 ^ 2 ^ ...` conta, já que cada
+    /// potência fica dentro da anterior, mas `1 + 1 + ...` não tem limite de tamanho).
+    pub max_nesting: usize,
+    /// Chamadas de função dentro de outras, durante o cálculo.
+    pub max_call_depth: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_nesting: 256,
+            max_call_depth: 128,
+        }
+    }
+}
+
+/// Nomes disponíveis durante a criação e o cálculo de uma expressão, além das constantes.
+///
+/// ```
+/// use asdf_calc::{EvalContext, Expression};
+///
+/// let mut context = EvalContext::new();
+/// context.set_variable("rate", 0.05);
+///
+/// let expression = Expression::with_context("1000 * rate", &context).expect("expressão válida");
+/// assert_eq!(expression.evaluate_with(&context), Ok(50.0));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvalContext {
+    variables: BTreeMap<String, f64>,
+    /// A dimensão das variáveis que guardam uma grandeza com unidade (`d = 5 km`). As que não
+    /// aparecem aqui são números sem unidade.
+    dimensions: BTreeMap<String, Dimension>,
+    /// Constantes do usuário, que escondem as variáveis e as constantes de `CONSTANTS`.
+    constants: BTreeMap<String, f64>,
+    functions: BTreeMap<String, Arc<Function>>,
+    /// Unidades do usuário, que escondem as de `UNITS` de mesmo nome.
+    units: BTreeMap<String, Unit>,
+    /// Compartilhada com as cópias do contexto.
+    interrupt: InterruptFlag,
+    limits: Limits,
+    /// Aceita `5k`, `10u`... como `5e3` e `10e-6`.
+    si_suffixes: bool,
+    /// Só aceita a sintaxe sem atalhos, veja `EvalContext::set_strict`.
+    strict: bool,
+    angle_mode: AngleMode,
+    /// Distância máxima entre dois números para que as comparações os considerem iguais.
+    epsilon: f64,
+    /// Tolerância relativa de `~=`, `None` para `APPROX_TOLERANCE`.
+    approx_tolerance: Option<f64>,
+    word_size: WordSize,
+    deterministic: bool,
+    summation: Summation,
+}
+
+impl EvalContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define o valor de uma variável, sem unidade, retornando o valor anterior caso ela já
+    /// existisse.
+    pub fn set_variable(&mut self, name: impl Into<String>, value: f64) -> Option<f64> {
+        self.set_quantity(name, value, Dimension::NONE)
+    }
+
+    /// Mesmo que `set_variable`, para uma grandeza de dimensão `dimension`, com o valor em
+    /// unidades do SI.
+    pub fn set_quantity(
+        &mut self,
+        name: impl Into<String>,
+        value: f64,
+        dimension: Dimension,
+    ) -> Option<f64> {
+        let name = name.into();
+        if dimension.is_none() {
+            self.dimensions.remove(&name);
+        } else {
+            self.dimensions.insert(name.clone(), dimension);
+        }
+        self.variables.insert(name, value)
+    }
+
+    /// Define uma constante, que não pode ser alterada por atribuições (`Statement` as rejeita
+    /// com `Error::AssignmentToConstant`). Retorna o valor anterior caso ela já existisse.
+    pub fn define_constant(&mut self, name: impl Into<String>, value: f64) -> Option<f64> {
+        self.constants.insert(name.into(), value)
+    }
+
+    /// Valor de uma constante definida com `define_constant`.
+    pub fn constant(&self, name: &str) -> Option<f64> {
+        self.constants.get(name).copied()
+    }
+
+    pub fn constants(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.constants
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+    }
+
+    pub fn remove_variable(&mut self, name: &str) -> Option<f64> {
+        self.dimensions.remove(name);
+        self.variables.remove(name)
+    }
+
+    /// A dimensão de um nome usado numa expressão, sem unidade para as constantes e os nomes
+    /// que não existem.
+    pub fn dimension(&self, name: &str) -> Dimension {
+        if self.constant(name).is_some() {
+            return Dimension::NONE;
+        }
+        self.dimensions.get(name).copied().unwrap_or_default()
+    }
+
+    /// A unidade que pode ser escrita com o nome `name` (`km`, `N`...).
+    pub fn unit(&self, name: &str) -> Option<Unit> {
+        self.units.get(name).copied().or_else(|| units::unit(name))
+    }
+
+    /// Define uma unidade (`furlong`, 201.168 metros), retornando a anterior caso já existisse
+    /// uma do usuário com o mesmo nome. Como as funções, as unidades são resolvidas quando a
+    /// expressão é lida, então redefinir uma não muda as expressões que já a usavam.
+    pub fn define_unit(&mut self, name: impl Into<String>, unit: Unit) -> Option<Unit> {
+        self.units.insert(name.into(), unit)
+    }
+
+    pub fn remove_unit(&mut self, name: &str) -> Option<Unit> {
+        self.units.remove(name)
+    }
+
+    /// As unidades definidas com `define_unit`.
+    pub fn units(&self) -> impl Iterator<Item = (&str, Unit)> {
+        self.units.iter().map(|(name, unit)| (name.as_str(), *unit))
+    }
+
+    pub fn variable(&self, name: &str) -> Option<f64> {
+        self.variables.get(name).copied()
+    }
+
+    pub fn variables(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+    }
+
+    /// Define uma função, retornando a anterior caso já existisse uma com o mesmo nome.
+    pub fn define_function(
+        &mut self,
+        name: impl Into<String>,
+        function: Function,
+    ) -> Option<Function> {
+        self.functions
+            .insert(name.into(), Arc::new(function))
+            .map(Arc::unwrap_or_clone)
+    }
+
+    pub fn remove_function(&mut self, name: &str) -> Option<Function> {
+        self.functions.remove(name).map(Arc::unwrap_or_clone)
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.get(name).map(|function| function.as_ref())
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = (&str, &Function)> {
+        self.functions
+            .iter()
+            .map(|(name, function)| (name.as_str(), function.as_ref()))
+    }
+
+    /// Versão compartilhada da função, guardada pelas chamadas a ela.
+    pub(crate) fn shared_function(&self, name: &str) -> Option<Arc<Function>> {
+        self.functions.get(name).cloned()
+    }
+
+    pub fn interrupt_flag(&self) -> &InterruptFlag {
+        &self.interrupt
+    }
+
+    /// Troca a flag que interrompe os cálculos feitos com este contexto.
+    pub fn set_interrupt_flag(&mut self, flag: InterruptFlag) {
+        self.interrupt = flag;
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Se os sufixos do SI são aceitos, o que nunca acontece no modo estrito.
+    pub fn si_suffixes(&self) -> bool {
+        self.si_suffixes && !self.strict
+    }
+
+    /// Liga ou desliga os sufixos do SI depois dos números (`5k`, `3.3M`, `10u`, `2n`), que
+    /// começam desligados para que não atrapalhem a leitura de outros sufixos no futuro.
+    pub fn set_si_suffixes(&mut self, enabled: bool) {
+        self.si_suffixes = enabled;
+    }
+
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Liga o modo estrito, para quem usa a calculadora para validar valores de configuração ou
+    /// de cobrança e não quer que nada seja adivinhado: a multiplicação de um número por uma
+    /// unidade precisa do `*` (`5 * km`, e não `5 km`) e os sufixos do SI deixam de ser aceitos,
+    /// mesmo com `set_si_suffixes`. A vírgula nunca é um separador decimal, então fica como
+    /// está.
+    ///
+    /// ```
+    /// use asdf_calc::{Error, EvalContext, Expression, Span};
+    ///
+    /// let mut context = EvalContext::new();
+    /// context.set_strict(true);
+    /// assert_eq!(
+    ///     Expression::with_context("5 km", &context),
+    ///     Err(vec![Error::ImplicitMultiplication(Span::new(2, 4))])
+    /// );
+    /// assert!(Expression::with_context("5 * km", &context).is_ok());
+    /// ```
+    pub fn set_strict(&mut self, enabled: bool) {
+        self.strict = enabled;
+    }
+
+    pub fn angle_mode(&self) -> AngleMode {
+        self.angle_mode
+    }
+
+    pub fn set_angle_mode(&mut self, angle_mode: AngleMode) {
+        self.angle_mode = angle_mode;
+    }
+
+    pub fn word_size(&self) -> WordSize {
+        self.word_size
+    }
+
+    pub fn set_word_size(&mut self, word_size: WordSize) {
+        self.word_size = word_size;
+    }
+
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    /// Tolerância de `==`, `!=`, `<=`... (`a == b` quando `|a - b| <= epsilon`). Começa em zero,
+    /// com as comparações exatas. Ela é absoluta, então vale o mesmo para números de qualquer
+    /// tamanho: com `1e-9`, `0.1 + 0.2 == 0.3`, mas também `1e-10 == 0`. Para uma
+    /// tolerância proporcional aos números, use `~=`. Para uma só comparação, há
+    /// `equal(a, b, epsilon)`.
+    pub fn set_epsilon(&mut self, epsilon: f64) {
+        self.epsilon = epsilon;
+    }
+
+    pub fn approx_tolerance(&self) -> f64 {
+        self.approx_tolerance.unwrap_or(APPROX_TOLERANCE)
+    }
+
+    /// Tolerância de `~=`, que é relativa: `a ~= b` quando `|a - b|` é no máximo `tolerance`
+    /// vezes o maior dos dois em módulo, então com `1e-3` tanto `1000 ~= 1000.5` quanto
+    /// `0.001 ~= 0.0010000005`, só que com um mínimo de `tolerance` para os números menores
+    /// que 1, para que `x ~= 0` funcione. Começa em `APPROX_TOLERANCE`, e para uma só
+    /// comparação há `approx(a, b, tolerance)`.
+    pub fn set_approx_tolerance(&mut self, tolerance: f64) {
+        self.approx_tolerance = Some(tolerance);
+    }
+
+    pub fn deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Liga o modo determinístico, em que os resultados são os mesmos, bit a bit, em qualquer
+    /// plataforma: `sin`, `^`, `gamma` e as outras funções que dependem da libm do sistema
+    /// passam a ser calculadas por implementações próprias, que só usam operações que o IEEE 754
+    /// define exatamente, sem FMA. As funções com vários argumentos, como `min`, já os combinam
+    /// sempre da esquerda para a direita. Começa desligado, já que a libm costuma ser mais
+    /// rápida e um pouco mais precisa.
+    pub fn set_deterministic(&mut self, enabled: bool) {
+        self.deterministic = enabled;
+    }
+
+    pub fn summation(&self) -> Summation {
+        self.summation
+    }
+
+    pub fn set_summation(&mut self, summation: Summation) {
+        self.summation = summation;
+    }
+
+    pub(crate) fn libm(&self) -> &'static Libm {
+        if self.deterministic {
+            &PORTABLE
+        } else {
+            &math::SYSTEM
+        }
+    }
+
+    /// Valor de um nome usado numa expressão. As constantes do usuário escondem as variáveis, e
+    /// as variáveis escondem as constantes de `CONSTANTS` de mesmo nome.
+    pub(crate) fn resolve(&self, name: &str) -> Option<f64> {
+        self.constant(name)
+            .or_else(|| self.variable(name))
+            .or_else(|| constant(name))
+    }
+}
+
+#[test]
+fn word_size_should_read_integers_with_or_without_sign() {
+    assert_eq!(WordSize::U8.to_bits(255.0), Some(0xff));
+    assert_eq!(WordSize::U8.to_bits(-1.0), Some(0xff));
+    assert_eq!(WordSize::U8.to_bits(-128.0), Some(0x80));
+    assert_eq!(WordSize::U8.to_bits(256.0), None);
+    assert_eq!(WordSize::U8.to_bits(-129.0), None);
+    assert_eq!(WordSize::U8.to_bits(1.5), None);
+    assert_eq!(WordSize::U8.to_bits(f64::NAN), None);
+    assert_eq!(WordSize::U64.to_bits(-1.0), Some(u64::MAX));
+    assert_eq!(WordSize::U64.to_bits(1e30), None);
+    assert_eq!(WordSize::from_bits(16), Some(WordSize::U16));
+    assert_eq!(WordSize::from_bits(12), None);
+}
+
+#[test]
+fn summation_should_limit_the_error_of_long_sums() {
+    // a soma exata dos 0.1 (que não é exatamente um décimo) arredonda para 100000
+    let tenths = alloc::vec![0.1; 1_000_000];
+    let naive = Summation::Naive.sum(&tenths);
+    assert!((naive - 100000.0).abs() > 1e-6);
+    assert_eq!(Summation::Kahan.sum(&tenths), 100000.0);
+    assert_eq!(Summation::Neumaier.sum(&tenths), 100000.0);
+    assert!((Summation::Pairwise.sum(&tenths) - 100000.0).abs() < 1e-9);
+
+    let cancelling = [1.0, 1e100, 1.0, -1e100];
+    assert_eq!(Summation::Naive.sum(&cancelling), 0.0);
+    assert_eq!(Summation::Kahan.sum(&cancelling), 0.0);
+    assert_eq!(Summation::Neumaier.sum(&cancelling), 2.0);
+
+    for summation in Summation::ALL {
+        assert_eq!(summation.sum(&[f64::INFINITY, 1.0]), f64::INFINITY);
+        assert!(summation.sum(&[f64::INFINITY, -f64::INFINITY]).is_nan());
+        assert!(summation.sum(&[1.0, f64::NAN]).is_nan());
+        assert_eq!(Summation::from_name(summation.name()), Some(summation));
+    }
+    assert_eq!(Summation::from_name("fast"), None);
+}
+
+#[test]
+fn eval_context_should_resolve_variables_before_constants() {
+    let mut context = EvalContext::new();
+    assert_eq!(context.resolve("e"), Some(core::f64::consts::E));
+    assert_eq!(context.resolve("x"), None);
+
+    context.set_variable("x", 2.0);
+    context.set_variable("e", 3.0);
+    assert_eq!(context.resolve("x"), Some(2.0));
+    assert_eq!(context.resolve("e"), Some(3.0));
+
+    context.remove_variable("e");
+    assert_eq!(context.resolve("e"), Some(core::f64::consts::E));
+
+    context.define_constant("x", 4.0);
+    context.define_constant("pi", 3.0);
+    assert_eq!(context.resolve("x"), Some(4.0));
+    assert_eq!(context.resolve("pi"), Some(3.0));
+}
+
+#[test]
+fn eval_context_should_resolve_user_units_before_builtin_ones() {
+    let mut context = EvalContext::new();
+    assert_eq!(context.unit("furlong"), None);
+    let furlong = Unit::new(201.168, Dimension::LENGTH);
+    context.define_unit("furlong", furlong);
+    assert_eq!(context.unit("furlong"), Some(furlong));
+
+    let mile = context.unit("mi");
+    context.define_unit("mi", Unit::new(1852.0, Dimension::LENGTH));
+    assert_eq!(context.unit("mi").map(|unit| unit.factor), Some(1852.0));
+    context.remove_unit("mi");
+    assert_eq!(context.unit("mi"), mile);
+    assert_eq!(context.units().count(), 1);
+}
+
+#[test]
+fn strict_mode_should_reject_implicit_conveniences() {
+    use crate::{
+        error::{Error, Span},
+        expression::Expression,
+    };
+
+    let mut context = EvalContext::new();
+    context.set_si_suffixes(true);
+    context.set_strict(true);
+    assert!(!context.si_suffixes());
+    assert!(Expression::with_context("5k", &context).is_err());
+    assert_eq!(
+        Expression::with_context("2 * (3 m^2)", &context),
+        Err(vec![Error::ImplicitMultiplication(Span::new(7, 8))])
+    );
+    let expression = Expression::with_context("2 * 3 * m^2 + 1e3 * m^2", &context).unwrap();
+    assert_eq!(expression.evaluate_with(&context), Ok(1006.0));
+
+    context.set_strict(false);
+    assert!(context.si_suffixes());
+    assert!(Expression::with_context("3 m^2", &context).is_ok());
+}
@@ -0,0 +1,59 @@
+//! `polar(x, y)` e `cart(r, θ)`, que convertem entre coordenadas cartesianas e polares.
+//!
+//! Como as expressões só têm valores numéricos, o par resultante não pode ser usado dentro de
+//! outra expressão: as duas funções são comandos que ocupam a linha inteira e mostram o par.
+
+use asdf_calc::AngleMode;
+
+/// `(r, θ)` do ponto `(x, y)`, com `θ` no modo de ângulo `mode` e entre -π e π (ou -180° e
+/// 180°).
+pub fn polar(x: f64, y: f64, mode: AngleMode) -> (f64, f64) {
+    (x.hypot(y), mode.from_radians(y.atan2(x)))
+}
+
+/// `(x, y)` do ponto a uma distância `r` da origem, no ângulo `theta` (no modo `mode`).
+pub fn cart(r: f64, theta: f64, mode: AngleMode) -> (f64, f64) {
+    let theta = mode.to_radians(theta);
+    (r * theta.cos(), r * theta.sin())
+}
+
+/// Argumentos de `name(...)` se a linha inteira for essa chamada.
+pub fn call_arguments<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let arguments = line
+        .strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+
+    // em `polar(1) + (2)` o último `)` não fecha o primeiro
+    let mut depth = 0usize;
+    for char in arguments.chars() {
+        match char {
+            '(' => depth += 1,
+            ')' => depth = depth.checked_sub(1)?,
+            _ => {}
+        }
+    }
+    Some(arguments)
+}
+
+#[test]
+fn polar_and_cart_should_convert_between_coordinates() {
+    assert_eq!(polar(3.0, 4.0, AngleMode::Radians), (5.0, 4f64.atan2(3.0)));
+    assert_eq!(polar(0.0, 2.0, AngleMode::Degrees), (2.0, 90.0));
+    assert_eq!(polar(-1.0, -1.0, AngleMode::Degrees).1, -135.0);
+
+    let (x, y) = cart(2.0, 90.0, AngleMode::Degrees);
+    assert!(x.abs() < 1e-15 && y == 2.0);
+    let (x, y) = cart(5.0, 4f64.atan2(3.0), AngleMode::Radians);
+    assert!((x - 3.0).abs() < 1e-12 && (y - 4.0).abs() < 1e-12);
+}
+
+#[test]
+fn call_arguments_should_only_match_whole_line_calls() {
+    assert_eq!(call_arguments("polar(3, 4)", "polar"), Some("3, 4"));
+    assert_eq!(call_arguments("polar (x, (y))", "polar"), Some("x, (y)"));
+    assert_eq!(call_arguments("polar(3, 4) + 1", "polar"), None);
+    assert_eq!(call_arguments("polar(3) * (4)", "polar"), None);
+    assert_eq!(call_arguments("polarity", "polar"), None);
+}
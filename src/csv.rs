@@ -0,0 +1,304 @@
+//! `asdf-calc csv <arquivo> --formula <expressão>`: calcula a fórmula para cada linha de um CSV,
+//! com as colunas da linha como variáveis, e escreve o CSV com o resultado numa coluna nova no
+//! fim.
+//!
+//! A primeira linha do arquivo tem os nomes das colunas, que viram nomes de variáveis com `_` no
+//! lugar dos espaços e outros caracteres que não podem fazer parte de um nome (`preço unitário`
+//! vira `preço_unitário`). Células que não são números deixam a variável indefinida naquela linha,
+//! e as linhas em que a fórmula não pode ser calculada ficam com a coluna nova vazia.
+
+use std::{fs, path::Path, thread};
+
+use asdf_calc::{Error, EvalContext, Expression};
+
+/// Lê o texto de um CSV separado por vírgulas, em que os campos podem estar entre aspas (com `""`
+/// para uma aspa dentro deles, e quebras de linha).
+pub fn parse(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            char => field.push(char),
+        }
+    }
+    if quoted {
+        return Err("aspas sem fechamento".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Uma linha do CSV, com aspas só nos campos que precisam delas.
+pub fn write_record(fields: &[String]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect();
+    fields.join(",")
+}
+
+/// Nome da variável de uma coluna, ou `None` se não sobrar nada que possa ser um nome.
+pub fn variable_name(header: &str) -> Option<String> {
+    let name: String = header
+        .trim()
+        .chars()
+        .map(|char| {
+            if char.is_alphanumeric() || char == '_' {
+                char
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    name.starts_with(|char: char| char.is_alphabetic() || char == '_')
+        .then_some(name)
+}
+
+/// Calcula `formula` para cada linha de `text`, retornando o CSV com a coluna `column` nova e os
+/// erros das linhas que não puderam ser calculadas. `context` tem os nomes que a fórmula pode
+/// usar além das colunas, e as linhas são divididas entre `jobs` threads.
+pub fn evaluate(
+    text: &str,
+    formula: &str,
+    column: &str,
+    context: &EvalContext,
+    jobs: usize,
+) -> Result<(String, Vec<String>), String> {
+    let mut records = parse(text)?.into_iter();
+    let mut header = records
+        .next()
+        .ok_or_else(|| "o arquivo não tem a linha com os nomes das colunas".to_string())?;
+    let names: Vec<Option<String>> = header.iter().map(|name| variable_name(name)).collect();
+
+    // as colunas precisam existir para que a fórmula seja lida, mesmo que o valor mude a cada
+    // linha
+    let mut context = context.clone();
+    for name in names.iter().flatten() {
+        context.set_variable(name.clone(), 0.0);
+    }
+    let expression = Expression::with_context(formula, &context).map_err(|errors| {
+        let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        format!("--formula: {}", messages.join("; "))
+    })?;
+
+    let records: Vec<Vec<String>> = records.collect();
+    header.push(column.to_string());
+    let mut output = format!("{}\n", write_record(&header));
+    let mut errors = Vec::new();
+    for (chunk_output, chunk_errors) in
+        evaluate_records(&expression, &context, &names, &records, jobs)
+    {
+        output.push_str(&chunk_output);
+        errors.extend(chunk_errors);
+    }
+    Ok((output, errors))
+}
+
+/// As linhas de saída e os erros de cada bloco de linhas, na ordem das linhas. Com mais de um
+/// job as linhas são divididas em blocos seguidos, um por thread, cada uma com a sua cópia do
+/// contexto.
+fn evaluate_records(
+    expression: &Expression,
+    context: &EvalContext,
+    names: &[Option<String>],
+    records: &[Vec<String>],
+    jobs: usize,
+) -> Vec<(String, Vec<String>)> {
+    let evaluate_chunk = |first: usize, chunk: &[Vec<String>]| {
+        let mut context = context.clone();
+        let mut output = String::new();
+        let mut errors = Vec::new();
+        for (index, record) in chunk.iter().enumerate() {
+            let mut record = record.clone();
+            match evaluate_record(expression, &mut context, names, &record) {
+                Ok(result) => record.push(result.to_string()),
+                Err(message) => {
+                    // a primeira linha depois do cabeçalho é a 2 do arquivo, se nenhum campo
+                    // tiver uma quebra de linha
+                    errors.push(format!("linha {}: {}", first + index + 2, message));
+                    record.push(String::new());
+                }
+            }
+            output.push_str(&write_record(&record));
+            output.push('\n');
+        }
+        (output, errors)
+    };
+    if jobs <= 1 || records.len() <= 1 {
+        return vec![evaluate_chunk(0, records)];
+    }
+
+    let chunk_size = records.len().div_ceil(jobs);
+    thread::scope(|scope| {
+        let handles: Vec<_> = records
+            .chunks(chunk_size)
+            .enumerate()
+            .map(|(index, chunk)| scope.spawn(move || evaluate_chunk(index * chunk_size, chunk)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("thread do csv entrou em pânico"))
+            .collect()
+    })
+}
+
+fn evaluate_record(
+    expression: &Expression,
+    context: &mut EvalContext,
+    names: &[Option<String>],
+    record: &[String],
+) -> Result<f64, String> {
+    for (index, name) in names.iter().enumerate() {
+        let Some(name) = name else {
+            continue;
+        };
+        match record.get(index).and_then(|cell| cell.trim().parse().ok()) {
+            Some(value) => context.set_variable(name.clone(), value),
+            None => context.remove_variable(name),
+        };
+    }
+    expression
+        .evaluate_with(context)
+        .map_err(|error| match &error {
+            Error::UnknownIdentifier(name, _) => match names
+                .iter()
+                .position(|column| column.as_deref() == Some(name.as_str()))
+            {
+                Some(index) => format!(
+                    "o valor de '{}' não é um número ('{}')",
+                    name,
+                    record.get(index).map_or("", |cell| cell.trim())
+                ),
+                None => error.to_string(),
+            },
+            _ => error.to_string(),
+        })
+}
+
+/// Retorna o código de saída do processo, 1 se alguma linha não pôde ser calculada.
+pub fn run(file: &Path, formula: &str, column: &str, jobs: usize, context: &EvalContext) -> i32 {
+    let result = fs::read_to_string(file)
+        .map_err(|error| format!("não foi possível ler {}: {}", file.display(), error))
+        .and_then(|text| evaluate(&text, formula, column, context, jobs));
+    match result {
+        Ok((output, errors)) => {
+            print!("{}", output);
+            for error in &errors {
+                eprintln!("{}", error);
+            }
+            i32::from(!errors.is_empty())
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            1
+        }
+    }
+}
+
+#[test]
+fn parse_should_read_quoted_fields() {
+    assert_eq!(
+        parse("a,b\r\n\"x, \"\"y\"\"\",2\n\"linha\nnova\",\n").unwrap(),
+        vec![
+            vec!["a".to_string(), "b".to_string()],
+            vec!["x, \"y\"".to_string(), "2".to_string()],
+            vec!["linha\nnova".to_string(), String::new()],
+        ]
+    );
+    assert_eq!(parse("a,b").unwrap(), vec![vec!["a", "b"]]);
+    assert!(parse("\"a").is_err());
+    assert_eq!(
+        write_record(&["x, \"y\"".to_string(), "2".to_string()]),
+        "\"x, \"\"y\"\"\",2"
+    );
+}
+
+#[test]
+fn evaluate_should_add_the_formula_column() {
+    let text = "\
+item,price,qty,tax
+caneta,2.5,4,0.1
+\"caderno, grande\",10,1,0
+borracha,,2,0
+";
+    let (output, errors) = evaluate(
+        text,
+        "price * qty * (1 + tax)",
+        "total",
+        &EvalContext::new(),
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        output,
+        "\
+item,price,qty,tax,total
+caneta,2.5,4,0.1,11
+\"caderno, grande\",10,1,0,10
+borracha,,2,0,
+"
+    );
+    assert_eq!(
+        errors,
+        vec!["linha 4: o valor de 'price' não é um número ('')".to_string()]
+    );
+
+    assert_eq!(
+        variable_name(" preço unitário "),
+        Some("preço_unitário".to_string())
+    );
+    assert_eq!(variable_name("1st"), None);
+    assert!(evaluate(text, "price *", "total", &EvalContext::new(), 1).is_err());
+    assert!(evaluate("", "1", "total", &EvalContext::new(), 1).is_err());
+}
+
+#[test]
+fn evaluate_should_keep_the_order_of_the_rows_with_many_jobs() {
+    let mut text = "n\n".to_string();
+    for n in 0..1000 {
+        text.push_str(&format!(
+            "{}\n",
+            if n == 500 {
+                "x".to_string()
+            } else {
+                n.to_string()
+            }
+        ));
+    }
+    let sequential = evaluate(&text, "n * 2", "double", &EvalContext::new(), 1).unwrap();
+    for jobs in [2, 3, 8, 2000] {
+        assert_eq!(
+            evaluate(&text, "n * 2", "double", &EvalContext::new(), jobs).unwrap(),
+            sequential
+        );
+    }
+    assert!(sequential.0.ends_with("\n998,1996\n999,1998\n"));
+    assert_eq!(
+        sequential.1,
+        vec!["linha 502: o valor de 'n' não é um número ('x')".to_string()]
+    );
+}
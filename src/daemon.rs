@@ -0,0 +1,177 @@
+//! Daemon (`asdf-calc daemon`) que mantém uma sessão aberta num socket Unix, e o cliente
+//! (`asdf-calc send "x * 2"`) que conversa com ele, para que atalhos do shell calculem na hora
+//! compartilhando as mesmas variáveis e o mesmo histórico.
+//!
+//! Cada conexão manda uma única linha e recebe `ok` ou `err` numa primeira linha, seguida da
+//! saída correspondente.
+
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{grid, pool::ThreadPool, session::Session};
+
+/// `$XDG_RUNTIME_DIR/asdf-calc.sock`, ou um arquivo por usuário em `/tmp` se a variável não
+/// estiver definida.
+pub fn default_socket_path() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => Path::new(&dir).join("asdf-calc.sock"),
+        None => {
+            let user = env::var("USER").unwrap_or_else(|_| "default".to_string());
+            env::temp_dir().join(format!("asdf-calc-{}.sock", user))
+        }
+    }
+}
+
+/// Responde a uma linha recebida por uma conexão.
+pub fn respond(session: &mut Session, request: &str) -> String {
+    let request = request.trim();
+    if request == "history" {
+        let mut response = "ok\n".to_string();
+        for (i, entry) in session.history.iter().enumerate() {
+            response.push_str(&format!("{}: {} = {}\n", i + 1, entry.input, entry.result));
+        }
+        return response;
+    }
+    if request == "grid" {
+        return format!("ok\n{}", grid::render(&session.context));
+    }
+    if let Some(response) = session.command(request) {
+        return match response {
+            Ok(message) => format!("ok\n{}\n", message),
+            Err(message) => format!("err\n{}\n", message),
+        };
+    }
+
+    let result = session.eval(request);
+    if let Err(message) = session.save_workspace() {
+        return format!("err\n{}\n", message);
+    }
+    if let Some(message) = session.log_error.take() {
+        return format!("err\n{}\n", message);
+    }
+    match result {
+        Ok(result) => match &session.last_stored {
+            Some(name) => format!("ok\n{} = {}\n", name, session.format_result(result)),
+            None => format!("ok\n{}\n", session.format_result(result)),
+        },
+        Err(errors) => {
+            let mut response = "err\n".to_string();
+            for error in errors {
+                response.push_str(&format!("{}\n", error));
+            }
+            response
+        }
+    }
+}
+
+/// A linha é lida antes de pegar a sessão, que só fica presa enquanto a resposta é calculada.
+fn handle_connection(session: &Mutex<Session>, stream: UnixStream) -> io::Result<()> {
+    // um cliente travado não pode impedir o daemon de atender os outros
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+
+    let response = respond(&mut session.lock().unwrap(), &request);
+    let mut stream = stream;
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
+
+/// Atende as conexões com um `ThreadPool`, todas com a mesma sessão.
+pub fn run_daemon(socket_path: &Path, session: Session) -> io::Result<()> {
+    if socket_path.exists() {
+        if UnixStream::connect(socket_path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("já existe um daemon escutando em {}", socket_path.display()),
+            ));
+        }
+        // socket que sobrou de um daemon que não foi encerrado corretamente
+        std::fs::remove_file(socket_path)?;
+    }
+
+    let listener = UnixListener::bind(socket_path)?;
+    eprintln!("escutando em {}", socket_path.display());
+
+    let session = Arc::new(Mutex::new(session));
+    let pool = ThreadPool::new(ThreadPool::default_size());
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let session = Arc::clone(&session);
+                pool.execute(move || {
+                    if let Err(error) = handle_connection(&session, stream) {
+                        eprintln!("erro ao responder conexão: {}", error);
+                    }
+                });
+            }
+            Err(error) => eprintln!("erro ao aceitar conexão: {}", error),
+        }
+    }
+
+    Ok(())
+}
+
+/// Manda `request` para o daemon, retornando se ele foi calculado com sucesso e a saída.
+pub fn send(socket_path: &Path, request: &str) -> io::Result<(bool, String)> {
+    let mut stream = UnixStream::connect(socket_path).map_err(|error| {
+        io::Error::new(
+            error.kind(),
+            format!(
+                "não foi possível conectar ao daemon em {} ({}), ele foi iniciado com `asdf-calc daemon`?",
+                socket_path.display(),
+                error
+            ),
+        )
+    })?;
+    writeln!(stream, "{}", request.replace('\n', " "))?;
+    stream.flush()?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let (status, output) = response.split_once('\n').unwrap_or((&response, ""));
+    Ok((status == "ok", output.to_string()))
+}
+
+#[test]
+fn respond_should_share_the_session_between_requests() {
+    let mut session = Session::default();
+    assert_eq!(respond(&mut session, "x = 21\n"), "ok\n21\n");
+    assert_eq!(respond(&mut session, "x * 2\n"), "ok\n42\n");
+    assert!(respond(&mut session, "y").starts_with("err\n"));
+    assert_eq!(
+        respond(&mut session, "history"),
+        "ok\n1: x = 21 = 21\n2: x * 2 = 42\n"
+    );
+    assert_eq!(respond(&mut session, "format sci"), "ok\nformato: sci\n");
+    assert_eq!(respond(&mut session, "x * 200"), "ok\n4.2e3\n");
+}
+
+#[test]
+fn send_should_talk_to_a_running_daemon() {
+    let socket_path = env::temp_dir().join(format!("asdf-calc-test-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path).unwrap();
+
+    let server = std::thread::spawn(move || {
+        let session = Mutex::new(Session::default());
+        for stream in listener.incoming().take(2) {
+            handle_connection(&session, stream.unwrap()).unwrap();
+        }
+    });
+
+    assert_eq!(
+        send(&socket_path, "x = 2 * 3").unwrap(),
+        (true, "6\n".to_string())
+    );
+    assert!(!send(&socket_path, "x +").unwrap().0);
+
+    server.join().unwrap();
+    std::fs::remove_file(&socket_path).unwrap();
+}
@@ -0,0 +1,180 @@
+//! Datas do calendário gregoriano escritas como `2024-03-01`, para os comandos que fazem contas
+//! com dias (`workdays`, `add_workdays`) e com timestamps Unix (`unix`, `from_unix`). As
+//! expressões não têm datas, já que `2024-03-01` é uma subtração, então esses comandos ocupam a
+//! linha inteira, como `polar`. As horas são sempre em UTC.
+
+use std::fmt::{self, Display, Formatter};
+
+/// Um dia, contado a partir de 1970-01-01.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date(pub i64);
+
+/// Os dias da semana, na ordem de `Date::weekday`.
+pub const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+impl Date {
+    /// `aaaa-mm-dd`, com o mês e o dia de dois dígitos.
+    pub fn parse(text: &str) -> Result<Date, String> {
+        let text = text.trim();
+        let invalid = || format!("data inválida '{}' (use aaaa-mm-dd)", text);
+        let mut parts = text.splitn(3, '-');
+        let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let digits = |part: &str, length: usize| {
+            part.len() == length && part.bytes().all(|byte| byte.is_ascii_digit())
+        };
+        if !digits(year, 4) || !digits(month, 2) || !digits(day, 2) {
+            return Err(invalid());
+        }
+        let (year, month, day) = (
+            year.parse().map_err(|_| invalid())?,
+            month.parse().map_err(|_| invalid())?,
+            day.parse().map_err(|_| invalid())?,
+        );
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return Err(invalid());
+        }
+        Ok(Date(days_from_civil(year, month, day)))
+    }
+
+    /// O dia do timestamp Unix `seconds`.
+    pub fn from_timestamp(seconds: i64) -> Date {
+        Date(seconds.div_euclid(86_400))
+    }
+
+    /// O dia da semana, de 0 (segunda) a 6 (domingo).
+    pub fn weekday(self) -> usize {
+        // 1970-01-01 foi uma quinta
+        (self.0 + 3).rem_euclid(7) as usize
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.0);
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// Segundos desde 1970-01-01 00:00 de `aaaa-mm-dd`, `aaaa-mm-dd hh:mm` ou
+/// `aaaa-mm-dd hh:mm:ss`, aceitando também o `T` e o `Z` da ISO 8601 (`2024-05-01T12:00Z`).
+pub fn parse_timestamp(text: &str) -> Result<i64, String> {
+    let text = text.trim();
+    let invalid = || format!("data e hora inválidas '{}' (use aaaa-mm-dd hh:mm:ss)", text);
+    let (date, time) = match text.find([' ', 'T', 't']) {
+        Some(index) => (&text[..index], text[index + 1..].trim()),
+        None => (text, ""),
+    };
+    let date = Date::parse(date)?;
+    let time = time.strip_suffix(['Z', 'z']).unwrap_or(time);
+    let mut seconds = 0;
+    if !time.is_empty() {
+        let parts: Vec<&str> = time.split(':').collect();
+        if !(2..=3).contains(&parts.len())
+            || parts
+                .iter()
+                .any(|part| part.len() != 2 || !part.bytes().all(|byte| byte.is_ascii_digit()))
+        {
+            return Err(invalid());
+        }
+        let values: Vec<i64> = parts.iter().map(|part| part.parse().unwrap_or(0)).collect();
+        let [hours, minutes, second] = [values[0], values[1], values.get(2).copied().unwrap_or(0)];
+        if hours > 23 || minutes > 59 || second > 59 {
+            return Err(invalid());
+        }
+        seconds = hours * 3600 + minutes * 60 + second;
+    }
+    Ok(date.0 * 86_400 + seconds)
+}
+
+/// `aaaa-mm-dd hh:mm:ss` do timestamp Unix `seconds`.
+pub fn format_timestamp(seconds: i64) -> String {
+    let seconds_of_day = seconds.rem_euclid(86_400);
+    format!(
+        "{} {:02}:{:02}:{:02}",
+        Date::from_timestamp(seconds),
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60
+    )
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Dias de 1970-01-01 até a data, pelo algoritmo de Howard Hinnant.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = i64::from((month + 9) % 12);
+    let day_of_year = (153 * month_index + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Data do calendário gregoriano a `days` dias de 1970-01-01, pelo algoritmo de Howard Hinnant.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[test]
+fn date_should_round_trip_through_days() {
+    assert_eq!(Date::parse("1970-01-01"), Ok(Date(0)));
+    assert_eq!(Date::parse("2000-02-29").unwrap().to_string(), "2000-02-29");
+    assert_eq!(Date::parse("1969-12-31"), Ok(Date(-1)));
+    for days in [-800_000, -1, 0, 59, 11_016, 19_723, 2_932_896] {
+        let (year, month, day) = civil_from_days(days);
+        assert_eq!(days_from_civil(year, month, day), days);
+    }
+    assert!(Date::parse("2023-02-29").is_err());
+    assert!(Date::parse("2024-13-01").is_err());
+    assert!(Date::parse("2024-1-01").is_err());
+    assert!(Date::parse("2024-01-01-01").is_err());
+    assert!(Date::parse("2024").is_err());
+}
+
+#[test]
+fn timestamps_should_be_read_in_utc() {
+    assert_eq!(parse_timestamp("2024-05-01 12:00"), Ok(1_714_564_800));
+    assert_eq!(parse_timestamp("2024-05-01T12:00:00Z"), Ok(1_714_564_800));
+    assert_eq!(parse_timestamp("2024-05-01"), Ok(1_714_521_600));
+    assert_eq!(parse_timestamp("1969-12-31 23:59:59"), Ok(-1));
+    assert!(parse_timestamp("2024-05-01 24:00").is_err());
+    assert!(parse_timestamp("2024-05-01 12").is_err());
+    assert!(parse_timestamp("2024-05-01 1:00").is_err());
+    assert_eq!(format_timestamp(1_714_564_800), "2024-05-01 12:00:00");
+    assert_eq!(format_timestamp(-1), "1969-12-31 23:59:59");
+}
+
+#[test]
+fn weekday_should_start_on_monday() {
+    assert_eq!(WEEKDAYS[Date(0).weekday()], "thu");
+    assert_eq!(
+        WEEKDAYS[Date::parse("2024-01-01").unwrap().weekday()],
+        "mon"
+    );
+    assert_eq!(WEEKDAYS[Date(-1).weekday()], "wed");
+}
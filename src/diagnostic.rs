@@ -0,0 +1,231 @@
+//! Erros no formato escolhido com `--error-format`: o texto de sempre (`human`), ou um objeto
+//! JSON por linha (`json`), como o `--error-format json` do rustc, para que programas que chamam
+//! a calculadora possam marcar o trecho exato com erro.
+//!
+//! Cada objeto tem o tipo do erro (`Error::kind`), a mensagem sem a coluna, o trecho em bytes do
+//! input (`end` exclusivo) e uma sugestão de correção, ou `null`. Para `total = rat * 2`:
+//!
+//! ```json
+//! {"kind":"unknown_identifier","message":"nome desconhecido 'rat'","span":{"start":8,"end":11},"suggestion":"você quis dizer 'rate'?"}
+//! ```
+
+use asdf_calc::{Error, EvalContext, Limit, BUILTINS, CONSTANTS, UNITS};
+
+use crate::json::Json;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+impl ErrorFormat {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "human" => Ok(ErrorFormat::Human),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(format!(
+                "formato de erro desconhecido '{}' (use human ou json)",
+                text
+            )),
+        }
+    }
+}
+
+/// O erro como objeto JSON, com o trecho convertido de caracteres para bytes de `input`. A
+/// mensagem é a de sempre, com a coluna, no formato `human`, e sem ela no `json`.
+pub fn diagnostic_json(
+    error: &Error,
+    input: &str,
+    format: ErrorFormat,
+    context: &EvalContext,
+) -> Json {
+    let span = error.span();
+    let message = match format {
+        ErrorFormat::Human => error.to_string(),
+        ErrorFormat::Json => error.message(),
+    };
+    Json::object([
+        ("kind", Json::from(error.kind())),
+        ("message", Json::from(message)),
+        (
+            "span",
+            Json::object([
+                ("start", Json::from(byte_offset(input, span.start))),
+                ("end", Json::from(byte_offset(input, span.end))),
+            ]),
+        ),
+        (
+            "suggestion",
+            suggestion(error, context).map_or(Json::Null, Json::from),
+        ),
+    ])
+}
+
+/// Os erros de `input` no formato `format`, uma linha por erro.
+pub fn format_errors(
+    errors: &[Error],
+    input: &str,
+    format: ErrorFormat,
+    context: &EvalContext,
+) -> String {
+    let lines: Vec<String> = errors
+        .iter()
+        .map(|error| match format {
+            ErrorFormat::Human => error.to_string(),
+            ErrorFormat::Json => diagnostic_json(error, input, format, context).to_string(),
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Posição em bytes do caractere `index` de `text`, ou o tamanho do texto se ele não for tão
+/// longo.
+fn byte_offset(text: &str, index: usize) -> usize {
+    text.char_indices()
+        .nth(index)
+        .map_or(text.len(), |(offset, _)| offset)
+}
+
+/// Como corrigir `error`, para todos os erros de síntaxe e de cálculo que têm uma correção
+/// comum. Números inválidos, argumentos fora do domínio e interrupções não têm.
+fn suggestion(error: &Error, context: &EvalContext) -> Option<String> {
+    match error {
+        Error::UnknownIdentifier(name, _) => {
+            let names = context
+                .variables()
+                .map(|(name, _)| name)
+                .chain(context.constants().map(|(name, _)| name))
+                .chain(CONSTANTS.iter().map(|(name, _)| *name))
+                .chain(UNITS.iter().map(|(name, _)| *name));
+            closest(name, names).map(|name| format!("você quis dizer '{}'?", name))
+        }
+        Error::UnknownFunction(name, _) => {
+            let names = context
+                .functions()
+                .map(|(name, _)| name)
+                .chain(BUILTINS.iter().map(|builtin| builtin.name()));
+            closest(name, names).map(|name| format!("você quis dizer '{}'?", name))
+        }
+        Error::UnclosedParenthesis(_) => Some("feche o parêntese com ')'".to_string()),
+        Error::ExtraParenthesis(_) => Some("remova o ')' ou abra um '(' antes dele".to_string()),
+        Error::InvalidAssignment(_) => Some("para comparar dois valores use '=='".to_string()),
+        Error::ImplicitMultiplication(_) => {
+            Some("escreva o '*' entre o número e a unidade".to_string())
+        }
+        Error::MissingOperand(_) | Error::TrailingOperator(_) => {
+            Some("complete a operação com um número ou remova o operador".to_string())
+        }
+        Error::MissingOperator(_) => {
+            Some("escreva um operador, como '*', entre os dois valores".to_string())
+        }
+        Error::ConsecutiveOperators(_) => Some("remova um dos operadores".to_string()),
+        Error::EmptyParenthesis(_) => {
+            Some("escreva uma expressão dentro dos parênteses ou remova-os".to_string())
+        }
+        Error::UnexpectedCharacter(char, _) => Some(format!("remova o '{}'", char)),
+        Error::WrongArgumentCount(name, expected, _, _) => {
+            Some(format!("passe {} argumento(s) para '{}'", expected, name))
+        }
+        Error::TooFewArguments(name, minimum, _, _) => Some(format!(
+            "passe pelo menos {} argumento(s) para '{}'",
+            minimum, name
+        )),
+        Error::LimitExceeded(Limit::Nesting(_), _) => {
+            Some("guarde partes da expressão em variáveis".to_string())
+        }
+        Error::LimitExceeded(Limit::CallDepth(_), _) => {
+            Some("confira se a recursão da função chega numa condição de parada".to_string())
+        }
+        Error::AssignmentToConstant(name, _) => {
+            Some(format!("use outro nome em vez de '{}'", name))
+        }
+        Error::IncompatibleUnits(..) => {
+            Some("converta os valores para a mesma grandeza antes de combiná-los".to_string())
+        }
+        Error::UnitExponent(_) => Some(
+            "use um expoente conhecido, que deixe as unidades com potências inteiras".to_string(),
+        ),
+        _ => None,
+    }
+}
+
+/// O nome de `names` mais parecido com `name`, se algum for parecido o bastante (no máximo uma
+/// edição a cada três letras, então nomes curtos como `x` não recebem sugestões).
+fn closest<'a>(name: &str, names: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = name.chars().count() / 3;
+    names
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Distância de Levenshtein: quantas inserções, remoções ou trocas de um caractere levam de `a`
+/// a `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[test]
+fn diagnostics_should_have_byte_spans_and_suggestions() {
+    let mut context = EvalContext::new();
+    context.set_variable("rate", 0.05);
+    let input = "preço = rat * 2";
+    let errors = asdf_calc::Statement::new(input, &context).unwrap_err();
+    assert_eq!(
+        format_errors(&errors, input, ErrorFormat::Json, &context),
+        r#"{"kind":"unknown_identifier","message":"nome desconhecido 'rat'","span":{"start":9,"end":12},"suggestion":"você quis dizer 'rate'?"}"#
+    );
+    assert_eq!(
+        format_errors(&errors, input, ErrorFormat::Human, &context),
+        "coluna 9: nome desconhecido 'rat'"
+    );
+
+    let error = Error::UnknownFunction("gama".to_string(), asdf_calc::Span::new(0, 4));
+    assert_eq!(
+        suggestion(&error, &context),
+        Some("você quis dizer 'gamma'?".to_string())
+    );
+    // os erros do cálculo também têm sugestões
+    let input = "5 m + 2 s";
+    let error = asdf_calc::Expression::with_context(input, &context)
+        .unwrap()
+        .dimension(&context)
+        .unwrap_err();
+    assert_eq!(
+        diagnostic_json(&error, input, ErrorFormat::Json, &context).to_string(),
+        r#"{"kind":"incompatible_units","message":"unidades incompatíveis: 'm' e 's'","span":{"start":2,"end":9},"suggestion":"converta os valores para a mesma grandeza antes de combiná-los"}"#
+    );
+    let error = Error::LimitExceeded(Limit::CallDepth(128), asdf_calc::Span::new(0, 1));
+    assert!(suggestion(&error, &context).is_some());
+    let error = Error::WrongArgumentCount("sin".to_string(), 1, 2, asdf_calc::Span::new(0, 3));
+    assert_eq!(
+        suggestion(&error, &context),
+        Some("passe 1 argumento(s) para 'sin'".to_string())
+    );
+    let error = Error::UnknownIdentifier("xyz".to_string(), asdf_calc::Span::new(0, 3));
+    assert_eq!(suggestion(&error, &context), None);
+    let error = Error::UnknownIdentifier("x".to_string(), asdf_calc::Span::new(0, 1));
+    assert_eq!(suggestion(&error, &context), None);
+}
+
+#[test]
+fn edit_distance_should_count_single_character_edits() {
+    assert_eq!(edit_distance("rate", "rate"), 0);
+    assert_eq!(edit_distance("rat", "rate"), 1);
+    assert_eq!(edit_distance("sinn", "sin"), 1);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "abc"), 3);
+}
@@ -0,0 +1,140 @@
+//! Como os resultados com dimensão de tempo são mostrados quando a linha não pede uma unidade
+//! com `to`: como as pessoas escrevem (`8h 53m 20s`), como um relógio (`08:53:20`) ou em horas
+//! (`8.888888888888889 h`), escolhido com `format duration humanized|hh:mm:ss|decimal`.
+
+use crate::format::Notation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DurationFormat {
+    #[default]
+    Humanized,
+    /// `hh:mm:ss`, com as horas passando de 24 em vez de virar dias, como nos agendamentos.
+    Clock,
+    /// Horas com casas decimais, como nas planilhas de horas trabalhadas.
+    Decimal,
+}
+
+impl DurationFormat {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.trim() {
+            "humanized" => Ok(DurationFormat::Humanized),
+            "hh:mm:ss" => Ok(DurationFormat::Clock),
+            "decimal" => Ok(DurationFormat::Decimal),
+            other => Err(format!(
+                "formato de duração desconhecido: {} (use humanized, hh:mm:ss ou decimal)",
+                other
+            )),
+        }
+    }
+
+    /// `seconds` neste formato, com as horas decimais escritas na notação `notation`.
+    pub fn format(self, seconds: f64, notation: &Notation) -> String {
+        match self {
+            DurationFormat::Humanized => humanize(seconds),
+            DurationFormat::Clock => clock(seconds),
+            DurationFormat::Decimal => format!("{} h", notation.format(seconds / 3600.0)),
+        }
+    }
+}
+
+impl std::fmt::Display for DurationFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DurationFormat::Humanized => write!(f, "humanized"),
+            DurationFormat::Clock => write!(f, "hh:mm:ss"),
+            DurationFormat::Decimal => write!(f, "decimal"),
+        }
+    }
+}
+
+/// Dias, horas, minutos e segundos, omitindo os que são zero, com os segundos arredondados
+/// para milissegundos (`1d 2h 0.5s`).
+pub fn humanize(seconds: f64) -> String {
+    // acima disso os milissegundos não cabem mais num `u64` sem perder precisão
+    if !seconds.is_finite() || seconds.abs() >= 1e15 {
+        return format!("{}s", seconds);
+    }
+    let mut millis = (seconds.abs() * 1000.0).round() as u64;
+    let mut parts = Vec::new();
+    for (suffix, length) in [("d", 86_400_000), ("h", 3_600_000), ("m", 60_000)] {
+        if millis >= length {
+            parts.push(format!("{}{}", millis / length, suffix));
+            millis %= length;
+        }
+    }
+    if millis > 0 || parts.is_empty() {
+        let fraction = format!("{:03}", millis % 1000);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            parts.push(format!("{}s", millis / 1000));
+        } else {
+            parts.push(format!("{}.{}s", millis / 1000, fraction));
+        }
+    }
+    let sign = if seconds < 0.0 && parts != ["0s"] {
+        "-"
+    } else {
+        ""
+    };
+    format!("{}{}", sign, parts.join(" "))
+}
+
+/// `hh:mm:ss`, com os milissegundos depois dos segundos se houver (`00:00:01.5`).
+pub fn clock(seconds: f64) -> String {
+    if !seconds.is_finite() || seconds.abs() >= 1e15 {
+        return format!("{}s", seconds);
+    }
+    let millis = (seconds.abs() * 1000.0).round() as u64;
+    let (hours, minutes) = (millis / 3_600_000, millis / 60_000 % 60);
+    let fraction = format!("{:03}", millis % 1000);
+    let fraction = fraction.trim_end_matches('0');
+    let sign = if seconds < 0.0 && millis > 0 { "-" } else { "" };
+    let mut text = format!(
+        "{}{:02}:{:02}:{:02}",
+        sign,
+        hours,
+        minutes,
+        millis / 1000 % 60
+    );
+    if !fraction.is_empty() {
+        text.push('.');
+        text.push_str(fraction);
+    }
+    text
+}
+
+#[test]
+fn humanize_should_split_the_duration_in_units() {
+    assert_eq!(humanize(32000.0), "8h 53m 20s");
+    assert_eq!(humanize(86400.0 + 7200.5), "1d 2h 0.5s");
+    assert_eq!(humanize(90.0), "1m 30s");
+    assert_eq!(humanize(0.25), "0.25s");
+    assert_eq!(humanize(0.0), "0s");
+    assert_eq!(humanize(-0.0001), "0s");
+    assert_eq!(humanize(-60.0), "-1m");
+    assert_eq!(humanize(f64::INFINITY), "infs");
+}
+
+#[test]
+fn clock_should_keep_counting_hours_past_a_day() {
+    assert_eq!(clock(32000.0), "08:53:20");
+    assert_eq!(clock(93600.0), "26:00:00");
+    assert_eq!(clock(1.5), "00:00:01.5");
+    assert_eq!(clock(-3600.0), "-01:00:00");
+    assert_eq!(clock(-0.0001), "00:00:00");
+}
+
+#[test]
+fn duration_format_should_be_chosen_by_name() {
+    let notation = Notation::default();
+    let format = |name: &str| {
+        DurationFormat::parse(name)
+            .unwrap()
+            .format(5400.0, &notation)
+    };
+    assert_eq!(format("humanized"), "1h 30m");
+    assert_eq!(format("hh:mm:ss"), "01:30:00");
+    assert_eq!(format("decimal"), "1.5 h");
+    assert!(DurationFormat::parse("iso").is_err());
+    assert_eq!(DurationFormat::Clock.to_string(), "hh:mm:ss");
+}
@@ -0,0 +1,699 @@
+//! Editor de linha da calculadora interativa: setas, Home/End, histórico com ↑/↓ e busca nele com
+//! Ctrl+R, destaque do `(` correspondente quando um `)` é digitado, Tab para completar os nomes
+//! das opções de `set` e `show`, Alt+. e Alt+, para inserir o último resultado e a última linha
+//! (como o Alt+. do bash) e, opcionalmente, fechamento automático dos parênteses. Quando o
+//! terminal não pode entrar em modo raw, as linhas são lidas inteiras.
+
+use std::io::{self, BufRead, Read, Write};
+
+use crate::{
+    session,
+    terminal::{self, RawMode},
+};
+
+/// Opções do editor, configuráveis com `match_brackets on|off` e `autoclose on|off` (no
+/// `init.calc`, por exemplo).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EditorOptions {
+    /// Destaca o `(` correspondente logo depois de um `)` ser digitado.
+    pub match_brackets: bool,
+    /// Digitar `(` também insere o `)`, que é pulado se for digitado em seguida.
+    pub autoclose: bool,
+}
+
+impl Default for EditorOptions {
+    fn default() -> Self {
+        EditorOptions {
+            match_brackets: true,
+            autoclose: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    Up,
+    Down,
+    /// Ctrl+C, que descarta a linha.
+    Interrupt,
+    /// Ctrl+D, que encerra o input se a linha estiver vazia.
+    Eof,
+    /// Ctrl+R, busca no histórico.
+    Search,
+    /// Ctrl+G, que cancela a busca.
+    Cancel,
+    Tab,
+    /// Alt+., insere o último resultado.
+    LastResult,
+    /// Alt+,, insere a última linha enviada.
+    LastInput,
+    /// Teclas e sequências que o editor não usa.
+    Ignored,
+}
+
+/// Lê uma tecla do terminal em modo raw. Retorna `None` no fim do input.
+fn read_key(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let Some(byte) = read_byte(input)? else {
+        return Ok(None);
+    };
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x01 => Key::Home,
+        0x05 => Key::End,
+        0x02 => Key::Left,
+        0x06 => Key::Right,
+        0x03 => Key::Interrupt,
+        0x04 => Key::Eof,
+        0x12 => Key::Search,
+        0x07 => Key::Cancel,
+        b'\t' => Key::Tab,
+        0x1b => read_escape(input)?,
+        byte if byte < 0x20 => Key::Ignored,
+        byte => {
+            // o resto do caractere em UTF-8, pela quantidade de 1s no começo do primeiro byte
+            let length = match byte.leading_ones() {
+                0 => 1,
+                length @ 2..=4 => length as usize,
+                _ => return Ok(Some(Key::Ignored)),
+            };
+            let mut bytes = vec![byte];
+            for _ in 1..length {
+                match read_byte(input)? {
+                    Some(byte) => bytes.push(byte),
+                    None => return Ok(None),
+                }
+            }
+            match std::str::from_utf8(&bytes) {
+                Ok(text) => Key::Char(text.chars().next().unwrap_or_default()),
+                Err(_) => Key::Ignored,
+            }
+        }
+    };
+    Ok(Some(key))
+}
+
+/// Sequências que começam com ESC: `ESC [ A` (↑), `ESC [ 3 ~` (Delete), `ESC O H` (Home)... e
+/// as teclas com Alt, que chegam como ESC seguido do caractere.
+fn read_escape(input: &mut impl Read) -> io::Result<Key> {
+    let key = match read_byte(input)? {
+        Some(b'[') | Some(b'O') => match read_byte(input)? {
+            Some(b'A') => Key::Up,
+            Some(b'B') => Key::Down,
+            Some(b'C') => Key::Right,
+            Some(b'D') => Key::Left,
+            Some(b'H') => Key::Home,
+            Some(b'F') => Key::End,
+            Some(digit @ b'0'..=b'9') => {
+                let mut number = vec![digit];
+                loop {
+                    match read_byte(input)? {
+                        Some(b'~') | None => break,
+                        Some(byte) => number.push(byte),
+                    }
+                }
+                match number.as_slice() {
+                    b"1" | b"7" => Key::Home,
+                    b"4" | b"8" => Key::End,
+                    b"3" => Key::Delete,
+                    _ => Key::Ignored,
+                }
+            }
+            _ => Key::Ignored,
+        },
+        Some(b'.') => Key::LastResult,
+        Some(b',') => Key::LastInput,
+        _ => Key::Ignored,
+    };
+    Ok(key)
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Como a busca no histórico terminou.
+#[derive(Debug, Clone, PartialEq)]
+enum SearchEnd {
+    /// Enter, que envia a linha encontrada.
+    Submit(String),
+    /// Outra tecla de edição (setas, Home...), que deixa a linha encontrada para ser editada.
+    Edit(String),
+    /// Ctrl+G ou Ctrl+C, que voltam para a linha de antes da busca.
+    Cancel,
+    /// Fim do input.
+    Eof,
+}
+
+/// Texto da linha sendo editada, com o cursor como índice de caractere.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct LineBuffer {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl LineBuffer {
+    fn set(&mut self, text: &str) {
+        self.chars = text.chars().collect();
+        self.cursor = self.chars.len();
+    }
+
+    fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    fn insert(&mut self, char: char, options: EditorOptions) {
+        if options.autoclose && char == ')' && self.chars.get(self.cursor) == Some(&')') {
+            self.cursor += 1;
+            return;
+        }
+        self.chars.insert(self.cursor, char);
+        self.cursor += 1;
+        if options.autoclose && char == '(' {
+            self.chars.insert(self.cursor, ')');
+        }
+    }
+
+    /// Insere `text` no cursor como está, sem o fechamento automático dos parênteses.
+    fn insert_text(&mut self, text: &str) {
+        let chars: Vec<char> = text.chars().collect();
+        let length = chars.len();
+        self.chars.splice(self.cursor..self.cursor, chars);
+        self.cursor += length;
+    }
+
+    fn backspace(&mut self, options: EditorOptions) {
+        if self.cursor == 0 {
+            return;
+        }
+        self.cursor -= 1;
+        let removed = self.chars.remove(self.cursor);
+        // apagar o `(` de um `()` vazio também apaga o `)` que foi inserido junto
+        if options.autoclose && removed == '(' && self.chars.get(self.cursor) == Some(&')') {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    /// Completa a palavra antes do cursor com `session::complete`: com um só nome possível, ele
+    /// inteiro e um espaço; com vários, o começo que todos eles têm em comum. Retorna os nomes
+    /// possíveis quando não há nada a completar, para que sejam mostrados.
+    fn complete(&mut self) -> Vec<&'static str> {
+        let before: String = self.chars[..self.cursor].iter().collect();
+        let names = session::complete(&before);
+        let typed = before.len() - before.trim_end_matches(|char| char != ' ').len();
+        let common = match names.as_slice() {
+            [] => return names,
+            [name] => format!("{} ", name),
+            [first, rest @ ..] => {
+                let mut common = first.to_string();
+                for name in rest {
+                    while !name.starts_with(&common) {
+                        common.pop();
+                    }
+                }
+                common
+            }
+        };
+        if common.len() == typed {
+            return names;
+        }
+        for char in common[typed..].chars() {
+            self.chars.insert(self.cursor, char);
+            self.cursor += 1;
+        }
+        Vec::new()
+    }
+
+    /// Posição do `(` correspondente ao `)` logo antes do cursor.
+    fn matching_open(&self) -> Option<usize> {
+        if self.cursor == 0 || self.chars[self.cursor - 1] != ')' {
+            return None;
+        }
+        let mut depth = 0usize;
+        for i in (0..self.cursor).rev() {
+            match self.chars[i] {
+                ')' => depth += 1,
+                '(' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Sequência que redesenha a linha depois do prompt, com o caractere de `highlight` em vídeo
+    /// reverso, e deixa o cursor do terminal na posição do cursor.
+    fn render(&self, prompt: &str, highlight: Option<usize>) -> String {
+        let mut output = format!("\r{}", prompt);
+        for (i, char) in self.chars.iter().enumerate() {
+            if highlight == Some(i) {
+                output.push_str(&format!("\x1b[7m{}\x1b[0m", char));
+            } else {
+                output.push(*char);
+            }
+        }
+        output.push_str("\x1b[K");
+        let after_cursor = self.chars.len() - self.cursor;
+        if after_cursor > 0 {
+            output.push_str(&format!("\x1b[{}D", after_cursor));
+        }
+        output
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Editor {
+    /// Linhas já enviadas, da mais antiga para a mais recente.
+    history: Vec<String>,
+    /// O que o Alt+. insere, escrito como pode ser lido de volta.
+    last_result: Option<String>,
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Editor::default()
+    }
+
+    /// Guarda o resultado que o Alt+. insere, ou esquece o anterior com `None`.
+    pub fn set_last_result(&mut self, literal: Option<String>) {
+        self.last_result = literal;
+    }
+
+    /// Mostra `prompt` e lê uma linha, sem a quebra de linha do fim. Retorna `None` no fim do
+    /// input (Ctrl+D numa linha vazia).
+    pub fn read_line(
+        &mut self,
+        prompt: &str,
+        options: EditorOptions,
+    ) -> io::Result<Option<String>> {
+        let line = self.read_answer(prompt, options)?;
+        if let Some(line) = &line {
+            self.remember(line);
+        }
+        Ok(line)
+    }
+
+    /// Como `read_line`, mas sem guardar a linha no histórico, para respostas como a do `[s/N]`.
+    pub fn read_answer(
+        &mut self,
+        prompt: &str,
+        options: EditorOptions,
+    ) -> io::Result<Option<String>> {
+        let mut stdout = io::stdout();
+        let Some(raw_mode) = RawMode::enable() else {
+            stdout.write_all(prompt.as_bytes())?;
+            stdout.flush()?;
+            let mut line = String::new();
+            if io::stdin().lock().read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let length = line.trim_end_matches(['\r', '\n']).len();
+            line.truncate(length);
+            return Ok(Some(line));
+        };
+
+        let line = self.edit(prompt, options, &mut io::stdin().lock(), &mut stdout);
+        drop(raw_mode);
+        line
+    }
+
+    /// Guarda `line` no histórico, como se ela tivesse sido digitada.
+    pub fn remember(&mut self, line: &str) {
+        if !line.trim().is_empty() && self.history.last().map(String::as_str) != Some(line) {
+            self.history.push(line.to_string());
+        }
+    }
+
+    fn edit(
+        &self,
+        prompt: &str,
+        options: EditorOptions,
+        input: &mut impl Read,
+        output: &mut impl Write,
+    ) -> io::Result<Option<String>> {
+        let mut buffer = LineBuffer::default();
+        // posição no histórico enquanto ele é percorrido com ↑/↓, e a linha que estava sendo
+        // escrita antes disso
+        let mut browsing: Option<(usize, String)> = None;
+        let mut highlight = None;
+        output.write_all(buffer.render(prompt, None).as_bytes())?;
+        output.flush()?;
+
+        loop {
+            let Some(key) = read_key(input)? else {
+                return Ok(None);
+            };
+            highlight = match key {
+                Key::Char(char) => {
+                    buffer.insert(char, options);
+                    // o destaque é a única cor do editor, e some com o `NO_COLOR`
+                    if char == ')' && options.match_brackets && terminal::colors() {
+                        buffer.matching_open()
+                    } else {
+                        None
+                    }
+                }
+                Key::Enter => {
+                    // sem o destaque, que ficaria na tela depois da linha ser enviada
+                    output.write_all(buffer.render(prompt, None).as_bytes())?;
+                    output.write_all(b"\n")?;
+                    output.flush()?;
+                    return Ok(Some(buffer.text()));
+                }
+                Key::Interrupt => {
+                    output.write_all(b"^C\n")?;
+                    output.flush()?;
+                    return Ok(Some(String::new()));
+                }
+                Key::Eof if buffer.chars.is_empty() => {
+                    output.write_all(b"\n")?;
+                    output.flush()?;
+                    return Ok(None);
+                }
+                Key::Eof | Key::Delete => {
+                    buffer.delete();
+                    None
+                }
+                Key::Backspace => {
+                    buffer.backspace(options);
+                    None
+                }
+                Key::Left => {
+                    buffer.cursor = buffer.cursor.saturating_sub(1);
+                    None
+                }
+                Key::Right => {
+                    buffer.cursor = (buffer.cursor + 1).min(buffer.chars.len());
+                    None
+                }
+                Key::Home => {
+                    buffer.cursor = 0;
+                    None
+                }
+                Key::End => {
+                    buffer.cursor = buffer.chars.len();
+                    None
+                }
+                Key::Up => {
+                    let index = match &browsing {
+                        Some((index, _)) => index.checked_sub(1),
+                        None => self.history.len().checked_sub(1),
+                    };
+                    if let Some(index) = index {
+                        let current = browsing.take().map(|(_, current)| current);
+                        browsing = Some((index, current.unwrap_or_else(|| buffer.text())));
+                        buffer.set(&self.history[index]);
+                    }
+                    None
+                }
+                Key::Down => {
+                    if let Some((index, current)) = browsing.take() {
+                        if index + 1 < self.history.len() {
+                            buffer.set(&self.history[index + 1]);
+                            browsing = Some((index + 1, current));
+                        } else {
+                            buffer.set(&current);
+                        }
+                    }
+                    None
+                }
+                Key::Search => match self.search(input, output)? {
+                    SearchEnd::Submit(line) => {
+                        buffer.set(&line);
+                        output.write_all(buffer.render(prompt, None).as_bytes())?;
+                        output.write_all(b"\n")?;
+                        output.flush()?;
+                        return Ok(Some(line));
+                    }
+                    SearchEnd::Edit(line) => {
+                        browsing = None;
+                        buffer.set(&line);
+                        None
+                    }
+                    SearchEnd::Cancel => None,
+                    SearchEnd::Eof => return Ok(None),
+                },
+                Key::Tab => {
+                    let names = buffer.complete();
+                    if !names.is_empty() {
+                        output.write_all(format!("\r\n{}\r\n", names.join("  ")).as_bytes())?;
+                    }
+                    None
+                }
+                Key::LastResult => {
+                    buffer.insert_text(self.last_result.as_deref().unwrap_or_default());
+                    None
+                }
+                Key::LastInput => {
+                    buffer.insert_text(self.history.last().map_or("", String::as_str));
+                    None
+                }
+                Key::Cancel | Key::Ignored => highlight,
+            };
+            output.write_all(buffer.render(prompt, highlight).as_bytes())?;
+            output.flush()?;
+        }
+    }
+
+    /// Busca incremental no histórico, da linha mais recente para a mais antiga: cada caractere
+    /// digitado refina a busca, e outro Ctrl+R passa para a ocorrência anterior.
+    fn search(&self, input: &mut impl Read, output: &mut impl Write) -> io::Result<SearchEnd> {
+        let mut query = String::new();
+        // a linha encontrada, como índice no histórico
+        let mut found: Option<usize> = None;
+        loop {
+            let line = found.map_or("", |index| self.history[index].as_str());
+            // a última linha encontrada continua na tela quando a busca mais longa falha
+            let label = if line.contains(&query) {
+                "busca"
+            } else {
+                "busca falhou"
+            };
+            output.write_all(format!("\r({})'{}': {}\x1b[K", label, query, line).as_bytes())?;
+            output.flush()?;
+
+            let Some(key) = read_key(input)? else {
+                return Ok(SearchEnd::Eof);
+            };
+            match key {
+                Key::Char(char) => {
+                    query.push(char);
+                    // a linha atual ainda pode conter a busca mais longa
+                    let end = found.map_or(self.history.len(), |index| index + 1);
+                    found = self.find(&query, end).or(found);
+                }
+                Key::Backspace => {
+                    query.pop();
+                    found = self.find(&query, self.history.len());
+                }
+                Key::Search if !query.is_empty() => {
+                    let end = found.unwrap_or(self.history.len());
+                    found = self.find(&query, end).or(found);
+                }
+                Key::Search | Key::Ignored => {}
+                Key::Enter => return Ok(SearchEnd::Submit(line.to_string())),
+                Key::Cancel | Key::Interrupt | Key::Eof => return Ok(SearchEnd::Cancel),
+                _ => return Ok(SearchEnd::Edit(line.to_string())),
+            }
+        }
+    }
+
+    /// A linha mais recente antes de `end` no histórico que contém `query`.
+    fn find(&self, query: &str, end: usize) -> Option<usize> {
+        if query.is_empty() {
+            return None;
+        }
+        self.history[..end]
+            .iter()
+            .rposition(|line| line.contains(query))
+    }
+}
+
+#[test]
+fn read_key_should_decode_escape_sequences_and_utf8() {
+    let mut input: &[u8] = b"\x1b[A\x1b[3~\x1bOH\x1b[4~a\r\x7f\x03\xc2\xb0";
+    let mut keys = Vec::new();
+    while let Some(key) = read_key(&mut input).unwrap() {
+        keys.push(key);
+    }
+    assert_eq!(
+        keys,
+        vec![
+            Key::Up,
+            Key::Delete,
+            Key::Home,
+            Key::End,
+            Key::Char('a'),
+            Key::Enter,
+            Key::Backspace,
+            Key::Interrupt,
+            Key::Char('\u{b0}'),
+        ]
+    );
+}
+
+#[test]
+fn line_buffer_should_auto_close_and_match_brackets() {
+    let autoclose = EditorOptions {
+        match_brackets: true,
+        autoclose: true,
+    };
+    let mut buffer = LineBuffer::default();
+    for char in "max(1, (2".chars() {
+        buffer.insert(char, autoclose);
+    }
+    assert_eq!(buffer.text(), "max(1, (2))");
+    buffer.insert(')', autoclose);
+    assert_eq!(buffer.text(), "max(1, (2))");
+    assert_eq!(buffer.matching_open(), Some(7));
+    buffer.insert(')', autoclose);
+    assert_eq!(buffer.matching_open(), Some(3));
+    assert_eq!(buffer.cursor, buffer.chars.len());
+
+    let mut buffer = LineBuffer::default();
+    buffer.insert('(', autoclose);
+    buffer.backspace(autoclose);
+    assert_eq!(buffer.text(), "");
+
+    let mut buffer = LineBuffer::default();
+    for char in "1 + 2)".chars() {
+        buffer.insert(char, EditorOptions::default());
+    }
+    assert_eq!(buffer.text(), "1 + 2)");
+    assert_eq!(buffer.matching_open(), None);
+}
+
+#[test]
+fn editor_should_edit_and_recall_lines() {
+    let editor = Editor {
+        history: vec!["1 + 1".to_string(), "2 * 3".to_string()],
+        ..Editor::default()
+    };
+    let edit = |keys: &[u8]| {
+        let mut input = keys;
+        editor
+            .edit("> ", EditorOptions::default(), &mut input, &mut Vec::new())
+            .unwrap()
+    };
+    assert_eq!(edit(b"12\x1b[D3\r"), Some("132".to_string()));
+    assert_eq!(edit(b"x\x1b[A\x1b[A\r"), Some("1 + 1".to_string()));
+    assert_eq!(edit(b"x\x1b[A\x1b[B\r"), Some("x".to_string()));
+    assert_eq!(edit(b"abc\x01\x1b[3~\r"), Some("bc".to_string()));
+    assert_eq!(edit(b"abc\x03"), Some(String::new()));
+    assert_eq!(edit(b"\x04"), None);
+}
+
+#[test]
+fn editor_should_search_the_history() {
+    let editor = Editor {
+        history: vec![
+            "rate = 0.05".to_string(),
+            "1000 * rate".to_string(),
+            "2 + 2".to_string(),
+        ],
+        ..Editor::default()
+    };
+    let edit = |keys: &[u8]| {
+        let mut input = keys;
+        editor
+            .edit("> ", EditorOptions::default(), &mut input, &mut Vec::new())
+            .unwrap()
+    };
+    assert_eq!(edit(b"\x12rate\r"), Some("1000 * rate".to_string()));
+    assert_eq!(edit(b"\x12rate\x12\r"), Some("rate = 0.05".to_string()));
+    assert_eq!(edit(b"\x12rate =\r"), Some("rate = 0.05".to_string()));
+    // sem outra ocorrência, a busca fica na última encontrada
+    assert_eq!(edit(b"\x12rate\x12\x12\r"), Some("rate = 0.05".to_string()));
+    assert_eq!(edit(b"\x122 +\x1b[C * 3\r"), Some("2 + 2 * 3".to_string()));
+    assert_eq!(edit(b"9\x12rate\x07\r"), Some("9".to_string()));
+    assert_eq!(edit(b"\x12xyz\r"), Some(String::new()));
+}
+
+#[test]
+fn line_buffer_should_render_the_highlight_and_cursor() {
+    let mut buffer = LineBuffer::default();
+    buffer.set("(1)+2");
+    buffer.cursor = 3;
+    assert_eq!(
+        buffer.render("> ", Some(0)),
+        "\r> \x1b[7m(\x1b[0m1)+2\x1b[K\x1b[2D"
+    );
+}
+
+#[test]
+fn line_buffer_should_complete_option_names() {
+    let mut buffer = LineBuffer::default();
+    buffer.set("set an");
+    assert!(buffer.complete().is_empty());
+    assert_eq!(buffer.text(), "set angle ");
+    buffer.set("show a");
+    assert_eq!(
+        buffer.complete(),
+        ["angle", "autostore", "all_bases", "approx", "autoclose"]
+    );
+    assert_eq!(buffer.text(), "show a");
+    buffer.set("set matchx");
+    buffer.cursor = 9;
+    assert!(buffer.complete().is_empty());
+    assert_eq!(buffer.text(), "set match_brackets x");
+    buffer.set("show o");
+    assert!(buffer.complete().is_empty());
+    assert_eq!(buffer.text(), "show options ");
+    buffer.set("2 + an");
+    assert!(buffer.complete().is_empty());
+    assert_eq!(buffer.text(), "2 + an");
+}
+
+#[test]
+fn editor_should_insert_the_last_result_and_input() {
+    let mut editor = Editor {
+        history: vec!["1 + 1".to_string(), "2 km * 3".to_string()],
+        ..Editor::default()
+    };
+    let edit = |editor: &Editor, keys: &[u8]| {
+        let mut input = keys;
+        editor
+            .edit("> ", EditorOptions::default(), &mut input, &mut Vec::new())
+            .unwrap()
+    };
+    assert_eq!(edit(&editor, b"x + \x1b.\r"), Some("x + ".to_string()));
+    editor.set_last_result(Some("6 km".to_string()));
+    assert_eq!(
+        edit(&editor, b"(\x1b.) * 2\r"),
+        Some("(6 km) * 2".to_string())
+    );
+    assert_eq!(
+        edit(&editor, b"\x1b, to m\r"),
+        Some("2 km * 3 to m".to_string())
+    );
+    assert_eq!(
+        edit(&editor, b"2\x1b[D\x1b,\r"),
+        Some("2 km * 32".to_string())
+    );
+}
@@ -0,0 +1,171 @@
+//! Substituição de `${NOME}` pelo valor de uma variável de ambiente (`--env`), feita no texto
+//! antes dele ser lido, para que scripts possam passar valores com `WIDTH=3 asdf-calc ...`.
+
+use asdf_calc::{Error, Span};
+
+/// O texto com as variáveis trocadas, junto das posições das trocas, para que os erros
+/// encontrados nele depois apontem para o texto original.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interpolated {
+    pub text: String,
+    /// O trecho de cada `${NOME}` no texto original e quantos caracteres ficaram no lugar dele.
+    replacements: Vec<(Span, usize)>,
+}
+
+impl Interpolated {
+    /// `text` sem nenhuma variável trocada.
+    pub fn unchanged(text: &str) -> Self {
+        Interpolated {
+            text: text.to_string(),
+            replacements: Vec::new(),
+        }
+    }
+
+    /// `errors` com os trechos do texto interpolado trocados pelos do original. Um trecho que
+    /// começa ou termina dentro de um valor passa a começar ou terminar no `${NOME}` dele.
+    pub fn restore(&self, errors: Vec<Error>) -> Vec<Error> {
+        errors
+            .into_iter()
+            .map(|error| {
+                let span = error.span();
+                let span = Span::new(
+                    self.original(span.start, false),
+                    self.original(span.end, true),
+                );
+                error.with_span(span)
+            })
+            .collect()
+    }
+
+    /// A posição no texto original do caractere `index` do interpolado, ou do fim de um trecho
+    /// que termina em `index` se `end`.
+    fn original(&self, index: usize, end: bool) -> usize {
+        // o quanto as trocas anteriores à atual mudaram as posições
+        let mut shift = 0;
+        for (span, length) in &self.replacements {
+            let start = (span.start as isize - shift) as usize;
+            let (before, inside) = if end {
+                (index <= start, index <= start + length)
+            } else {
+                (index < start, index < start + length)
+            };
+            if before {
+                break;
+            }
+            if inside {
+                return if end { span.end } else { span.start };
+            }
+            shift += (span.end - span.start) as isize - *length as isize;
+        }
+        (index as isize + shift) as usize
+    }
+}
+
+/// Troca cada `${NOME}` de `text` pelo valor numérico que `lookup` der para `NOME`, entre
+/// parênteses para que um valor negativo não mude o sentido da expressão. Variáveis que não
+/// existem ou que não são números viram erros apontando para o `${NOME}` correspondente.
+pub fn interpolate<F>(text: &str, lookup: F) -> Result<Interpolated, Vec<Error>>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut replacements = Vec::new();
+    let mut errors = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '$' || chars.get(i + 1) != Some(&'{') {
+            output.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        let Some(length) = chars[i + 2..].iter().position(|char| *char == '}') else {
+            errors.push(Error::UnexpectedCharacter('$', Span::new(i, i + 1)));
+            break;
+        };
+        let name: String = chars[i + 2..i + 2 + length].iter().collect();
+        let span = Span::new(i, i + 3 + length);
+        i = span.end;
+
+        match lookup(&name) {
+            None => errors.push(Error::UnknownIdentifier(format!("${{{}}}", name), span)),
+            Some(value) => match value.trim().parse::<f64>() {
+                Ok(number) if number.is_finite() => {
+                    let value = format!("({})", number);
+                    replacements.push((span, value.chars().count()));
+                    output.push_str(&value);
+                }
+                _ => errors.push(Error::ToF64ParseError(
+                    format!("${{{}}} = {}", name, value),
+                    span,
+                )),
+            },
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(Interpolated {
+            text: output,
+            replacements,
+        })
+    } else {
+        Err(errors)
+    }
+}
+
+/// `interpolate` usando as variáveis de ambiente do processo.
+pub fn interpolate_env(text: &str) -> Result<Interpolated, Vec<Error>> {
+    interpolate(text, |name| std::env::var(name).ok())
+}
+
+#[test]
+fn interpolate_should_replace_numeric_variables() {
+    let lookup = |name: &str| match name {
+        "WIDTH" => Some("3".to_string()),
+        "OFFSET" => Some(" -1.5 ".to_string()),
+        "NAME" => Some("abc".to_string()),
+        _ => None,
+    };
+    assert_eq!(
+        interpolate("${WIDTH} * 2 - ${OFFSET}", lookup).map(|interpolated| interpolated.text),
+        Ok("(3) * 2 - (-1.5)".to_string())
+    );
+    assert_eq!(
+        interpolate("${HEIGHT} + ${NAME} + ${WIDTH", lookup),
+        Err(vec![
+            Error::UnknownIdentifier("${HEIGHT}".to_string(), Span::new(0, 9)),
+            Error::ToF64ParseError("${NAME} = abc".to_string(), Span::new(12, 19)),
+            Error::UnexpectedCharacter('$', Span::new(22, 23)),
+        ])
+    );
+}
+
+#[test]
+fn restore_should_point_errors_to_the_original_text() {
+    let lookup = |name: &str| match name {
+        "OFFSET" => Some("-1.5".to_string()),
+        "X" => Some("2".to_string()),
+        _ => None,
+    };
+    let input = "${OFFSET} * 2 + ${X} +";
+    let interpolated = interpolate(input, lookup).unwrap();
+    assert_eq!(interpolated.text, "(-1.5) * 2 + (2) +");
+    let errors = asdf_calc::Expression::new(&interpolated.text).unwrap_err();
+    assert_eq!(
+        interpolated.restore(errors),
+        vec![Error::TrailingOperator(Span::new(21, 22))]
+    );
+
+    // um trecho que cobre um valor trocado cobre o `${NOME}` inteiro
+    let errors = vec![Error::UnitExponent(Span::new(1, 15))];
+    assert_eq!(
+        interpolated.restore(errors),
+        vec![Error::UnitExponent(Span::new(0, 20))]
+    );
+    assert_eq!(
+        Interpolated::unchanged("1 +").restore(vec![Error::TrailingOperator(Span::new(2, 3))]),
+        vec![Error::TrailingOperator(Span::new(2, 3))]
+    );
+}
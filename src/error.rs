@@ -0,0 +1,302 @@
+use alloc::string::String;
+use core::fmt::{Display, Formatter};
+
+use crate::units::Dimension;
+
+/// Trecho do input, em índices de caracteres, ao qual um token ou um erro se refere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// Coluna (começando em 1) usada nas mensagens de erro.
+    pub fn column(&self) -> usize {
+        self.start + 1
+    }
+}
+
+/// Limite de `Limits` que foi excedido, com o valor configurado.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Nesting(usize),
+    CallDepth(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::enum_variant_names)]
+pub enum Error {
+    ToF64ParseError(String, Span),
+    ExtraParenthesis(Span),
+    UnclosedParenthesis(Span),
+    MissingOperand(Span),
+    MissingOperator(Span),
+    TrailingOperator(Span),
+    ConsecutiveOperators(Span),
+    EmptyParenthesis(Span),
+    UnknownIdentifier(String, Span),
+    UnexpectedCharacter(char, Span),
+    InvalidAssignment(Span),
+    UnknownFunction(String, Span),
+    /// Nome da função, quantidade de parâmetros e quantidade de argumentos passados.
+    WrongArgumentCount(String, usize, usize, Span),
+    /// Como `WrongArgumentCount`, para funções variádicas com um mínimo de argumentos.
+    TooFewArguments(String, usize, usize, Span),
+    /// Argumentos para os quais a função não está definida (`clamp(x, 1, 0)`).
+    OutOfDomain(String, Span),
+    /// O cálculo foi interrompido pela `InterruptFlag` do contexto, na chamada de função indicada.
+    Interrupted(Span),
+    LimitExceeded(Limit, Span),
+    /// Atribuição a uma constante definida com `EvalContext::define_constant`.
+    AssignmentToConstant(String, Span),
+    /// Operação entre grandezas que não podem ser combinadas (`1 m + 1 s`), com a dimensão de
+    /// cada lado.
+    IncompatibleUnits(Dimension, Dimension, Span),
+    /// Potência de uma grandeza com unidade cujo resultado não teria uma dimensão (`m^0.5`).
+    UnitExponent(Span),
+    /// Unidade escrita logo depois de um número (`5 km`) no modo estrito, em que o `*` é
+    /// obrigatório.
+    ImplicitMultiplication(Span),
+}
+
+impl Error {
+    pub fn span(&self) -> Span {
+        match self {
+            Error::ToF64ParseError(_, span)
+            | Error::ExtraParenthesis(span)
+            | Error::UnclosedParenthesis(span)
+            | Error::MissingOperand(span)
+            | Error::MissingOperator(span)
+            | Error::TrailingOperator(span)
+            | Error::ConsecutiveOperators(span)
+            | Error::EmptyParenthesis(span)
+            | Error::UnknownIdentifier(_, span)
+            | Error::UnexpectedCharacter(_, span)
+            | Error::InvalidAssignment(span)
+            | Error::UnknownFunction(_, span)
+            | Error::WrongArgumentCount(_, _, _, span)
+            | Error::TooFewArguments(_, _, _, span)
+            | Error::OutOfDomain(_, span)
+            | Error::Interrupted(span)
+            | Error::LimitExceeded(_, span)
+            | Error::AssignmentToConstant(_, span)
+            | Error::IncompatibleUnits(_, _, span)
+            | Error::UnitExponent(span)
+            | Error::ImplicitMultiplication(span) => *span,
+        }
+    }
+
+    /// O mesmo erro apontando para `span`, para quem calcula um texto diferente do que foi
+    /// digitado e precisa levar as posições de volta ao original.
+    pub fn with_span(mut self, span: Span) -> Self {
+        match &mut self {
+            Error::ToF64ParseError(_, old)
+            | Error::ExtraParenthesis(old)
+            | Error::UnclosedParenthesis(old)
+            | Error::MissingOperand(old)
+            | Error::MissingOperator(old)
+            | Error::TrailingOperator(old)
+            | Error::ConsecutiveOperators(old)
+            | Error::EmptyParenthesis(old)
+            | Error::UnknownIdentifier(_, old)
+            | Error::UnexpectedCharacter(_, old)
+            | Error::InvalidAssignment(old)
+            | Error::UnknownFunction(_, old)
+            | Error::WrongArgumentCount(_, _, _, old)
+            | Error::TooFewArguments(_, _, _, old)
+            | Error::OutOfDomain(_, old)
+            | Error::Interrupted(old)
+            | Error::LimitExceeded(_, old)
+            | Error::AssignmentToConstant(_, old)
+            | Error::IncompatibleUnits(_, _, old)
+            | Error::UnitExponent(old)
+            | Error::ImplicitMultiplication(old) => *old = span,
+        }
+        self
+    }
+
+    /// Nome estável do tipo do erro (`unknown_identifier`, `trailing_operator`...), para
+    /// programas que precisam tratar cada um de um jeito sem depender das mensagens.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::ToF64ParseError(..) => "invalid_number",
+            Error::ExtraParenthesis(_) => "extra_parenthesis",
+            Error::UnclosedParenthesis(_) => "unclosed_parenthesis",
+            Error::MissingOperand(_) => "missing_operand",
+            Error::MissingOperator(_) => "missing_operator",
+            Error::TrailingOperator(_) => "trailing_operator",
+            Error::ConsecutiveOperators(_) => "consecutive_operators",
+            Error::EmptyParenthesis(_) => "empty_parenthesis",
+            Error::UnknownIdentifier(..) => "unknown_identifier",
+            Error::UnexpectedCharacter(..) => "unexpected_character",
+            Error::InvalidAssignment(_) => "invalid_assignment",
+            Error::UnknownFunction(..) => "unknown_function",
+            Error::WrongArgumentCount(..) => "wrong_argument_count",
+            Error::TooFewArguments(..) => "too_few_arguments",
+            Error::OutOfDomain(..) => "out_of_domain",
+            Error::Interrupted(_) => "interrupted",
+            Error::LimitExceeded(..) => "limit_exceeded",
+            Error::AssignmentToConstant(..) => "assignment_to_constant",
+            Error::IncompatibleUnits(..) => "incompatible_units",
+            Error::UnitExponent(_) => "unit_exponent",
+            Error::ImplicitMultiplication(_) => "implicit_multiplication",
+        }
+    }
+
+    /// A mensagem do erro sem a coluna do começo, que o `Display` inclui.
+    pub fn message(&self) -> String {
+        alloc::format!("{}", Message(self))
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "coluna {}: {}", self.span().column(), Message(self))
+    }
+}
+
+struct Message<'a>(&'a Error);
+
+impl Display for Message<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Error::ToF64ParseError(text_portion, _) => {
+                write!(
+                    f,
+                    "não foi possível transformar o trecho do input ({}) em um número f64",
+                    text_portion
+                )
+            }
+            Error::ExtraParenthesis(_) => {
+                write!(
+                    f,
+                    "síntaxe incorreta, parêntese ')' sem um '(' correspondente"
+                )
+            }
+            Error::UnclosedParenthesis(_) => {
+                write!(f, "síntaxe incorreta, parêntese '(' nunca é fechado")
+            }
+            Error::MissingOperand(_) => {
+                write!(f, "síntaxe incorreta, era esperado um número neste trecho")
+            }
+            Error::MissingOperator(_) => {
+                write!(
+                    f,
+                    "síntaxe incorreta, era esperado um operador antes deste trecho"
+                )
+            }
+            Error::TrailingOperator(_) => {
+                write!(f, "síntaxe incorreta, operador sem um número à sua direita")
+            }
+            Error::ConsecutiveOperators(_) => {
+                write!(f, "síntaxe incorreta, operadores consecutivos")
+            }
+            Error::EmptyParenthesis(_) => {
+                write!(f, "síntaxe incorreta, parênteses vazios")
+            }
+            Error::UnknownIdentifier(name, _) => {
+                write!(f, "nome desconhecido '{}'", name)
+            }
+            Error::UnexpectedCharacter(char, _) => {
+                write!(f, "caractere inesperado '{}'", char)
+            }
+            Error::InvalidAssignment(_) => {
+                write!(
+                    f,
+                    "'=' só pode ser usado no começo da linha, para dar um valor a um nome (x = 2)"
+                )
+            }
+            Error::UnknownFunction(name, _) => {
+                write!(f, "função desconhecida '{}'", name)
+            }
+            Error::WrongArgumentCount(name, expected, found, _) => {
+                write!(
+                    f,
+                    "a função '{}' recebe {} argumento(s), mas {} foram passados",
+                    name, expected, found
+                )
+            }
+            Error::TooFewArguments(name, minimum, found, _) => {
+                write!(
+                    f,
+                    "a função '{}' recebe pelo menos {} argumento(s), mas {} foram passados",
+                    name, minimum, found
+                )
+            }
+            Error::OutOfDomain(name, _) => {
+                write!(f, "argumentos fora do domínio da função '{}'", name)
+            }
+            Error::Interrupted(_) => write!(f, "cálculo interrompido"),
+            Error::LimitExceeded(Limit::Nesting(limit), _) => {
+                write!(
+                    f,
+                    "expressão aninhada demais (o limite é de {} níveis)",
+                    limit
+                )
+            }
+            Error::LimitExceeded(Limit::CallDepth(limit), _) => {
+                write!(
+                    f,
+                    "chamadas de função aninhadas demais (o limite é de {} níveis)",
+                    limit
+                )
+            }
+            Error::AssignmentToConstant(name, _) => {
+                write!(f, "'{}' é uma constante e não pode ser alterada", name)
+            }
+            Error::IncompatibleUnits(lhs, rhs, _) => {
+                write!(
+                    f,
+                    "unidades incompatíveis: {} e {}",
+                    Quantity(*lhs),
+                    Quantity(*rhs)
+                )
+            }
+            Error::UnitExponent(_) => {
+                write!(
+                    f,
+                    "expoente inválido para um valor com unidade (m^2 e (m^2)^0.5 valem, m^0.5 não)"
+                )
+            }
+            Error::ImplicitMultiplication(_) => {
+                write!(f, "multiplicação implícita não é aceita no modo estrito")
+            }
+        }
+    }
+}
+
+/// Uma dimensão nas mensagens de erro.
+struct Quantity(Dimension);
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        if self.0.is_none() {
+            write!(f, "número sem unidade")
+        } else {
+            write!(f, "'{}'", self.0)
+        }
+    }
+}
+
+#[test]
+fn message_should_not_include_the_column() {
+    let error = Error::UnknownIdentifier("y".into(), Span::new(4, 5));
+    assert_eq!(error.kind(), "unknown_identifier");
+    assert_eq!(error.message(), "nome desconhecido 'y'");
+    assert_eq!(
+        alloc::format!("{}", error),
+        "coluna 5: nome desconhecido 'y'"
+    );
+
+    let error = Error::IncompatibleUnits(Dimension::LENGTH, Dimension::NONE, Span::new(0, 6));
+    assert_eq!(error.kind(), "incompatible_units");
+    assert_eq!(
+        error.message(),
+        "unidades incompatíveis: 'm' e número sem unidade"
+    );
+}
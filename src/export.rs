@@ -0,0 +1,275 @@
+//! `export json <arquivo>` e `import json <arquivo>`, que levam o histórico, as variáveis e as
+//! funções de uma sessão para outra, ou para outras ferramentas.
+//!
+//! O arquivo é um objeto JSON com estes campos, todos obrigatórios menos `dimensions`:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "history": [{"input": "x = 2 * 3", "result": 6}],
+//!   "variables": {"x": 6, "d": 5000},
+//!   "dimensions": {"d": [1, 0, 0, 0, 0, 0]},
+//!   "functions": [{"name": "hypot", "parameters": ["a", "b"], "body": "(a^2 + b^2)^0.5"}]
+//! }
+//! ```
+//!
+//! O histórico vai da linha mais antiga para a mais recente, e os resultados e valores que não
+//! podem ser escritos como número em JSON vão como texto (`"inf"`, `"-inf"` e `"NaN"`), como nas
+//! respostas da API. As variáveis com unidade têm o valor em unidades do SI, e os expoentes da
+//! dimensão delas em `dimensions`, na ordem de `SI_BASES`; as que não aparecem ali não têm
+//! unidade.
+
+use std::{fs, path::Path};
+
+use asdf_calc::{Dimension, BASES};
+
+use crate::{
+    json::{result_from_json, result_json, Json},
+    session::HistoryEntry,
+};
+
+/// Versão do formato, que muda se os campos mudarem de um jeito que leitores antigos não
+/// entenderiam.
+pub const VERSION: f64 = 1.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionExport {
+    pub history: Vec<HistoryEntry>,
+    pub variables: Vec<(String, f64, Dimension)>,
+    pub functions: Vec<FunctionDefinition>,
+}
+
+impl SessionExport {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("não foi possível ler {}: {}", path.display(), error))?;
+        Json::parse(&text)
+            .and_then(|json| SessionExport::from_json(&json))
+            .map_err(|message| format!("{}: {}", path.display(), message))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, format!("{}\n", self.to_json()))
+            .map_err(|error| format!("não foi possível salvar {}: {}", path.display(), error))
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object([
+            ("version", Json::from(VERSION)),
+            (
+                "history",
+                Json::Array(
+                    self.history
+                        .iter()
+                        .map(|entry| {
+                            Json::object([
+                                ("input", Json::from(entry.input.as_str())),
+                                ("result", result_json(entry.result)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "variables",
+                Json::Object(
+                    self.variables
+                        .iter()
+                        .map(|(name, value, _)| (name.clone(), result_json(*value)))
+                        .collect(),
+                ),
+            ),
+            (
+                "dimensions",
+                Json::Object(
+                    self.variables
+                        .iter()
+                        .filter(|(_, _, dimension)| !dimension.is_none())
+                        .map(|(name, _, dimension)| {
+                            let exponents = dimension
+                                .exponents()
+                                .map(|exponent| Json::from(f64::from(exponent)));
+                            (name.clone(), Json::Array(exponents.to_vec()))
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "functions",
+                Json::Array(
+                    self.functions
+                        .iter()
+                        .map(|function| {
+                            Json::object([
+                                ("name", Json::from(function.name.as_str())),
+                                (
+                                    "parameters",
+                                    Json::Array(
+                                        function
+                                            .parameters
+                                            .iter()
+                                            .map(|parameter| Json::from(parameter.as_str()))
+                                            .collect(),
+                                    ),
+                                ),
+                                ("body", Json::from(function.body.as_str())),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        match json.get("version") {
+            Some(Json::Number(version)) if *version == VERSION => {}
+            Some(Json::Number(version)) => {
+                return Err(format!("versão {} não suportada", version));
+            }
+            _ => return Err("o campo \"version\" precisa ser um número".to_string()),
+        }
+        let array = |key: &str| {
+            json.get(key)
+                .and_then(Json::as_array)
+                .ok_or_else(|| format!("o campo \"{}\" precisa ser uma lista", key))
+        };
+        let string = |json: &Json, key: &str, field: &str| {
+            json.get(key)
+                .and_then(Json::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| format!("{}: \"{}\" precisa ser uma string", field, key))
+        };
+
+        let mut export = SessionExport::default();
+        for (index, entry) in array("history")?.iter().enumerate() {
+            let field = format!("history[{}]", index);
+            export.history.push(HistoryEntry {
+                input: string(entry, "input", &field)?,
+                result: entry
+                    .get("result")
+                    .and_then(result_from_json)
+                    .ok_or_else(|| format!("{}: \"result\" precisa ser um número", field))?,
+            });
+        }
+
+        let Some(Json::Object(variables)) = json.get("variables") else {
+            return Err("o campo \"variables\" precisa ser um objeto".to_string());
+        };
+        let dimensions = json.get("dimensions");
+        if !matches!(dimensions, None | Some(Json::Object(_))) {
+            return Err("o campo \"dimensions\" precisa ser um objeto".to_string());
+        }
+        for (name, value) in variables {
+            let value = result_from_json(value)
+                .ok_or_else(|| format!("variables.{}: o valor precisa ser um número", name))?;
+            let dimension = match dimensions.and_then(|dimensions| dimensions.get(name)) {
+                Some(exponents) => parse_dimension(exponents).ok_or_else(|| {
+                    format!(
+                        "dimensions.{}: precisa ser uma lista de {} expoentes inteiros",
+                        name, BASES
+                    )
+                })?,
+                None => Dimension::NONE,
+            };
+            export.variables.push((name.clone(), value, dimension));
+        }
+
+        for (index, function) in array("functions")?.iter().enumerate() {
+            let field = format!("functions[{}]", index);
+            let parameters = function
+                .get("parameters")
+                .and_then(Json::as_array)
+                .and_then(|parameters| {
+                    parameters
+                        .iter()
+                        .map(|parameter| parameter.as_str().map(str::to_string))
+                        .collect::<Option<Vec<String>>>()
+                })
+                .ok_or_else(|| {
+                    format!("{}: \"parameters\" precisa ser uma lista de strings", field)
+                })?;
+            export.functions.push(FunctionDefinition {
+                name: string(function, "name", &field)?,
+                parameters,
+                body: string(function, "body", &field)?,
+            });
+        }
+        Ok(export)
+    }
+}
+
+/// Os expoentes de uma dimensão em `dimensions`.
+fn parse_dimension(json: &Json) -> Option<Dimension> {
+    let exponents = json
+        .as_array()?
+        .iter()
+        .map(|exponent| match exponent {
+            Json::Number(exponent) if exponent.fract() == 0.0 => {
+                i8::try_from(*exponent as i64).ok()
+            }
+            _ => None,
+        })
+        .collect::<Option<Vec<i8>>>()?;
+    Some(Dimension::new(exponents.try_into().ok()?))
+}
+
+#[test]
+fn export_should_be_written_in_the_documented_schema() {
+    let export = SessionExport {
+        history: vec![HistoryEntry {
+            input: "x = 2 * 3".to_string(),
+            result: 6.0,
+        }],
+        variables: vec![
+            ("x".to_string(), 6.0, Dimension::NONE),
+            ("big".to_string(), f64::INFINITY, Dimension::NONE),
+            ("d".to_string(), 5000.0, Dimension::LENGTH),
+        ],
+        functions: vec![FunctionDefinition {
+            name: "hypot".to_string(),
+            parameters: vec!["a".to_string(), "b".to_string()],
+            body: "(a^2 + b^2)^0.5".to_string(),
+        }],
+    };
+    let json = export.to_json();
+    assert_eq!(
+        json.to_string(),
+        r#"{"version":1,"history":[{"input":"x = 2 * 3","result":6}],"variables":{"x":6,"big":"inf","d":5000},"dimensions":{"d":[1,0,0,0,0,0]},"functions":[{"name":"hypot","parameters":["a","b"],"body":"(a^2 + b^2)^0.5"}]}"#
+    );
+    assert_eq!(SessionExport::from_json(&json), Ok(export));
+}
+
+#[test]
+fn import_should_reject_invalid_documents() {
+    let error = |text: &str| SessionExport::from_json(&Json::parse(text).unwrap()).unwrap_err();
+    assert_eq!(
+        error(r#"{"version": 2, "history": [], "variables": {}, "functions": []}"#),
+        "versão 2 não suportada"
+    );
+    assert_eq!(
+        error(r#"{"version": 1, "variables": {}, "functions": []}"#),
+        "o campo \"history\" precisa ser uma lista"
+    );
+    assert_eq!(
+        error(r#"{"version": 1, "history": [{"input": "1"}], "variables": {}, "functions": []}"#),
+        "history[0]: \"result\" precisa ser um número"
+    );
+    assert_eq!(
+        error(r#"{"version": 1, "history": [], "variables": {"x": "1"}, "functions": []}"#),
+        "variables.x: o valor precisa ser um número"
+    );
+    assert_eq!(
+        error(
+            r#"{"version": 1, "history": [], "variables": {"x": 1}, "dimensions": {"x": [1]}, "functions": []}"#
+        ),
+        "dimensions.x: precisa ser uma lista de 6 expoentes inteiros"
+    );
+}
@@ -0,0 +1,1983 @@
+use alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec};
+use core::ops::{Index, IndexMut};
+
+use crate::{
+    builtins::{Builtin, Units},
+    context::EvalContext,
+    error::{Error, Limit, Span},
+    lexer::{tokenize_with, Token},
+    math::Libm,
+    observer::{EvalNode, EvalObserver, Unobserved},
+    parser::{validate, Delimiter, Parser},
+    significance::{self, Precision},
+    units::{Dimension, Unit},
+};
+
+#[cfg(feature = "std")]
+use crate::lexer::tokenize_reader;
+#[cfg(test)]
+use crate::math;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum OperationKind {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    /// `~=`, igualdade com a tolerância relativa do contexto (`APPROX_TOLERANCE`, se não for
+    /// trocada).
+    Approx,
+    And,
+    Or,
+}
+
+#[test]
+fn opeartion_should_operate_with_correctly_for_add() {
+    let op = OperationKind::Add;
+    assert_eq!(op.operate_with(&3.0, &5.0, 0.0, &math::SYSTEM), 8.0);
+}
+
+#[test]
+fn opeartion_should_operate_with_correctly_for_subtract() {
+    let op = OperationKind::Subtract;
+    assert_eq!(op.operate_with(&3.0, &5.0, 0.0, &math::SYSTEM), -2.0);
+}
+
+#[test]
+fn opeartion_should_operate_with_correctly_for_multiply() {
+    let op = OperationKind::Multiply;
+    assert_eq!(op.operate_with(&3.0, &5.0, 0.0, &math::SYSTEM), 15.0);
+}
+
+#[test]
+fn opeartion_should_operate_with_correctly_for_divide() {
+    let op = OperationKind::Divide;
+    assert_eq!(op.operate_with(&3.0, &5.0, 0.0, &math::SYSTEM), 3.0 / 5.0);
+}
+
+#[test]
+fn opeartion_should_operate_with_correctly_for_power() {
+    let op = OperationKind::Power;
+    assert_eq!(op.operate_with(&3.0, &5.0, 0.0, &math::SYSTEM), 243.0);
+}
+
+#[test]
+fn opeartion_should_compare_with_the_tolerance() {
+    assert_eq!(
+        OperationKind::Equal.operate_with(&0.3, &(0.1 + 0.2), 0.0, &math::SYSTEM),
+        0.0
+    );
+    assert_eq!(
+        OperationKind::Equal.operate_with(&0.3, &(0.1 + 0.2), 1e-9, &math::SYSTEM),
+        1.0
+    );
+    assert_eq!(
+        OperationKind::NotEqual.operate_with(&1.0, &2.0, 0.0, &math::SYSTEM),
+        1.0
+    );
+    assert_eq!(
+        OperationKind::Less.operate_with(&1.0, &1.05, 0.1, &math::SYSTEM),
+        0.0
+    );
+    assert_eq!(
+        OperationKind::LessEqual.operate_with(&1.05, &1.0, 0.1, &math::SYSTEM),
+        1.0
+    );
+    assert_eq!(
+        OperationKind::Greater.operate_with(&2.0, &1.0, 0.1, &math::SYSTEM),
+        1.0
+    );
+    assert_eq!(
+        OperationKind::GreaterEqual.operate_with(&1.0, &2.0, 0.0, &math::SYSTEM),
+        0.0
+    );
+    assert_eq!(
+        OperationKind::Equal.operate_with(&f64::NAN, &f64::NAN, 1.0, &math::SYSTEM),
+        0.0
+    );
+    assert_eq!(
+        OperationKind::NotEqual.operate_with(&f64::NAN, &f64::NAN, 0.0, &math::SYSTEM),
+        1.0
+    );
+    assert_eq!(
+        OperationKind::Approx.operate_with(&0.3, &(0.1 + 0.2), 0.0, &math::SYSTEM),
+        1.0
+    );
+    assert_eq!(
+        OperationKind::Approx.operate_with(&1e20, &(1e20 + 1e5), 0.0, &math::SYSTEM),
+        1.0
+    );
+    assert_eq!(
+        OperationKind::Approx.operate_with(&1e-12, &0.0, 0.0, &math::SYSTEM),
+        1.0
+    );
+    assert_eq!(
+        OperationKind::Approx.operate_with(&1.0, &1.001, 0.0, &math::SYSTEM),
+        0.0
+    );
+    assert_eq!(
+        OperationKind::Approx.operate_with(&f64::NAN, &f64::NAN, 0.0, &math::SYSTEM),
+        0.0
+    );
+    assert_eq!(
+        OperationKind::And.operate_with(&2.0, &0.0, 0.0, &math::SYSTEM),
+        0.0
+    );
+    assert_eq!(
+        OperationKind::Or.operate_with(&0.0, &-3.0, 0.0, &math::SYSTEM),
+        1.0
+    );
+}
+
+/// Valor de uma condição: qualquer número diferente de zero é verdadeiro.
+pub(crate) fn is_true(value: f64) -> bool {
+    value != 0.0
+}
+
+/// Tolerância padrão de `~=`, relativa ao maior dos dois números (e absoluta para números
+/// menores que 1). Pode ser trocada com `EvalContext::set_approx_tolerance`.
+pub const APPROX_TOLERANCE: f64 = 1e-9;
+
+/// Se `a` e `b` diferem em até `tolerance` vezes o maior dos dois em módulo, ou em até
+/// `tolerance` quando os dois estão perto de zero.
+pub(crate) fn approx_equal(a: f64, b: f64, tolerance: f64) -> bool {
+    (a - b).abs() <= tolerance * a.abs().max(b.abs()).max(1.0)
+}
+
+/// Se `measure` calcula a precisão do resultado das operações do tipo `kind`, em vez de contar
+/// o resultado como exato.
+fn is_measured(kind: OperationKind) -> bool {
+    matches!(
+        kind,
+        OperationKind::Add
+            | OperationKind::Subtract
+            | OperationKind::Multiply
+            | OperationKind::Divide
+            | OperationKind::Power
+    )
+}
+
+pub(crate) fn from_bool(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+impl OperationKind {
+    /// `operate_with` com o `epsilon` e a tolerância de `~=` do contexto.
+    pub(crate) fn operate_in(&self, lhs: &f64, rhs: &f64, context: &EvalContext) -> f64 {
+        match self {
+            OperationKind::Approx => {
+                from_bool(approx_equal(*lhs, *rhs, context.approx_tolerance()))
+            }
+            kind => kind.operate_with(lhs, rhs, context.epsilon(), context.libm()),
+        }
+    }
+
+    /// As comparações e operações lógicas resultam em 1 ou 0, e números a até `epsilon` de
+    /// distância um do outro são considerados iguais.
+    pub(crate) fn operate_with(&self, lhs: &f64, rhs: &f64, epsilon: f64, libm: &Libm) -> f64 {
+        let equal = (lhs - rhs).abs() <= epsilon;
+        match self {
+            OperationKind::Add => lhs + rhs,
+            OperationKind::Subtract => lhs - rhs,
+            OperationKind::Multiply => lhs * rhs,
+            OperationKind::Divide => lhs / rhs,
+            OperationKind::Power => (libm.pow)(*lhs, *rhs),
+            OperationKind::Equal => from_bool(equal),
+            OperationKind::NotEqual => from_bool(!equal),
+            OperationKind::Less => from_bool(lhs < rhs && !equal),
+            OperationKind::LessEqual => from_bool(lhs < rhs || equal),
+            OperationKind::Greater => from_bool(lhs > rhs && !equal),
+            OperationKind::GreaterEqual => from_bool(lhs > rhs || equal),
+            OperationKind::Approx => from_bool(approx_equal(*lhs, *rhs, APPROX_TOLERANCE)),
+            OperationKind::And => from_bool(is_true(*lhs) && is_true(*rhs)),
+            OperationKind::Or => from_bool(is_true(*lhs) || is_true(*rhs)),
+        }
+    }
+
+    pub(crate) fn symbol(&self) -> &'static str {
+        match self {
+            OperationKind::Add => "+",
+            OperationKind::Subtract => "-",
+            OperationKind::Multiply => "*",
+            OperationKind::Divide => "/",
+            OperationKind::Power => "^",
+            OperationKind::Equal => "==",
+            OperationKind::NotEqual => "!=",
+            OperationKind::Less => "<",
+            OperationKind::LessEqual => "<=",
+            OperationKind::Greater => ">",
+            OperationKind::GreaterEqual => ">=",
+            OperationKind::Approx => "~=",
+            OperationKind::And => "and",
+            OperationKind::Or => "or",
+        }
+    }
+
+    /// Quanto maior, mais cedo a operação deve ser calculada.
+    pub(crate) fn precedence(&self) -> u8 {
+        match self {
+            OperationKind::Or => 1,
+            OperationKind::And => 2,
+            OperationKind::Equal
+            | OperationKind::NotEqual
+            | OperationKind::Less
+            | OperationKind::LessEqual
+            | OperationKind::Greater
+            | OperationKind::GreaterEqual
+            | OperationKind::Approx => 3,
+            OperationKind::Add | OperationKind::Subtract => 4,
+            OperationKind::Multiply | OperationKind::Divide => 5,
+            OperationKind::Power => 6,
+        }
+    }
+
+    pub(crate) fn is_comparison(&self) -> bool {
+        self.precedence() == OperationKind::Equal.precedence()
+    }
+
+    /// `2 ^ 3 ^ 2` é lido como `2 ^ (3 ^ 2)`, enquanto as outras operações são lidas da esquerda
+    /// para a direita.
+    pub(crate) fn is_right_associative(&self) -> bool {
+        *self == OperationKind::Power
+    }
+}
+
+/// Posição de um nó no `Arena` da expressão a que ele pertence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct NodeId(u32);
+
+/// Argumentos de uma chamada, guardados em sequência na lista de filhos do `Arena`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Children {
+    start: u32,
+    len: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    Number(f64),
+    /// Número escrito no input, com a precisão em que foi escrito.
+    Literal(f64, Precision),
+    Variable(String, Span),
+    /// Unidade escrita pelo nome (`km`), que vale o fator dela.
+    Unit(String, Unit, Span),
+    /// Parâmetro da função sendo calculada, pela posição.
+    Parameter(usize),
+    Call(Arc<Function>, Children, Span),
+    Builtin(&'static Builtin, Children, Span),
+    /// `if(condição, então, senão)`, que só calcula o lado escolhido.
+    If([NodeId; 3]),
+    Negate(NodeId),
+    Not(NodeId),
+    Operation(OperationKind, NodeId, NodeId),
+    /// Comparações encadeadas (`1 < x <= 10`), verdadeiras quando cada comparação com o valor
+    /// anterior é, sem que os valores do meio sejam calculados duas vezes.
+    Comparisons(NodeId, Vec<(OperationKind, NodeId)>),
+}
+
+/// Os nós de uma expressão, todos num só vetor e ligados pelas posições (`NodeId`) em vez de
+/// cada um numa alocação própria, o que deixa expressões grandes, como as geradas por outros
+/// programas, com poucas alocações e os nós perto uns dos outros na memória. Os nós são
+/// adicionados pelo parser e não mudam depois que a expressão é criada.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Arena {
+    nodes: Vec<Node>,
+    children: Vec<NodeId>,
+}
+
+impl Index<NodeId> for Arena {
+    type Output = Node;
+
+    fn index(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0 as usize]
+    }
+}
+
+impl IndexMut<NodeId> for Arena {
+    fn index_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0 as usize]
+    }
+}
+
+impl Arena {
+    pub(crate) fn push(&mut self, node: Node) -> NodeId {
+        let id = NodeId(self.nodes.len() as u32);
+        self.nodes.push(node);
+        id
+    }
+
+    pub(crate) fn push_children(&mut self, ids: &[NodeId]) -> Children {
+        let start = self.children.len() as u32;
+        self.children.extend_from_slice(ids);
+        Children {
+            start,
+            len: ids.len() as u32,
+        }
+    }
+
+    pub(crate) fn children(&self, children: Children) -> &[NodeId] {
+        let start = children.start as usize;
+        &self.children[start..start + children.len as usize]
+    }
+
+    /// `arguments` são os valores dos parâmetros da função a que o nó pertence, e ficam vazios
+    /// fora de funções, e `depth` é quantas chamadas de função estão em andamento.
+    fn evaluate<O: EvalObserver + ?Sized>(
+        &self,
+        id: NodeId,
+        context: &EvalContext,
+        arguments: &[f64],
+        depth: usize,
+        observer: &mut O,
+    ) -> Result<f64, Error> {
+        observer.on_node_enter(self.describe(id));
+        let result = self.compute(id, context, arguments, depth, observer);
+        observer.on_node_result(self.describe(id), &result);
+        result
+    }
+
+    fn compute<O: EvalObserver + ?Sized>(
+        &self,
+        id: NodeId,
+        context: &EvalContext,
+        arguments: &[f64],
+        depth: usize,
+        observer: &mut O,
+    ) -> Result<f64, Error> {
+        match &self[id] {
+            Node::Number(num) | Node::Literal(num, _) => Ok(*num),
+            Node::Variable(name, span) => context
+                .resolve(name)
+                .ok_or_else(|| Error::UnknownIdentifier(name.clone(), *span)),
+            Node::Unit(_, unit, _) => Ok(unit.factor),
+            Node::Parameter(index) => Ok(arguments[*index]),
+            Node::Call(function, children, span) => {
+                if context.interrupt_flag().is_interrupted() {
+                    return Err(Error::Interrupted(*span));
+                }
+                let max_call_depth = context.limits().max_call_depth;
+                if depth >= max_call_depth {
+                    return Err(Error::LimitExceeded(
+                        Limit::CallDepth(max_call_depth),
+                        *span,
+                    ));
+                }
+
+                let values =
+                    self.evaluate_children(*children, context, arguments, depth, observer)?;
+                // a posição de uma interrupção dentro do corpo não faria sentido no input
+                let body = &function.body;
+                body.arena
+                    .evaluate(body.root, context, &values, depth + 1, observer)
+                    .map_err(|error| match error {
+                        Error::Interrupted(_) => Error::Interrupted(*span),
+                        Error::LimitExceeded(limit, _) => Error::LimitExceeded(limit, *span),
+                        error => error,
+                    })
+            }
+            Node::Builtin(builtin, children, span) => {
+                let values =
+                    self.evaluate_children(*children, context, arguments, depth, observer)?;
+                builtin
+                    .call(
+                        &values,
+                        context.angle_mode(),
+                        context.word_size(),
+                        context.libm(),
+                        context.summation(),
+                    )
+                    .ok_or_else(|| Error::OutOfDomain(builtin.name().into(), *span))
+            }
+            Node::If([condition, then, otherwise]) => {
+                if is_true(self.evaluate(*condition, context, arguments, depth, observer)?) {
+                    self.evaluate(*then, context, arguments, depth, observer)
+                } else {
+                    self.evaluate(*otherwise, context, arguments, depth, observer)
+                }
+            }
+            Node::Negate(node) => Ok(-self.evaluate(*node, context, arguments, depth, observer)?),
+            Node::Not(node) => Ok(from_bool(!is_true(
+                self.evaluate(*node, context, arguments, depth, observer)?,
+            ))),
+            Node::Comparisons(first, comparisons) => {
+                let mut lhs = self.evaluate(*first, context, arguments, depth, observer)?;
+                for (kind, node) in comparisons {
+                    let rhs = self.evaluate(*node, context, arguments, depth, observer)?;
+                    if !is_true(kind.operate_in(&lhs, &rhs, context)) {
+                        return Ok(0.0);
+                    }
+                    lhs = rhs;
+                }
+                Ok(1.0)
+            }
+            Node::Operation(..) => {
+                let (first, chain) = self.left_chain(id, |_| true);
+                // as operações de dentro da cadeia passam pelo observador como se `evaluate` as
+                // calculasse
+                for node in chain.iter().rev() {
+                    observer.on_node_enter(self.describe(*node));
+                }
+                let mut result = self.evaluate(first, context, arguments, depth, observer);
+                for node in chain {
+                    result = result.and_then(|lhs| {
+                        self.operate(node, lhs, context, arguments, depth, observer)
+                    });
+                    observer.on_node_result(self.describe(node), &result);
+                }
+                self.operate(id, result?, context, arguments, depth, observer)
+            }
+        }
+    }
+
+    /// Calcula a operação `id` a partir do lado esquerdo dela, já calculado.
+    fn operate<O: EvalObserver + ?Sized>(
+        &self,
+        id: NodeId,
+        lhs: f64,
+        context: &EvalContext,
+        arguments: &[f64],
+        depth: usize,
+        observer: &mut O,
+    ) -> Result<f64, Error> {
+        let (kind, rhs) = self.operation(id);
+        // o lado direito não é calculado quando o esquerdo já decide o resultado, para que ele
+        // possa ter cálculos que só valem quando a condição é verdadeira
+        match kind {
+            OperationKind::And if !is_true(lhs) => return Ok(0.0),
+            OperationKind::Or if is_true(lhs) => return Ok(1.0),
+            _ => {}
+        }
+        let rhs = self.evaluate(rhs, context, arguments, depth, observer)?;
+        Ok(kind.operate_in(&lhs, &rhs, context))
+    }
+
+    /// As operações encadeadas pela esquerda abaixo de `id`, na ordem do cálculo, e o primeiro
+    /// operando delas: `1` e `1 + 2` em `1 + 2 + 3`. A cadeia para na primeira operação para a
+    /// qual `follow` é falso. Os percursos da árvore calculam essas cadeias num loop em vez de
+    /// recursivamente, para que `1 + 1 + ...` possa ter qualquer tamanho sem estourar a pilha.
+    fn left_chain(&self, id: NodeId, follow: fn(OperationKind) -> bool) -> (NodeId, Vec<NodeId>) {
+        let mut chain = Vec::new();
+        let Node::Operation(_, mut first, _) = self[id] else {
+            unreachable!("nó que não é uma operação: {:?}", self[id]);
+        };
+        while let Node::Operation(kind, lhs, _) = &self[first] {
+            if !follow(*kind) {
+                break;
+            }
+            chain.push(first);
+            first = *lhs;
+        }
+        chain.reverse();
+        (first, chain)
+    }
+
+    /// O tipo e o lado direito da operação `id`.
+    fn operation(&self, id: NodeId) -> (OperationKind, NodeId) {
+        match &self[id] {
+            Node::Operation(kind, _, rhs) => (*kind, *rhs),
+            node => unreachable!("nó que não é uma operação: {:?}", node),
+        }
+    }
+
+    fn evaluate_children<O: EvalObserver + ?Sized>(
+        &self,
+        children: Children,
+        context: &EvalContext,
+        arguments: &[f64],
+        depth: usize,
+        observer: &mut O,
+    ) -> Result<Vec<f64>, Error> {
+        self.children(children)
+            .iter()
+            .map(|node| self.evaluate(*node, context, arguments, depth, observer))
+            .collect()
+    }
+
+    fn describe(&self, id: NodeId) -> EvalNode<'_> {
+        match &self[id] {
+            Node::Number(num) | Node::Literal(num, _) => EvalNode::Number(*num),
+            Node::Variable(name, _) => EvalNode::Variable(name),
+            Node::Unit(name, ..) => EvalNode::Unit(name),
+            Node::Parameter(index) => EvalNode::Parameter(*index),
+            Node::Call(_, _, span) => EvalNode::Call(*span),
+            Node::Builtin(builtin, ..) => EvalNode::Builtin(builtin.name()),
+            Node::If(_) => EvalNode::If,
+            Node::Negate(_) => EvalNode::Negate,
+            Node::Not(_) => EvalNode::Not,
+            Node::Operation(kind, ..) => EvalNode::Operation(kind.symbol()),
+            Node::Comparisons(..) => EvalNode::Comparisons,
+        }
+    }
+
+    /// Calcula o nó como `evaluate`, junto da precisão do resultado, que fica vazia quando ele é
+    /// exato. Variáveis, constantes, funções do usuário e condições contam como exatas.
+    fn measure(
+        &self,
+        id: NodeId,
+        context: &EvalContext,
+    ) -> Result<(f64, Option<Precision>), Error> {
+        match &self[id] {
+            Node::Literal(num, precision) => Ok((*num, Some(*precision))),
+            Node::Negate(node) => {
+                let (value, precision) = self.measure(*node, context)?;
+                Ok((-value, precision))
+            }
+            Node::Builtin(builtin, children, span) => {
+                let measured = self
+                    .children(*children)
+                    .iter()
+                    .map(|node| self.measure(*node, context))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let values: Vec<f64> = measured.iter().map(|(value, _)| *value).collect();
+                let value = builtin
+                    .call(
+                        &values,
+                        context.angle_mode(),
+                        context.word_size(),
+                        context.libm(),
+                        context.summation(),
+                    )
+                    .ok_or_else(|| Error::OutOfDomain(builtin.name().into(), *span))?;
+                let figures = measured
+                    .iter()
+                    .filter_map(|(_, precision)| precision.map(|precision| precision.figures))
+                    .min();
+                Ok((
+                    value,
+                    figures.map(|figures| Precision::with_figures(value, figures)),
+                ))
+            }
+            Node::Operation(kind, ..) if is_measured(*kind) => {
+                let (first, chain) = self.left_chain(id, is_measured);
+                let mut measured = self.measure(first, context)?;
+                for node in chain.into_iter().chain([id]) {
+                    measured = self.measure_operation(node, measured, context)?;
+                }
+                Ok(measured)
+            }
+            _ => Ok((self.evaluate(id, context, &[], 0, &mut Unobserved)?, None)),
+        }
+    }
+
+    /// Calcula a operação `id` como `measure`, a partir do lado esquerdo dela, já medido.
+    fn measure_operation(
+        &self,
+        id: NodeId,
+        (lhs, lhs_precision): (f64, Option<Precision>),
+        context: &EvalContext,
+    ) -> Result<(f64, Option<Precision>), Error> {
+        let (kind, rhs) = self.operation(id);
+        let (rhs, rhs_precision) = self.measure(rhs, context)?;
+        let value = kind.operate_in(&lhs, &rhs, context);
+        let precision = match kind {
+            OperationKind::Add | OperationKind::Subtract => {
+                significance::sum(value, lhs_precision, rhs_precision)
+            }
+            // o expoente não limita os algarismos da base, a não ser que ela seja exata
+            OperationKind::Power => lhs_precision
+                .or(rhs_precision)
+                .map(|precision| Precision::with_figures(value, precision.figures)),
+            _ => significance::product(value, lhs_precision, rhs_precision),
+        };
+        Ok((value, precision))
+    }
+
+    /// Se o resultado do nó só depende das configurações do contexto, sem variáveis, constantes
+    /// ou funções do usuário.
+    fn is_pure(&self, id: NodeId) -> bool {
+        match &self[id] {
+            Node::Number(_) | Node::Literal(..) | Node::Unit(..) => true,
+            Node::Variable(..) | Node::Parameter(_) | Node::Call(..) => false,
+            Node::Builtin(_, children, _) => self
+                .children(*children)
+                .iter()
+                .all(|node| self.is_pure(*node)),
+            Node::If(nodes) => nodes.iter().all(|node| self.is_pure(*node)),
+            Node::Negate(node) | Node::Not(node) => self.is_pure(*node),
+            Node::Operation(..) => {
+                let (first, chain) = self.left_chain(id, |_| true);
+                self.is_pure(first)
+                    && chain
+                        .into_iter()
+                        .chain([id])
+                        .all(|node| self.is_pure(self.operation(node).1))
+            }
+            Node::Comparisons(first, comparisons) => {
+                self.is_pure(*first) && comparisons.iter().all(|(_, node)| self.is_pure(*node))
+            }
+        }
+    }
+
+    /// Se o nó pode ser calculado por `evaluate_lanes`. Chamadas de função do usuário, `if`,
+    /// `and`, `or` e comparações encadeadas calculam só parte dos nós, então ficam de fora.
+    fn is_vectorizable(&self, id: NodeId) -> bool {
+        match &self[id] {
+            Node::Number(_) | Node::Literal(..) | Node::Variable(..) | Node::Unit(..) => true,
+            Node::Builtin(_, children, _) => self
+                .children(*children)
+                .iter()
+                .all(|node| self.is_vectorizable(*node)),
+            Node::Negate(node) | Node::Not(node) => self.is_vectorizable(*node),
+            Node::Operation(..) => {
+                let (first, chain) = self.left_chain(id, |_| true);
+                self.is_vectorizable(first)
+                    && chain.into_iter().chain([id]).all(|node| {
+                        let (kind, rhs) = self.operation(node);
+                        !matches!(kind, OperationKind::And | OperationKind::Or)
+                            && self.is_vectorizable(rhs)
+                    })
+            }
+            Node::Parameter(_) | Node::Call(..) | Node::If(_) | Node::Comparisons(..) => false,
+        }
+    }
+
+    /// Calcula o nó para `LANES` valores de `variable` de uma vez, uma operação por vez sobre
+    /// todos eles, o que o compilador consegue transformar em instruções SIMD.
+    fn evaluate_lanes(
+        &self,
+        id: NodeId,
+        context: &EvalContext,
+        variable: &str,
+        inputs: &Lanes,
+    ) -> Result<Lanes, Error> {
+        match &self[id] {
+            Node::Number(num) | Node::Literal(num, _) => Ok([*num; LANES]),
+            Node::Unit(_, unit, _) => Ok([unit.factor; LANES]),
+            // as constantes do usuário escondem as variáveis, como em `EvalContext::resolve`
+            Node::Variable(name, _) if name == variable && context.constant(name).is_none() => {
+                Ok(*inputs)
+            }
+            Node::Variable(name, span) => context
+                .resolve(name)
+                .map(|value| [value; LANES])
+                .ok_or_else(|| Error::UnknownIdentifier(name.clone(), *span)),
+            Node::Negate(node) => {
+                let values = self.evaluate_lanes(*node, context, variable, inputs)?;
+                Ok(values.map(|value| -value))
+            }
+            Node::Not(node) => {
+                let values = self.evaluate_lanes(*node, context, variable, inputs)?;
+                Ok(values.map(|value| from_bool(!is_true(value))))
+            }
+            Node::Operation(..) => {
+                let (first, chain) = self.left_chain(id, |_| true);
+                let mut lhs = self.evaluate_lanes(first, context, variable, inputs)?;
+                for node in chain.into_iter().chain([id]) {
+                    let (kind, rhs) = self.operation(node);
+                    let rhs = self.evaluate_lanes(rhs, context, variable, inputs)?;
+                    lhs = match kind {
+                        OperationKind::Add => core::array::from_fn(|i| lhs[i] + rhs[i]),
+                        OperationKind::Subtract => core::array::from_fn(|i| lhs[i] - rhs[i]),
+                        OperationKind::Multiply => core::array::from_fn(|i| lhs[i] * rhs[i]),
+                        OperationKind::Divide => core::array::from_fn(|i| lhs[i] / rhs[i]),
+                        kind => {
+                            core::array::from_fn(|i| kind.operate_in(&lhs[i], &rhs[i], context))
+                        }
+                    };
+                }
+                Ok(lhs)
+            }
+            Node::Builtin(builtin, children, span) => {
+                let arguments = self
+                    .children(*children)
+                    .iter()
+                    .map(|node| self.evaluate_lanes(*node, context, variable, inputs))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut values = Vec::with_capacity(arguments.len());
+                let mut results = [0.0; LANES];
+                for (lane, result) in results.iter_mut().enumerate() {
+                    values.clear();
+                    values.extend(arguments.iter().map(|argument| argument[lane]));
+                    *result = builtin
+                        .call(
+                            &values,
+                            context.angle_mode(),
+                            context.word_size(),
+                            context.libm(),
+                            context.summation(),
+                        )
+                        .ok_or_else(|| Error::OutOfDomain(builtin.name().into(), *span))?;
+                }
+                Ok(results)
+            }
+            node @ (Node::Parameter(_) | Node::Call(..) | Node::If(_) | Node::Comparisons(..)) => {
+                unreachable!("nó que não pode ser calculado em lote: {:?}", node)
+            }
+        }
+    }
+
+    /// A dimensão do resultado do nó, conferindo que as unidades combinam em cada operação.
+    /// `parameters` são as dimensões dos argumentos da função a que o nó pertence.
+    fn dimension(
+        &self,
+        id: NodeId,
+        context: &EvalContext,
+        parameters: &[Dimension],
+        calls: &mut Calls,
+    ) -> Result<Dimension, Error> {
+        let incompatible = |lhs: Dimension, rhs: Dimension| {
+            Error::IncompatibleUnits(lhs, rhs, self.span(id).unwrap_or(Span::new(0, 0)))
+        };
+        let same = |lhs: Dimension, rhs: Dimension| {
+            if lhs == rhs {
+                Ok(lhs)
+            } else {
+                Err(incompatible(lhs, rhs))
+            }
+        };
+
+        match &self[id] {
+            Node::Number(_) | Node::Literal(..) => Ok(Dimension::NONE),
+            Node::Variable(name, _) => Ok(context.dimension(name)),
+            Node::Unit(_, unit, _) => Ok(unit.dimension),
+            Node::Parameter(index) => Ok(parameters[*index]),
+            Node::Call(function, children, span) => {
+                let arguments = self
+                    .children(*children)
+                    .iter()
+                    .map(|node| self.dimension(*node, context, parameters, calls))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // a chamada recursiva fica com a dimensão que a função teria se não houvesse
+                // unidades nela além das dos argumentos, para que o `if` que encerra a recursão
+                // possa ser conferido
+                let key = (Arc::as_ptr(function), arguments);
+                if calls.active.contains(&key.0) {
+                    return Ok(key.1.first().copied().unwrap_or_default());
+                }
+                if let Some(dimension) = calls.known.get(&key) {
+                    return Ok(*dimension);
+                }
+                if context.interrupt_flag().is_interrupted() {
+                    return Err(Error::Interrupted(*span));
+                }
+                let max_call_depth = context.limits().max_call_depth;
+                if calls.active.len() >= max_call_depth {
+                    return Err(Error::LimitExceeded(
+                        Limit::CallDepth(max_call_depth),
+                        *span,
+                    ));
+                }
+
+                calls.active.push(key.0);
+                let body = &function.body;
+                let result = body
+                    .arena
+                    .dimension(body.root, context, &key.1, calls)
+                    .map_err(|error| match error {
+                        Error::IncompatibleUnits(lhs, rhs, _) => {
+                            Error::IncompatibleUnits(lhs, rhs, *span)
+                        }
+                        Error::UnitExponent(_) => Error::UnitExponent(*span),
+                        Error::Interrupted(_) => Error::Interrupted(*span),
+                        Error::LimitExceeded(limit, _) => Error::LimitExceeded(limit, *span),
+                        error => error,
+                    })?;
+                calls.active.pop();
+                calls.known.insert(key, result);
+                Ok(result)
+            }
+            Node::Builtin(builtin, children, _) => {
+                let arguments = self
+                    .children(*children)
+                    .iter()
+                    .map(|node| self.dimension(*node, context, parameters, calls))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let (shared, kept) = match builtin.units() {
+                    Units::Fixed(expected, result) => {
+                        for (expected, argument) in expected.iter().zip(&arguments) {
+                            same(*expected, *argument)?;
+                        }
+                        return Ok(result);
+                    }
+                    Units::None => (0, false),
+                    Units::Kept(count) => (count, true),
+                    Units::Compared(count) => (count, false),
+                };
+                let shared = shared.min(arguments.len());
+                let dimension = arguments.first().copied().unwrap_or_default();
+                for argument in &arguments[..shared] {
+                    same(dimension, *argument)?;
+                }
+                for argument in &arguments[shared..] {
+                    same(Dimension::NONE, *argument)?;
+                }
+                Ok(if kept { dimension } else { Dimension::NONE })
+            }
+            Node::If([condition, then, otherwise]) => {
+                self.dimension(*condition, context, parameters, calls)?;
+                let then = self.dimension(*then, context, parameters, calls)?;
+                same(
+                    then,
+                    self.dimension(*otherwise, context, parameters, calls)?,
+                )
+            }
+            Node::Negate(node) => self.dimension(*node, context, parameters, calls),
+            Node::Not(node) => {
+                self.dimension(*node, context, parameters, calls)?;
+                Ok(Dimension::NONE)
+            }
+            Node::Comparisons(first, comparisons) => {
+                let first = self.dimension(*first, context, parameters, calls)?;
+                for (_, node) in comparisons {
+                    same(first, self.dimension(*node, context, parameters, calls)?)?;
+                }
+                Ok(Dimension::NONE)
+            }
+            Node::Operation(..) => {
+                let (first, chain) = self.left_chain(id, |_| true);
+                let mut lhs = self.dimension(first, context, parameters, calls)?;
+                for node in chain.into_iter().chain([id]) {
+                    lhs = self.operation_dimension(node, lhs, context, parameters, calls)?;
+                }
+                Ok(lhs)
+            }
+        }
+    }
+
+    /// A dimensão da operação `id` como em `dimension`, a partir da do lado esquerdo dela.
+    fn operation_dimension(
+        &self,
+        id: NodeId,
+        lhs: Dimension,
+        context: &EvalContext,
+        parameters: &[Dimension],
+        calls: &mut Calls,
+    ) -> Result<Dimension, Error> {
+        let span = || self.span(id).unwrap_or(Span::new(0, 0));
+        let same = |lhs: Dimension, rhs: Dimension| {
+            if lhs == rhs {
+                Ok(lhs)
+            } else {
+                Err(Error::IncompatibleUnits(lhs, rhs, span()))
+            }
+        };
+        let exponent_error = || Error::UnitExponent(span());
+
+        let (kind, rhs_id) = self.operation(id);
+        let rhs = self.dimension(rhs_id, context, parameters, calls)?;
+        match kind {
+            OperationKind::Add | OperationKind::Subtract => same(lhs, rhs),
+            OperationKind::Multiply => lhs.multiply(rhs).ok_or_else(exponent_error),
+            OperationKind::Divide => lhs.divide(rhs).ok_or_else(exponent_error),
+            OperationKind::Power => {
+                same(Dimension::NONE, rhs)?;
+                if lhs.is_none() {
+                    return Ok(lhs);
+                }
+                // o expoente precisa ser conhecido antes do cálculo, então não pode depender dos
+                // argumentos da função
+                let exponent = if self.has_parameters(rhs_id) {
+                    None
+                } else {
+                    self.evaluate(rhs_id, context, &[], 0, &mut Unobserved).ok()
+                };
+                exponent
+                    .and_then(|exponent| lhs.power(exponent))
+                    .ok_or_else(exponent_error)
+            }
+            OperationKind::And | OperationKind::Or => Ok(Dimension::NONE),
+            _ => same(lhs, rhs).map(|_| Dimension::NONE),
+        }
+    }
+
+    /// O trecho do input que cobre todos os nomes do nó, que são os únicos nós com uma posição.
+    fn span(&self, id: NodeId) -> Option<Span> {
+        let join = |spans: &mut dyn Iterator<Item = Option<Span>>| {
+            spans
+                .flatten()
+                .reduce(|lhs, rhs| Span::new(lhs.start.min(rhs.start), lhs.end.max(rhs.end)))
+        };
+        match &self[id] {
+            Node::Number(_) | Node::Literal(..) | Node::Parameter(_) => None,
+            Node::Variable(_, span) | Node::Unit(_, _, span) => Some(*span),
+            Node::Call(_, children, span) | Node::Builtin(_, children, span) => {
+                let children = self.children(*children).iter();
+                join(
+                    &mut [Some(*span)]
+                        .into_iter()
+                        .chain(children.map(|node| self.span(*node))),
+                )
+            }
+            Node::If(nodes) => join(&mut nodes.iter().map(|node| self.span(*node))),
+            Node::Negate(node) | Node::Not(node) => self.span(*node),
+            Node::Operation(..) => {
+                let (first, chain) = self.left_chain(id, |_| true);
+                let nodes = chain
+                    .into_iter()
+                    .chain([id])
+                    .map(|node| self.operation(node).1);
+                join(&mut [first].into_iter().chain(nodes).map(|node| self.span(node)))
+            }
+            Node::Comparisons(first, comparisons) => {
+                let nodes = comparisons.iter().map(|(_, node)| node);
+                join(
+                    &mut [first]
+                        .into_iter()
+                        .chain(nodes)
+                        .map(|node| self.span(*node)),
+                )
+            }
+        }
+    }
+
+    fn has_parameters(&self, id: NodeId) -> bool {
+        match &self[id] {
+            Node::Parameter(_) => true,
+            Node::Number(_) | Node::Literal(..) | Node::Variable(..) | Node::Unit(..) => false,
+            Node::Call(_, children, _) | Node::Builtin(_, children, _) => self
+                .children(*children)
+                .iter()
+                .any(|node| self.has_parameters(*node)),
+            Node::If(nodes) => nodes.iter().any(|node| self.has_parameters(*node)),
+            Node::Negate(node) | Node::Not(node) => self.has_parameters(*node),
+            Node::Operation(..) => {
+                let (first, chain) = self.left_chain(id, |_| true);
+                self.has_parameters(first)
+                    || chain
+                        .into_iter()
+                        .chain([id])
+                        .any(|node| self.has_parameters(self.operation(node).1))
+            }
+            Node::Comparisons(first, comparisons) => {
+                self.has_parameters(*first)
+                    || comparisons
+                        .iter()
+                        .any(|(_, node)| self.has_parameters(*node))
+            }
+        }
+    }
+}
+
+/// As funções do usuário vistas por `Arena::dimension`: as que estão sendo conferidas, para que
+/// as recursivas não sejam seguidas para sempre, e o resultado das que já foram para cada
+/// combinação de dimensões dos argumentos, para que `f(x) + f(x)` não confira `f` duas vezes.
+#[derive(Default)]
+struct Calls {
+    active: Vec<*const Function>,
+    known: BTreeMap<(*const Function, Vec<Dimension>), Dimension>,
+}
+
+/// Quantos valores `Expression::evaluate_range` calcula de uma vez.
+const LANES: usize = 8;
+
+type Lanes = [f64; LANES];
+
+/// Dispõe a informação necessária que definem uma expressão e organizada de tal forma que possa
+/// ser facilmente calculada.
+///
+/// # Exemplo
+///
+/// ```
+/// use asdf_calc::Expression;
+///
+/// let expression = Expression::new("9 + 2 - (5 + 3) * 2").expect("expressão válida");
+/// assert_eq!(expression.evaluate(), Ok(-5.0));
+/// ```
+///
+/// Internamente, os nós da expressão ficam todos no `Arena` dela, em que cada nó aponta para os
+/// seus operandos pela posição, e a posição da raiz fica guardada à parte. A representação dessa
+/// expressão seria dada pelo seguinte código (`Node` e `OperationKind` não são expostos fora do
+/// crate):
+/// ```ignore
+/// let mut arena = Arena::default();
+/// let nine = arena.push(Node::Literal(9.0, ...));
+/// let two = arena.push(Node::Literal(2.0, ...));
+/// let sum = arena.push(Node::Operation(OperationKind::Add, nine, two));
+/// let five = arena.push(Node::Literal(5.0, ...));
+/// let three = arena.push(Node::Literal(3.0, ...));
+/// let parenthesis_expression = arena.push(Node::Operation(OperationKind::Add, five, three));
+/// let other_two = arena.push(Node::Literal(2.0, ...));
+/// let product = arena.push(Node::Operation(
+///     OperationKind::Multiply,
+///     parenthesis_expression,
+///     other_two,
+/// ));
+/// let root = arena.push(Node::Operation(OperationKind::Subtract, sum, product));
+///
+/// let expression = Expression { arena, root };
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    arena: Arena,
+    root: NodeId,
+}
+
+impl Expression {
+    /// Cria a expressão a partir do texto, retornando todos os erros de síntaxe encontrados,
+    /// ordenados pela coluna em que aparecem. Só as constantes podem ser usadas pelo nome.
+    pub fn new(text: &str) -> Result<Self, Vec<Error>> {
+        Self::with_context(text, &EvalContext::default())
+    }
+
+    /// Mesmo que `Expression::new`, mas aceitando também os nomes definidos em `context`.
+    pub fn with_context(text: &str, context: &EvalContext) -> Result<Self, Vec<Error>> {
+        let mut errors = Vec::new();
+        let tokens = tokenize_with(text, context.si_suffixes(), &mut errors);
+        Self::from_tokens(tokens, text.chars().count(), errors, context, &[])
+    }
+
+    /// Mesmo que `Expression::with_context`, mas lendo o texto de `reader` aos poucos em vez de
+    /// precisar dele todo numa string, para expressões enormes geradas por outros programas. Os
+    /// erros de leitura (incluindo texto que não é UTF-8) vêm no resultado de fora, e os da
+    /// expressão no de dentro.
+    ///
+    /// ```
+    /// use asdf_calc::{EvalContext, Expression};
+    ///
+    /// let input = "(1 + 2) * 3".as_bytes();
+    /// let expression = Expression::from_reader(input, &EvalContext::new())
+    ///     .expect("leitura sem erros")
+    ///     .expect("expressão válida");
+    /// assert_eq!(expression.evaluate(), Ok(9.0));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_reader(
+        reader: impl std::io::Read,
+        context: &EvalContext,
+    ) -> std::io::Result<Result<Self, Vec<Error>>> {
+        let mut errors = Vec::new();
+        let (tokens, end) = tokenize_reader(reader, context.si_suffixes(), &mut errors)?;
+        Ok(Self::from_tokens(tokens, end, errors, context, &[]))
+    }
+
+    /// Valida e lê os tokens já separados de um texto com `end` caracteres, juntando os erros
+    /// encontrados aos `errors` vindos do lexer. Os nomes em `parameters` são lidos como
+    /// parâmetros, no caso do corpo de uma função.
+    pub(crate) fn from_tokens(
+        tokens: Vec<Token>,
+        end: usize,
+        mut errors: Vec<Error>,
+        context: &EvalContext,
+        parameters: &[String],
+    ) -> Result<Self, Vec<Error>> {
+        errors.extend(validate(&tokens, end));
+
+        let mut parser = Parser {
+            arena: Arena::default(),
+            tokens,
+            position: 0,
+            end,
+            errors,
+            context,
+            parameters,
+            depth: 0,
+            aborted: false,
+        };
+        let root = parser.parse_sequence(Delimiter::End);
+
+        if parser.errors.is_empty() {
+            Ok(Expression {
+                arena: parser.arena,
+                root,
+            })
+        } else {
+            let mut errors = parser.errors;
+            errors.sort_by_key(|error| error.span().start);
+            Err(errors)
+        }
+    }
+
+    pub fn evaluate(&self) -> Result<f64, Error> {
+        self.evaluate_with(&EvalContext::default())
+    }
+
+    /// Calcula a expressão usando o valor atual das variáveis de `context`, que pode ser
+    /// diferente do usado na criação dela.
+    pub fn evaluate_with(&self, context: &EvalContext) -> Result<f64, Error> {
+        self.arena
+            .evaluate(self.root, context, &[], 0, &mut Unobserved)
+    }
+
+    /// Mesmo que `evaluate_with`, passando cada nó calculado para `observer`.
+    pub fn evaluate_observed<O: EvalObserver + ?Sized>(
+        &self,
+        context: &EvalContext,
+        observer: &mut O,
+    ) -> Result<f64, Error> {
+        self.arena.evaluate(self.root, context, &[], 0, observer)
+    }
+
+    /// Se a expressão não usa nenhum nome (variáveis, constantes ou funções do usuário), e o
+    /// resultado dela depende só das configurações do contexto (modo de ângulo, tamanho da
+    /// palavra, `epsilon`...), podendo ser guardado.
+    ///
+    /// ```
+    /// use asdf_calc::Expression;
+    ///
+    /// assert!(Expression::new("max(2, 3) ^ 2").unwrap().is_pure());
+    /// assert!(!Expression::new("2 * pi").unwrap().is_pure());
+    /// ```
+    pub fn is_pure(&self) -> bool {
+        self.arena.is_pure(self.root)
+    }
+
+    /// Calcula a expressão para cada valor de `inputs` dado à variável `variable`, como
+    /// `evaluate_with` faria mudando a variável a cada vez, mas calculando vários valores de uma
+    /// vez quando a expressão só usa operações, variáveis e funções embutidas. Pensado para
+    /// tabelas, gráficos e integrais, em que a mesma expressão é calculada milhares de vezes.
+    ///
+    /// A variável precisa existir em `context` quando a expressão é lida, mas o valor dela não
+    /// importa. Em caso de erro, retorna o do primeiro valor de `inputs` que falhou.
+    ///
+    /// ```
+    /// use asdf_calc::{EvalContext, Expression};
+    ///
+    /// let mut context = EvalContext::new();
+    /// context.set_variable("x", 0.0);
+    /// let expression = Expression::with_context("x^2 + 1", &context).expect("expressão válida");
+    /// assert_eq!(
+    ///     expression.evaluate_range(&context, "x", &[0.0, 1.0, 2.0, 3.0]),
+    ///     Ok(vec![1.0, 2.0, 5.0, 10.0])
+    /// );
+    /// ```
+    pub fn evaluate_range(
+        &self,
+        context: &EvalContext,
+        variable: &str,
+        inputs: &[f64],
+    ) -> Result<Vec<f64>, Error> {
+        let mut results = Vec::with_capacity(inputs.len());
+        // o caminho de um valor por vez muda a variável numa cópia do contexto, feita só se
+        // for preciso
+        let mut scalar_context = None;
+        let mut evaluate_each = |inputs: &[f64], results: &mut Vec<f64>| {
+            let scalar_context = scalar_context.get_or_insert_with(|| context.clone());
+            for input in inputs {
+                scalar_context.set_variable(String::from(variable), *input);
+                results.push(self.evaluate_with(scalar_context)?);
+            }
+            Ok(())
+        };
+        if !self.arena.is_vectorizable(self.root) {
+            evaluate_each(inputs, &mut results)?;
+            return Ok(results);
+        }
+
+        for chunk in inputs.chunks(LANES) {
+            // o último bloco é completado repetindo o primeiro valor dele
+            let mut lanes = [chunk[0]; LANES];
+            lanes[..chunk.len()].copy_from_slice(chunk);
+            match self
+                .arena
+                .evaluate_lanes(self.root, context, variable, &lanes)
+            {
+                Ok(values) => results.extend_from_slice(&values[..chunk.len()]),
+                // calculado de novo um por vez para que o erro seja o do primeiro valor que
+                // falhou
+                Err(_) => evaluate_each(chunk, &mut results)?,
+            }
+        }
+        Ok(results)
+    }
+
+    /// Calcula a expressão, acompanhando quantos algarismos significativos o resultado tem a
+    /// partir dos números escritos nela (`2.0 * 3.00` tem dois). Fica vazio quando a expressão
+    /// não tem nenhum número medido, só variáveis e constantes, que contam como exatas.
+    ///
+    /// ```
+    /// use asdf_calc::{EvalContext, Expression};
+    ///
+    /// let expression = Expression::new("1.5 + 10.25").expect("expressão válida");
+    /// assert_eq!(
+    ///     expression.significant_figures(&EvalContext::default()),
+    ///     Ok((11.75, Some(3)))
+    /// );
+    /// ```
+    pub fn significant_figures(&self, context: &EvalContext) -> Result<(f64, Option<u32>), Error> {
+        let (value, precision) = self.arena.measure(self.root, context)?;
+        Ok((value, precision.map(|precision| precision.figures)))
+    }
+
+    /// A dimensão do resultado, a partir das unidades escritas na expressão e das guardadas
+    /// com as variáveis, sem calcular nada além dos expoentes das potências. Retorna
+    /// `Error::IncompatibleUnits` se alguma operação juntar grandezas que não combinam, como
+    /// `Statement::execute` faria.
+    ///
+    /// ```
+    /// use asdf_calc::{Dimension, EvalContext, Expression};
+    ///
+    /// let context = EvalContext::new();
+    /// let speed = Expression::new("100 km / 2 h").unwrap();
+    /// assert_eq!(speed.dimension(&context).unwrap().to_string(), "m/s");
+    /// assert!(Expression::new("1 m + 1 s").unwrap().dimension(&context).is_err());
+    /// assert_eq!(Expression::new("2 + 2").unwrap().dimension(&context), Ok(Dimension::NONE));
+    /// ```
+    pub fn dimension(&self, context: &EvalContext) -> Result<Dimension, Error> {
+        self.arena
+            .dimension(self.root, context, &[], &mut Calls::default())
+    }
+}
+
+/// Função definida pelo usuário, que pode ser chamada pelo nome depois de adicionada a um
+/// `EvalContext` com `EvalContext::define_function`.
+///
+/// As funções chamadas no corpo são resolvidas quando ele é lido, então redefinir uma delas
+/// depois não muda o comportamento das que já a usavam.
+///
+/// ```
+/// use asdf_calc::{EvalContext, Expression, Function};
+///
+/// let mut context = EvalContext::new();
+/// let hypot = Function::new(vec!["a".into(), "b".into()], "(a^2 + b^2)^0.5", &context)
+///     .expect("corpo válido");
+/// context.define_function("hypot", hypot);
+///
+/// let expression = Expression::with_context("hypot(3, 4)", &context).expect("expressão válida");
+/// assert_eq!(expression.evaluate_with(&context), Ok(5.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    parameters: Vec<String>,
+    body: Expression,
+    /// O texto do corpo, como foi passado para `Function::new`.
+    source: String,
+}
+
+impl Function {
+    /// Lê o corpo da função, onde podem ser usados os parâmetros, além dos nomes e funções de
+    /// `context`.
+    pub fn new(
+        parameters: Vec<String>,
+        body: &str,
+        context: &EvalContext,
+    ) -> Result<Self, Vec<Error>> {
+        let mut errors = Vec::new();
+        let source = String::from(body);
+        let tokens = tokenize_with(body, context.si_suffixes(), &mut errors);
+        let body =
+            Expression::from_tokens(tokens, body.chars().count(), errors, context, &parameters)?;
+        Ok(Function {
+            parameters,
+            body,
+            source,
+        })
+    }
+
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    /// O corpo como foi escrito, que pode ser lido de volta com `Function::new` para exportar
+    /// a função.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+#[test]
+fn expression_should_be_created_with_simple_strs_correctly() {
+    // 3 + 5
+    let expression = Expression::new("3 + 5").expect("falha na criação da Expression: [3 + 5]");
+    assert_eq!(expression.evaluate(), Ok(8.0));
+
+    let other_expression = Expression::new("3+5").expect("falha na criação da Expression: [3+5]");
+    assert_eq!(other_expression.evaluate(), Ok(8.0));
+}
+
+#[test]
+fn expression_should_be_created_correclty() {
+    // 3 + (3 + 5) * 6 + 4 - 3 / 2
+    let expression_str = "3 + (3 + 5) * 6 + 4 - 3 / 2";
+    let expression = Expression::new(expression_str)
+        .expect("falha na criação da Expression [3 + (3 + 5) * 6 + 4 - 3 / 2]");
+    assert_eq!(
+        expression.evaluate(),
+        Ok(3.0 + (3.0 + 5.0) * 6.0 + 4.0 - 3.0 / 2.0)
+    );
+}
+
+#[test]
+fn expression_should_be_created_with_nested_parenthesis() {
+    let expression = Expression::new("2 * ((1 + 2) * -(3 - 1))")
+        .expect("falha na criação da Expression [2 * ((1 + 2) * -(3 - 1))]");
+    assert_eq!(
+        expression.evaluate(),
+        Ok(2.0 * ((1.0 + 2.0) * -(3.0 - 1.0)))
+    );
+}
+
+#[test]
+fn expression_should_resolve_constants() {
+    let expression =
+        Expression::new("2 * pi + e").expect("falha na criação da Expression [2 * pi + e]");
+    assert_eq!(
+        expression.evaluate(),
+        Ok(2.0 * std::f64::consts::PI + std::f64::consts::E)
+    );
+}
+
+#[test]
+fn expression_should_report_unexpected_characters_only_once() {
+    let errors = Expression::new("2 + abc @ 1").unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            Error::UnknownIdentifier("abc".to_string(), Span::new(4, 7)),
+            Error::UnexpectedCharacter('@', Span::new(8, 9)),
+        ]
+    );
+}
+
+#[test]
+fn expression_should_track_significant_figures() {
+    let figures = |text: &str| {
+        Expression::new(text)
+            .unwrap()
+            .significant_figures(&EvalContext::default())
+            .unwrap()
+            .1
+    };
+    assert_eq!(figures("2.0 * 3.00"), Some(2));
+    assert_eq!(figures("12.0 / 4"), Some(1));
+    assert_eq!(figures("100.0 - 9.95"), Some(3));
+    assert_eq!(figures("100.0 - 99.95"), Some(1));
+    assert_eq!(figures("(1.5 + 10.25) * 2.000"), Some(3));
+    assert_eq!(figures("-2.50^2"), Some(3));
+    assert_eq!(figures("max(16.0, 2.5)"), Some(2));
+    assert_eq!(figures("2 * pi"), Some(1));
+    assert_eq!(figures("pi"), None);
+    assert_eq!(figures("1.0 < 2.0"), None);
+}
+
+#[test]
+fn expression_should_use_variables_from_the_context() {
+    let mut context = EvalContext::new();
+    context.set_variable("x", 4.0);
+
+    assert_eq!(
+        Expression::new("x * 2").unwrap_err(),
+        vec![Error::UnknownIdentifier("x".to_string(), Span::new(0, 1))]
+    );
+
+    let expression = Expression::with_context("x * 2", &context)
+        .expect("falha na criação da Expression [x * 2]");
+    assert_eq!(expression.evaluate_with(&context), Ok(8.0));
+
+    context.set_variable("x", 5.0);
+    assert_eq!(expression.evaluate_with(&context), Ok(10.0));
+
+    context.remove_variable("x");
+    assert_eq!(
+        expression.evaluate_with(&context),
+        Err(Error::UnknownIdentifier("x".to_string(), Span::new(0, 1)))
+    );
+}
+
+#[test]
+fn expression_should_be_evaluated_correctly() {
+    // 4 + 5 + 9 + 3 * 2 / 3
+    let mut arena = Arena::default();
+    let mut number = |num: f64| arena.push(Node::Number(num));
+    let (four, five, nine, three, two, other_three) = (
+        number(4.0),
+        number(5.0),
+        number(9.0),
+        number(3.0),
+        number(2.0),
+        number(3.0),
+    );
+    let first_sum = arena.push(Node::Operation(OperationKind::Add, four, five));
+    let sum = arena.push(Node::Operation(OperationKind::Add, first_sum, nine));
+    let product = arena.push(Node::Operation(OperationKind::Multiply, three, two));
+    let quotient = arena.push(Node::Operation(OperationKind::Divide, product, other_three));
+    let root = arena.push(Node::Operation(OperationKind::Add, sum, quotient));
+    let expression = Expression { arena, root };
+
+    assert_eq!(expression.evaluate(), Ok(4.0 + 5.0 + 9.0 + 3.0 * 2.0 / 3.0));
+}
+
+#[test]
+fn expression_should_keep_every_node_in_its_arena() {
+    // as comparações encadeadas trocam a primeira comparação no lugar, sem deixar nós que não
+    // fazem parte da árvore
+    let expression = Expression::new("1 < 2 <= max(3, 4, 5)").unwrap();
+    assert_eq!(expression.arena.nodes.len(), 7);
+    assert_eq!(expression.arena.children.len(), 3);
+    assert_eq!(expression.root, NodeId(2));
+    assert_eq!(expression.evaluate(), Ok(1.0));
+
+    let text = vec!["1"; 200].join(" + ");
+    let expression = Expression::new(&text).unwrap();
+    assert_eq!(expression.arena.nodes.len(), 399);
+    assert_eq!(expression.evaluate(), Ok(200.0));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn expression_should_be_read_from_a_stream() {
+    let mut context = EvalContext::new();
+    context.set_variable("x", 2.0);
+    // bem mais termos do que o limite de níveis, que as somas encadeadas não usam
+    let text = vec!["(x * 3 - 1)"; 10_000].join(" + ");
+    let expression = Expression::from_reader(text.as_bytes(), &context)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        expression,
+        Expression::with_context(&text, &context).unwrap()
+    );
+    assert_eq!(expression.evaluate_with(&context), Ok(50_000.0));
+
+    assert_eq!(
+        Expression::from_reader("1 +".as_bytes(), &context).unwrap(),
+        Expression::with_context("1 +", &context)
+    );
+    assert!(Expression::from_reader(&b"1 + \xff"[..], &context).is_err());
+}
+
+#[test]
+fn expression_should_report_every_error_in_the_line() {
+    let errors = Expression::new("2 + a3 * (4 - 1)) + 5 / x").unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            Error::UnknownIdentifier("a3".to_string(), Span::new(4, 6)),
+            Error::ExtraParenthesis(Span::new(16, 17)),
+            Error::UnknownIdentifier("x".to_string(), Span::new(24, 25)),
+        ]
+    );
+}
+
+#[test]
+fn expression_should_report_unclosed_parenthesis_and_missing_operators() {
+    let errors = Expression::new("(1 + ").unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            Error::UnclosedParenthesis(Span::new(0, 1)),
+            Error::TrailingOperator(Span::new(3, 4)),
+        ]
+    );
+
+    let errors = Expression::new("1 2 + 3").unwrap_err();
+    assert_eq!(errors, vec![Error::MissingOperator(Span::new(2, 3))]);
+}
+
+#[test]
+fn expression_should_read_powers_as_right_associative() {
+    let evaluate = |text| Expression::new(text).unwrap().evaluate();
+    assert_eq!(evaluate("2 ^ 3 ^ 2"), Ok(512.0));
+    assert_eq!(evaluate("2 * 3 ^ 2"), Ok(18.0));
+    assert_eq!(evaluate("-2 ^ 2"), Ok(-4.0));
+    assert_eq!(evaluate("2 ^ -1"), Ok(0.5));
+}
+
+#[test]
+fn expression_should_call_functions_from_the_context() {
+    let mut context = EvalContext::new();
+    context.set_variable("x", 10.0);
+    let double = Function::new(vec!["x".to_string()], "x * 2", &context).unwrap();
+    context.define_function("double", double);
+    let add = Function::new(
+        vec!["a".to_string(), "b".to_string()],
+        "a + b + x",
+        &context,
+    )
+    .unwrap();
+    context.define_function("add", add);
+
+    let expression = Expression::with_context("double(add(1, 2)) + x", &context).unwrap();
+    assert_eq!(expression.evaluate_with(&context), Ok(36.0));
+
+    assert_eq!(
+        Expression::with_context("double(1, 2) + f(3)", &context).unwrap_err(),
+        vec![
+            Error::WrongArgumentCount("double".to_string(), 1, 2, Span::new(0, 6)),
+            Error::UnknownFunction("f".to_string(), Span::new(15, 16)),
+        ]
+    );
+    assert_eq!(
+        Expression::with_context("(1, 2)", &context).unwrap_err(),
+        vec![Error::UnexpectedCharacter(',', Span::new(2, 3))]
+    );
+}
+
+#[test]
+fn expression_should_call_builtin_functions() {
+    let evaluate = |text: &str| Expression::new(text).map(|expression| expression.evaluate());
+    assert_eq!(evaluate("max(1, 7, 3) - min(4, -2)"), Ok(Ok(9.0)));
+    assert_eq!(evaluate("clamp(lerp(0, 10, 1.5), 0, 10)"), Ok(Ok(10.0)));
+    assert_eq!(
+        evaluate("1 + clamp(1, 2, 0)"),
+        Ok(Err(Error::OutOfDomain(
+            "clamp".to_string(),
+            Span::new(4, 9)
+        )))
+    );
+    assert_eq!(
+        evaluate("min() + lerp(1, 2)"),
+        Err(vec![
+            Error::TooFewArguments("min".to_string(), 1, 0, Span::new(0, 3)),
+            Error::WrongArgumentCount("lerp".to_string(), 3, 2, Span::new(8, 12)),
+        ])
+    );
+
+    let mut context = EvalContext::new();
+    let first = Function::new(vec!["a".to_string(), "b".to_string()], "a", &context).unwrap();
+    context.define_function("max", first);
+    let expression = Expression::with_context("max(1, 2)", &context).unwrap();
+    assert_eq!(expression.evaluate_with(&context), Ok(1.0));
+}
+
+#[test]
+fn expression_should_compare_and_combine_conditions() {
+    let evaluate = |text: &str| Expression::new(text).unwrap().evaluate();
+    assert_eq!(evaluate("1 + 1 == 2"), Ok(1.0));
+    assert_eq!(evaluate("2 * 3 < 5 or 4 >= 4"), Ok(1.0));
+    assert_eq!(evaluate("1 < 2 and 3 != 3"), Ok(0.0));
+    assert_eq!(evaluate("not 1 == 2"), Ok(1.0));
+    assert_eq!(evaluate("not 0 and 0"), Ok(0.0));
+    assert_eq!(evaluate("(1 < 2) + (2 < 3)"), Ok(2.0));
+    assert_eq!(evaluate("0.1 + 0.2 == 0.3"), Ok(0.0));
+    assert_eq!(evaluate("0.1 + 0.2 ~= 0.3"), Ok(1.0));
+    assert_eq!(evaluate("0.1 + 0.2 ~= 0.3 and 1 < 2"), Ok(1.0));
+    assert_eq!(evaluate("approx(1 / 3, 0.33, 0.05)"), Ok(1.0));
+
+    // o lado direito não é calculado, e a divisão por zero não chega a acontecer
+    let mut context = EvalContext::new();
+    let inverse = Function::new(vec!["x".to_string()], "clamp(1 / x, 0, 1)", &context).unwrap();
+    context.define_function("inverse", inverse);
+    let expression = Expression::with_context("0 and inverse(-1) or 1", &context).unwrap();
+    assert_eq!(expression.evaluate_with(&context), Ok(1.0));
+    context.set_epsilon(1e-9);
+    let expression = Expression::with_context("0.1 + 0.2 == 0.3", &context).unwrap();
+    assert_eq!(expression.evaluate_with(&context), Ok(1.0));
+    let expression = Expression::with_context("1000 ~= 1000.5", &context).unwrap();
+    assert_eq!(expression.evaluate_with(&context), Ok(0.0));
+    context.set_approx_tolerance(1e-3);
+    assert_eq!(expression.evaluate_with(&context), Ok(1.0));
+    assert_eq!(evaluate("equal(0.1 + 0.2, 0.3, 1e-12)"), Ok(1.0));
+    assert_eq!(evaluate("equal(1000, 1000.5, 0.1)"), Ok(0.0));
+
+    assert_eq!(
+        Expression::new("1 < not").unwrap_err(),
+        vec![Error::TrailingOperator(Span::new(4, 7))]
+    );
+    assert_eq!(
+        Expression::new("not * 2").unwrap_err(),
+        vec![Error::MissingOperand(Span::new(4, 5))]
+    );
+}
+
+#[test]
+fn expression_should_only_evaluate_the_chosen_branch_of_if() {
+    let mut context = EvalContext::new();
+    // imposto de 10% até 1000 e 20% sobre o que passar disso
+    let tax = Function::new(
+        vec!["x".to_string()],
+        "if(x <= 1000, x * 0.1, 100 + (x - 1000) * 0.2)",
+        &context,
+    )
+    .unwrap();
+    context.define_function("tax", tax);
+    let evaluate = |text: &str| {
+        Expression::with_context(text, &context)
+            .map(|expression| expression.evaluate_with(&context))
+    };
+    assert_eq!(evaluate("tax(500)"), Ok(Ok(50.0)));
+    assert_eq!(evaluate("tax(1500)"), Ok(Ok(200.0)));
+    assert_eq!(evaluate("if(1, 2, clamp(0, 1, -1))"), Ok(Ok(2.0)));
+    assert_eq!(
+        evaluate("if(0, 2, clamp(0, 1, -1))"),
+        Ok(Err(Error::OutOfDomain(
+            "clamp".to_string(),
+            Span::new(9, 14)
+        )))
+    );
+    assert_eq!(
+        evaluate("if(1, 2)"),
+        Err(vec![Error::WrongArgumentCount(
+            "if".to_string(),
+            3,
+            2,
+            Span::new(0, 2)
+        )])
+    );
+}
+
+#[test]
+fn expression_should_override_the_angle_mode_with_literal_suffixes() {
+    let mut context = EvalContext::new();
+    let evaluate = |text: &str, context: &EvalContext| {
+        Expression::with_context(text, context)
+            .unwrap()
+            .evaluate_with(context)
+            .unwrap()
+    };
+    assert!((evaluate("sin(30deg)", &context) - 0.5).abs() < 1e-15);
+    assert_eq!(evaluate("cos(0.5rad)", &context), math::cos(0.5));
+    assert_eq!(evaluate("180deg", &context), core::f64::consts::PI);
+
+    context.set_angle_mode(crate::context::AngleMode::Degrees);
+    assert_eq!(evaluate("sin(90deg)", &context), 1.0);
+    assert!((evaluate("sin(1.2rad)", &context) - math::sin(1.2)).abs() < 1e-15);
+    assert!((evaluate("1rad", &context) - 57.29577951308232).abs() < 1e-12);
+}
+
+#[test]
+fn expression_should_chain_comparisons() {
+    let mut context = EvalContext::new();
+    let evaluate = |text: &str, context: &EvalContext| {
+        Expression::with_context(text, context)
+            .unwrap()
+            .evaluate_with(context)
+    };
+    context.set_variable("x", 5.0);
+    assert_eq!(evaluate("1 < x < 10", &context), Ok(1.0));
+    assert_eq!(evaluate("10 > x > 1 and x != 6", &context), Ok(1.0));
+    context.set_variable("x", 15.0);
+    assert_eq!(evaluate("1 < x < 10", &context), Ok(0.0));
+    assert_eq!(evaluate("1 < x + 1 <= 16 < 20", &context), Ok(1.0));
+    // com parênteses, o resultado da primeira comparação é que é comparado
+    assert_eq!(evaluate("(1 < x) < 10", &context), Ok(1.0));
+    assert_eq!(evaluate("3 > 2 > 1", &context), Ok(1.0));
+    assert_eq!(evaluate("(3 > 2) > 1", &context), Ok(0.0));
+
+    // um valor falso interrompe a cadeia
+    assert_eq!(evaluate("2 < 1 < clamp(0, 1, -1)", &context), Ok(0.0));
+}
+
+#[test]
+fn expression_should_stop_when_interrupted() {
+    let mut context = EvalContext::new();
+    let identity = Function::new(vec!["x".to_string()], "x", &context).unwrap();
+    context.define_function("id", identity);
+    let expression = Expression::with_context("1 + id(2)", &context).unwrap();
+
+    context.interrupt_flag().interrupt();
+    assert_eq!(
+        expression.evaluate_with(&context),
+        Err(Error::Interrupted(Span::new(4, 6)))
+    );
+    context.interrupt_flag().reset();
+    assert_eq!(expression.evaluate_with(&context), Ok(3.0));
+}
+
+#[test]
+fn expression_should_report_exceeded_limits_instead_of_overflowing() {
+    let nested = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+    assert_eq!(
+        Expression::new(&nested).unwrap_err(),
+        vec![Error::LimitExceeded(
+            Limit::Nesting(crate::Limits::default().max_nesting),
+            Span::new(256, 257)
+        )]
+    );
+    // as operações encadeadas pela esquerda não aninham, mas cada potência fica dentro da
+    // anterior
+    let chained = "1 + ".repeat(100_000) + "1";
+    let expression = Expression::new(&chained).unwrap();
+    assert_eq!(expression.evaluate(), Ok(100_001.0));
+    assert_eq!(
+        expression.significant_figures(&EvalContext::new()),
+        Ok((100_001.0, Some(6)))
+    );
+    assert_eq!(
+        expression.dimension(&EvalContext::new()),
+        Ok(Dimension::NONE)
+    );
+    assert!(expression.is_pure());
+    let powers = "2 ^ ".repeat(100_000) + "2";
+    assert_eq!(
+        Expression::new(&powers).unwrap_err(),
+        vec![Error::LimitExceeded(
+            Limit::Nesting(256),
+            Span::new(1024, 1025)
+        )]
+    );
+    let negated = "-".repeat(100_000) + "1";
+    assert_eq!(Expression::new(&negated).unwrap_err().len(), 1);
+
+    let mut context = EvalContext::new();
+    context.set_limits(crate::Limits {
+        max_nesting: 3,
+        max_call_depth: 2,
+    });
+    assert_eq!(
+        Expression::with_context("((1))", &context).map(|expression| expression.evaluate()),
+        Ok(Ok(1.0))
+    );
+    assert_eq!(
+        Expression::with_context("(((1)))", &context).unwrap_err(),
+        vec![Error::LimitExceeded(Limit::Nesting(3), Span::new(3, 4))]
+    );
+
+    let f = Function::new(vec!["x".to_string()], "x + 1", &context).unwrap();
+    context.define_function("f", f);
+    let g = Function::new(vec!["x".to_string()], "f(x) * 2", &context).unwrap();
+    context.define_function("g", g);
+    let h = Function::new(vec!["x".to_string()], "g(x)", &context).unwrap();
+    context.define_function("h", h);
+    let expression = Expression::with_context("g(1)", &context).unwrap();
+    assert_eq!(expression.evaluate_with(&context), Ok(4.0));
+    let expression = Expression::with_context("1 + h(1)", &context).unwrap();
+    assert_eq!(
+        expression.evaluate_with(&context),
+        Err(Error::LimitExceeded(Limit::CallDepth(2), Span::new(4, 5)))
+    );
+}
+
+/// Gerador pseudo-aleatório (xorshift64*) dos testes de propriedade, com semente fixa para que
+/// uma falha sempre possa ser reproduzida.
+#[cfg(test)]
+struct Rng(u64);
+
+#[cfg(test)]
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+/// Expressão gerada aleatoriamente junto do valor esperado, calculado diretamente durante a
+/// geração sem passar pelo parser, e da precedência do seu operador mais externo (usada para
+/// decidir onde os parênteses são necessários).
+#[cfg(test)]
+struct Generated {
+    text: String,
+    value: f64,
+    precedence: u8,
+}
+
+#[cfg(test)]
+fn generate_expression(rng: &mut Rng, depth: u32) -> Generated {
+    const ATOM: u8 = 5;
+    let space = |rng: &mut Rng| if rng.below(2) == 0 { "" } else { " " };
+
+    if depth == 0 || rng.below(4) == 0 {
+        let text = match rng.below(4) {
+            0 => format!("{}.{}", rng.below(1000), rng.below(100)),
+            1 => "pi".to_string(),
+            _ => rng.below(100).to_string(),
+        };
+        let value = if text == "pi" {
+            std::f64::consts::PI
+        } else {
+            text.parse().unwrap()
+        };
+        return Generated {
+            text,
+            value,
+            precedence: ATOM,
+        };
+    }
+
+    match rng.below(6) {
+        0 => {
+            let operand = generate_expression(rng, depth - 1);
+            let text = if operand.precedence < ATOM {
+                format!("-({})", operand.text)
+            } else {
+                format!("-{}", operand.text)
+            };
+            Generated {
+                text,
+                value: -operand.value,
+                precedence: 3,
+            }
+        }
+        1 => {
+            let inner = generate_expression(rng, depth - 1);
+            Generated {
+                text: format!("({}{}{})", space(rng), inner.text, space(rng)),
+                value: inner.value,
+                precedence: ATOM,
+            }
+        }
+        _ => {
+            // a precedência é definida aqui mesmo, e não com `OperationKind::precedence`, para
+            // que um erro nela também seja pego
+            let (kind, symbol, precedence) = match rng.below(5) {
+                0 => (OperationKind::Add, "+", 1),
+                1 => (OperationKind::Subtract, "-", 1),
+                2 => (OperationKind::Multiply, "*", 2),
+                3 => (OperationKind::Divide, "/", 2),
+                _ => (OperationKind::Power, "^", 4),
+            };
+            let lhs = generate_expression(rng, depth - 1);
+            let rhs = generate_expression(rng, depth - 1);
+
+            // a esquerda só precisa de parênteses com precedência menor, mas a direita também
+            // precisa com precedência igual, já que `a - (b - c)` é diferente de `a - b - c`.
+            // Na potência é o contrário, já que `a ^ b ^ c` é `a ^ (b ^ c)`
+            let right_associative = kind == OperationKind::Power;
+            let lhs_text = if lhs.precedence < precedence
+                || (right_associative && lhs.precedence == precedence)
+            {
+                format!("({})", lhs.text)
+            } else {
+                lhs.text
+            };
+            let rhs_text = if rhs.precedence < precedence
+                || (!right_associative && rhs.precedence == precedence)
+            {
+                format!("({})", rhs.text)
+            } else {
+                rhs.text
+            };
+
+            let value = match kind {
+                OperationKind::Add => lhs.value + rhs.value,
+                OperationKind::Subtract => lhs.value - rhs.value,
+                OperationKind::Multiply => lhs.value * rhs.value,
+                OperationKind::Divide => lhs.value / rhs.value,
+                OperationKind::Power => lhs.value.powf(rhs.value),
+                _ => unreachable!("só operações aritméticas são geradas"),
+            };
+            Generated {
+                text: format!(
+                    "{}{}{}{}{}",
+                    lhs_text,
+                    space(rng),
+                    symbol,
+                    space(rng),
+                    rhs_text
+                ),
+                value,
+                precedence,
+            }
+        }
+    }
+}
+
+#[test]
+fn expression_should_match_reference_evaluation_for_random_expressions() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for case in 0..2000 {
+        let generated = generate_expression(&mut rng, 6);
+        let expression = Expression::new(&generated.text).unwrap_or_else(|errors| {
+            panic!(
+                "caso {}: falha na criação da Expression [{}]: {:?}",
+                case, generated.text, errors
+            )
+        });
+
+        let result = expression
+            .evaluate()
+            .expect("as expressões geradas só usam constantes");
+        assert!(
+            result == generated.value || (result.is_nan() && generated.value.is_nan()),
+            "caso {}: [{}] resultou em {}, era esperado {}",
+            case,
+            generated.text,
+            result,
+            generated.value
+        );
+    }
+}
+
+#[test]
+fn evaluate_range_should_match_evaluating_each_value() {
+    let mut context = EvalContext::new();
+    context.set_variable("x", 0.0);
+    context.set_variable("k", 3.0);
+    let inputs: Vec<f64> = (0..101).map(|i| -5.0 + i as f64 * 0.37).collect();
+    for text in [
+        "x^2 + k*x - sin(x) / 2",
+        "-x * (x > 1) + max(x, 0, k)",
+        "if(x < 0, -x, x) + 1",
+        "x > 0 and x < 10",
+        "not (x ~= 2)",
+    ] {
+        let expression = Expression::with_context(text, &context).unwrap();
+        let mut scalar_context = context.clone();
+        let expected: Vec<f64> = inputs
+            .iter()
+            .map(|input| {
+                scalar_context.set_variable("x", *input);
+                expression.evaluate_with(&scalar_context).unwrap()
+            })
+            .collect();
+        assert_eq!(
+            expression.evaluate_range(&context, "x", &inputs),
+            Ok(expected),
+            "{}",
+            text
+        );
+    }
+
+    let expression = Expression::with_context("unsigned(x)", &context).unwrap();
+    assert_eq!(
+        expression.evaluate_range(&context, "x", &[1.0, 2.0, 2.5, -1.5]),
+        Err(Error::OutOfDomain("unsigned".into(), Span::new(0, 8)))
+    );
+    let expression = Expression::with_context("x + 1", &context).unwrap();
+    assert_eq!(expression.evaluate_range(&context, "x", &[]), Ok(vec![]));
+}
+
+#[test]
+fn deterministic_mode_should_use_the_portable_functions() {
+    let mut context = EvalContext::new();
+    context.set_deterministic(true);
+    context.set_variable("x", 2.7);
+    let expression = Expression::with_context("sin(x) + x ^ 0.3 + gamma(x)", &context).unwrap();
+    let expected =
+        crate::portable::sin(2.7) + crate::portable::pow(2.7, 0.3) + crate::portable::gamma(2.7);
+    assert_eq!(
+        expression.evaluate_with(&context).unwrap().to_bits(),
+        expected.to_bits()
+    );
+
+    let inputs: Vec<f64> = (0..50).map(|i| 0.1 + i as f64 * 0.21).collect();
+    let mut scalar_context = context.clone();
+    let expected: Vec<u64> = inputs
+        .iter()
+        .map(|input| {
+            scalar_context.set_variable("x", *input);
+            expression.evaluate_with(&scalar_context).unwrap().to_bits()
+        })
+        .collect();
+    let results = expression.evaluate_range(&context, "x", &inputs).unwrap();
+    assert_eq!(
+        results
+            .iter()
+            .map(|result| result.to_bits())
+            .collect::<Vec<_>>(),
+        expected
+    );
+}
+
+#[test]
+fn expression_should_not_panic_with_mangled_expressions() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..2000 {
+        let mut chars: Vec<char> = generate_expression(&mut rng, 4).text.chars().collect();
+        for _ in 0..=rng.below(3) {
+            let position = rng.below(chars.len() as u64 + 1) as usize;
+            match rng.below(3) {
+                0 if position < chars.len() => {
+                    chars.remove(position);
+                }
+                _ => {
+                    let noise = [
+                        '(', ')', '+', '*', '^', ',', '<', '=', '!', '~', '@', 'x', '.', ' ',
+                    ];
+                    chars.insert(position, noise[rng.below(noise.len() as u64) as usize]);
+                }
+            }
+        }
+
+        let text: String = chars.into_iter().collect();
+        if let Ok(expression) = Expression::new(&text) {
+            let _ = expression.evaluate();
+        }
+    }
+}
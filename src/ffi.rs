@@ -0,0 +1,118 @@
+//! Interface em C para embutir a calculadora em outras linguagens. O header correspondente fica
+//! em `include/asdf_calc.h`.
+
+use std::{
+    ffi::{c_char, c_int, CStr, CString},
+    panic, ptr,
+};
+
+use crate::Expression;
+
+/// Retornado por `asdf_calc_eval` quando a expressão foi calculada.
+pub const ASDF_CALC_OK: c_int = 0;
+/// Retornado por `asdf_calc_eval` quando a expressão não pôde ser calculada, a mensagem de erro
+/// fica disponível em `err`.
+pub const ASDF_CALC_ERROR: c_int = 1;
+
+fn evaluate(expression: *const c_char) -> Result<f64, String> {
+    if expression.is_null() {
+        return Err("a expressão passada é um ponteiro nulo".to_string());
+    }
+
+    // SAFETY: quem chama garante que `expression` é uma string terminada em `\0` válida
+    let text = unsafe { CStr::from_ptr(expression) }
+        .to_str()
+        .map_err(|_| "a expressão passada não é UTF-8 válido".to_string())?;
+
+    let expression = Expression::new(text).map_err(|errors| {
+        errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+    expression.evaluate().map_err(|error| error.to_string())
+}
+
+/// Calcula `expression`, escrevendo o resultado em `out`.
+///
+/// Em caso de erro retorna `ASDF_CALC_ERROR` e, se `err` não for nulo, escreve nele uma mensagem
+/// (um erro por linha) que deve ser liberada com `asdf_calc_free_string`. Em caso de sucesso
+/// `err` recebe um ponteiro nulo.
+///
+/// # Safety
+///
+/// `expression` precisa ser nulo ou uma string terminada em `\0` válida, e `out` e `err` precisam
+/// ser nulos ou apontar para memória onde se possa escrever.
+#[no_mangle]
+pub unsafe extern "C" fn asdf_calc_eval(
+    expression: *const c_char,
+    out: *mut f64,
+    err: *mut *mut c_char,
+) -> c_int {
+    // um panic não pode atravessar a fronteira com o C
+    let result = panic::catch_unwind(|| evaluate(expression))
+        .unwrap_or_else(|_| Err("erro interno ao calcular a expressão".to_string()));
+
+    match result {
+        Ok(value) => {
+            if !out.is_null() {
+                *out = value;
+            }
+            if !err.is_null() {
+                *err = ptr::null_mut();
+            }
+            ASDF_CALC_OK
+        }
+        Err(message) => {
+            if !err.is_null() {
+                // a mensagem vem do Display dos erros, que nunca contém `\0`
+                *err = CString::new(message).unwrap_or_default().into_raw();
+            }
+            ASDF_CALC_ERROR
+        }
+    }
+}
+
+/// Libera uma string alocada pela biblioteca, como a mensagem de erro de `asdf_calc_eval`.
+///
+/// # Safety
+///
+/// `string` precisa ser nulo ou um ponteiro retornado pela biblioteca que ainda não foi liberado.
+#[no_mangle]
+pub unsafe extern "C" fn asdf_calc_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[test]
+fn asdf_calc_eval_should_write_the_result() {
+    let expression = CString::new("3 + (3 + 5) * 6").unwrap();
+    let mut out = 0.0;
+    let mut err = ptr::null_mut();
+
+    let status = unsafe { asdf_calc_eval(expression.as_ptr(), &mut out, &mut err) };
+    assert_eq!(status, ASDF_CALC_OK);
+    assert_eq!(out, 51.0);
+    assert!(err.is_null());
+}
+
+#[test]
+fn asdf_calc_eval_should_write_every_error() {
+    let expression = CString::new("2 + abc @ 1").unwrap();
+    let mut out = 0.0;
+    let mut err = ptr::null_mut();
+
+    let status = unsafe { asdf_calc_eval(expression.as_ptr(), &mut out, &mut err) };
+    assert_eq!(status, ASDF_CALC_ERROR);
+    let message = unsafe { CStr::from_ptr(err) }.to_str().unwrap().to_string();
+    assert_eq!(message.lines().count(), 2);
+    unsafe { asdf_calc_free_string(err) };
+}
+
+#[test]
+fn asdf_calc_eval_should_accept_null_pointers() {
+    let status = unsafe { asdf_calc_eval(ptr::null(), ptr::null_mut(), ptr::null_mut()) };
+    assert_eq!(status, ASDF_CALC_ERROR);
+}
@@ -0,0 +1,148 @@
+//! `amortize(principal, taxa, anos)`: as parcelas de um financiamento pela tabela Price, com
+//! parcelas mensais iguais e os juros da taxa anual (em pontos percentuais, `6.5` é 6,5% ao ano)
+//! capitalizados todo mês.
+
+use crate::table;
+
+/// Mais que isso a tabela teria mais linhas do que alguém consegue ler.
+const MAX_MONTHS: usize = 1200;
+
+/// Um mês do financiamento.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Installment {
+    pub payment: f64,
+    pub interest: f64,
+    /// Quanto da parcela abateu o saldo devedor.
+    pub amortization: f64,
+    /// O que ainda falta pagar depois da parcela.
+    pub balance: f64,
+}
+
+/// Parcela mensal que paga `principal` em `months` meses com os juros mensais `monthly_rate`.
+pub fn monthly_payment(principal: f64, monthly_rate: f64, months: usize) -> f64 {
+    if monthly_rate == 0.0 {
+        return principal / months as f64;
+    }
+    principal * monthly_rate / (1.0 - (1.0 + monthly_rate).powi(-(months as i32)))
+}
+
+/// As parcelas de cada mês, com a última acertada para que o saldo termine em zero apesar dos
+/// arredondamentos.
+pub fn schedule(principal: f64, rate: f64, years: f64) -> Result<Vec<Installment>, String> {
+    if !principal.is_finite() || principal <= 0.0 {
+        return Err(format!(
+            "o valor financiado precisa ser positivo: {}",
+            principal
+        ));
+    }
+    if !rate.is_finite() || rate < 0.0 {
+        return Err(format!("a taxa não pode ser negativa: {}", rate));
+    }
+    let months = years * 12.0;
+    if !(months >= 1.0 && months <= MAX_MONTHS as f64) || (months - months.round()).abs() > 1e-9 {
+        return Err(format!(
+            "o prazo precisa ser de um número inteiro de meses, entre 1 e {}: {} anos",
+            MAX_MONTHS, years
+        ));
+    }
+    let months = months.round() as usize;
+
+    let monthly_rate = rate / 100.0 / 12.0;
+    let payment = monthly_payment(principal, monthly_rate, months);
+    let mut balance = principal;
+    Ok((1..=months)
+        .map(|month| {
+            let interest = balance * monthly_rate;
+            let payment = if month == months {
+                balance + interest
+            } else {
+                payment
+            };
+            balance -= payment - interest;
+            Installment {
+                payment,
+                interest,
+                amortization: payment - interest,
+                balance: if month == months { 0.0 } else { balance },
+            }
+        })
+        .collect())
+}
+
+/// O resumo do financiamento, e a tabela com todos os meses se `table`. Os valores são
+/// mostrados em centavos.
+pub fn describe(principal: f64, rate: f64, years: f64, table: bool) -> Result<String, String> {
+    let installments = schedule(principal, rate, years)?;
+    let total = |field: fn(&Installment) -> f64| installments.iter().map(field).sum::<f64>();
+    let (paid, interest) = (
+        total(|installment| installment.payment),
+        total(|installment| installment.interest),
+    );
+    let mut output = format!(
+        "parcela mensal: {:.2}\ntotal pago: {:.2} em {} meses (juros: {:.2})",
+        installments[0].payment,
+        paid,
+        installments.len(),
+        interest
+    );
+    if table {
+        let header = ["mês", "parcela", "juros", "amortização", "saldo"].map(String::from);
+        let mut rows: Vec<Vec<String>> = installments
+            .iter()
+            .enumerate()
+            .map(|(month, installment)| {
+                vec![
+                    (month + 1).to_string(),
+                    format!("{:.2}", installment.payment),
+                    format!("{:.2}", installment.interest),
+                    format!("{:.2}", installment.amortization),
+                    format!("{:.2}", installment.balance),
+                ]
+            })
+            .collect();
+        rows.push(vec![
+            "total".to_string(),
+            format!("{:.2}", paid),
+            format!("{:.2}", interest),
+            format!("{:.2}", total(|installment| installment.amortization)),
+        ]);
+        output.push('\n');
+        output.push_str(table::render_to_fit(&header, &rows).trim_end());
+    }
+    Ok(output)
+}
+
+#[test]
+fn schedule_should_pay_off_the_loan() {
+    // 200 mil em 30 anos a 6,5% ao ano: 1264,14 por mês, como nas calculadoras de banco
+    let installments = schedule(200_000.0, 6.5, 30.0).unwrap();
+    assert_eq!(installments.len(), 360);
+    assert_eq!(format!("{:.2}", installments[0].payment), "1264.14");
+    assert_eq!(format!("{:.2}", installments[0].interest), "1083.33");
+    assert_eq!(installments[359].balance, 0.0);
+    let amortized: f64 = installments.iter().map(|month| month.amortization).sum();
+    assert!((amortized - 200_000.0).abs() < 1e-6);
+
+    let installments = schedule(1200.0, 0.0, 1.0).unwrap();
+    assert!(installments.iter().all(|month| month.payment == 100.0));
+    assert!(schedule(1000.0, 5.0, 1.0 / 24.0).is_err());
+    assert!(schedule(-1000.0, 5.0, 1.0).is_err());
+    assert!(schedule(1000.0, -5.0, 1.0).is_err());
+    assert!(schedule(1000.0, 5.0, 1000.0).is_err());
+}
+
+#[test]
+fn describe_should_show_the_totals_and_the_table() {
+    assert_eq!(
+        describe(1200.0, 0.0, 0.25, true).unwrap(),
+        "\
+parcela mensal: 400.00
+total pago: 1200.00 em 3 meses (juros: 0.00)
+  mês | parcela | juros | amortização |  saldo
+    1 |  400.00 |  0.00 |      400.00 | 800.00
+    2 |  400.00 |  0.00 |      400.00 | 400.00
+    3 |  400.00 |  0.00 |      400.00 |   0.00
+total | 1200.00 |  0.00 |     1200.00 |"
+    );
+    assert!(!describe(1200.0, 10.0, 1.0, false).unwrap().contains('|'));
+}
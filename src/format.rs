@@ -0,0 +1,444 @@
+//! Notação usada para mostrar os resultados (`format sci`, `format eng`...), a base dos
+//! inteiros (`set base hex`), e o formato de ângulos em graus, minutos e segundos (`to dms`).
+
+use asdf_calc::WordSize;
+
+/// Expoente a partir do qual `format auto` passa para a notação científica, quando nenhum é
+/// informado.
+pub const DEFAULT_AUTO_THRESHOLD: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Notation {
+    /// Como o Rust escreve o `f64`, sem expoente.
+    #[default]
+    Plain,
+    /// `1.2e-6`.
+    Scientific,
+    /// Como a científica, mas com o expoente sempre múltiplo de 3 (`12e3`, `1.2e-6`).
+    Engineering,
+    /// Científica para números com expoente de pelo menos o limite (em módulo), e sem expoente
+    /// para o resto.
+    Auto { threshold: u32 },
+    /// Com o prefixo do SI da ordem de grandeza (`1.2 k`, `3.4 M`), ou com os prefixos binários
+    /// de tamanhos de dados (`1.5 KiB`) se `binary`.
+    Human { binary: bool },
+}
+
+/// Prefixos de `format human` a partir de 10⁻¹⁵, um a cada potência de 1000.
+const DECIMAL_PREFIXES: [&str; 12] = ["f", "p", "n", "µ", "m", "", "k", "M", "G", "T", "P", "E"];
+/// Posição do prefixo vazio em `DECIMAL_PREFIXES`.
+const DECIMAL_UNIT: usize = 5;
+/// Prefixos de `format human bin`, um a cada potência de 1024.
+const BINARY_PREFIXES: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+impl Notation {
+    /// Lê o que vem depois de `format` (`sci`, `eng`, `auto 9`...).
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut words = text.split_whitespace();
+        let notation = match words.next() {
+            Some("plain") => Notation::Plain,
+            Some("sci") => Notation::Scientific,
+            Some("eng") => Notation::Engineering,
+            Some("human") => match words.next() {
+                None => Notation::Human { binary: false },
+                Some("bin") => Notation::Human { binary: true },
+                Some(other) => {
+                    return Err(format!("opção desconhecida para 'format human': {}", other))
+                }
+            },
+            Some("auto") => match words.next() {
+                None => Notation::Auto {
+                    threshold: DEFAULT_AUTO_THRESHOLD,
+                },
+                Some(threshold) => Notation::Auto {
+                    threshold: threshold.parse().map_err(|_| {
+                        format!("limite inválido para 'format auto': {}", threshold)
+                    })?,
+                },
+            },
+            Some(other) => {
+                return Err(format!(
+                    "formato desconhecido '{}' (os formatos são plain, sci, eng, auto e human)",
+                    other
+                ))
+            }
+            None => return Err("uso: format plain|sci|eng|auto [limite]|human [bin]".to_string()),
+        };
+        if let Some(extra) = words.next() {
+            return Err(format!("argumento a mais para 'format': {}", extra));
+        }
+        Ok(notation)
+    }
+
+    pub fn format(self, value: f64) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+
+        match self {
+            Notation::Plain => value.to_string(),
+            Notation::Scientific => format!("{:e}", value),
+            Notation::Engineering => engineering(value),
+            Notation::Auto { threshold } => {
+                let threshold = threshold as i32;
+                if value != 0.0 && decimal_exponent(value).abs() >= threshold {
+                    format!("{:e}", value)
+                } else {
+                    value.to_string()
+                }
+            }
+            Notation::Human { binary: false } => {
+                with_prefix(value, 1000.0, &DECIMAL_PREFIXES, DECIMAL_UNIT)
+            }
+            Notation::Human { binary: true } => with_prefix(value, 1024.0, &BINARY_PREFIXES, 0),
+        }
+    }
+
+    /// Escreve `value` arredondado para `figures` algarismos significativos, mantendo os zeros
+    /// que fazem parte deles (`6.0`). Sem expoente no `plain` e no `auto` abaixo do limite, e na
+    /// notação científica no resto.
+    pub fn format_significant(self, value: f64, figures: u32) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+
+        let decimals = figures.clamp(1, 17) as usize - 1;
+        let scientific = format!("{:.*e}", decimals, value);
+        // o arredondamento pode mudar o expoente (`9.96` com dois algarismos vira `1.0e1`)
+        let rounded = scientific.parse().unwrap_or(value);
+        let exponent = decimal_exponent(rounded);
+        let fixed = match self {
+            Notation::Plain => (-5..16).contains(&exponent),
+            Notation::Auto { threshold } => exponent.abs() < threshold as i32,
+            _ => false,
+        };
+        if fixed {
+            let decimals = (decimals as i32 - exponent).max(0) as usize;
+            format!("{:.*}", decimals, rounded)
+        } else {
+            scientific
+        }
+    }
+}
+
+impl std::fmt::Display for Notation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Notation::Plain => write!(f, "plain"),
+            Notation::Scientific => write!(f, "sci"),
+            Notation::Engineering => write!(f, "eng"),
+            Notation::Auto { threshold } => write!(f, "auto {}", threshold),
+            Notation::Human { binary: false } => write!(f, "human"),
+            Notation::Human { binary: true } => write!(f, "human bin"),
+        }
+    }
+}
+
+/// A base em que os resultados inteiros são mostrados (`set base hex`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Base {
+    #[default]
+    Decimal,
+    Hexadecimal,
+    Octal,
+    Binary,
+}
+
+impl Base {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.trim() {
+            "dec" => Ok(Base::Decimal),
+            "hex" => Ok(Base::Hexadecimal),
+            "oct" => Ok(Base::Octal),
+            "bin" => Ok(Base::Binary),
+            other => Err(format!(
+                "base desconhecida '{}' (use dec, hex, oct ou bin)",
+                other
+            )),
+        }
+    }
+
+    /// `value` na base, com o prefixo dela (`0xff`, `-0b101`), ou `None` na decimal e para
+    /// números que não são inteiros ou não cabem em 64 bits.
+    pub fn format(self, value: f64) -> Option<String> {
+        if self == Base::Decimal || value.fract() != 0.0 || value.abs() >= 2f64.powi(64) {
+            return None;
+        }
+        let sign = if value < 0.0 { "-" } else { "" };
+        let magnitude = value.abs() as u64;
+        Some(match self {
+            Base::Hexadecimal => format!("{}{:#x}", sign, magnitude),
+            Base::Octal => format!("{}{:#o}", sign, magnitude),
+            _ => format!("{}{:#b}", sign, magnitude),
+        })
+    }
+}
+
+impl std::fmt::Display for Base {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Base::Decimal => write!(f, "dec"),
+            Base::Hexadecimal => write!(f, "hex"),
+            Base::Octal => write!(f, "oct"),
+            Base::Binary => write!(f, "bin"),
+        }
+    }
+}
+
+/// Divide `value` pela potência de `base` que o deixa entre 1 e `base`, com duas casas decimais
+/// no máximo e o prefixo correspondente, sendo `prefixes[unit]` o prefixo de `base⁰`. Valores
+/// grandes ou pequenos demais para os prefixos ficam na notação científica.
+fn with_prefix(value: f64, base: f64, prefixes: &[&str], unit: usize) -> String {
+    let mut index = unit;
+    let mut scaled = value.abs();
+    while scaled >= base && index + 1 < prefixes.len() {
+        scaled /= base;
+        index += 1;
+    }
+    while scaled != 0.0 && scaled < 1.0 && index > 0 {
+        scaled *= base;
+        index -= 1;
+    }
+    // `999.999` seria mostrado como `1000 k`
+    if round_to_hundredths(scaled) >= base && index + 1 < prefixes.len() {
+        scaled /= base;
+        index += 1;
+    }
+    let rounded = round_to_hundredths(scaled);
+    if scaled != 0.0 && (rounded >= base || rounded < 1.0) {
+        return format!("{:e}", value);
+    }
+
+    let sign = if value < 0.0 { "-" } else { "" };
+    let number = format!("{:.2}", scaled);
+    let number = number.trim_end_matches('0').trim_end_matches('.');
+    if prefixes[index].is_empty() {
+        format!("{}{}", sign, number)
+    } else {
+        format!("{}{} {}", sign, number, prefixes[index])
+    }
+}
+
+fn round_to_hundredths(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Escreve um resultado inteiro em hexadecimal, binário e octal (`0xff  0b11111111  0o377`), ou
+/// `None` se ele não for inteiro ou não couber em 64 bits. Os negativos que cabem na palavra
+/// aparecem em complemento de dois, junto do valor sem sinal dos mesmos bits
+/// (`(u8: 255)  0xff  0b11111111  0o377` para `-1`).
+pub fn other_bases(value: f64, word_size: WordSize) -> Option<String> {
+    if value.fract() != 0.0 || value.abs() >= 2f64.powi(64) {
+        return None;
+    }
+    match word_size.to_bits(value) {
+        Some(bits) if value < 0.0 => Some(format!(
+            "(u{0}: {1})  {1:#x}  {1:#b}  {1:#o}",
+            word_size.bits(),
+            bits
+        )),
+        Some(bits) => Some(format!("{0:#x}  {0:#b}  {0:#o}", bits)),
+        None => {
+            let sign = if value < 0.0 { "-" } else { "" };
+            let magnitude = value.abs() as u64;
+            Some(format!("{0}{1:#x}  {0}{1:#b}  {0}{1:#o}", sign, magnitude))
+        }
+    }
+}
+
+/// Escreve o ângulo em graus como `30°15'50.5"`, com os segundos arredondados para duas casas.
+pub fn dms(degrees: f64) -> Result<String, String> {
+    if !degrees.is_finite() || degrees.abs() >= 1e12 {
+        return Err(format!(
+            "não é possível escrever {} em graus, minutos e segundos",
+            degrees
+        ));
+    }
+
+    // em centésimos de segundo, para que o arredondamento passe para os minutos e graus
+    // (59.999" vira 1', e não 60")
+    let hundredths = (degrees.abs() * 360_000.0).round() as u64;
+    let seconds = hundredths % 6000;
+    let seconds = format!("{}.{:02}", seconds / 100, seconds % 100);
+    let seconds = seconds.trim_end_matches('0').trim_end_matches('.');
+    let sign = if degrees < 0.0 && hundredths != 0 {
+        "-"
+    } else {
+        ""
+    };
+    Ok(format!(
+        "{}{}\u{b0}{}'{}\"",
+        sign,
+        hundredths / 360_000,
+        hundredths / 6000 % 60,
+        seconds
+    ))
+}
+
+/// Separa `{:e}` em sinal, dígitos significativos e expoente (`-1.25e3` vira `("-", "125", 3)`).
+fn split_scientific(value: f64) -> (&'static str, String, i32) {
+    let text = format!("{:e}", value.abs());
+    let (mantissa, exponent) = text
+        .split_once('e')
+        .expect("a notação científica sempre tem expoente");
+    let sign = if value.is_sign_negative() { "-" } else { "" };
+    (
+        sign,
+        mantissa.replace('.', ""),
+        exponent.parse().expect("o expoente sempre é um inteiro"),
+    )
+}
+
+fn decimal_exponent(value: f64) -> i32 {
+    split_scientific(value).2
+}
+
+/// Move o ponto decimal nos dígitos do `{:e}` ao invés de dividir por uma potência de 10, que poderia
+/// mudar os últimos dígitos (`1.2e-6 / 1e-6` não é exatamente `1.2`).
+fn engineering(value: f64) -> String {
+    if value == 0.0 {
+        return format!("{:e}", value);
+    }
+
+    let (sign, mut digits, exponent) = split_scientific(value);
+    let engineering_exponent = exponent.div_euclid(3) * 3;
+    let integer_digits = (exponent - engineering_exponent) as usize + 1;
+    while digits.len() < integer_digits {
+        digits.push('0');
+    }
+
+    let (integer, fraction) = digits.split_at(integer_digits);
+    if fraction.is_empty() {
+        format!("{}{}e{}", sign, integer, engineering_exponent)
+    } else {
+        format!("{}{}.{}e{}", sign, integer, fraction, engineering_exponent)
+    }
+}
+
+#[test]
+fn notation_should_format_results() {
+    assert_eq!(Notation::Plain.format(0.0000012), "0.0000012");
+    assert_eq!(Notation::Scientific.format(0.0000012), "1.2e-6");
+    assert_eq!(Notation::Scientific.format(5.3e9), "5.3e9");
+    assert_eq!(Notation::Engineering.format(5.3e9), "5.3e9");
+    assert_eq!(Notation::Engineering.format(0.0000012), "1.2e-6");
+    assert_eq!(Notation::Engineering.format(12345.0), "12.345e3");
+    assert_eq!(Notation::Engineering.format(-0.00025), "-250e-6");
+    assert_eq!(Notation::Engineering.format(100.0), "100e0");
+    assert_eq!(Notation::Engineering.format(0.0), "0e0");
+    assert_eq!(Notation::Engineering.format(f64::INFINITY), "inf");
+
+    let auto = Notation::Auto { threshold: 6 };
+    assert_eq!(auto.format(5.3e9), "5.3e9");
+    assert_eq!(auto.format(0.0000012), "1.2e-6");
+    assert_eq!(auto.format(123456.5), "123456.5");
+    assert_eq!(auto.format(0.5), "0.5");
+    assert_eq!(auto.format(0.0), "0");
+}
+
+#[test]
+fn notation_should_format_significant_figures() {
+    assert_eq!(Notation::Plain.format_significant(6.0, 2), "6.0");
+    assert_eq!(Notation::Plain.format_significant(11.75, 3), "11.8");
+    assert_eq!(Notation::Plain.format_significant(9.96, 2), "10");
+    assert_eq!(Notation::Plain.format_significant(1234.0, 2), "1200");
+    assert_eq!(
+        Notation::Plain.format_significant(-0.0012345, 3),
+        "-0.00123"
+    );
+    assert_eq!(Notation::Scientific.format_significant(1234.0, 2), "1.2e3");
+    assert_eq!(Notation::Engineering.format_significant(6.0, 3), "6.00e0");
+    assert_eq!(
+        Notation::Auto { threshold: 3 }.format_significant(1234.0, 3),
+        "1.23e3"
+    );
+}
+
+#[test]
+fn notation_should_format_results_with_magnitude_prefixes() {
+    let human = Notation::Human { binary: false };
+    assert_eq!(human.format(1200.0), "1.2 k");
+    assert_eq!(human.format(3.4e6), "3.4 M");
+    assert_eq!(human.format(-5_612_345_678.0), "-5.61 G");
+    assert_eq!(human.format(999_999.0), "1 M");
+    assert_eq!(human.format(42.0), "42");
+    assert_eq!(human.format(0.0033), "3.3 m");
+    assert_eq!(human.format(0.0), "0");
+    assert_eq!(human.format(1e30), "1e30");
+    assert_eq!(human.format(1e-20), "1e-20");
+
+    let binary = Notation::Human { binary: true };
+    assert_eq!(binary.format(512.0), "512 B");
+    assert_eq!(binary.format(1536.0), "1.5 KiB");
+    assert_eq!(binary.format(8.0 * 1024.0 * 1024.0 * 1024.0), "8 GiB");
+    assert_eq!(binary.format(0.5), "5e-1");
+}
+
+#[test]
+fn other_bases_should_write_integers_only() {
+    let u64 = WordSize::U64;
+    assert_eq!(
+        other_bases(255.0, u64),
+        Some("0xff  0b11111111  0o377".to_string())
+    );
+    assert_eq!(other_bases(0.0, u64), Some("0x0  0b0  0o0".to_string()));
+    assert_eq!(other_bases(2.5, u64), None);
+    assert_eq!(other_bases(1e30, u64), None);
+    assert_eq!(other_bases(f64::NAN, u64), None);
+
+    assert_eq!(
+        other_bases(-10.0, WordSize::U8),
+        Some("(u8: 246)  0xf6  0b11110110  0o366".to_string())
+    );
+    assert_eq!(
+        other_bases(-300.0, WordSize::U8),
+        Some("-0x12c  -0b100101100  -0o454".to_string())
+    );
+}
+
+#[test]
+fn base_should_write_integers_with_a_prefix() {
+    assert_eq!(Base::Hexadecimal.format(255.0), Some("0xff".to_string()));
+    assert_eq!(Base::Binary.format(-5.0), Some("-0b101".to_string()));
+    assert_eq!(Base::Octal.format(8.0), Some("0o10".to_string()));
+    assert_eq!(Base::Hexadecimal.format(0.5), None);
+    assert_eq!(Base::Decimal.format(255.0), None);
+    assert_eq!(Base::parse("hex"), Ok(Base::Hexadecimal));
+    assert!(Base::parse("b3").is_err());
+}
+
+#[test]
+fn dms_should_write_degrees_minutes_and_seconds() {
+    assert_eq!(
+        dms(30.0 + 15.0 / 60.0 + 50.0 / 3600.0),
+        Ok("30\u{b0}15'50\"".to_string())
+    );
+    assert_eq!(dms(-12.5), Ok("-12\u{b0}30'0\"".to_string()));
+    assert_eq!(dms(1.0 / 3600.0 * 0.25), Ok("0\u{b0}0'0.25\"".to_string()));
+    assert_eq!(dms(10.0 - 1e-7), Ok("10\u{b0}0'0\"".to_string()));
+    assert!(dms(f64::NAN).is_err());
+}
+
+#[test]
+fn notation_should_be_parsed_from_the_format_command() {
+    assert_eq!(
+        Notation::parse("human bin"),
+        Ok(Notation::Human { binary: true })
+    );
+    assert!(Notation::parse("human kb").is_err());
+    assert_eq!(Notation::parse("eng"), Ok(Notation::Engineering));
+    assert_eq!(
+        Notation::parse("auto"),
+        Ok(Notation::Auto {
+            threshold: DEFAULT_AUTO_THRESHOLD
+        })
+    );
+    assert_eq!(
+        Notation::parse(" auto  9 "),
+        Ok(Notation::Auto { threshold: 9 })
+    );
+    assert!(Notation::parse("auto -1").is_err());
+    assert!(Notation::parse("sci 2").is_err());
+    assert!(Notation::parse("hex").is_err());
+    assert!(Notation::parse("").is_err());
+}
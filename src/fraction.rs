@@ -0,0 +1,81 @@
+//! Aproximação de resultados por frações simples (`as_fraction`), usando frações contínuas.
+
+/// Maior denominador usado por `as_fraction` quando nenhum é informado.
+pub const DEFAULT_MAX_DENOMINATOR: u64 = 1000;
+
+/// Fração mais próxima de `value` com denominador de no máximo `max_denominator`, como
+/// `(numerador, denominador)`. Retorna `None` para valores que não são finitos ou que são grandes
+/// demais para que a parte inteira caiba exatamente no `f64`.
+pub fn approximate(value: f64, max_denominator: u64) -> Option<(i64, u64)> {
+    if !value.is_finite() || value.abs() >= 2f64.powi(53) || max_denominator == 0 {
+        return None;
+    }
+
+    // as duas últimas convergentes, começando pelas "convergentes" 0/1 e 1/0 da definição
+    let (mut p0, mut q0, mut p1, mut q1) = (0u64, 1u64, 1u64, 0u64);
+    let target = value.abs();
+    let mut x = target;
+    loop {
+        let a = x.floor();
+        if a * q1 as f64 + q0 as f64 > max_denominator as f64 {
+            // a melhor aproximação pode ser uma semiconvergente entre as duas últimas
+            let m = (max_denominator - q0) / q1;
+            let (p2, q2) = (p0 + m * p1, q0 + m * q1);
+            let distance = |p: u64, q: u64| (target - p as f64 / q as f64).abs();
+            if distance(p2, q2) < distance(p1, q1) {
+                (p1, q1) = (p2, q2);
+            }
+            break;
+        }
+
+        let a = a as u64;
+        (p0, q0, p1, q1) = (p1, q1, a * p1 + p0, a * q1 + q0);
+        let fraction = x - x.floor();
+        if fraction == 0.0 {
+            break;
+        }
+        x = 1.0 / fraction;
+    }
+
+    let numerator = p1 as i64;
+    Some((if value < 0.0 { -numerator } else { numerator }, q1))
+}
+
+/// Texto de `as_fraction`: a fração e, se ela não for exata, a diferença para o valor
+/// (`2/3 (±3.3e-5)`).
+pub fn describe(value: f64, max_denominator: u64) -> Result<String, String> {
+    let (numerator, denominator) = approximate(value, max_denominator)
+        .ok_or_else(|| format!("não é possível escrever {} como fração", value))?;
+
+    let mut text = if denominator == 1 {
+        numerator.to_string()
+    } else {
+        format!("{}/{}", numerator, denominator)
+    };
+    let error = (value - numerator as f64 / denominator as f64).abs();
+    if error != 0.0 {
+        text.push_str(&format!(" (±{:.1e})", error));
+    }
+    Ok(text)
+}
+
+#[test]
+fn approximate_should_find_the_nearest_simple_fraction() {
+    assert_eq!(approximate(0.6667, 1000), Some((2, 3)));
+    assert_eq!(approximate(0.5, 1000), Some((1, 2)));
+    assert_eq!(approximate(-0.1, 1000), Some((-1, 10)));
+    assert_eq!(approximate(3.0, 1000), Some((3, 1)));
+    assert_eq!(approximate(std::f64::consts::PI, 100), Some((311, 99)));
+    assert_eq!(approximate(std::f64::consts::PI, 1000), Some((355, 113)));
+    assert_eq!(approximate(0.0001, 1000), Some((0, 1)));
+    assert_eq!(approximate(f64::NAN, 1000), None);
+    assert_eq!(approximate(1e300, 1000), None);
+}
+
+#[test]
+fn describe_should_show_the_approximation_error() {
+    assert_eq!(describe(0.6667, 1000), Ok("2/3 (±3.3e-5)".to_string()));
+    assert_eq!(describe(0.75, 1000), Ok("3/4".to_string()));
+    assert_eq!(describe(-2.0, 1000), Ok("-2".to_string()));
+    assert!(describe(f64::INFINITY, 1000).is_err());
+}
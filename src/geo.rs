@@ -0,0 +1,131 @@
+//! Distâncias sobre a superfície da Terra entre duas coordenadas em graus, usadas por
+//! `haversine` e `vincenty`. Os resultados são em metros, como todo comprimento.
+
+use crate::math::Libm;
+
+/// Raio médio da Terra, em metros (o da IUGG).
+const MEAN_RADIUS: f64 = 6_371_008.8;
+
+/// O semieixo maior e o achatamento do elipsoide do WGS 84, o do GPS.
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257223563;
+
+/// Mais que isso a fórmula de Vincenty não converge, o que só acontece entre pontos quase
+/// antípodas.
+const MAX_ITERATIONS: usize = 200;
+
+/// Se a latitude e a longitude são coordenadas válidas.
+fn valid(lat: f64, lon: f64) -> bool {
+    (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)
+}
+
+/// Distância pela fórmula de haversine, que considera a Terra uma esfera e erra em até 0,5%.
+pub(crate) fn haversine(start: [f64; 2], end: [f64; 2], libm: &Libm) -> Option<f64> {
+    let ([lat1, lon1], [lat2, lon2]) = (start, end);
+    if !valid(lat1, lon1) || !valid(lat2, lon2) {
+        return None;
+    }
+    let sqrt = |value: f64| (libm.pow)(value, 0.5);
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let half_phi = (phi2 - phi1) / 2.0;
+    let half_lambda = (lon2 - lon1).to_radians() / 2.0;
+    let a = (libm.sin)(half_phi) * (libm.sin)(half_phi)
+        + (libm.cos)(phi1) * (libm.cos)(phi2) * (libm.sin)(half_lambda) * (libm.sin)(half_lambda);
+    let a = a.clamp(0.0, 1.0);
+    Some(MEAN_RADIUS * 2.0 * (libm.atan2)(sqrt(a), sqrt(1.0 - a)))
+}
+
+/// Distância pela fórmula inversa de Vincenty sobre o elipsoide do WGS 84, precisa até o
+/// milímetro, ou `None` se ela não convergir.
+pub(crate) fn vincenty(start: [f64; 2], end: [f64; 2], libm: &Libm) -> Option<f64> {
+    let ([lat1, lon1], [lat2, lon2]) = (start, end);
+    if !valid(lat1, lon1) || !valid(lat2, lon2) {
+        return None;
+    }
+    let sqrt = |value: f64| (libm.pow)(value, 0.5);
+    let (a, f) = (WGS84_A, WGS84_F);
+    let b = (1.0 - f) * a;
+    let reduced = |lat: f64| (libm.atan)((1.0 - f) * (libm.tan)(lat.to_radians()));
+    let (u1, u2) = (reduced(lat1), reduced(lat2));
+    let (sin_u1, cos_u1, sin_u2, cos_u2) = (
+        (libm.sin)(u1),
+        (libm.cos)(u1),
+        (libm.sin)(u2),
+        (libm.cos)(u2),
+    );
+    let l = (lon2 - lon1).to_radians();
+
+    let mut lambda = l;
+    for _ in 0..MAX_ITERATIONS {
+        let (sin_lambda, cos_lambda) = ((libm.sin)(lambda), (libm.cos)(lambda));
+        let cross = cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda;
+        let sin_sigma = sqrt((cos_u2 * sin_lambda) * (cos_u2 * sin_lambda) + cross * cross);
+        if sin_sigma == 0.0 {
+            // os dois pontos são o mesmo
+            return Some(0.0);
+        }
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = (libm.atan2)(sin_sigma, cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos2_alpha = 1.0 - sin_alpha * sin_alpha;
+        // nos pontos sobre o equador, `cos2_alpha` é zero
+        let cos_2sigma_m = if cos2_alpha == 0.0 {
+            0.0
+        } else {
+            cos_sigma - 2.0 * sin_u1 * sin_u2 / cos2_alpha
+        };
+        let c = f / 16.0 * cos2_alpha * (4.0 + f * (4.0 - 3.0 * cos2_alpha));
+        let previous = lambda;
+        lambda = l
+            + (1.0 - c)
+                * f
+                * sin_alpha
+                * (sigma
+                    + c * sin_sigma
+                        * (cos_2sigma_m
+                            + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)));
+        if (lambda - previous).abs() < 1e-12 {
+            let u2 = cos2_alpha * (a * a - b * b) / (b * b);
+            let big_a = 1.0 + u2 / 16384.0 * (4096.0 + u2 * (-768.0 + u2 * (320.0 - 175.0 * u2)));
+            let big_b = u2 / 1024.0 * (256.0 + u2 * (-128.0 + u2 * (74.0 - 47.0 * u2)));
+            let delta_sigma = big_b
+                * sin_sigma
+                * (cos_2sigma_m
+                    + big_b / 4.0
+                        * (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)
+                            - big_b / 6.0
+                                * cos_2sigma_m
+                                * (-3.0 + 4.0 * sin_sigma * sin_sigma)
+                                * (-3.0 + 4.0 * cos_2sigma_m * cos_2sigma_m)));
+            return Some(b * big_a * (sigma - delta_sigma));
+        }
+    }
+    None
+}
+
+#[test]
+fn geodesic_distances_should_match_known_values() {
+    use crate::math::SYSTEM;
+
+    // o exemplo do artigo de Vincenty, de Flinders Peak a Buninyong
+    let flinders = [-37.95103342, 144.42486789];
+    let buninyong = [-37.65282114, 143.92649554];
+    let distance = vincenty(flinders, buninyong, &SYSTEM).unwrap();
+    assert!((distance - 54972.271).abs() < 1e-3, "{}", distance);
+    let distance = haversine(flinders, buninyong, &SYSTEM).unwrap();
+    assert!(
+        (distance - 54972.271).abs() < 54972.271 * 0.005,
+        "{}",
+        distance
+    );
+
+    // um quarto do equador
+    let distance = haversine([0.0, 0.0], [0.0, 90.0], &SYSTEM).unwrap();
+    assert!((distance - MEAN_RADIUS * core::f64::consts::FRAC_PI_2).abs() < 1e-6);
+    let distance = vincenty([0.0, 0.0], [0.0, 90.0], &SYSTEM).unwrap();
+    assert!((distance - WGS84_A * core::f64::consts::FRAC_PI_2).abs() < 1e-3);
+
+    assert_eq!(vincenty([10.0, 20.0], [10.0, 20.0], &SYSTEM), Some(0.0));
+    assert_eq!(haversine([91.0, 0.0], [0.0, 0.0], &SYSTEM), None);
+    assert_eq!(vincenty([0.0, 0.0], [0.5, 179.7], &SYSTEM), None);
+}
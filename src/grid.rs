@@ -0,0 +1,101 @@
+//! Células no estilo de planilha: variáveis com nomes como `A1` ou `B12` são mostradas numa
+//! tabela pelo comando `grid`, e podem ser usadas normalmente nas expressões (`A1 + A2 * 0.1`).
+
+use std::collections::BTreeSet;
+
+use asdf_calc::EvalContext;
+
+use crate::table;
+
+/// Coluna e linha (começando em 0) de um nome como `B12`, caso ele seja uma célula.
+pub fn parse_cell(name: &str) -> Option<(usize, usize)> {
+    let digits_start = name.find(|char: char| char.is_ascii_digit())?;
+    let (letters, digits) = name.split_at(digits_start);
+    if letters.is_empty()
+        || !letters.chars().all(|char| char.is_ascii_alphabetic())
+        || !digits.chars().all(|char| char.is_ascii_digit())
+        || digits.starts_with('0')
+    {
+        return None;
+    }
+
+    let column = letters.chars().try_fold(0usize, |column, char| {
+        let value = (char.to_ascii_uppercase() as u8 - b'A') as usize + 1;
+        column.checked_mul(26)?.checked_add(value)
+    })?;
+    let row: usize = digits.parse().ok()?;
+    Some((column - 1, row - 1))
+}
+
+/// `0` é `A`, `25` é `Z` e `26` é `AA`.
+pub fn column_name(mut column: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (column % 26) as u8);
+        if column < 26 {
+            break;
+        }
+        column = column / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).expect("só letras ASCII")
+}
+
+/// Tabela com as células definidas em `context`, mostrando só as linhas e colunas usadas.
+pub fn render(context: &EvalContext) -> String {
+    let cells: Vec<((usize, usize), f64)> = context
+        .variables()
+        .filter_map(|(name, value)| parse_cell(name).map(|cell| (cell, value)))
+        .collect();
+    if cells.is_empty() {
+        return "nenhuma célula definida (A1 = 2, por exemplo)\n".to_string();
+    }
+
+    let columns: BTreeSet<usize> = cells.iter().map(|((column, _), _)| *column).collect();
+    let rows: BTreeSet<usize> = cells.iter().map(|((_, row), _)| *row).collect();
+    let value = |column: usize, row: usize| {
+        cells
+            .iter()
+            .find(|(cell, _)| *cell == (column, row))
+            .map(|(_, value)| value.to_string())
+            .unwrap_or_default()
+    };
+
+    let header: Vec<String> = [String::new()]
+        .into_iter()
+        .chain(columns.iter().map(|&column| column_name(column)))
+        .collect();
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|&row| {
+            [(row + 1).to_string()]
+                .into_iter()
+                .chain(columns.iter().map(|&column| value(column, row)))
+                .collect()
+        })
+        .collect();
+    table::render_to_fit(&header, &rows)
+}
+
+#[test]
+fn parse_cell_should_read_spreadsheet_references() {
+    assert_eq!(parse_cell("a1"), Some((0, 0)));
+    assert_eq!(parse_cell("B12"), Some((1, 11)));
+    assert_eq!(parse_cell("AA3"), Some((26, 2)));
+    assert_eq!(parse_cell("x"), None);
+    assert_eq!(parse_cell("a0"), None);
+    assert_eq!(parse_cell("a1b"), None);
+    assert_eq!(column_name(0), "A");
+    assert_eq!(column_name(26), "AA");
+    assert_eq!(column_name(701), "ZZ");
+}
+
+#[test]
+fn render_should_show_only_used_rows_and_columns() {
+    let mut context = EvalContext::new();
+    context.set_variable("a1", 100.0);
+    context.set_variable("a3", 2.5);
+    context.set_variable("c1", 7.0);
+    context.set_variable("rate", 0.1);
+    assert_eq!(render(&context), "  |   A | C\n1 | 100 | 7\n3 | 2.5 |  \n");
+}
@@ -0,0 +1,94 @@
+//! Script de inicialização (`~/.config/asdf-calc/init.calc`), executado antes da calculadora
+//! interativa e do daemon para que cada sessão já comece com as constantes e configurações do
+//! usuário. `--no-init` pula o script.
+//!
+//! Cada linha é um comando da sessão (`set angle deg`, `set format eng`...) ou uma expressão, e
+//! linhas vazias ou começando com `#` são ignoradas. Um erro numa linha não impede as seguintes.
+//! `const nome = expressão` define uma constante que nenhuma atribuição da sessão consegue alterar.
+//! `unit define nome = expressão` define uma unidade (`unit define furlong = 201.168 m`), e
+//! `alias nome = texto` um apelido para as linhas seguintes (`alias k = * 1000`).
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::session::Session;
+
+/// `$XDG_CONFIG_HOME/asdf-calc/init.calc`, ou `~/.config/asdf-calc/init.calc` se a variável não
+/// estiver definida.
+pub fn default_path() -> Option<PathBuf> {
+    let config = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => Path::new(&env::var_os("HOME")?).join(".config"),
+    };
+    Some(config.join("asdf-calc/init.calc"))
+}
+
+/// Executa o script em `path`, se ele existir, retornando as mensagens de erro de cada linha
+/// que falhou. As linhas do script não entram no histórico nem podem ser desfeitas com `undo`.
+pub fn run(path: &Path, session: &mut Session) -> Vec<String> {
+    match fs::read_to_string(path) {
+        Ok(text) => run_script(&text, &path.display().to_string(), session),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(error) => vec![format!(
+            "não foi possível ler {}: {}",
+            path.display(),
+            error
+        )],
+    }
+}
+
+fn run_script(text: &str, source: &str, session: &mut Session) -> Vec<String> {
+    let mut messages = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = session.expand_aliases(line);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let errors = match session.command(line) {
+            Some(result) => result.err().into_iter().collect(),
+            None => {
+                let (history, journal) = (session.history.len(), session.undo_journal.len());
+                let result = session.eval(line);
+                session.history.truncate(history);
+                session.undo_journal.truncate(journal);
+                match result {
+                    Ok(_) => Vec::new(),
+                    Err(errors) => errors.iter().map(|error| error.to_string()).collect(),
+                }
+            }
+        };
+        for error in errors {
+            messages.push(format!("{} {}: {}", source, number + 1, error));
+        }
+    }
+    messages
+}
+
+#[test]
+fn init_script_should_prepare_the_session() {
+    let mut session = Session::default();
+    let script = "\
+# constantes do dia a dia
+tax = 0.25
+const rate = 0.5
+angle deg
+
+price = 100 * (1 + tax)
+format qualquer
+1 +
+";
+    let messages = run_script(script, "init.calc", &mut session);
+    assert_eq!(messages.len(), 2, "{:?}", messages);
+    assert!(messages[0].starts_with("init.calc 7: formato desconhecido"));
+    assert!(messages[1].starts_with("init.calc 8: "));
+
+    assert_eq!(session.context.variable("price"), Some(125.0));
+    assert_eq!(session.eval("sin(90)"), Ok(1.0));
+    assert!(session.eval("rate = 1").is_err());
+    assert_eq!(session.history.len(), 1);
+    assert!(session.undo().is_err());
+}
@@ -0,0 +1,142 @@
+//! `inspect <expressão>`, que mostra como o resultado está guardado no `f64`: o valor exato, os
+//! bits, o sinal, o expoente e a mantissa, e os `f64` vizinhos. Ajuda a explicar por que
+//! `0.1 + 0.2` não é `0.3`.
+
+/// Viés do expoente do `f64`, e quantos bits a mantissa tem.
+const EXPONENT_BIAS: i32 = 1023;
+const MANTISSA_BITS: u32 = 52;
+
+/// Descrição de `value`, uma linha por item.
+pub fn describe(value: f64) -> String {
+    let bits = value.to_bits();
+    let sign = bits >> 63;
+    let exponent = ((bits >> MANTISSA_BITS) & 0x7ff) as i32;
+    let mantissa = bits & ((1 << MANTISSA_BITS) - 1);
+
+    let mut lines = vec![format!("bits: {:#018x}", bits)];
+    lines.push(format!(
+        "sinal: {} ({})",
+        sign,
+        if sign == 0 { "positivo" } else { "negativo" }
+    ));
+    match exponent {
+        0x7ff if mantissa == 0 => lines.push("expoente: 2047 (infinito)".to_string()),
+        0x7ff => lines.push("expoente: 2047 (NaN)".to_string()),
+        0 => lines.push(format!("expoente: 0 (subnormal, 2^{})", 1 - EXPONENT_BIAS)),
+        _ => lines.push(format!(
+            "expoente: {} - {} = {}",
+            exponent,
+            EXPONENT_BIAS,
+            exponent - EXPONENT_BIAS
+        )),
+    }
+    if !value.is_finite() {
+        lines.push(format!("mantissa: {:#x}", mantissa));
+        return lines.join("\n");
+    }
+
+    // a mantissa dos normais tem um 1 implícito antes do ponto, e a dos subnormais um 0
+    let (integer, exponent) = if exponent == 0 {
+        (mantissa, 1 - EXPONENT_BIAS - MANTISSA_BITS as i32)
+    } else {
+        (
+            mantissa | 1 << MANTISSA_BITS,
+            exponent - EXPONENT_BIAS - MANTISSA_BITS as i32,
+        )
+    };
+    lines.push(format!(
+        "mantissa: {:#x} ({})",
+        mantissa,
+        exact_decimal(integer, -(MANTISSA_BITS as i32))
+    ));
+    lines.insert(
+        0,
+        format!(
+            "valor exato: {}{}",
+            if sign == 0 { "" } else { "-" },
+            exact_decimal(integer, exponent)
+        ),
+    );
+    lines.push(format!("anterior: {}", value.next_down()));
+    lines.push(format!("próximo: {}", value.next_up()));
+    lines.join("\n")
+}
+
+/// `integer * 2^exponent` escrito em decimal com todos os dígitos, que sempre terminam já que
+/// `2^-n` é `5^n / 10^n`.
+fn exact_decimal(integer: u64, exponent: i32) -> String {
+    // dígitos do menos significativo para o mais
+    let mut digits: Vec<u8> = integer
+        .to_string()
+        .bytes()
+        .rev()
+        .map(|b| b - b'0')
+        .collect();
+    let multiplier = if exponent >= 0 { 2 } else { 5 };
+    for _ in 0..exponent.unsigned_abs() {
+        let mut carry = 0;
+        for digit in digits.iter_mut() {
+            let product = *digit * multiplier + carry;
+            *digit = product % 10;
+            carry = product / 10;
+        }
+        if carry > 0 {
+            digits.push(carry);
+        }
+    }
+
+    let decimals = if exponent < 0 {
+        exponent.unsigned_abs() as usize
+    } else {
+        0
+    };
+    while digits.len() <= decimals {
+        digits.push(0);
+    }
+    let text: String = digits
+        .iter()
+        .rev()
+        .map(|digit| (b'0' + digit) as char)
+        .collect();
+    let (integer, fraction) = text.split_at(text.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+#[test]
+fn describe_should_show_how_the_value_is_stored() {
+    assert_eq!(
+        describe(0.1),
+        "\
+valor exato: 0.1000000000000000055511151231257827021181583404541015625
+bits: 0x3fb999999999999a
+sinal: 0 (positivo)
+expoente: 1019 - 1023 = -4
+mantissa: 0x999999999999a (1.600000000000000088817841970012523233890533447265625)
+anterior: 0.09999999999999999
+próximo: 0.10000000000000002"
+    );
+
+    let description = describe(-2.0);
+    assert!(description.starts_with("valor exato: -2\nbits: 0xc000000000000000\n"));
+    assert!(description.contains("mantissa: 0x0 (1)"));
+
+    assert!(describe(5e-324).contains("expoente: 0 (subnormal, 2^-1022)"));
+    assert!(describe(5e-324).starts_with("valor exato: 0.000000000000"));
+    assert!(describe(f64::INFINITY).contains("expoente: 2047 (infinito)"));
+    assert!(!describe(f64::NAN).contains("valor exato"));
+}
+
+#[test]
+fn exact_decimal_should_write_every_digit() {
+    assert_eq!(exact_decimal(3, 0), "3");
+    assert_eq!(exact_decimal(3, 4), "48");
+    assert_eq!(exact_decimal(3, -2), "0.75");
+    assert_eq!(exact_decimal(1, -3), "0.125");
+    assert_eq!(exact_decimal(0, -1074), "0");
+    assert_eq!(exact_decimal(1, 64), "18446744073709551616");
+}
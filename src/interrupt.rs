@@ -0,0 +1,105 @@
+//! Interrupção de um cálculo em andamento, por tempo limite (`--timeout`) ou pelo Ctrl+C, sem
+//! encerrar o processo.
+
+use std::{
+    sync::{Arc, Condvar, Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use asdf_calc::InterruptFlag;
+
+/// Thread que liga a flag quando o prazo do cálculo atual passa. Uma única thread atende
+/// todos os cálculos, esperando até o próximo prazo ser definido.
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    deadline: Arc<(Mutex<Option<Instant>>, Condvar)>,
+}
+
+impl Watchdog {
+    pub fn new(flag: InterruptFlag) -> Self {
+        let deadline = Arc::new((Mutex::new(None::<Instant>), Condvar::new()));
+        let shared = Arc::clone(&deadline);
+        thread::spawn(move || {
+            let (lock, condvar) = &*shared;
+            let mut deadline = lock.lock().unwrap();
+            loop {
+                deadline = match *deadline {
+                    None => condvar.wait(deadline).unwrap(),
+                    Some(instant) if Instant::now() >= instant => {
+                        flag.interrupt();
+                        *deadline = None;
+                        deadline
+                    }
+                    Some(instant) => {
+                        let timeout = instant - Instant::now();
+                        condvar.wait_timeout(deadline, timeout).unwrap().0
+                    }
+                };
+            }
+        });
+        Watchdog { deadline }
+    }
+
+    /// Começa a contar `timeout` a partir de agora.
+    pub fn arm(&self, timeout: Duration) {
+        let (lock, condvar) = &*self.deadline;
+        *lock.lock().unwrap() = Some(Instant::now() + timeout);
+        condvar.notify_one();
+    }
+
+    pub fn disarm(&self) {
+        let (lock, condvar) = &*self.deadline;
+        *lock.lock().unwrap() = None;
+        condvar.notify_one();
+    }
+}
+
+/// Flag ligada pelo tratador do Ctrl+C.
+static CTRL_C_FLAG: OnceLock<InterruptFlag> = OnceLock::new();
+
+#[cfg(unix)]
+extern "C" fn on_sigint(_: std::os::raw::c_int) {
+    // só operações atômicas, que podem ser feitas dentro de um tratador de sinal
+    if let Some(flag) = CTRL_C_FLAG.get() {
+        flag.interrupt();
+    }
+}
+
+/// Faz o Ctrl+C ligar `flag` ao invés de encerrar o processo. Só pode ser chamada uma vez, e
+/// só tem efeito em sistemas Unix.
+pub fn interrupt_on_ctrl_c(flag: InterruptFlag) {
+    if CTRL_C_FLAG.set(flag).is_err() {
+        return;
+    }
+
+    #[cfg(unix)]
+    {
+        const SIGINT: std::os::raw::c_int = 2;
+        extern "C" {
+            fn signal(signum: std::os::raw::c_int, handler: usize) -> usize;
+        }
+        // SAFETY: `on_sigint` só faz operações atômicas
+        unsafe {
+            signal(
+                SIGINT,
+                on_sigint as extern "C" fn(std::os::raw::c_int) as usize,
+            );
+        }
+    }
+}
+
+#[test]
+fn watchdog_should_interrupt_after_the_deadline() {
+    let flag = InterruptFlag::new();
+    let watchdog = Watchdog::new(flag.clone());
+
+    watchdog.arm(Duration::from_millis(10));
+    watchdog.disarm();
+    thread::sleep(Duration::from_millis(30));
+    assert!(!flag.is_interrupted());
+
+    watchdog.arm(Duration::from_millis(10));
+    thread::sleep(Duration::from_millis(100));
+    assert!(flag.is_interrupted());
+}
@@ -0,0 +1,399 @@
+//! Leitura e escrita do pouco de JSON usado pelos modos de integração (servidor HTTP, RPC...).
+
+use std::fmt::{Display, Formatter, Write};
+
+use asdf_calc::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Os pares são mantidos na ordem em que foram lidos (ou inseridos).
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = JsonParser {
+            chars: text.chars().collect(),
+            position: 0,
+        };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("conteúdo extra depois do valor"));
+        }
+        Ok(value)
+    }
+
+    pub fn object<const N: usize>(pairs: [(&str, Json); N]) -> Json {
+        Json::Object(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(pairs) => pairs
+                .iter()
+                .find(|(pair_key, _)| pair_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(string: &str) -> Self {
+        Json::String(string.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(string: String) -> Self {
+        Json::String(string)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(num: f64) -> Self {
+        Json::Number(num)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(num: usize) -> Self {
+        Json::Number(num as f64)
+    }
+}
+
+fn write_string(f: &mut Formatter<'_>, string: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for char in string.chars() {
+        match char {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            char if (char as u32) < 0x20 => write!(f, "\\u{:04x}", char as u32)?,
+            char => f.write_char(char)?,
+        }
+    }
+    f.write_char('"')
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(bool) => write!(f, "{}", bool),
+            // JSON não tem como representar infinito ou NaN
+            Json::Number(num) if !num.is_finite() => f.write_str("null"),
+            Json::Number(num) => write!(f, "{}", num),
+            Json::String(string) => write_string(f, string),
+            Json::Array(values) => {
+                f.write_char('[')?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(pairs) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl JsonParser {
+    fn error(&self, message: &str) -> String {
+        format!("JSON inválido na posição {}: {}", self.position, message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|char| char.is_whitespace())
+        {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.chars.get(self.position) == Some(&expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("era esperado '{}'", expected)))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.chars.get(self.position) {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => Ok(Json::String(self.parse_string()?)),
+            Some('t') => self.parse_keyword("true", Json::Bool(true)),
+            Some('f') => self.parse_keyword("false", Json::Bool(false)),
+            Some('n') => self.parse_keyword("null", Json::Null),
+            Some(char) if *char == '-' || char.is_ascii_digit() => self.parse_number(),
+            Some(_) => Err(self.error("valor inesperado")),
+            None => Err(self.error("fim inesperado")),
+        }
+    }
+
+    fn parse_keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        let end = self.position + keyword.len();
+        if end <= self.chars.len()
+            && self.chars[self.position..end]
+                .iter()
+                .copied()
+                .eq(keyword.chars())
+        {
+            self.position = end;
+            Ok(value)
+        } else {
+            Err(self.error("valor inesperado"))
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|char| char.is_ascii_digit() || "+-.eE".contains(*char))
+        {
+            self.position += 1;
+        }
+        let text: String = self.chars[start..self.position].iter().collect();
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| self.error("número inválido"))
+    }
+
+    fn parse_hex_escape(&mut self) -> Result<u32, String> {
+        let end = self.position + 4;
+        if end > self.chars.len() {
+            return Err(self.error("escape \\u incompleto"));
+        }
+        let text: String = self.chars[self.position..end].iter().collect();
+        self.position = end;
+        u32::from_str_radix(&text, 16).map_err(|_| self.error("escape \\u inválido"))
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            let char = *self
+                .chars
+                .get(self.position)
+                .ok_or_else(|| self.error("string nunca é fechada"))?;
+            self.position += 1;
+            match char {
+                '"' => return Ok(string),
+                '\\' => {
+                    let escaped = *self
+                        .chars
+                        .get(self.position)
+                        .ok_or_else(|| self.error("escape incompleto"))?;
+                    self.position += 1;
+                    match escaped {
+                        '"' | '\\' | '/' => string.push(escaped),
+                        'n' => string.push('\n'),
+                        'r' => string.push('\r'),
+                        't' => string.push('\t'),
+                        'b' => string.push('\u{8}'),
+                        'f' => string.push('\u{c}'),
+                        'u' => {
+                            let mut code = self.parse_hex_escape()?;
+                            // caracteres fora do plano básico vêm em dois escapes (surrogates)
+                            if (0xd800..0xdc00).contains(&code)
+                                && self.chars.get(self.position) == Some(&'\\')
+                                && self.chars.get(self.position + 1) == Some(&'u')
+                            {
+                                self.position += 2;
+                                let low = self.parse_hex_escape()?;
+                                code =
+                                    0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00));
+                            }
+                            string.push(
+                                char::from_u32(code)
+                                    .ok_or_else(|| self.error("escape \\u inválido"))?,
+                            );
+                        }
+                        _ => return Err(self.error("escape inválido")),
+                    }
+                }
+                char => string.push(char),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.chars.get(self.position) == Some(&']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.chars.get(self.position) {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("era esperado ',' ou ']'")),
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut pairs = Vec::new();
+        self.skip_whitespace();
+        if self.chars.get(self.position) == Some(&'}') {
+            self.position += 1;
+            return Ok(Json::Object(pairs));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            pairs.push((key, self.parse_value()?));
+            self.skip_whitespace();
+            match self.chars.get(self.position) {
+                Some(',') => self.position += 1,
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(pairs));
+                }
+                _ => return Err(self.error("era esperado ',' ou '}'")),
+            }
+        }
+    }
+}
+
+/// Erros de síntaxe, ou de cálculo, no formato usado pelas respostas da API.
+pub fn errors_json(errors: &[Error]) -> Json {
+    Json::Array(
+        errors
+            .iter()
+            .map(|error| {
+                Json::object([
+                    ("message", Json::from(error.to_string())),
+                    ("start", Json::from(error.span().start)),
+                    ("end", Json::from(error.span().end)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+/// Resultados que não podem ser escritos como número em JSON (infinito e NaN) vão como texto.
+pub fn result_json(result: f64) -> Json {
+    if result.is_finite() {
+        Json::from(result)
+    } else {
+        Json::from(result.to_string())
+    }
+}
+
+/// Lê de volta um resultado escrito por `result_json`.
+pub fn result_from_json(json: &Json) -> Option<f64> {
+    match json {
+        Json::Number(num) => Some(*num),
+        Json::String(text) => text.parse().ok().filter(|num: &f64| !num.is_finite()),
+        _ => None,
+    }
+}
+
+#[test]
+fn json_should_parse_nested_values() {
+    let json = Json::parse(r#" {"expr": "2+2", "vars": {"x": -1.5e2, "ok": [true, null]}} "#)
+        .expect("JSON válido");
+    assert_eq!(json.get("expr").and_then(Json::as_str), Some("2+2"));
+    assert_eq!(
+        json.get("vars").and_then(|vars| vars.get("x")),
+        Some(&Json::Number(-150.0))
+    );
+    assert_eq!(
+        json.get("vars").and_then(|vars| vars.get("ok")),
+        Some(&Json::Array(vec![Json::Bool(true), Json::Null]))
+    );
+}
+
+#[test]
+fn json_should_round_trip_escaped_strings() {
+    let json = Json::parse(r#""linha\n\"aspas\" é \ud83d\ude00""#).expect("JSON válido");
+    assert_eq!(json, Json::from("linha\n\"aspas\" é 😀"));
+    assert_eq!(json.to_string(), "\"linha\\n\\\"aspas\\\" é 😀\"");
+}
+
+#[test]
+fn json_should_reject_invalid_documents() {
+    assert!(Json::parse(r#"{"a": }"#).is_err());
+    assert!(Json::parse(r#"{"a": 1} x"#).is_err());
+    assert!(Json::parse(r#""sem fim"#).is_err());
+}
+
+#[test]
+fn json_should_write_objects_in_order() {
+    let json = Json::object([("result", Json::from(4.0)), ("text", Json::from("4"))]);
+    assert_eq!(json.to_string(), r#"{"result":4,"text":"4"}"#);
+}
+
+#[test]
+fn results_should_be_read_back_from_json() {
+    for result in [2.5, -0.0, f64::INFINITY, f64::NEG_INFINITY] {
+        assert_eq!(result_from_json(&result_json(result)), Some(result));
+    }
+    assert!(result_from_json(&result_json(f64::NAN)).is_some_and(f64::is_nan));
+    assert_eq!(result_from_json(&Json::from("2.5")), None);
+    assert_eq!(result_from_json(&Json::Null), None);
+}
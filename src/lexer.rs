@@ -0,0 +1,642 @@
+use alloc::{format, string::String, vec, vec::Vec};
+
+use crate::{
+    error::{Error, Span},
+    expression::OperationKind,
+    math,
+    significance::Precision,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenKind {
+    /// Número com a precisão em que foi escrito, usada quando os algarismos significativos são
+    /// acompanhados.
+    Number(f64, Precision),
+    /// Ângulo escrito em graus, minutos e segundos (`30°15'50"`) ou com o sufixo `deg`,
+    /// convertido para o modo de ângulo do contexto pelo parser.
+    Degrees(f64),
+    /// Ângulo escrito com o sufixo `rad` (`1.2rad`), também convertido pelo parser.
+    Radians(f64),
+    Identifier(String),
+    /// Trecho que não pôde ser compreendido, o erro correspondente já foi registrado pelo lexer.
+    Invalid,
+    /// Caractere que não faz parte da linguagem, tratado como se fosse um operador desconhecido
+    /// para que o resto da linha continue sendo lido normalmente.
+    Unexpected,
+    Assign,
+    /// `not`, que nega a condição à sua direita.
+    Not,
+    /// Separa os argumentos de uma chamada de função.
+    Comma,
+    Operator(OperationKind),
+    OpenParenthesis,
+    CloseParenthesis,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Token {
+    pub(crate) kind: TokenKind,
+    pub(crate) span: Span,
+}
+
+/// Sufixos do SI aceitos logo depois de um número (`5k`, `10u`), com a potência de 10
+/// correspondente. O `µ` aparece tanto como o símbolo de micro quanto como a letra grega.
+const SI_SUFFIXES: [(char, i32); 10] = [
+    ('T', 12),
+    ('G', 9),
+    ('M', 6),
+    ('k', 3),
+    ('m', -3),
+    ('u', -6),
+    ('\u{b5}', -6),
+    ('\u{3bc}', -6),
+    ('n', -9),
+    ('p', -12),
+];
+
+/// Separa o texto em tokens, registrando em `errors` todo trecho que não puder ser compreendido
+/// ao invés de parar no primeiro problema.
+#[cfg(test)]
+pub(crate) fn tokenize(text: &str, errors: &mut Vec<Error>) -> Vec<Token> {
+    tokenize_with(text, false, errors)
+}
+
+/// Mesmo que `tokenize`, aceitando os sufixos do SI depois dos números se `si_suffixes`.
+pub(crate) fn tokenize_with(text: &str, si_suffixes: bool, errors: &mut Vec<Error>) -> Vec<Token> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        let kind = match chars[i] {
+            char if char.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '+' => TokenKind::Operator(OperationKind::Add),
+            '-' => TokenKind::Operator(OperationKind::Subtract),
+            '*' => TokenKind::Operator(OperationKind::Multiply),
+            '/' => TokenKind::Operator(OperationKind::Divide),
+            '^' => TokenKind::Operator(OperationKind::Power),
+            '=' | '!' | '~' | '<' | '>' if chars.get(i + 1) == Some(&'=') => {
+                let kind = match chars[i] {
+                    '=' => OperationKind::Equal,
+                    '!' => OperationKind::NotEqual,
+                    '~' => OperationKind::Approx,
+                    '<' => OperationKind::LessEqual,
+                    _ => OperationKind::GreaterEqual,
+                };
+                tokens.push(Token {
+                    kind: TokenKind::Operator(kind),
+                    span: Span::new(i, i + 2),
+                });
+                i += 2;
+                continue;
+            }
+            '<' => TokenKind::Operator(OperationKind::Less),
+            '>' => TokenKind::Operator(OperationKind::Greater),
+            '(' => TokenKind::OpenParenthesis,
+            ')' => TokenKind::CloseParenthesis,
+            '=' => TokenKind::Assign,
+            ',' => TokenKind::Comma,
+            char if char.is_ascii_digit() || char == '.' => {
+                let start = i;
+                while i < chars.len()
+                    && is_number_char(&chars[start..i], chars[i], chars.get(i + 1))
+                {
+                    i += 1;
+                }
+
+                if chars.get(i) == Some(&'\u{b0}') {
+                    let (end, degrees) = read_dms(&chars, start, i);
+                    let span = Span::new(start, end);
+                    let kind = if let Some(degrees) = degrees {
+                        TokenKind::Degrees(degrees)
+                    } else {
+                        let text_portion = chars[start..end].iter().collect();
+                        errors.push(Error::ToF64ParseError(text_portion, span));
+                        TokenKind::Invalid
+                    };
+                    tokens.push(Token { kind, span });
+                    i = end;
+                    continue;
+                }
+
+                let span = Span::new(start, i);
+                let text_portion: String = chars[start..i].iter().collect();
+                // os sufixos de ângulo são os únicos que valem mesmo sem os sufixos do SI, e
+                // podem vir depois de um (`5mrad`)
+                let angle = [
+                    ("deg", TokenKind::Degrees as fn(f64) -> TokenKind),
+                    ("rad", TokenKind::Radians),
+                ]
+                .into_iter()
+                .find_map(|(suffix, kind)| {
+                    let number = text_portion.strip_suffix(suffix)?;
+                    parse_number(number, si_suffixes).map(kind)
+                });
+                let kind = if let Some(angle) = angle {
+                    angle
+                } else if let Some(num) = parse_radix(&text_portion) {
+                    TokenKind::Number(num, Precision::with_place(num, 0))
+                } else if let Some(num) = parse_number(&text_portion, si_suffixes) {
+                    TokenKind::Number(num, literal_precision(&text_portion, si_suffixes))
+                } else {
+                    errors.push(Error::ToF64ParseError(text_portion, span));
+                    TokenKind::Invalid
+                };
+                tokens.push(Token { kind, span });
+                continue;
+            }
+            char if char.is_alphabetic() || char == '_' => {
+                let start = i;
+                // o ponto separa o grupo do nome das funções agrupadas (`money.tip`), e só faz
+                // parte do nome se vier seguido de uma letra
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric()
+                        || chars[i] == '_'
+                        || (chars[i] == '.'
+                            && chars
+                                .get(i + 1)
+                                .is_some_and(|next| next.is_alphabetic() || *next == '_')))
+                {
+                    i += 1;
+                }
+
+                let name: String = chars[start..i].iter().collect();
+                let kind = match name.as_str() {
+                    "and" => TokenKind::Operator(OperationKind::And),
+                    "or" => TokenKind::Operator(OperationKind::Or),
+                    "not" => TokenKind::Not,
+                    _ => TokenKind::Identifier(name),
+                };
+                tokens.push(Token {
+                    kind,
+                    span: Span::new(start, i),
+                });
+                continue;
+            }
+            char => {
+                errors.push(Error::UnexpectedCharacter(char, Span::new(i, i + 1)));
+                TokenKind::Unexpected
+            }
+        };
+
+        tokens.push(Token {
+            kind,
+            span: Span::new(i, i + 1),
+        });
+        i += 1;
+    }
+
+    tokens
+}
+
+/// Mesmo que `tokenize_with`, mas lendo o texto de `reader` aos poucos, sem que ele precise estar
+/// todo na memória. Retorna os tokens e quantos caracteres foram lidos.
+///
+/// O texto lido é separado em trechos que terminam num espaço, parênteses ou vírgula, que nunca
+/// fazem parte de um token maior, e cada trecho é lido por `tokenize_with` assim que termina.
+/// Um texto sem nenhum deles fica todo na memória até o fim.
+#[cfg(feature = "std")]
+pub(crate) fn tokenize_reader(
+    mut reader: impl std::io::Read,
+    si_suffixes: bool,
+    errors: &mut Vec<Error>,
+) -> std::io::Result<(Vec<Token>, usize)> {
+    use std::io::{Error as IoError, ErrorKind};
+
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+    let mut pending = String::new();
+    // bytes do fim do último bloco que ainda não formam um caractere completo
+    let mut incomplete = Vec::new();
+    let mut buffer = [0; 8192];
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(read) => read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        if read == 0 {
+            break;
+        }
+        incomplete.extend_from_slice(&buffer[..read]);
+        let valid = match core::str::from_utf8(&incomplete) {
+            Ok(text) => text.len(),
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(_) => {
+                return Err(IoError::new(
+                    ErrorKind::InvalidData,
+                    "o input não é UTF-8 válido",
+                ))
+            }
+        };
+        // `valid` bytes já foram validados por `from_utf8`
+        pending.push_str(core::str::from_utf8(&incomplete[..valid]).unwrap_or_default());
+        incomplete.drain(..valid);
+
+        if let Some((index, char)) = pending
+            .char_indices()
+            .rev()
+            .find(|(_, char)| char.is_whitespace() || matches!(char, '(' | ')' | ','))
+        {
+            let end = index + char.len_utf8();
+            offset += tokenize_segment(&pending[..end], offset, si_suffixes, &mut tokens, errors);
+            pending.drain(..end);
+        }
+    }
+    if !incomplete.is_empty() {
+        return Err(IoError::new(
+            ErrorKind::UnexpectedEof,
+            "o input termina no meio de um caractere",
+        ));
+    }
+    offset += tokenize_segment(&pending, offset, si_suffixes, &mut tokens, errors);
+    Ok((tokens, offset))
+}
+
+/// Lê os tokens de um trecho que começa no caractere `offset` do input, retornando quantos
+/// caracteres ele tem.
+#[cfg(feature = "std")]
+fn tokenize_segment(
+    text: &str,
+    offset: usize,
+    si_suffixes: bool,
+    tokens: &mut Vec<Token>,
+    errors: &mut Vec<Error>,
+) -> usize {
+    let shift = |span: Span| Span::new(span.start + offset, span.end + offset);
+    let mut segment_errors = Vec::new();
+    tokens.extend(
+        tokenize_with(text, si_suffixes, &mut segment_errors)
+            .into_iter()
+            .map(|token| Token {
+                span: shift(token.span),
+                ..token
+            }),
+    );
+    // os únicos erros que o lexer registra
+    errors.extend(segment_errors.into_iter().map(|error| match error {
+        Error::ToF64ParseError(text, span) => Error::ToF64ParseError(text, shift(span)),
+        Error::UnexpectedCharacter(char, span) => Error::UnexpectedCharacter(char, shift(span)),
+        error => error,
+    }));
+    text.chars().count()
+}
+
+fn parse_number(text: &str, si_suffixes: bool) -> Option<f64> {
+    if let Ok(number) = text.parse::<f64>() {
+        return Some(number);
+    }
+    if !si_suffixes {
+        return None;
+    }
+
+    let suffix = text.chars().last()?;
+    let (_, exponent) = SI_SUFFIXES.iter().find(|(char, _)| *char == suffix)?;
+    let mantissa = &text[..text.len() - suffix.len_utf8()];
+    if mantissa.contains(['e', 'E']) {
+        mantissa
+            .parse::<f64>()
+            .ok()
+            .map(|mantissa| mantissa * math::pow(10.0, f64::from(*exponent)))
+    } else {
+        // escrito como expoente para que `10u` seja exatamente `10e-6`, sem o erro de
+        // arredondamento de `10 * 1e-6`
+        format!("{}e{}", mantissa, exponent).parse().ok()
+    }
+}
+
+/// Inteiros em hexadecimal (`0xff`), binário (`0b1010`) ou octal (`0o17`), que podem ter `_`
+/// separando os dígitos (`0xdead_beef`).
+fn parse_radix(text: &str) -> Option<f64> {
+    let radix = match text.get(..2)? {
+        "0x" | "0X" => 16,
+        "0b" | "0B" => 2,
+        "0o" | "0O" => 8,
+        _ => return None,
+    };
+    let digits = text[2..].replace('_', "");
+    if digits.is_empty() || digits.starts_with(['+', '-']) {
+        return None;
+    }
+    u64::from_str_radix(&digits, radix)
+        .ok()
+        .map(|num| num as f64)
+}
+
+/// Precisão de um número já aceito por `parse_number`.
+fn literal_precision(text: &str, si_suffixes: bool) -> Precision {
+    let suffix = text.chars().last().filter(|_| si_suffixes);
+    match SI_SUFFIXES.iter().find(|(char, _)| Some(*char) == suffix) {
+        Some((char, exponent)) => {
+            Precision::of_literal(&text[..text.len() - char.len_utf8()], *exponent)
+        }
+        None => Precision::of_literal(text, 0),
+    }
+}
+
+/// Lê o ângulo em graus que começa em `start`, com o `°` em `degrees_end`, seguido opcionalmente
+/// dos minutos (`15'`) e dos segundos (`50"`). Retorna onde o ângulo termina e o seu valor em
+/// graus, que fica vazio se só o último componente não for inteiro ou se os minutos ou segundos
+/// não forem menores que 60.
+fn read_dms(chars: &[char], start: usize, degrees_end: usize) -> (usize, Option<f64>) {
+    let mut components = vec![&chars[start..degrees_end]];
+    let mut i = degrees_end + 1;
+    for marker in ['\'', '"'] {
+        let mut end = i;
+        while end < chars.len() && (chars[end].is_ascii_digit() || chars[end] == '.') {
+            end += 1;
+        }
+        if end == i || chars.get(end) != Some(&marker) {
+            break;
+        }
+        components.push(&chars[i..end]);
+        i = end + 1;
+    }
+
+    let last = components.len() - 1;
+    let mut degrees = 0.0;
+    let mut unit = 1.0;
+    for (index, component) in components.iter().enumerate() {
+        let text: String = component.iter().collect();
+        let value = match text.parse::<f64>() {
+            Ok(value) if index == last || !text.contains('.') => value,
+            _ => return (i, None),
+        };
+        if index > 0 && value >= 60.0 {
+            return (i, None);
+        }
+        degrees += value / unit;
+        unit *= 60.0;
+    }
+    (i, Some(degrees))
+}
+
+/// Diz se `char` ainda faz parte do número que começou em `previous`. Além dos dígitos e do ponto,
+/// letras coladas ao número fazem parte dele (para que `3abc` seja um número inválido, e não um
+/// número seguido de um nome), e o sinal só é aceito logo após o `e` de um expoente (`1e-5`).
+fn is_number_char(previous: &[char], char: char, next: Option<&char>) -> bool {
+    match char {
+        '+' | '-' => {
+            matches!(previous.last(), Some('e') | Some('E'))
+                && previous[..previous.len() - 1]
+                    .iter()
+                    .all(|char| char.is_ascii_digit() || *char == '.')
+                && next.is_some_and(|next| next.is_ascii_digit())
+        }
+        char => char.is_alphanumeric() || char == '.' || char == '_',
+    }
+}
+
+#[test]
+fn tokenize_should_read_numbers_with_exponents() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("1.5e-3-2E2", &mut errors);
+    assert!(errors.is_empty());
+    assert_eq!(
+        tokens.iter().map(|token| &token.kind).collect::<Vec<_>>(),
+        vec![
+            &TokenKind::Number(1.5e-3, Precision::of_literal("1.5e-3", 0)),
+            &TokenKind::Operator(OperationKind::Subtract),
+            &TokenKind::Number(2e2, Precision::of_literal("2E2", 0)),
+        ]
+    );
+}
+
+/// Entrega no máximo `size` bytes por leitura, para que os tokens e os caracteres fiquem
+/// divididos entre leituras.
+#[cfg(all(test, feature = "std"))]
+struct Trickle<'a> {
+    bytes: &'a [u8],
+    size: usize,
+}
+
+#[cfg(all(test, feature = "std"))]
+impl std::io::Read for Trickle<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.size.min(buffer.len()).min(self.bytes.len());
+        buffer[..read].copy_from_slice(&self.bytes[..read]);
+        self.bytes = &self.bytes[read..];
+        Ok(read)
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn tokenize_reader_should_match_tokenize_with() {
+    let texts = [
+        "1.5e-3 - 2E2 * (0xff, 3abc)",
+        "30°15'50\" + máx(ação, 10k)  @ 5mrad",
+        "x>=1 and(y!=2)or not z ~= 3",
+        "",
+    ];
+    for text in texts {
+        let mut expected_errors = Vec::new();
+        let expected = tokenize_with(text, true, &mut expected_errors);
+        for size in [1, 2, 3, 7, 8192] {
+            let mut errors = Vec::new();
+            let reader = Trickle {
+                bytes: text.as_bytes(),
+                size,
+            };
+            let (tokens, end) = tokenize_reader(reader, true, &mut errors).unwrap();
+            assert_eq!(tokens, expected, "{} ({} bytes por leitura)", text, size);
+            assert_eq!(errors, expected_errors, "{}", text);
+            assert_eq!(end, text.chars().count());
+        }
+    }
+
+    let mut errors = Vec::new();
+    let reader = Trickle {
+        bytes: "1 + ç".as_bytes().split_last().unwrap().1,
+        size: 1,
+    };
+    assert!(tokenize_reader(reader, false, &mut errors).is_err());
+}
+
+#[test]
+fn tokenize_should_read_integers_in_other_bases() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("0xFF + 0b1010 - 0o17 * 0xdead_beef", &mut errors);
+    assert!(errors.is_empty());
+    let numbers: Vec<f64> = tokens
+        .iter()
+        .filter_map(|token| match token.kind {
+            TokenKind::Number(number, _) => Some(number),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(numbers, vec![255.0, 10.0, 15.0, 3735928559.0]);
+
+    for text in ["0x", "0b102", "0xg", "0x-1"] {
+        let mut errors = Vec::new();
+        tokenize(text, &mut errors);
+        assert_eq!(errors.len(), 1, "{}", text);
+    }
+}
+
+#[test]
+fn tokenize_should_read_si_suffixes_only_when_enabled() {
+    let numbers = |text: &str, si_suffixes: bool| {
+        let mut errors = Vec::new();
+        let tokens = tokenize_with(text, si_suffixes, &mut errors);
+        (
+            tokens
+                .into_iter()
+                .filter_map(|token| match token.kind {
+                    TokenKind::Number(number, _) => Some(number),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            errors.len(),
+        )
+    };
+
+    assert_eq!(
+        numbers("5k 3.3M 10u 2n 4.7\u{b5} 1e3k 1.5m", true),
+        (vec![5e3, 3.3e6, 10e-6, 2e-9, 4.7e-6, 1e6, 1.5e-3], 0)
+    );
+    assert_eq!(numbers("5k", false), (vec![], 1));
+    assert_eq!(numbers("5x 5kk", true), (vec![], 2));
+}
+
+#[test]
+fn tokenize_should_read_grouped_names() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("money.tip(x.5) + a.", &mut errors);
+    assert_eq!(
+        tokens.iter().map(|token| &token.kind).collect::<Vec<_>>(),
+        vec![
+            &TokenKind::Identifier("money.tip".to_string()),
+            &TokenKind::OpenParenthesis,
+            &TokenKind::Identifier("x".to_string()),
+            &TokenKind::Number(0.5, Precision::of_literal(".5", 0)),
+            &TokenKind::CloseParenthesis,
+            &TokenKind::Operator(OperationKind::Add),
+            &TokenKind::Identifier("a".to_string()),
+            &TokenKind::Invalid,
+        ]
+    );
+    assert_eq!(tokens[0].span, Span::new(0, 9));
+}
+
+#[test]
+fn tokenize_should_read_angle_suffixes() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("30deg + 1.2rad - 2.5e1deg - deg2 - 1degs", &mut errors);
+    assert_eq!(
+        tokens.iter().map(|token| &token.kind).collect::<Vec<_>>(),
+        vec![
+            &TokenKind::Degrees(30.0),
+            &TokenKind::Operator(OperationKind::Add),
+            &TokenKind::Radians(1.2),
+            &TokenKind::Operator(OperationKind::Subtract),
+            &TokenKind::Degrees(25.0),
+            &TokenKind::Operator(OperationKind::Subtract),
+            &TokenKind::Identifier("deg2".to_string()),
+            &TokenKind::Operator(OperationKind::Subtract),
+            &TokenKind::Invalid,
+        ]
+    );
+    assert_eq!(
+        errors,
+        vec![Error::ToF64ParseError(
+            "1degs".to_string(),
+            Span::new(35, 40)
+        )]
+    );
+
+    let tokens = tokenize_with("5mrad", true, &mut errors);
+    assert_eq!(tokens[0].kind, TokenKind::Radians(5e-3));
+}
+
+#[test]
+fn tokenize_should_read_degrees_minutes_and_seconds() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("30\u{b0}15'50\" + 45\u{b0} - 1\u{b0}30.5'", &mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(
+        tokens.iter().map(|token| &token.kind).collect::<Vec<_>>(),
+        vec![
+            &TokenKind::Degrees(30.0 + 15.0 / 60.0 + 50.0 / 3600.0),
+            &TokenKind::Operator(OperationKind::Add),
+            &TokenKind::Degrees(45.0),
+            &TokenKind::Operator(OperationKind::Subtract),
+            &TokenKind::Degrees(1.0 + 30.5 / 60.0),
+        ]
+    );
+    assert_eq!(tokens[0].span, Span::new(0, 9));
+
+    tokenize("10\u{b0}75' 1.5\u{b0}30'", &mut errors);
+    assert_eq!(
+        errors,
+        vec![
+            Error::ToF64ParseError("10\u{b0}75'".to_string(), Span::new(0, 6)),
+            Error::ToF64ParseError("1.5\u{b0}30'".to_string(), Span::new(7, 14)),
+        ]
+    );
+}
+
+#[test]
+fn tokenize_should_read_comparisons_and_logical_operators() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("a<=b == c != d>e and not f<g or h >= i ~= j", &mut errors);
+    assert!(errors.is_empty());
+    let operators: Vec<_> = tokens
+        .iter()
+        .filter(|token| !matches!(token.kind, TokenKind::Identifier(_)))
+        .map(|token| (&token.kind, token.span))
+        .collect();
+    assert_eq!(
+        operators,
+        vec![
+            (
+                &TokenKind::Operator(OperationKind::LessEqual),
+                Span::new(1, 3)
+            ),
+            (&TokenKind::Operator(OperationKind::Equal), Span::new(5, 7)),
+            (
+                &TokenKind::Operator(OperationKind::NotEqual),
+                Span::new(10, 12)
+            ),
+            (
+                &TokenKind::Operator(OperationKind::Greater),
+                Span::new(14, 15)
+            ),
+            (&TokenKind::Operator(OperationKind::And), Span::new(17, 20)),
+            (&TokenKind::Not, Span::new(21, 24)),
+            (&TokenKind::Operator(OperationKind::Less), Span::new(26, 27)),
+            (&TokenKind::Operator(OperationKind::Or), Span::new(29, 31)),
+            (
+                &TokenKind::Operator(OperationKind::GreaterEqual),
+                Span::new(34, 36)
+            ),
+            (
+                &TokenKind::Operator(OperationKind::Approx),
+                Span::new(39, 41)
+            ),
+        ]
+    );
+
+    tokenize("1 ! 2", &mut errors);
+    assert_eq!(
+        errors,
+        vec![Error::UnexpectedCharacter('!', Span::new(2, 3))]
+    );
+}
+
+#[test]
+fn tokenize_should_report_unexpected_characters() {
+    let mut errors = Vec::new();
+    tokenize("2 @ 3.4.5 # pi", &mut errors);
+    assert_eq!(
+        errors,
+        vec![
+            Error::UnexpectedCharacter('@', Span::new(2, 3)),
+            Error::ToF64ParseError("3.4.5".to_string(), Span::new(4, 9)),
+            Error::UnexpectedCharacter('#', Span::new(10, 11)),
+        ]
+    );
+}
@@ -0,0 +1,82 @@
+//! Calculadora de expressões aritméticas.
+//!
+//! ```
+//! use asdf_calc::Expression;
+//!
+//! let expression = Expression::new("3 + (3 + 5) * 6").expect("expressão válida");
+//! assert_eq!(expression.evaluate(), Ok(51.0));
+//! ```
+//!
+//! Sem a feature `std` (ligada por padrão), o crate compila em `no_std`, precisando apenas de
+//! `alloc`, para que possa ser usado em firmware.
+//!
+//! # Threads
+//!
+//! `EvalContext`, `Expression`, `Function` e `Statement` são `Send + Sync`, e essa garantia é
+//! verificada na compilação do crate. O cálculo só lê o contexto (`Expression::evaluate_with`
+//! recebe `&EvalContext`), então um mesmo contexto e uma mesma expressão podem ser usados por
+//! várias threads ao mesmo tempo, por exemplo dentro de um `Arc`. Mudar o contexto precisa de
+//! acesso exclusivo (`&mut`), com um `Mutex` ou uma cópia por thread, e a única mutabilidade
+//! interior é a da `InterruptFlag`, que é atômica e compartilhada entre as cópias.
+//!
+//! ```
+//! use std::{sync::Arc, thread};
+//!
+//! use asdf_calc::{EvalContext, Expression};
+//!
+//! let mut context = EvalContext::new();
+//! context.set_variable("x", 3.0);
+//! let context = Arc::new(context);
+//! let expression = Arc::new(Expression::with_context("x ^ 2", &context).unwrap());
+//!
+//! let handles: Vec<_> = (0..4)
+//!     .map(|_| {
+//!         let (context, expression) = (Arc::clone(&context), Arc::clone(&expression));
+//!         thread::spawn(move || expression.evaluate_with(&context))
+//!     })
+//!     .collect();
+//! for handle in handles {
+//!     assert_eq!(handle.join().unwrap(), Ok(9.0));
+//! }
+//! ```
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+extern crate alloc;
+
+mod builtins;
+mod context;
+mod error;
+mod expression;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod geo;
+mod lexer;
+mod lint;
+mod math;
+mod observer;
+mod parser;
+mod portable;
+mod significance;
+mod statement;
+mod units;
+
+pub use builtins::{Angle, Arity, Builtin, BUILTINS};
+pub use context::{AngleMode, EvalContext, InterruptFlag, Limits, Summation, WordSize, CONSTANTS};
+pub use error::{Error, Limit, Span};
+pub use expression::{Expression, Function, APPROX_TOLERANCE};
+pub use lint::{lint, Note};
+pub use observer::{EvalNode, EvalObserver};
+pub use statement::Statement;
+pub use units::{Dimension, Unit, BASES, SI_BASES, UNITS};
+
+// falha na compilação se algum campo novo deixar de poder ser compartilhado entre threads
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<EvalContext>();
+    assert_send_sync::<Expression>();
+    assert_send_sync::<Function>();
+    assert_send_sync::<Statement>();
+    assert_send_sync::<InterruptFlag>();
+    assert_send_sync::<Error>();
+    assert_send_sync::<EvalNode<'static>>();
+};
@@ -0,0 +1,294 @@
+//! Avisos sobre trechos que são válidos, mas que podem não ser lidos como quem escreveu
+//! esperava, como `-2^2` ou `1/2 km`. Eles não impedem o cálculo: cada um só diz como o trecho
+//! foi lido.
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::{self, Display, Formatter};
+
+use crate::{
+    context::EvalContext,
+    error::Span,
+    expression::OperationKind,
+    lexer::{tokenize_with, Token, TokenKind},
+};
+
+/// Um aviso de `lint`, com o trecho a que ele se refere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    pub message: String,
+    pub span: Span,
+}
+
+impl Display for Note {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "coluna {}: nota: {}", self.span.column(), self.message)
+    }
+}
+
+/// Os trechos ambíguos de `text`, na ordem em que aparecem:
+/// - o `-` unário antes de uma potência, que se aplica a ela toda (`-2^2` é `-(2^2)`);
+/// - um número com unidade depois de `/`, que divide pelos dois (`1/2 km` é `1/(2 km)`);
+/// - o `^` logo depois de um número com unidade, que só eleva a unidade (`3 m^2` é `3 (m^2)`);
+/// - potências encadeadas, lidas da direita para a esquerda (`2^3^2` é `2^(3^2)`).
+///
+/// Os erros de sintaxe são ignorados, já que eles são mostrados pela leitura da expressão.
+///
+/// ```
+/// use asdf_calc::{lint, EvalContext};
+///
+/// let notes = lint("-2^2 + 1", &EvalContext::new());
+/// assert_eq!(notes[0].message, "`-2^2` é lido como `-(2^2)`");
+/// ```
+pub fn lint(text: &str, context: &EvalContext) -> Vec<Note> {
+    let chars: Vec<char> = text.chars().collect();
+    let tokens = tokenize_with(text, context.si_suffixes(), &mut Vec::new());
+    let source = |first: usize, last: usize| -> String {
+        let (start, end) = (tokens[first].span.start, tokens[last].span.end);
+        chars[start..end.min(chars.len())]
+            .iter()
+            .collect::<String>()
+    };
+    let is_unit = |index: usize| match tokens.get(index) {
+        Some(Token {
+            kind: TokenKind::Identifier(name),
+            ..
+        }) => {
+            !matches!(
+                tokens.get(index + 1).map(|token| &token.kind),
+                Some(TokenKind::OpenParenthesis)
+            ) && context.unit(name).is_some()
+        }
+        _ => false,
+    };
+    let quantity = |index: usize| {
+        matches!(
+            tokens.get(index).map(|token| &token.kind),
+            Some(TokenKind::Number(..))
+        ) && is_unit(index + 1)
+    };
+    let spaced = |first: usize| {
+        let number = source(first, first);
+        let unit = source(first + 1, first + 1);
+        format!("{} {}", number, unit)
+    };
+
+    let mut notes = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let TokenKind::Operator(kind) = token.kind else {
+            continue;
+        };
+        match kind {
+            // com `--2^2`, só o `-` mais próximo é avisado
+            OperationKind::Subtract if expects_operand(&tokens, i) && !is_minus(&tokens, i + 1) => {
+                let Some(power) = operand_end(&tokens, i + 1) else {
+                    continue;
+                };
+                if !is_power(&tokens, power) || quantity(i + 1) {
+                    continue;
+                }
+                let Some(end) = operand_end(&tokens, power + 1) else {
+                    continue;
+                };
+                let written = source(i, end - 1);
+                notes.push(Note {
+                    message: format!(
+                        "`{}` é lido como `-({})`",
+                        written,
+                        written[1..].trim_start()
+                    ),
+                    span: Span::new(token.span.start, tokens[end - 1].span.end),
+                });
+            }
+            OperationKind::Divide if quantity(i + 1) => {
+                let (Some(start), true) = (operand_start(&tokens, i), i > 0) else {
+                    continue;
+                };
+                let left = source(start, i - 1);
+                let right = spaced(i + 1);
+                notes.push(Note {
+                    message: format!("`{}/{}` é lido como `{}/({})`", left, right, left, right),
+                    span: Span::new(tokens[start].span.start, tokens[i + 2].span.end),
+                });
+            }
+            OperationKind::Power if i >= 2 && quantity(i - 2) => {
+                let Some(end) = operand_end(&tokens, i + 1) else {
+                    continue;
+                };
+                let number = source(i - 2, i - 2);
+                let power = source(i - 1, end - 1);
+                notes.push(Note {
+                    message: format!(
+                        "`{} {}` é lido como `{} ({})`, só a unidade é elevada",
+                        number, power, number, power
+                    ),
+                    span: Span::new(tokens[i - 2].span.start, tokens[end - 1].span.end),
+                });
+            }
+            OperationKind::Power => {
+                // só o primeiro `^` de cada sequência é avisado
+                let Some(start) = operand_start(&tokens, i) else {
+                    continue;
+                };
+                if start > 0 && is_power(&tokens, start - 1) {
+                    continue;
+                }
+                let Some(middle) = operand_end(&tokens, i + 1) else {
+                    continue;
+                };
+                if !is_power(&tokens, middle) {
+                    continue;
+                }
+                let Some(end) = operand_end(&tokens, middle + 1) else {
+                    continue;
+                };
+                let base = source(start, i - 1);
+                let exponent = source(i + 1, end - 1);
+                notes.push(Note {
+                    message: format!(
+                        "`{}^{}` é lido como `{}^({})`",
+                        base, exponent, base, exponent
+                    ),
+                    span: Span::new(tokens[start].span.start, tokens[end - 1].span.end),
+                });
+            }
+            _ => {}
+        }
+    }
+    notes
+}
+
+fn is_minus(tokens: &[Token], index: usize) -> bool {
+    matches!(
+        tokens.get(index).map(|token| &token.kind),
+        Some(TokenKind::Operator(OperationKind::Subtract))
+    )
+}
+
+fn is_power(tokens: &[Token], index: usize) -> bool {
+    matches!(
+        tokens.get(index).map(|token| &token.kind),
+        Some(TokenKind::Operator(OperationKind::Power))
+    )
+}
+
+/// Se o operador na posição `index` é unário, como o `validate` do parser decide.
+fn expects_operand(tokens: &[Token], index: usize) -> bool {
+    index == 0
+        || matches!(
+            tokens[index - 1].kind,
+            TokenKind::Operator(_)
+                | TokenKind::OpenParenthesis
+                | TokenKind::Comma
+                | TokenKind::Not
+                | TokenKind::Assign
+        )
+}
+
+/// A posição logo depois do operando simples que começa em `index`: um número, um nome, uma
+/// chamada ou um trecho entre parênteses, com os `-` unários antes dele.
+fn operand_end(tokens: &[Token], mut index: usize) -> Option<usize> {
+    while let Some(TokenKind::Operator(OperationKind::Subtract)) =
+        tokens.get(index).map(|token| &token.kind)
+    {
+        index += 1;
+    }
+    match &tokens.get(index)?.kind {
+        TokenKind::Number(..) | TokenKind::Degrees(_) | TokenKind::Radians(_) => Some(index + 1),
+        TokenKind::Identifier(_) => match tokens.get(index + 1).map(|token| &token.kind) {
+            Some(TokenKind::OpenParenthesis) => closing(tokens, index + 1),
+            _ => Some(index + 1),
+        },
+        TokenKind::OpenParenthesis => closing(tokens, index),
+        _ => None,
+    }
+}
+
+/// A posição logo depois do `)` que fecha o `(` em `open`.
+fn closing(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        match token.kind {
+            TokenKind::OpenParenthesis => depth += 1,
+            TokenKind::CloseParenthesis => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// O começo do operando simples que termina logo antes do operador em `index`, como em
+/// `operand_end`, mas além disso aceitando um número com unidade (`2 km`).
+fn operand_start(tokens: &[Token], index: usize) -> Option<usize> {
+    let last = index.checked_sub(1)?;
+    match &tokens[last].kind {
+        TokenKind::Number(..) | TokenKind::Degrees(_) | TokenKind::Radians(_) => Some(last),
+        TokenKind::Identifier(_) => match last.checked_sub(1).map(|index| &tokens[index].kind) {
+            Some(TokenKind::Number(..)) => Some(last - 1),
+            _ => Some(last),
+        },
+        TokenKind::CloseParenthesis => {
+            let mut depth = 0;
+            for open in (0..=last).rev() {
+                match tokens[open].kind {
+                    TokenKind::CloseParenthesis => depth += 1,
+                    TokenKind::OpenParenthesis => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return match open.checked_sub(1).map(|index| &tokens[index].kind) {
+                                Some(TokenKind::Identifier(_)) => Some(open - 1),
+                                _ => Some(open),
+                            };
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn lint_should_explain_ambiguous_constructs() {
+    let context = EvalContext::new();
+    let messages = |text: &str| -> Vec<String> {
+        lint(text, &context)
+            .into_iter()
+            .map(|note| note.message)
+            .collect()
+    };
+    assert_eq!(messages("-2^2"), ["`-2^2` é lido como `-(2^2)`"]);
+    assert_eq!(messages("--2 ^ 2"), ["`-2 ^ 2` é lido como `-(2 ^ 2)`"]);
+    assert_eq!(
+        messages("3 * -x^(1/2)"),
+        ["`-x^(1/2)` é lido como `-(x^(1/2))`"]
+    );
+    assert_eq!(messages("1/2 km"), ["`1/2 km` é lido como `1/(2 km)`"]);
+    assert_eq!(
+        messages("(a + b)/2 km"),
+        ["`(a + b)/2 km` é lido como `(a + b)/(2 km)`"]
+    );
+    assert_eq!(
+        messages("3 m^2"),
+        ["`3 m^2` é lido como `3 (m^2)`, só a unidade é elevada"]
+    );
+    assert_eq!(messages("2^3^2"), ["`2^3^2` é lido como `2^(3^2)`"]);
+    assert_eq!(
+        messages("sqrt(4)^2^-1"),
+        ["`sqrt(4)^2^-1` é lido como `sqrt(4)^(2^-1)`"]
+    );
+    assert_eq!(lint("-2^2", &context)[0].span, Span::new(0, 4));
+    assert_eq!(lint("x = 1/2 km", &context)[0].span, Span::new(4, 10));
+
+    for text in [
+        "(-2)^2", "2 - 3^2", "1/(2 km)", "2 km * 3", "(2^3)^2", "-2 * 3", "1/2",
+    ] {
+        assert_eq!(messages(text), Vec::<String>::new(), "{}", text);
+    }
+}
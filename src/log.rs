@@ -0,0 +1,101 @@
+//! Registro dos cálculos (`log on`), em que cada linha calculada com sucesso é acrescentada ao
+//! fim de um arquivo com a hora, o input e o resultado, para quem precisa mostrar depois de onde
+//! veio cada número. O arquivo nunca é reescrito, só aumenta.
+//!
+//! Cada linha tem a hora em UTC no formato RFC 3339, o input e o resultado separados por TAB
+//! (`2026-10-14T12:30:00Z<TAB>1000 * 0.05<TAB>50`).
+
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::date::civil_from_days;
+
+/// `$XDG_DATA_HOME/asdf-calc/calc.log`, ou `~/.local/share/asdf-calc/calc.log` se a variável não
+/// estiver definida.
+pub fn default_path() -> Option<PathBuf> {
+    let data = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => Path::new(&env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(data.join("asdf-calc/calc.log"))
+}
+
+/// Acrescenta o cálculo ao fim do arquivo, criando ele (e o diretório) se preciso.
+pub fn append(path: &Path, time: SystemTime, input: &str, result: f64) -> Result<(), String> {
+    let error = |error: std::io::Error| {
+        format!(
+            "não foi possível escrever no log {}: {}",
+            path.display(),
+            error
+        )
+    };
+    if let Some(directory) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(directory).map_err(error)?;
+    }
+    let mut file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .map_err(error)?;
+    writeln!(file, "{}", line(time, input, result)).map_err(error)
+}
+
+fn line(time: SystemTime, input: &str, result: f64) -> String {
+    // um TAB no input confundiria as colunas
+    format!(
+        "{}\t{}\t{}",
+        timestamp(time),
+        input.trim().replace('\t', " "),
+        result
+    )
+}
+
+/// `time` em UTC no formato RFC 3339, com precisão de segundos.
+pub fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let seconds_of_day = seconds % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60
+    )
+}
+
+#[test]
+fn timestamp_should_be_written_in_utc() {
+    use std::time::Duration;
+
+    assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    let time = UNIX_EPOCH + Duration::from_secs(1_792_000_000);
+    assert_eq!(timestamp(time), "2026-10-14T17:46:40Z");
+    let leap_day = UNIX_EPOCH + Duration::from_secs(951_782_400 + 3661);
+    assert_eq!(timestamp(leap_day), "2000-02-29T01:01:01Z");
+}
+
+#[test]
+fn append_should_add_lines_to_the_end_of_the_log() {
+    let directory = env::temp_dir().join(format!("asdf-calc-log-{}", std::process::id()));
+    let path = directory.join("calc.log");
+    let _ = fs::remove_dir_all(&directory);
+
+    append(&path, UNIX_EPOCH, "1000 * 0.05", 50.0).unwrap();
+    append(&path, UNIX_EPOCH, "x =\t0.1 ", 0.1).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "1970-01-01T00:00:00Z\t1000 * 0.05\t50\n1970-01-01T00:00:00Z\tx = 0.1\t0.1\n"
+    );
+    fs::remove_dir_all(&directory).unwrap();
+}
@@ -1,371 +1,233 @@
-use std::{
-    fmt::{Display, Formatter},
-    io::{self, Write},
-};
-
-#[derive(Debug)]
-enum Error {
-    ToF64ParseError(String),
-    ExtraParenthesis(String),
-}
-
-impl Display for Error {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Error::ToF64ParseError(text_portion) => {
-                write!(
-                    f,
-                    "não foi possível transformar o trecho do input ({}) em um número f64",
-                    text_portion
-                )
-            }
-            Error::ExtraParenthesis(text_portion) => {
-                write!(
-                    f,
-                    "síntaxe incorreta no trecho '{}', parênteses há mais do que o necessário",
-                    text_portion
-                )
-            }
-        }
+mod alias;
+mod annotate;
+mod autocorrect;
+mod batch;
+mod bc;
+mod bench;
+mod cache;
+mod calendar;
+mod cli;
+mod color;
+mod coordinates;
+mod csv;
+#[cfg(unix)]
+mod daemon;
+mod date;
+mod diagnostic;
+mod duration;
+mod editor;
+mod env;
+mod export;
+mod finance;
+mod format;
+mod fraction;
+mod grid;
+mod init;
+mod inspect;
+mod interrupt;
+mod json;
+mod log;
+mod oneshot;
+mod pager;
+mod pool;
+mod prompt;
+mod repl;
+mod replay;
+mod rpc;
+mod screen;
+mod server;
+mod session;
+mod table;
+mod terminal;
+mod test_runner;
+mod unit_system;
+mod watch;
+mod workspace;
+
+use asdf_calc::{EvalContext, Expression};
+
+use crate::{cli::Command, session::Session};
+
+/// Calcula as definições de `-D` (e as de cada conjunto do `compare`) em ordem, para que cada
+/// uma possa usar as anteriores. O erro diz qual definição não pôde ser calculada.
+fn define_variables(
+    mut context: EvalContext,
+    definitions: &[(String, String)],
+) -> Result<EvalContext, String> {
+    for (name, value) in definitions {
+        let result = Expression::with_context(value, &context)
+            .and_then(|expression| {
+                expression
+                    .evaluate_with(&context)
+                    .map_err(|error| vec![error])
+            })
+            .map_err(|errors| {
+                let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                format!("{}={}: {}", name, value, messages.join("; "))
+            })?;
+        context.set_variable(name.clone(), result);
     }
+    Ok(context)
 }
 
-#[derive(Debug, Clone)]
-enum Operation {
-    Add(f64),
-    Subtract(f64),
-    Multiply(f64),
-    Divide(f64),
-}
-
-#[test]
-fn opeartion_should_operate_with_correctly_for_add() {
-    let op = Operation::Add(5.0);
-    assert_eq!(op.operate_with(&3.0), 8.0);
-}
-
-#[test]
-fn opeartion_should_operate_with_correctly_for_subtract() {
-    let op = Operation::Subtract(5.0);
-    assert_eq!(op.operate_with(&3.0), -2.0);
-}
-
-#[test]
-fn opeartion_should_operate_with_correctly_for_multiply() {
-    let op = Operation::Multiply(5.0);
-    assert_eq!(op.operate_with(&3.0), 15.0);
-}
-
-#[test]
-fn opeartion_should_operate_with_correctly_for_divide() {
-    let op = Operation::Divide(5.0);
-    assert_eq!(op.operate_with(&3.0), 3.0 / 5.0);
-}
-
-impl Operation {
-    fn operate_with(&self, other: &f64) -> f64 {
-        match self {
-            Operation::Add(num) => num + other,
-            Operation::Subtract(num) => other - num,
-            Operation::Multiply(num) => num * other,
-            Operation::Divide(num) => other / num,
+fn main() {
+    let args = cli::split_global_options(std::env::args().skip(1))
+        .and_then(|(options, args)| Ok((options, cli::parse_args(args)?)));
+    let (options, command) = match args {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, cli::USAGE);
+            std::process::exit(2);
         }
-    }
-
-    fn is_multiply_or_divide(&self) -> bool {
-        match self {
-            Operation::Multiply(_) => true,
-            Operation::Divide(_) => true,
-            _ => false,
+    };
+    let mut context = EvalContext::new();
+    context.set_si_suffixes(!options.no_si);
+    context.set_strict(options.strict);
+    context.set_deterministic(options.deterministic);
+    let context = match define_variables(context, &options.definitions) {
+        Ok(context) => context,
+        Err(message) => {
+            eprintln!("-D {}", message);
+            std::process::exit(2);
         }
+    };
+    let mut session = Session::with_context(context.clone());
+    session.interpolate_env = options.env;
+    session.set_timeout(options.timeout);
+    session.workspace_directory = workspace::default_directory();
+    // nesses modos o texto vem de outros programas, que não deveriam poder ler o ambiente
+    if options.env
+        && matches!(
+            command,
+            Command::Serve { .. } | Command::Rpc | Command::Bc { .. }
+        )
+    {
+        eprintln!("--env não pode ser usado com serve, --rpc ou --compat");
+        std::process::exit(2);
     }
-}
-
-#[derive(Debug)]
-enum OperationKind {
-    Add,
-    Subtract,
-    Multiply,
-    Divide,
-}
-
-/// Dispõe a informação necessária que definem uma expressão e organizada de tal forma que possa
-/// ser facilmente calculada.
-///
-/// # Exemplo
-///
-/// A representação da seguinte expressão "`9 + 2 - (5 + 3) * 2`" usando esta struct seria dada pelo
-/// seguinte código:
-/// ```rust
-/// let parenthesis_expression = Expression {
-///     operations: vec![
-///         Opeartion::AddNumber(5.0),
-///         Opeartion::AddNumber(3.0),
-///     ],
-/// }
-///
-/// let expression = Expression {
-///     operations: vec![
-///         Operation::Add(9.0),
-///         Opeartion::Add(2.0),
-///         Operation::Subtract(parenthesis_expression.evaluate()),
-///         Opeartion::Multiply(2.0),
-///     ],
-/// }
-/// ```
-#[derive(Debug, Default)]
-struct Expression {
-    operations: Vec<Operation>, // a ordem dos valores desse Vec IMPORTA
-}
-
-impl Expression {
-    fn empty() -> Self {
-        Expression {
-            operations: Vec::default(),
+    if !options.no_init && matches!(command, Command::Repl | Command::Daemon { .. }) {
+        if let Some(path) = init::default_path() {
+            for message in init::run(&path, &mut session) {
+                eprintln!("{}", message);
+            }
         }
     }
 
-    fn push_op<T>(&mut self, kind: &OperationKind, val: T)
-    where
-        T: Into<f64>,
-    {
-        let num = val.into();
-        match kind {
-            OperationKind::Add => {
-                self.operations.push(Operation::Add(num));
-            }
-            OperationKind::Subtract => {
-                self.operations.push(Operation::Subtract(num));
+    match command {
+        Command::Repl => repl::Repl::new(session).run(),
+        Command::Serve { port } => {
+            if let Err(error) = server::serve(port, context) {
+                eprintln!("não foi possível iniciar o servidor: {}", error);
+                std::process::exit(1);
             }
-            OperationKind::Multiply => {
-                self.operations.push(Operation::Multiply(num));
+        }
+        Command::Rpc => {
+            if let Err(error) = rpc::run(context) {
+                eprintln!("erro na comunicação RPC: {}", error);
+                std::process::exit(1);
             }
-            OperationKind::Divide => {
-                self.operations.push(Operation::Divide(num));
+        }
+        Command::OneShot { copy } => {
+            std::process::exit(oneshot::run(copy, options.error_format, session))
+        }
+        Command::Check { expression } => std::process::exit(oneshot::run_check(
+            &expression,
+            options.error_format,
+            &session,
+        )),
+        Command::Raw {
+            expression,
+            newline,
+        } => std::process::exit(oneshot::run_raw(
+            &expression,
+            newline,
+            options.error_format,
+            session,
+        )),
+        Command::Table { files, numbers } => {
+            std::process::exit(batch::run(&files, numbers, session))
+        }
+        Command::Bc { files, mathlib } => {
+            let scale = if mathlib { bc::MATHLIB_SCALE } else { 0 };
+            std::process::exit(bc::run(&files, scale, &context))
+        }
+        #[cfg(unix)]
+        Command::Daemon { socket } => {
+            let socket = socket.unwrap_or_else(daemon::default_socket_path);
+            if let Err(error) = daemon::run_daemon(&socket, session) {
+                eprintln!("não foi possível iniciar o daemon: {}", error);
+                std::process::exit(1);
             }
-        };
-    }
-
-    fn new(text: &str) -> Result<Self, Error> {
-        let pure_text = text.trim().replace(" ", "");
-
-        let mut expression = Self::empty();
-
-        let mut current_determined_operation = OperationKind::Add;
-        let mut accumulated_text = String::new();
-        let mut inside_parenthesis = false;
-
-        let chars: Vec<&str> = pure_text.split("").collect();
-        let chars_len = chars.len();
-
-        for (i, char) in chars.into_iter().enumerate() {
-            match char {
-                "+" | "-" | "*" | "/" => {
-                    if !inside_parenthesis {
-                        if accumulated_text.len() > 0 {
-                            if let Ok(num) = accumulated_text.parse::<f64>() {
-                                expression.push_op(&current_determined_operation, num);
-                            } else {
-                                return Err(Error::ToF64ParseError(accumulated_text));
-                            }
+        }
+        #[cfg(unix)]
+        Command::Send { socket, expression } => {
+            let socket = socket.unwrap_or_else(daemon::default_socket_path);
+            let expression = if options.env {
+                match env::interpolate_env(&expression) {
+                    Ok(interpolated) => interpolated.text,
+                    Err(errors) => {
+                        for error in errors {
+                            eprintln!("{}", error);
                         }
+                        std::process::exit(1);
                     }
                 }
-                "(" => {
-                    if inside_parenthesis {
-                        return Err(Error::ExtraParenthesis(accumulated_text));
-                    }
-                    inside_parenthesis = true;
-                }
-                ")" => {
-                    if inside_parenthesis {
-                        inside_parenthesis = false;
-                        let parenthesis_expression = Expression::new(&accumulated_text)?;
-                        expression.push_op(
-                            &current_determined_operation,
-                            parenthesis_expression.evaluate(),
-                        );
-                        accumulated_text = String::new();
-                    } else {
-                        return Err(Error::ExtraParenthesis(accumulated_text));
-                    }
-                }
-                _ => {}
+            } else {
+                expression
             };
-
-            if !inside_parenthesis {
-                match char {
-                    "+" => {
-                        current_determined_operation = OperationKind::Add;
-                        accumulated_text = String::new();
-                    }
-                    "-" => {
-                        current_determined_operation = OperationKind::Subtract;
-                        accumulated_text = String::new();
+            match daemon::send(&socket, &expression) {
+                Ok((success, output)) => {
+                    print!("{}", output);
+                    if !success {
+                        std::process::exit(1);
                     }
-                    "*" => {
-                        current_determined_operation = OperationKind::Multiply;
-                        accumulated_text = String::new();
-                    }
-                    "/" => {
-                        current_determined_operation = OperationKind::Divide;
-                        accumulated_text = String::new();
-                    }
-                    "(" | ")" => {}
-                    char => {
-                        accumulated_text.push_str(char);
-                    }
-                };
-            } else {
-                if char != "(" && char != ")" {
-                    accumulated_text.push_str(char);
                 }
-            }
-
-            if i + 1 == chars_len && accumulated_text.len() > 0 {
-                if let Ok(num) = accumulated_text.parse::<f64>() {
-                    expression.push_op(&current_determined_operation, num);
-                } else {
-                    return Err(Error::ToF64ParseError(accumulated_text));
+                Err(error) => {
+                    eprintln!("{}", error);
+                    std::process::exit(1);
                 }
             }
         }
-
-        Ok(expression)
-    }
-
-    fn evaluate(&self) -> f64 {
-        let mut result = 0.0;
-
-        let mut temp_operations = self.operations.clone();
-        let mut i = 0;
-        while i < temp_operations.len() {
-            let operation = &temp_operations[i];
-
-            if i + 1 < temp_operations.len() {
-                let next_operation = &temp_operations[i + 1];
-                if next_operation.is_multiply_or_divide() {
-                    match operation {
-                        Operation::Add(num) => {
-                            temp_operations[i] = Operation::Add(next_operation.operate_with(&num));
-                        }
-                        Operation::Subtract(num) => {
-                            temp_operations[i] =
-                                Operation::Subtract(next_operation.operate_with(&num));
-                        }
-                        _ => {} //cannot happen
-                    }
-                    temp_operations.remove(i + 1);
-                } else {
-                    match operation {
-                        Operation::Add(num) => {
-                            result += num;
-                        }
-                        Operation::Subtract(num) => {
-                            result -= num;
-                        }
-                        _ => {} // the other possible operations will already been taken into account
-                    }
-                    i += 1;
-                }
-            } else {
-                match operation {
-                    Operation::Add(num) => {
-                        result += num;
-                    }
-                    Operation::Subtract(num) => {
-                        result -= num;
-                    }
-                    _ => {} // the other possible operations will already been taken into account
-                }
-                i += 1;
+        #[cfg(not(unix))]
+        Command::Daemon { .. } | Command::Send { .. } => {
+            eprintln!("o daemon só está disponível em sistemas Unix");
+            std::process::exit(1);
+        }
+        Command::Watch { file } => {
+            if let Err(error) = watch::run(&file, &session) {
+                eprintln!("erro ao acompanhar {}: {}", file.display(), error);
+                std::process::exit(1);
             }
         }
-
-        result
-    }
-}
-
-#[test]
-fn expression_should_be_created_with_simple_strs_correctly() {
-    // 3 + 5
-    let expression = Expression::new("3 + 5").expect("falha na criação da Expression: [3 + 5]");
-    assert_eq!(expression.evaluate(), 8.0);
-
-    let other_expression = Expression::new("3+5").expect("falha na criação da Expression: [3+5]");
-    assert_eq!(other_expression.evaluate(), 8.0);
-}
-
-#[test]
-fn expression_should_be_created_correclty() {
-    // 3 + (3 + 5) * 6 + 4 - 3 / 2
-    let expression_str = "3 + (3 + 5) * 6 + 4 - 3 / 2";
-    let expression = Expression::new(expression_str)
-        .expect("falha na criação da Expression [3 + (3 + 5) * 6 + 4 - 3 / 2]");
-    assert_eq!(
-        expression.evaluate(),
-        3.0 + (3.0 + 5.0) * 6.0 + 4.0 - 3.0 / 2.0
-    );
-}
-
-#[test]
-fn expression_should_be_evaluated_correctly() {
-    // 4 + 5 + 9 + 3 * 2 / 3
-    let expression = Expression {
-        operations: vec![
-            Operation::Add(4.0),
-            Operation::Add(5.0),
-            Operation::Add(9.0),
-            Operation::Add(3.0),
-            Operation::Multiply(2.0),
-            Operation::Divide(3.0),
-        ],
-    };
-
-    assert_eq!(expression.evaluate(), 4.0 + 5.0 + 9.0 + 3.0 * 2.0 / 3.0);
-}
-
-fn app() {
-    let mut out_handle = io::stdout();
-    out_handle
-        .write_all(b"> ")
-        .expect("não foi possível escrever '> ' no terminal");
-    out_handle
-        .flush()
-        .expect("não foi possível forçar escrita no terminal");
-
-    let mut expression_string = String::new();
-    io::stdin()
-        .read_line(&mut expression_string)
-        .expect("não foi possível ler input pelo terminal");
-    expression_string = expression_string.trim().to_lowercase().to_string();
-
-    if expression_string == "clear" {
-        print!("\x1B[2J\x1B[1;1H");
-    } else if expression_string == "exit" {
-        std::process::exit(0);
-    } else {
-        let expression = Expression::new(expression_string.as_str()).expect(
-            format!(
-                "não foi possível compreender a expressão escrita [{}]",
-                expression_string
-            )
-            .as_str(),
-        );
-
-        let calculation_result = expression.evaluate();
-
-        out_handle
-            .write_all(format!("{}\n", calculation_result).as_bytes())
-            .expect("não foi possível escrever resultado no terminal");
-        out_handle
-            .flush()
-            .expect("não foi possível forçar escrita no terminal");
-    }
-}
-
-fn main() {
-    loop {
-        app()
+        Command::Annotate { file } => {
+            if let Err(error) = annotate::run(&file, &session) {
+                eprintln!("não foi possível anotar {}: {}", file.display(), error);
+                std::process::exit(1);
+            }
+        }
+        Command::Csv {
+            file,
+            formula,
+            column,
+            jobs,
+        } => std::process::exit(csv::run(&file, &formula, &column, jobs, &context)),
+        Command::Replay { file, interactive } => {
+            if let Err(message) = replay::run(&file, interactive, session.notation) {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+        }
+        Command::Test { files } => std::process::exit(test_runner::run(&files, &session)),
+        Command::Bench {
+            iterations,
+            expression,
+        } => match bench::bench(&expression, iterations, &session) {
+            Ok(report) => print!("{}", report),
+            Err(message) => {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+        },
+        Command::Help => println!("{}", cli::USAGE),
     }
 }
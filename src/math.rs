@@ -0,0 +1,116 @@
+//! Funções de `f64` que não existem em `core`. Com `std` são usadas as da biblioteca padrão, e
+//! sem ela as da libm com que o programa final for ligado. As que a biblioteca padrão não tem
+//! (`gamma`, `erf`...) sempre vêm da libm, que a `std` já liga ao programa.
+
+#[cfg(any(feature = "std", test))]
+pub(crate) fn pow(base: f64, exponent: f64) -> f64 {
+    base.powf(exponent)
+}
+
+#[cfg(not(any(feature = "std", test)))]
+pub(crate) fn pow(base: f64, exponent: f64) -> f64 {
+    extern "C" {
+        fn pow(base: f64, exponent: f64) -> f64;
+    }
+    // SAFETY: `pow` da libm não tem pré-condições.
+    unsafe { pow(base, exponent) }
+}
+
+/// Declara funções de um argumento com o mesmo nome no `f64` e na libm.
+macro_rules! unary {
+    ($($name:ident),* $(,)?) => {
+        $(
+            #[cfg(any(feature = "std", test))]
+            pub(crate) fn $name(value: f64) -> f64 {
+                value.$name()
+            }
+
+            #[cfg(not(any(feature = "std", test)))]
+            pub(crate) fn $name(value: f64) -> f64 {
+                extern "C" {
+                    fn $name(value: f64) -> f64;
+                }
+                // SAFETY: as funções da libm não têm pré-condições.
+                unsafe { $name(value) }
+            }
+        )*
+    };
+}
+
+unary!(round, trunc, sin, cos, tan, asin, acos, atan, sinh, cosh, tanh, asinh, acosh, atanh);
+
+#[cfg(any(feature = "std", test))]
+pub(crate) fn atan2(y: f64, x: f64) -> f64 {
+    y.atan2(x)
+}
+
+#[cfg(not(any(feature = "std", test)))]
+pub(crate) fn atan2(y: f64, x: f64) -> f64 {
+    extern "C" {
+        fn atan2(y: f64, x: f64) -> f64;
+    }
+    // SAFETY: `atan2` da libm não tem pré-condições.
+    unsafe { atan2(y, x) }
+}
+
+/// Declara funções de um argumento que só existem na libm, com o nome usado aqui e o da libm.
+macro_rules! libm_only {
+    ($($name:ident => $libm_name:ident),* $(,)?) => {
+        $(
+            pub(crate) fn $name(value: f64) -> f64 {
+                extern "C" {
+                    fn $libm_name(value: f64) -> f64;
+                }
+                // SAFETY: as funções da libm não têm pré-condições.
+                unsafe { $libm_name(value) }
+            }
+        )*
+    };
+}
+
+// o `lgamma` da glibc também escreve o sinal de Γ(x) numa variável global, que não é usada aqui
+libm_only!(gamma => tgamma, lgamma => lgamma, erf => erf, erfc => erfc);
+
+/// As funções que mudam de uma libm para outra, usadas pelas funções embutidas e por `^`: as
+/// daqui (`SYSTEM`), ou as de `portable` no modo determinístico.
+pub(crate) struct Libm {
+    pub(crate) pow: fn(f64, f64) -> f64,
+    pub(crate) sin: fn(f64) -> f64,
+    pub(crate) cos: fn(f64) -> f64,
+    pub(crate) tan: fn(f64) -> f64,
+    pub(crate) asin: fn(f64) -> f64,
+    pub(crate) acos: fn(f64) -> f64,
+    pub(crate) atan: fn(f64) -> f64,
+    pub(crate) atan2: fn(f64, f64) -> f64,
+    pub(crate) sinh: fn(f64) -> f64,
+    pub(crate) cosh: fn(f64) -> f64,
+    pub(crate) tanh: fn(f64) -> f64,
+    pub(crate) asinh: fn(f64) -> f64,
+    pub(crate) acosh: fn(f64) -> f64,
+    pub(crate) atanh: fn(f64) -> f64,
+    pub(crate) gamma: fn(f64) -> f64,
+    pub(crate) lgamma: fn(f64) -> f64,
+    pub(crate) erf: fn(f64) -> f64,
+    pub(crate) erfc: fn(f64) -> f64,
+}
+
+pub(crate) const SYSTEM: Libm = Libm {
+    pow,
+    sin,
+    cos,
+    tan,
+    asin,
+    acos,
+    atan,
+    atan2,
+    sinh,
+    cosh,
+    tanh,
+    asinh,
+    acosh,
+    atanh,
+    gamma,
+    lgamma,
+    erf,
+    erfc,
+};
@@ -0,0 +1,180 @@
+use crate::error::{Error, Span};
+
+/// Recebe cada nó de uma expressão enquanto ela é calculada por
+/// `Expression::evaluate_observed`, para depuradores, profilers ou registros de como uma
+/// fórmula chegou ao resultado. Os dois métodos não fazem nada por padrão, e os cálculos sem
+/// observador não passam por eles.
+///
+/// Os nós chegam na ordem do cálculo: `on_node_enter` antes dos operandos e `on_node_result`
+/// depois, então a profundidade de cada nó é quantos `on_node_enter` ainda não tiveram o seu
+/// `on_node_result`. Os nós que não são calculados (o lado não escolhido de um `if`, o direito
+/// de um `and` falso) não aparecem, e os do corpo de uma função do usuário aparecem dentro da
+/// chamada, com os parâmetros como `EvalNode::Parameter`.
+///
+/// ```
+/// use asdf_calc::{Error, EvalContext, EvalNode, EvalObserver, Expression};
+///
+/// #[derive(Default)]
+/// struct Trace(Vec<String>);
+///
+/// impl EvalObserver for Trace {
+///     fn on_node_result(&mut self, node: EvalNode<'_>, result: &Result<f64, Error>) {
+///         if let (EvalNode::Operation(symbol), Ok(value)) = (node, result) {
+///             self.0.push(format!("{} = {}", symbol, value));
+///         }
+///     }
+/// }
+///
+/// let expression = Expression::new("(1 + 2) * 4").unwrap();
+/// let mut trace = Trace::default();
+/// let result = expression.evaluate_observed(&EvalContext::new(), &mut trace);
+/// assert_eq!(result, Ok(12.0));
+/// assert_eq!(trace.0, ["+ = 3", "* = 12"]);
+/// ```
+pub trait EvalObserver {
+    fn on_node_enter(&mut self, node: EvalNode<'_>) {
+        let _ = node;
+    }
+
+    fn on_node_result(&mut self, node: EvalNode<'_>, result: &Result<f64, Error>) {
+        let _ = (node, result);
+    }
+}
+
+/// Nó sendo calculado, como o `EvalObserver` o vê.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EvalNode<'a> {
+    /// Número escrito no input, ou já convertido pelo parser (como um ângulo em graus).
+    Number(f64),
+    /// Variável ou constante, pelo nome.
+    Variable(&'a str),
+    /// Unidade, pelo nome (`km`).
+    Unit(&'a str),
+    /// Parâmetro da função do usuário sendo calculada, pela posição.
+    Parameter(usize),
+    /// Chamada de função do usuário, com o trecho do input em que ela foi escrita.
+    Call(Span),
+    /// Chamada de função embutida, pelo nome.
+    Builtin(&'static str),
+    If,
+    Negate,
+    Not,
+    /// Operação com dois operandos, pelo símbolo (`+`, `<=`, `and`...).
+    Operation(&'static str),
+    /// Comparações encadeadas (`1 < x <= 10`).
+    Comparisons,
+}
+
+/// Observador dos cálculos comuns, que o compilador elimina por completo.
+pub(crate) struct Unobserved;
+
+impl EvalObserver for Unobserved {}
+
+#[cfg(test)]
+#[derive(Default)]
+struct Recorder(alloc::vec::Vec<alloc::string::String>);
+
+#[cfg(test)]
+impl EvalObserver for Recorder {
+    fn on_node_enter(&mut self, node: EvalNode<'_>) {
+        self.0.push(alloc::format!("> {:?}", node));
+    }
+
+    fn on_node_result(&mut self, node: EvalNode<'_>, result: &Result<f64, Error>) {
+        let result = match result {
+            Ok(value) => alloc::format!("{}", value),
+            Err(error) => alloc::format!("{}", error),
+        };
+        self.0.push(alloc::format!("< {:?} = {}", node, result));
+    }
+}
+
+#[test]
+fn observer_should_see_every_evaluated_node_in_order() {
+    use crate::{EvalContext, Expression, Function};
+
+    let mut context = EvalContext::new();
+    context.set_variable("x", 2.0);
+    let double = Function::new(vec!["n".into()], "n * 2", &context).unwrap();
+    context.define_function("double", double);
+
+    let expression = Expression::with_context("if(x > 1, double(x), y)", &{
+        let mut context = context.clone();
+        context.set_variable("y", 0.0);
+        context
+    })
+    .unwrap();
+    let mut recorder = Recorder::default();
+    assert_eq!(
+        expression.evaluate_observed(&context, &mut recorder),
+        Ok(4.0)
+    );
+    assert_eq!(
+        recorder.0,
+        [
+            "> If",
+            "> Operation(\">\")",
+            "> Variable(\"x\")",
+            "< Variable(\"x\") = 2",
+            "> Number(1.0)",
+            "< Number(1.0) = 1",
+            "< Operation(\">\") = 1",
+            "> Call(Span { start: 10, end: 16 })",
+            "> Variable(\"x\")",
+            "< Variable(\"x\") = 2",
+            "> Operation(\"*\")",
+            "> Parameter(0)",
+            "< Parameter(0) = 2",
+            "> Number(2.0)",
+            "< Number(2.0) = 2",
+            "< Operation(\"*\") = 4",
+            "< Call(Span { start: 10, end: 16 }) = 4",
+            "< If = 4",
+        ]
+    );
+
+    // as operações encadeadas são calculadas num loop, mas aparecem como se fossem recursivas
+    let expression = Expression::with_context("1 - x + 3", &context).unwrap();
+    let mut recorder = Recorder::default();
+    assert_eq!(
+        expression.evaluate_observed(&context, &mut recorder),
+        Ok(2.0)
+    );
+    assert_eq!(
+        recorder.0,
+        [
+            "> Operation(\"+\")",
+            "> Operation(\"-\")",
+            "> Number(1.0)",
+            "< Number(1.0) = 1",
+            "> Variable(\"x\")",
+            "< Variable(\"x\") = 2",
+            "< Operation(\"-\") = -1",
+            "> Number(3.0)",
+            "< Number(3.0) = 3",
+            "< Operation(\"+\") = 2",
+        ]
+    );
+    let expression = Expression::with_context("1 + unsigned(x / 3) + 2", &context).unwrap();
+    let mut recorder = Recorder::default();
+    assert!(expression
+        .evaluate_observed(&context, &mut recorder)
+        .is_err());
+    assert_eq!(
+        recorder.0[recorder.0.len() - 2..],
+        [
+            "< Operation(\"+\") = coluna 5: argumentos fora do domínio da função 'unsigned'",
+            "< Operation(\"+\") = coluna 5: argumentos fora do domínio da função 'unsigned'",
+        ]
+    );
+
+    let expression = Expression::with_context("sign(unsigned(x / 3))", &context).unwrap();
+    let mut recorder = Recorder::default();
+    assert!(expression
+        .evaluate_observed(&context, &mut recorder)
+        .is_err());
+    assert_eq!(
+        recorder.0.last().map(String::as_str),
+        Some("< Builtin(\"sign\") = coluna 6: argumentos fora do domínio da função 'unsigned'")
+    );
+}
@@ -0,0 +1,215 @@
+//! Modo de uma linha só (`--one-shot`), pensado para lançadores como rofi, dmenu e Alfred: lê
+//! uma expressão do stdin, escreve apenas o resultado e sai. O `--raw` faz o mesmo para scripts
+//! de shell, e o `--check` só confere se a expressão é válida.
+
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+use crate::{
+    diagnostic::{diagnostic_json, format_errors, ErrorFormat},
+    json::Json,
+    session::Session,
+};
+
+/// Programas tentados, em ordem, para colocar o resultado na área de transferência.
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    &["pbcopy"],
+    &["clip"],
+];
+
+/// Coloca `text` na área de transferência usando o primeiro programa disponível.
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    for command in CLIPBOARD_COMMANDS {
+        let child = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = child else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "nenhum programa de área de transferência encontrado (wl-copy, xclip, xsel, pbcopy ou clip)",
+    ))
+}
+
+/// Calcula `line`, retornando o texto exato a ser escrito, na unidade do resultado ou na do
+/// `to` como na calculadora interativa, ou os erros no formato `format`.
+pub fn evaluate_line(
+    line: &str,
+    session: &mut Session,
+    format: ErrorFormat,
+) -> Result<String, String> {
+    let line = line.trim();
+    session
+        .eval(line)
+        .map(|result| session.format_result(result))
+        .map_err(|errors| format_errors(&errors, line, format, &session.context))
+}
+
+/// Retorna o código de saída do processo.
+pub fn run(copy: bool, format: ErrorFormat, mut session: Session) -> i32 {
+    let mut line = String::new();
+    if let Err(error) = io::stdin().read_line(&mut line) {
+        eprintln!("não foi possível ler input: {}", error);
+        return 1;
+    }
+
+    match evaluate_line(&line, &mut session, format) {
+        Ok(result) => {
+            println!("{}", result);
+            if copy {
+                if let Err(error) = copy_to_clipboard(&result) {
+                    eprintln!("{}", error);
+                    return 1;
+                }
+            }
+            0
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            1
+        }
+    }
+}
+
+/// `expression`, ou uma linha do stdin se ela estiver vazia.
+fn input_line(expression: &str) -> Option<String> {
+    let mut line = expression.to_string();
+    if line.is_empty() {
+        if let Err(error) = io::stdin().read_line(&mut line) {
+            eprintln!("não foi possível ler input: {}", error);
+            return None;
+        }
+    }
+    Some(line)
+}
+
+/// `--raw`: como o `--one-shot`, mas a expressão pode vir dos argumentos e a quebra de linha no
+/// fim é opcional. Retorna o código de saída do processo.
+pub fn run_raw(expression: &str, newline: bool, format: ErrorFormat, mut session: Session) -> i32 {
+    let Some(line) = input_line(expression) else {
+        return 1;
+    };
+
+    match evaluate_line(&line, &mut session, format) {
+        Ok(result) => {
+            let mut stdout = io::stdout().lock();
+            let written = if newline {
+                writeln!(stdout, "{}", result)
+            } else {
+                write!(stdout, "{}", result)
+            };
+            match written.and_then(|()| stdout.flush()) {
+                Ok(()) => 0,
+                Err(error) => {
+                    eprintln!("não foi possível escrever o resultado: {}", error);
+                    1
+                }
+            }
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            1
+        }
+    }
+}
+
+/// Resultado do `--check`: `{"valid": true}`, ou `{"valid": false, "errors": [...]}` com os
+/// erros como os do `--error-format json` em qualquer formato, que só muda as mensagens.
+pub fn check_json(line: &str, session: &Session, format: ErrorFormat) -> Json {
+    let line = line.trim();
+    match session.check(line) {
+        Ok(()) => Json::object([("valid", Json::Bool(true))]),
+        Err(errors) => Json::object([
+            ("valid", Json::Bool(false)),
+            (
+                "errors",
+                Json::Array(
+                    errors
+                        .iter()
+                        .map(|error| diagnostic_json(error, line, format, &session.context))
+                        .collect(),
+                ),
+            ),
+        ]),
+    }
+}
+
+/// `--check`: lê a expressão sem calcular, escrevendo o resultado de `check_json`. Retorna o
+/// código de saída do processo, 1 se a expressão tiver erros.
+pub fn run_check(expression: &str, format: ErrorFormat, session: &Session) -> i32 {
+    let Some(line) = input_line(expression) else {
+        return 1;
+    };
+    let json = check_json(&line, session, format);
+    println!("{}", json);
+    if json.get("valid") == Some(&Json::Bool(true)) {
+        0
+    } else {
+        1
+    }
+}
+
+#[test]
+fn evaluate_line_should_return_only_the_result() {
+    let mut session = Session::default();
+    assert_eq!(
+        evaluate_line("2 + 2\n", &mut session, ErrorFormat::Human),
+        Ok("4".to_string())
+    );
+    assert_eq!(
+        evaluate_line("1 / 8", &mut session, ErrorFormat::Human),
+        Ok("0.125".to_string())
+    );
+    assert_eq!(
+        evaluate_line("5 km to mi", &mut session, ErrorFormat::Human),
+        Ok("3.1068559611866697 mi".to_string())
+    );
+    assert_eq!(
+        evaluate_line("2 km + 500 m", &mut session, ErrorFormat::Human),
+        Ok("2500 m".to_string())
+    );
+    assert!(evaluate_line("2 +", &mut session, ErrorFormat::Human).is_err());
+    assert_eq!(
+        evaluate_line(" 2 +", &mut session, ErrorFormat::Json),
+        Err(r#"{"kind":"trailing_operator","message":"síntaxe incorreta, operador sem um número à sua direita","span":{"start":2,"end":3},"suggestion":"complete a operação com um número ou remova o operador"}"#.to_string())
+    );
+}
+
+#[test]
+fn check_json_should_describe_the_errors() {
+    let session = Session::default();
+    assert_eq!(
+        check_json("2 * 3\n", &session, ErrorFormat::Human).to_string(),
+        r#"{"valid":true}"#
+    );
+    assert_eq!(
+        check_json("2 *", &session, ErrorFormat::Human).to_string(),
+        r#"{"valid":false,"errors":[{"kind":"trailing_operator","message":"coluna 3: síntaxe incorreta, operador sem um número à sua direita","span":{"start":2,"end":3},"suggestion":"complete a operação com um número ou remova o operador"}]}"#
+    );
+    assert_eq!(
+        check_json("2 *", &session, ErrorFormat::Json).to_string(),
+        r#"{"valid":false,"errors":[{"kind":"trailing_operator","message":"síntaxe incorreta, operador sem um número à sua direita","span":{"start":2,"end":3},"suggestion":"complete a operação com um número ou remova o operador"}]}"#
+    );
+    for line in ["1 m + 1 s", "1 m to s"] {
+        let json = check_json(line, &session, ErrorFormat::Json);
+        assert_eq!(json.get("valid"), Some(&Json::Bool(false)), "{}", line);
+    }
+}
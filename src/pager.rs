@@ -0,0 +1,200 @@
+//! Paginação da saída da calculadora interativa que não cabe no terminal (`grid`, `errors`,
+//! blocos do `:paste`...), pelo programa de `$PAGER` ou, sem ele, por um paginador simples que
+//! mostra uma tela por vez. `pager off` desliga a paginação.
+
+use std::{
+    env,
+    io::{self, BufRead, IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PagerMode {
+    /// Pagina só quando a saída tem mais linhas que o terminal.
+    #[default]
+    Auto,
+    Off,
+}
+
+impl PagerMode {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.trim() {
+            "auto" | "on" => Ok(PagerMode::Auto),
+            "off" => Ok(PagerMode::Off),
+            other => Err(format!(
+                "opção desconhecida para 'pager': {} (use auto ou off)",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for PagerMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PagerMode::Auto => write!(f, "auto"),
+            PagerMode::Off => write!(f, "off"),
+        }
+    }
+}
+
+/// Linhas do terminal do stdout, ou `None` se o stdout não for um terminal. O tamanho é lido a
+/// cada chamada, então acompanha o terminal quando ele é redimensionado.
+pub fn terminal_height() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    window_size()
+        .map(|(rows, _)| rows)
+        .or_else(|| env::var("LINES").ok()?.parse().ok())
+}
+
+/// Colunas do terminal do stdout, como em `terminal_height`.
+pub fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    window_size()
+        .map(|(_, columns)| columns)
+        .or_else(|| env::var("COLUMNS").ok()?.parse().ok())
+}
+
+/// Linhas e colunas da janela do terminal.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+fn window_size() -> Option<(usize, usize)> {
+    #[repr(C)]
+    struct WinSize {
+        rows: u16,
+        columns: u16,
+        x_pixels: u16,
+        y_pixels: u16,
+    }
+    #[cfg(target_os = "macos")]
+    const TIOCGWINSZ: std::os::raw::c_ulong = 0x40087468;
+    #[cfg(not(target_os = "macos"))]
+    const TIOCGWINSZ: std::os::raw::c_ulong = 0x5413;
+    extern "C" {
+        fn ioctl(fd: std::os::raw::c_int, request: std::os::raw::c_ulong, ...) -> i32;
+    }
+
+    let mut size = WinSize {
+        rows: 0,
+        columns: 0,
+        x_pixels: 0,
+        y_pixels: 0,
+    };
+    // SAFETY: `TIOCGWINSZ` só escreve um `winsize` no ponteiro passado
+    let result = unsafe { ioctl(1, TIOCGWINSZ, &mut size as *mut WinSize) };
+    (result == 0 && size.rows > 0 && size.columns > 0)
+        .then_some((size.rows as usize, size.columns as usize))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+fn window_size() -> Option<(usize, usize)> {
+    None
+}
+
+/// Escreve `text` no stdout, paginando se ele não couber no terminal.
+pub fn write(text: &str, mode: PagerMode) -> io::Result<()> {
+    let height = match (mode, terminal_height()) {
+        (PagerMode::Auto, Some(height)) if screen_rows(text, terminal_width()) >= height => height,
+        _ => return io::stdout().write_all(text.as_bytes()),
+    };
+
+    if let Some(pager) = env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+    {
+        let mut words = pager.split_whitespace();
+        let program = words.next().unwrap_or_default();
+        if let Ok(mut child) = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .spawn()
+        {
+            if let Some(mut stdin) = child.stdin.take() {
+                // o paginador pode ser fechado antes de ler tudo
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            child.wait()?;
+            return Ok(());
+        }
+    }
+
+    page(
+        text,
+        height,
+        &mut io::stdin().lock(),
+        &mut io::stdout().lock(),
+    )
+}
+
+/// Quantas linhas do terminal `text` ocupa, contando as que passam de `width` colunas como
+/// quebradas pelo terminal.
+fn screen_rows(text: &str, width: Option<usize>) -> usize {
+    text.lines()
+        .map(|line| match width {
+            Some(width) if width > 0 => line.chars().count().div_ceil(width).max(1),
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Paginador embutido: mostra `height - 1` linhas por vez, esperando um Enter para continuar ou
+/// um `q` para parar.
+fn page(
+    text: &str,
+    height: usize,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let lines: Vec<&str> = text.lines().collect();
+    let page_size = height.saturating_sub(1).max(1);
+    for (i, page) in lines.chunks(page_size).enumerate() {
+        for line in page {
+            writeln!(output, "{}", line)?;
+        }
+        if (i + 1) * page_size >= lines.len() {
+            break;
+        }
+
+        write!(output, "-- mais (Enter continua, q para) --")?;
+        output.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 || answer.trim().eq_ignore_ascii_case("q") {
+            writeln!(output)?;
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn pager_should_show_one_screen_at_a_time() {
+    let text = "1\n2\n3\n4\n5\n";
+    let mut output = Vec::new();
+    page(text, 3, &mut "\n\n".as_bytes(), &mut output).unwrap();
+    let prompt = "-- mais (Enter continua, q para) --";
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        format!("1\n2\n{0}3\n4\n{0}5\n", prompt)
+    );
+
+    let mut output = Vec::new();
+    page(text, 3, &mut "q\n".as_bytes(), &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        format!("1\n2\n{}\n", prompt)
+    );
+
+    assert_eq!(PagerMode::parse(" off "), Ok(PagerMode::Off));
+    assert!(PagerMode::parse("less").is_err());
+}
+
+#[test]
+fn screen_rows_should_count_wrapped_lines() {
+    let text = format!("1\n{}\n\n{}\n", "x".repeat(80), "x".repeat(81));
+    assert_eq!(screen_rows(&text, Some(80)), 5);
+    assert_eq!(screen_rows(&text, Some(40)), 7);
+    assert_eq!(screen_rows(&text, None), 4);
+}
@@ -0,0 +1,576 @@
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::{
+    builtins::{builtin, Arity},
+    context::EvalContext,
+    error::{Error, Limit, Span},
+    expression::{Arena, Node, NodeId, OperationKind},
+    lexer::{Token, TokenKind},
+};
+
+#[cfg(test)]
+use crate::lexer::tokenize;
+
+/// Verifica onde os operadores aparecem em relação aos números e parênteses, rejeitando
+/// operadores no fim de um trecho (`3 +`), operadores consecutivos (`3 ++ 4`) e parênteses vazios
+/// (`()`, a não ser numa chamada de função). O único operador aceito logo após outro é o `-`
+/// unário (`3 * -2`), e no começo da expressão, de um parênteses ou de um argumento também é
+/// aceito o `+` unário.
+pub(crate) fn validate(tokens: &[Token], end: usize) -> Vec<Error> {
+    let mut errors = Vec::new();
+
+    if tokens.is_empty() {
+        errors.push(Error::MissingOperand(Span::new(end, end + 1)));
+        return errors;
+    }
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        match token.kind {
+            TokenKind::Operator(kind) => {
+                let expects_operand = i == 0
+                    || matches!(
+                        tokens[i - 1].kind,
+                        TokenKind::OpenParenthesis | TokenKind::Comma | TokenKind::Not
+                    );
+                if expects_operand && kind != OperationKind::Add && kind != OperationKind::Subtract
+                {
+                    errors.push(Error::MissingOperand(token.span));
+                }
+
+                let mut run_end = i + 1;
+                while let Some(Token {
+                    kind: TokenKind::Operator(_),
+                    ..
+                }) = tokens.get(run_end)
+                {
+                    run_end += 1;
+                }
+
+                let last = &tokens[run_end - 1];
+                let only_unary_minus_after = tokens[i + 1..run_end]
+                    .iter()
+                    .all(|token| token.kind == TokenKind::Operator(OperationKind::Subtract));
+                if !only_unary_minus_after {
+                    errors.push(Error::ConsecutiveOperators(Span::new(
+                        token.span.start,
+                        last.span.end,
+                    )));
+                }
+
+                match tokens.get(run_end).map(|token| &token.kind) {
+                    None | Some(TokenKind::CloseParenthesis) | Some(TokenKind::Comma) => {
+                        errors.push(Error::TrailingOperator(last.span));
+                    }
+                    _ => {}
+                }
+
+                i = run_end;
+                continue;
+            }
+            TokenKind::OpenParenthesis => {
+                let is_call = i > 0 && matches!(tokens[i - 1].kind, TokenKind::Identifier(_));
+                if let Some(next) = tokens.get(i + 1) {
+                    if next.kind == TokenKind::CloseParenthesis && !is_call {
+                        errors.push(Error::EmptyParenthesis(Span::new(
+                            token.span.start,
+                            next.span.end,
+                        )));
+                    }
+                }
+            }
+            TokenKind::Comma => {
+                let after_operand = i > 0
+                    && !matches!(
+                        tokens[i - 1].kind,
+                        TokenKind::OpenParenthesis | TokenKind::Comma
+                    );
+                if !after_operand {
+                    errors.push(Error::MissingOperand(token.span));
+                }
+                match tokens.get(i + 1) {
+                    None => errors.push(Error::MissingOperand(Span::new(end, end + 1))),
+                    Some(next) if next.kind == TokenKind::CloseParenthesis => {
+                        errors.push(Error::MissingOperand(next.span));
+                    }
+                    _ => {}
+                }
+            }
+            TokenKind::Not => {
+                if matches!(
+                    tokens.get(i + 1).map(|token| &token.kind),
+                    None | Some(TokenKind::CloseParenthesis) | Some(TokenKind::Comma)
+                ) {
+                    errors.push(Error::TrailingOperator(token.span));
+                }
+            }
+            // atribuições válidas já foram separadas por `Statement::new`
+            TokenKind::Assign => errors.push(Error::InvalidAssignment(token.span)),
+            _ => {}
+        }
+        i += 1;
+    }
+
+    errors
+}
+
+#[test]
+fn validate_should_reject_trailing_operators() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("3 +", &mut errors);
+    assert_eq!(
+        validate(&tokens, 3),
+        vec![Error::TrailingOperator(Span::new(2, 3))]
+    );
+
+    let tokens = tokenize("(3 *) + 1", &mut errors);
+    assert_eq!(
+        validate(&tokens, 9),
+        vec![Error::TrailingOperator(Span::new(3, 4))]
+    );
+}
+
+#[test]
+fn validate_should_reject_consecutive_operators() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("3 ++ 4 */ 2", &mut errors);
+    assert_eq!(
+        validate(&tokens, 11),
+        vec![
+            Error::ConsecutiveOperators(Span::new(2, 4)),
+            Error::ConsecutiveOperators(Span::new(7, 9)),
+        ]
+    );
+}
+
+#[test]
+fn validate_should_accept_unary_signs() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("-3 * -2 - -(+1)", &mut errors);
+    assert_eq!(validate(&tokens, 15), vec![]);
+}
+
+#[test]
+fn validate_should_reject_empty_parenthesis() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("2 * ( )", &mut errors);
+    assert_eq!(
+        validate(&tokens, 7),
+        vec![Error::EmptyParenthesis(Span::new(4, 7))]
+    );
+}
+
+#[test]
+fn validate_should_reject_leading_binary_operators() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("* 2", &mut errors);
+    assert_eq!(
+        validate(&tokens, 3),
+        vec![Error::MissingOperand(Span::new(0, 1))]
+    );
+}
+
+#[test]
+fn validate_should_accept_function_calls() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("f() + g(1, -2, (3))", &mut errors);
+    assert_eq!(validate(&tokens, 19), vec![]);
+}
+
+#[test]
+fn validate_should_reject_missing_arguments() {
+    let mut errors = Vec::new();
+    let tokens = tokenize("f(, 2 *, )", &mut errors);
+    assert_eq!(
+        validate(&tokens, 10),
+        vec![
+            Error::MissingOperand(Span::new(2, 3)),
+            Error::TrailingOperator(Span::new(6, 7)),
+            Error::MissingOperand(Span::new(9, 10)),
+        ]
+    );
+}
+
+/// Onde termina o trecho lido por `Parser::parse_sequence`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Delimiter {
+    /// Fim do input.
+    End,
+    /// `)` que fecha um parênteses.
+    Parenthesis,
+    /// `,` ou `)` depois de um argumento de uma chamada de função.
+    Argument,
+}
+
+/// Analisador sintático descendente recursivo que, ao encontrar um erro, registra o problema e
+/// segue a partir do próximo operador, para que todos os erros de uma linha sejam mostrados de
+/// uma só vez.
+pub(crate) struct Parser<'a> {
+    /// Onde os nós lidos são guardados, e que vira o da expressão no fim da leitura.
+    pub(crate) arena: Arena,
+    pub(crate) tokens: Vec<Token>,
+    pub(crate) position: usize,
+    /// Posição logo após o último caractere do input, usada nos erros de fim de linha.
+    pub(crate) end: usize,
+    pub(crate) errors: Vec<Error>,
+    /// Usado para saber quais nomes e funções existem.
+    pub(crate) context: &'a EvalContext,
+    /// Parâmetros da função cujo corpo está sendo lido, que escondem as variáveis de mesmo nome.
+    pub(crate) parameters: &'a [String],
+    /// Níveis de `parse_unary` em andamento, por onde passam todas as recursões.
+    pub(crate) depth: usize,
+    /// Ligado quando o limite de aninhamento é excedido. O resto do input é descartado, e os
+    /// erros que isso causaria deixam de ser registrados.
+    pub(crate) aborted: bool,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        if token.is_some() {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn error(&mut self, error: Error) {
+        if !self.aborted {
+            self.errors.push(error);
+        }
+    }
+
+    /// Diz se `depth` níveis ainda cabem no limite, e caso não caibam registra o erro e
+    /// descarta o resto do input.
+    fn check_nesting(&mut self, depth: usize) -> bool {
+        let max_nesting = self.context.limits().max_nesting;
+        if depth <= max_nesting {
+            return true;
+        }
+
+        let span = self.current_span();
+        self.error(Error::LimitExceeded(Limit::Nesting(max_nesting), span));
+        self.aborted = true;
+        self.position = self.tokens.len();
+        false
+    }
+
+    fn current_span(&self) -> Span {
+        self.peek()
+            .map(|token| token.span)
+            .unwrap_or(Span::new(self.end, self.end + 1))
+    }
+
+    /// Lê uma expressão completa, e continua lendo o que sobrar até o `delimiter`, registrando o
+    /// que estiver sobrando.
+    pub(crate) fn parse_sequence(&mut self, delimiter: Delimiter) -> NodeId {
+        let root = self.parse_binary(0);
+
+        while let Some(token) = self.peek() {
+            let span = token.span;
+            match token.kind {
+                TokenKind::CloseParenthesis if delimiter != Delimiter::End => break,
+                TokenKind::Comma if delimiter == Delimiter::Argument => break,
+                TokenKind::Comma => {
+                    self.error(Error::UnexpectedCharacter(',', span));
+                    self.advance();
+                    self.parse_binary(0);
+                }
+                TokenKind::CloseParenthesis => {
+                    self.error(Error::ExtraParenthesis(span));
+                    self.advance();
+                    // o resultado é descartado por causa do erro, e continuar a partir de
+                    // `root` deixaria `1) + 1) + ...` crescer sem passar pelo limite
+                    let nan = self.nan();
+                    self.continue_binary(nan, 0);
+                }
+                _ => {
+                    self.error(Error::MissingOperator(span));
+                    self.parse_binary(0);
+                }
+            }
+        }
+
+        root
+    }
+
+    fn parse_binary(&mut self, min_precedence: u8) -> NodeId {
+        let lhs = self.parse_unary();
+        self.continue_binary(lhs, min_precedence)
+    }
+
+    fn continue_binary(&mut self, mut lhs: NodeId, min_precedence: u8) -> NodeId {
+        // se `lhs` é uma comparação lida neste loop, à qual as próximas devem ser juntadas
+        let mut comparing = false;
+        loop {
+            match self.peek().map(|token| &token.kind) {
+                Some(TokenKind::Operator(kind)) => {
+                    let kind = *kind;
+                    if kind.precedence() < min_precedence {
+                        break;
+                    }
+                    self.advance();
+
+                    // as operações encadeadas pela esquerda ficam no mesmo nível, mas cada
+                    // potência de `2 ^ 2 ^ ...` fica dentro da anterior
+                    let rhs = if kind.is_right_associative() {
+                        if !self.check_nesting(self.depth + 1) {
+                            break;
+                        }
+                        self.depth += 1;
+                        let rhs = self.parse_binary(kind.precedence());
+                        self.depth -= 1;
+                        rhs
+                    } else {
+                        self.parse_binary(kind.precedence() + 1)
+                    };
+                    // a comparação anterior é trocada no lugar, sem deixar nós soltos no arena
+                    match &mut self.arena[lhs] {
+                        Node::Operation(previous, first, middle)
+                            if comparing && kind.is_comparison() =>
+                        {
+                            self.arena[lhs] =
+                                Node::Comparisons(*first, vec![(*previous, *middle), (kind, rhs)]);
+                        }
+                        Node::Comparisons(_, comparisons) if comparing && kind.is_comparison() => {
+                            comparisons.push((kind, rhs));
+                        }
+                        _ => lhs = self.arena.push(Node::Operation(kind, lhs, rhs)),
+                    }
+                    comparing = kind.is_comparison();
+                }
+                Some(TokenKind::Unexpected) | Some(TokenKind::Assign) => {
+                    self.advance();
+                    self.parse_unary();
+                    lhs = self.nan();
+                }
+                _ => break,
+            }
+        }
+
+        lhs
+    }
+
+    fn parse_unary(&mut self) -> NodeId {
+        if !self.check_nesting(self.depth + 1) {
+            return self.nan();
+        }
+        self.depth += 1;
+
+        // sinais de `+` repetidos já foram rejeitados por `validate`, mas são pulados sem
+        // recursão para que uma sequência enorme deles não estoure a pilha
+        while let Some(TokenKind::Operator(OperationKind::Add)) =
+            self.peek().map(|token| &token.kind)
+        {
+            self.advance();
+        }
+
+        let node = match self.peek().map(|token| &token.kind) {
+            // `-2 ^ 2` é `-(2 ^ 2)`
+            Some(TokenKind::Operator(OperationKind::Subtract)) => {
+                self.advance();
+                let node = self.parse_binary(OperationKind::Power.precedence());
+                self.arena.push(Node::Negate(node))
+            }
+            // `not a == b` é `not (a == b)`, mas `not a and b` é `(not a) and b`
+            Some(TokenKind::Not) => {
+                self.advance();
+                let node = self.parse_binary(OperationKind::Equal.precedence());
+                self.arena.push(Node::Not(node))
+            }
+            _ => self.parse_primary(),
+        };
+        self.depth -= 1;
+        node
+    }
+
+    fn parse_primary(&mut self) -> NodeId {
+        let span = self.current_span();
+        let node = match self.peek().map(|token| token.kind.clone()) {
+            Some(TokenKind::Number(num, precision)) => {
+                self.advance();
+                let number = self.arena.push(Node::Literal(num, precision));
+                return match self.parse_unit() {
+                    Some(unit) => {
+                        self.arena
+                            .push(Node::Operation(OperationKind::Multiply, number, unit))
+                    }
+                    None => number,
+                };
+            }
+            Some(TokenKind::Degrees(degrees)) => {
+                self.advance();
+                Node::Number(self.context.angle_mode().from_degrees(degrees))
+            }
+            Some(TokenKind::Radians(radians)) => {
+                self.advance();
+                Node::Number(self.context.angle_mode().from_radians(radians))
+            }
+            Some(TokenKind::Identifier(name)) => {
+                self.advance();
+                if let Some(TokenKind::OpenParenthesis) = self.peek().map(|token| &token.kind) {
+                    return self.parse_call(name, span);
+                }
+
+                if let Some(index) = self
+                    .parameters
+                    .iter()
+                    .position(|parameter| *parameter == name)
+                {
+                    Node::Parameter(index)
+                } else if self.context.resolve(&name).is_some() {
+                    Node::Variable(name, span)
+                } else if let Some(unit) = self.context.unit(&name) {
+                    Node::Unit(name, unit, span)
+                } else {
+                    self.error(Error::UnknownIdentifier(name, span));
+                    Node::Number(f64::NAN)
+                }
+            }
+            Some(TokenKind::Invalid) => {
+                self.advance();
+                Node::Number(f64::NAN)
+            }
+            Some(TokenKind::Unexpected) | Some(TokenKind::Assign) => {
+                self.advance();
+                self.parse_unary();
+                Node::Number(f64::NAN)
+            }
+            Some(TokenKind::OpenParenthesis) => {
+                self.advance();
+                let node = self.parse_sequence(Delimiter::Parenthesis);
+                if let Some(TokenKind::CloseParenthesis) = self.peek().map(|token| &token.kind) {
+                    self.advance();
+                } else {
+                    self.error(Error::UnclosedParenthesis(span));
+                }
+                return node;
+            }
+            // a falta do número já foi registrada por `validate`, então o operador ou o `)` é
+            // só deixado para quem chamou, para que a leitura continue a partir dele (o `not`
+            // nunca chega aqui, já que é lido por `parse_unary`)
+            Some(TokenKind::Operator(_))
+            | Some(TokenKind::Not)
+            | Some(TokenKind::CloseParenthesis)
+            | Some(TokenKind::Comma)
+            | None => Node::Number(f64::NAN),
+        };
+        self.arena.push(node)
+    }
+
+    /// Lê a unidade escrita logo depois de um número (`5 km`), que multiplica o número e vale
+    /// mesmo que exista uma variável com o mesmo nome. Um `^` logo depois dela só se aplica a
+    /// ela, então `3 m^2` são três metros quadrados. No modo estrito, é um erro.
+    fn parse_unit(&mut self) -> Option<NodeId> {
+        let Some(Token {
+            kind: TokenKind::Identifier(name),
+            span,
+        }) = self.peek().cloned()
+        else {
+            return None;
+        };
+        if let Some(TokenKind::OpenParenthesis) =
+            self.tokens.get(self.position + 1).map(|token| &token.kind)
+        {
+            return None;
+        }
+        let unit = self.context.unit(&name)?;
+        self.advance();
+        if self.context.strict() {
+            self.error(Error::ImplicitMultiplication(span));
+        }
+
+        let node = self.arena.push(Node::Unit(name, unit, span));
+        if let Some(TokenKind::Operator(OperationKind::Power)) =
+            self.peek().map(|token| &token.kind)
+        {
+            self.advance();
+            let exponent = self.parse_unary();
+            return Some(
+                self.arena
+                    .push(Node::Operation(OperationKind::Power, node, exponent)),
+            );
+        }
+        Some(node)
+    }
+
+    /// Nó que fica no lugar de um trecho com erro, e que nunca chega a ser calculado.
+    fn nan(&mut self) -> NodeId {
+        self.arena.push(Node::Number(f64::NAN))
+    }
+
+    /// Lê os argumentos de uma chamada a `name`, começando pelo `(`.
+    fn parse_call(&mut self, name: String, span: Span) -> NodeId {
+        let open_span = self.current_span();
+        self.advance();
+
+        let mut arguments = Vec::new();
+        if !matches!(
+            self.peek().map(|token| &token.kind),
+            Some(TokenKind::CloseParenthesis)
+        ) {
+            loop {
+                arguments.push(self.parse_sequence(Delimiter::Argument));
+                if let Some(TokenKind::Comma) = self.peek().map(|token| &token.kind) {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+        }
+
+        if let Some(TokenKind::CloseParenthesis) = self.peek().map(|token| &token.kind) {
+            self.advance();
+        } else {
+            self.error(Error::UnclosedParenthesis(open_span));
+        }
+
+        let node = match self.context.shared_function(&name) {
+            Some(function) if function.parameters().len() == arguments.len() => {
+                Node::Call(function, self.arena.push_children(&arguments), span)
+            }
+            Some(function) => {
+                self.error(Error::WrongArgumentCount(
+                    name,
+                    function.parameters().len(),
+                    arguments.len(),
+                    span,
+                ));
+                Node::Number(f64::NAN)
+            }
+            // não pode ser uma função embutida comum, que calcularia todos os argumentos
+            None if name == "if" => match <[NodeId; 3]>::try_from(arguments) {
+                Ok(nodes) => Node::If(nodes),
+                Err(arguments) => {
+                    self.error(Error::WrongArgumentCount(name, 3, arguments.len(), span));
+                    Node::Number(f64::NAN)
+                }
+            },
+            None => match builtin(&name) {
+                Some(builtin) if builtin.arity().accepts(arguments.len()) => {
+                    Node::Builtin(builtin, self.arena.push_children(&arguments), span)
+                }
+                Some(builtin) => {
+                    self.error(match builtin.arity() {
+                        Arity::Exactly(expected) => {
+                            Error::WrongArgumentCount(name, expected, arguments.len(), span)
+                        }
+                        Arity::AtLeast(minimum) => {
+                            Error::TooFewArguments(name, minimum, arguments.len(), span)
+                        }
+                    });
+                    Node::Number(f64::NAN)
+                }
+                None => {
+                    self.error(Error::UnknownFunction(name, span));
+                    Node::Number(f64::NAN)
+                }
+            },
+        };
+        self.arena.push(node)
+    }
+}
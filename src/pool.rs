@@ -0,0 +1,89 @@
+//! Conjunto fixo de threads que atendem as conexões do `serve` e do `daemon`, para que muitas
+//! conexões ao mesmo tempo não criem uma thread para cada uma.
+
+use std::{
+    num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+pub struct ThreadPool {
+    sender: Option<mpsc::Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ThreadPool {
+    pub fn new(size: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..size.max(1))
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                thread::spawn(move || loop {
+                    // o lock é solto antes do trabalho começar, para que as outras threads
+                    // continuem pegando trabalhos
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        // um pânico ao atender uma conexão não pode deixar o pool com uma
+                        // thread a menos
+                        Ok(job) => {
+                            let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                        }
+                        Err(_) => break,
+                    }
+                })
+            })
+            .collect();
+        ThreadPool {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    /// Uma thread por processador, e pelo menos duas, para que uma conexão lenta não segure as
+    /// outras.
+    pub fn default_size() -> usize {
+        thread::available_parallelism()
+            .map_or(1, NonZeroUsize::get)
+            .max(2)
+    }
+
+    pub fn execute(&self, job: impl FnOnce() + Send + 'static) {
+        if let Some(sender) = &self.sender {
+            // as threads só param quando o pool é descartado, então sempre há quem receba
+            let _ = sender.send(Box::new(job));
+        }
+    }
+}
+
+/// Espera os trabalhos que já foram enviados terminarem.
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[test]
+fn thread_pool_should_run_every_job_before_being_dropped() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let threads = Arc::new(Mutex::new(std::collections::HashSet::new()));
+    let pool = ThreadPool::new(3);
+    for _ in 0..100 {
+        let (count, threads) = (Arc::clone(&count), Arc::clone(&threads));
+        pool.execute(move || {
+            count.fetch_add(1, Ordering::Relaxed);
+            threads.lock().unwrap().insert(thread::current().id());
+        });
+    }
+    drop(pool);
+    assert_eq!(count.load(Ordering::Relaxed), 100);
+    assert!(threads.lock().unwrap().len() <= 3);
+}
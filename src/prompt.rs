@@ -0,0 +1,89 @@
+//! Prompt da calculadora interativa, configurável com `prompt "<modelo>"` usando marcadores que
+//! são trocados pelo estado atual da sessão (`"[{angle}|{base}] {line}> "` vira `[deg|dec] 12> `).
+
+/// Prompt usado quando nenhum modelo foi configurado.
+pub const DEFAULT_PROMPT: &str = "> ";
+
+/// Marcadores aceitos no modelo: o formato dos resultados (`plain`, `sci`...), a base dos
+/// resultados (por enquanto sempre `dec`), o modo de ângulo (`deg` ou `rad`), o número da linha
+/// (começando em 1) e o workspace em uso (vazio se não houver um).
+pub const TOKENS: [&str; 5] = ["mode", "base", "angle", "line", "workspace"];
+
+/// Partes do modelo: texto que aparece como está, ou o nome de um marcador.
+#[derive(Debug, Clone, PartialEq)]
+enum Piece<'a> {
+    Text(&'a str),
+    Token(&'a str),
+}
+
+/// Separa o modelo em texto e marcadores. `{{` e `}}` escrevem as próprias chaves.
+fn pieces(template: &str) -> Result<Vec<Piece<'_>>, String> {
+    let mut pieces = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        pieces.push(Piece::Text(&rest[..start]));
+        let brace = &rest[start..start + 1];
+        if rest[start + 1..].starts_with(brace) {
+            pieces.push(Piece::Text(brace));
+            rest = &rest[start + 2..];
+            continue;
+        }
+        if brace == "}" {
+            return Err("'}' sem um '{' correspondente no prompt".to_string());
+        }
+
+        let length = rest[start + 1..]
+            .find('}')
+            .ok_or_else(|| "'{' sem um '}' correspondente no prompt".to_string())?;
+        let name = &rest[start + 1..start + 1 + length];
+        if !TOKENS.contains(&name) {
+            return Err(format!(
+                "marcador desconhecido '{{{}}}' (os marcadores são {})",
+                name,
+                TOKENS.join(", ")
+            ));
+        }
+        pieces.push(Piece::Token(name));
+        rest = &rest[start + 2 + length..];
+    }
+    pieces.push(Piece::Text(rest));
+    Ok(pieces)
+}
+
+pub fn validate(template: &str) -> Result<(), String> {
+    pieces(template).map(|_| ())
+}
+
+/// Monta o prompt trocando cada marcador pelo que `value` der para ele. O modelo já deve ter
+/// sido conferido por `validate`, e um modelo inválido é mostrado como está.
+pub fn render(template: &str, value: impl Fn(&str) -> String) -> String {
+    let Ok(pieces) = pieces(template) else {
+        return template.to_string();
+    };
+    pieces
+        .into_iter()
+        .map(|piece| match piece {
+            Piece::Text(text) => text.to_string(),
+            Piece::Token(name) => value(name),
+        })
+        .collect()
+}
+
+#[test]
+fn prompt_should_replace_the_tokens() {
+    let value = |name: &str| match name {
+        "angle" => "deg".to_string(),
+        "line" => "12".to_string(),
+        _ => "?".to_string(),
+    };
+    assert_eq!(render("[{angle}|{base}] {line}> ", value), "[deg|?] 12> ");
+    assert_eq!(render("{{{angle}}} ", value), "{deg} ");
+    assert_eq!(render("> ", value), "> ");
+
+    assert_eq!(validate("{line}> "), Ok(()));
+    assert!(validate("{hex}> ")
+        .unwrap_err()
+        .contains("marcador desconhecido '{hex}'"));
+    assert!(validate("{line> ").is_err());
+    assert!(validate("line}> ").is_err());
+}
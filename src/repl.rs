@@ -0,0 +1,544 @@
+//! Calculadora interativa, lendo uma linha por vez do terminal.
+
+use std::time::Duration;
+
+use asdf_calc::{Error, Span};
+
+use asdf_calc::AngleMode;
+
+use crate::{
+    editor::Editor,
+    grid, interrupt, pager, prompt,
+    session::{self, Session},
+    terminal,
+};
+
+/// Monta uma linha com um `^` embaixo de cada coluna com erro, para ser mostrada logo abaixo
+/// do input.
+fn error_markers(errors: &[Error], offset: usize) -> String {
+    markers(errors.iter().map(Error::span), offset)
+}
+
+/// `^` embaixo do começo de cada trecho, deslocados por `offset` colunas.
+fn markers(spans: impl IntoIterator<Item = Span>, offset: usize) -> String {
+    let mut markers = " ".repeat(offset);
+    for span in spans {
+        let column = offset + span.start;
+        let current_len = markers.chars().count();
+        if column >= current_len {
+            markers.push_str(&" ".repeat(column - current_len));
+            markers.push('^');
+        }
+    }
+    markers
+}
+
+/// O prompt da resposta ao "você quis dizer ...?".
+const CONFIRMATION: &str = "[s/N] ";
+
+/// O começo do `:help`, com os comandos da calculadora interativa. Os da sessão vêm de
+/// `session::COMMANDS`.
+const HELP: &str = "\
+comandos (os que não são exclusivos do ':' também valem sem ele, a não ser que exista uma
+variável com o mesmo nome):
+    :help                       mostra esta mensagem
+    :quit, :exit                sai da calculadora (como o Ctrl+D)
+    :clear                      limpa a tela
+    :grid                       as células (A1, B2...) numa tabela
+    :errors                     as últimas linhas com erro
+    :paste                      calcula as linhas coladas de uma vez, até ':end'
+    :time                       mostra quanto a leitura e o cálculo demoram";
+
+/// O `:help` inteiro.
+fn help() -> String {
+    let mut help = HELP.to_string();
+    for (usage, description) in session::COMMANDS {
+        // as contas da linha inteira não levam `:`
+        let usage = if usage.contains('(') {
+            usage.to_string()
+        } else {
+            format!(":{}", usage)
+        };
+        help.push_str(&format!("\n    {:<28}{}", usage, description));
+    }
+    help
+}
+
+/// Linha que termina o bloco do `:paste`.
+const PASTE_END: &str = ":end";
+
+pub struct Repl {
+    session: Session,
+    editor: Editor,
+    /// `:time`, mostra quanto a leitura e o cálculo demoraram depois de cada resultado.
+    show_timing: bool,
+    /// Linhas lidas até agora, para o `{line}` do prompt.
+    lines: usize,
+    /// Largura do último prompt mostrado, para que as marcações de erro fiquem embaixo do input.
+    prompt_width: usize,
+    /// Linhas coletadas desde o `:paste`, que só são calculadas quando o bloco termina.
+    paste: Option<String>,
+    /// A linha corrigida que foi oferecida com "você quis dizer ...?", calculada se a próxima
+    /// linha for um `s`.
+    correction: Option<String>,
+}
+
+impl Repl {
+    pub fn new(session: Session) -> Self {
+        Repl {
+            session,
+            editor: Editor::new(),
+            show_timing: false,
+            lines: 0,
+            prompt_width: prompt::DEFAULT_PROMPT.chars().count(),
+            paste: None,
+            correction: None,
+        }
+    }
+
+    fn prompt(&self) -> String {
+        prompt::render(self.session.prompt_template(), |token| match token {
+            "mode" => self.session.notation.to_string(),
+            "base" => self.session.base.to_string(),
+            "angle" => match self.session.context.angle_mode() {
+                AngleMode::Degrees => "deg".to_string(),
+                AngleMode::Radians => "rad".to_string(),
+            },
+            "line" => (self.lines + 1).to_string(),
+            "workspace" => self.session.workspace().unwrap_or_default().to_string(),
+            _ => String::new(),
+        })
+    }
+
+    pub fn run(&mut self) -> ! {
+        // o Ctrl+C só interrompe o cálculo em andamento, para sair existem o `exit` e o Ctrl+D
+        interrupt::interrupt_on_ctrl_c(self.session.context.interrupt_flag().clone());
+        loop {
+            self.step();
+        }
+    }
+
+    fn step(&mut self) {
+        // as linhas coladas aparecem sem prompt, como no terminal de onde vieram
+        let prompt = if self.paste.is_some() {
+            String::new()
+        } else if self.correction.is_some() {
+            CONFIRMATION.to_string()
+        } else {
+            self.prompt()
+        };
+        self.prompt_width = prompt.chars().count();
+
+        let input = if self.correction.is_some() {
+            self.editor.read_answer(&prompt, self.session.editor)
+        } else {
+            self.editor.read_line(&prompt, self.session.editor)
+        };
+        let input = input.expect("não foi possível ler input pelo terminal");
+        let Some(input) = input else {
+            // fim do input (ctrl+d ou fim do arquivo redirecionado), que também termina o bloco
+            // do `:paste`
+            if self.paste.is_some() {
+                let output = self.respond(PASTE_END);
+                let _ = pager::write(&output, self.session.pager);
+            }
+            std::process::exit(0);
+        };
+        self.lines += 1;
+
+        let output = self.respond(&input);
+        pager::write(&output, self.session.pager)
+            .expect("não foi possível escrever resultado no terminal");
+    }
+
+    /// Texto a ser mostrado depois de uma linha de input.
+    fn respond(&mut self, input: &str) -> String {
+        if let Some(correction) = self.correction.take() {
+            let answer = input.trim().to_lowercase();
+            if !["s", "sim", "y", "yes"].contains(&answer.as_str()) {
+                return String::new();
+            }
+            self.editor.remember(&correction);
+            // a linha corrigida é mostrada sozinha, e as marcações de erro ficam embaixo dela
+            self.prompt_width = 0;
+            let output = self.respond(&correction);
+            return format!("{}\n{}", correction, output);
+        }
+
+        let mut output = match self.paste.take() {
+            Some(mut block) if input.trim() != PASTE_END => {
+                block.push_str(input.trim_end_matches(['\r', '\n']));
+                block.push('\n');
+                self.paste = Some(block);
+                return String::new();
+            }
+            Some(block) => self.run_block(&block),
+            None => self.respond_line(input),
+        };
+        if let Err(message) = self.session.save_workspace() {
+            output.push_str(&format!("{}\n", message));
+        }
+        if let Some(message) = self.session.log_error.take() {
+            output.push_str(&format!("{}\n", message));
+        }
+        output
+    }
+
+    fn respond_line(&mut self, input: &str) -> String {
+        let input = &self.session.expand_aliases(input);
+        let leading_whitespace = input.chars().count() - input.trim_start().chars().count();
+        let line = input.trim().to_lowercase();
+        // os comandos sem o `:` só valem quando não são o nome de uma variável
+        let (command, prefixed) = match line.strip_prefix(':') {
+            Some(command) => (command.trim_start(), true),
+            None if self.session.is_calculation(&line) => {
+                return self.evaluate(input, leading_whitespace)
+            }
+            None => (line.as_str(), false),
+        };
+
+        match command {
+            "" => String::new(),
+            "clear" => terminal::clear_screen(),
+            "exit" => std::process::exit(0),
+            "quit" if prefixed => std::process::exit(0),
+            "help" if prefixed => format!("{}\n", help()),
+            "grid" => grid::render(&self.session.context),
+            "errors" => self.recent_errors(),
+            "paste" if prefixed => {
+                self.paste = Some(String::new());
+                format!(
+                    "modo paste: cole as linhas e termine com '{}' ou Ctrl+D\n",
+                    PASTE_END
+                )
+            }
+            "time" if prefixed => {
+                self.show_timing = !self.show_timing;
+                format!(
+                    "tempos {}\n",
+                    if self.show_timing {
+                        "ligados"
+                    } else {
+                        "desligados"
+                    }
+                )
+            }
+            _ => match self.session.command(input) {
+                Some(Ok(message) | Err(message)) => format!("{}\n", message),
+                None => self.evaluate(input, leading_whitespace),
+            },
+        }
+    }
+
+    /// Calcula o bloco do `:paste` em ordem, como um script: linhas vazias ou começando com `#`
+    /// são puladas, e os erros dizem a linha do bloco em que aconteceram.
+    fn run_block(&mut self, block: &str) -> String {
+        let mut output = String::new();
+        for (number, line) in block.lines().enumerate() {
+            let line = self.session.expand_aliases(line);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match self.session.command(line) {
+                Some(Ok(message)) => output.push_str(&format!("{}\n", message)),
+                Some(Err(message)) => {
+                    output.push_str(&format!("linha {}: {}\n", number + 1, message))
+                }
+                None => match self.session.eval(line) {
+                    Ok(result) => {
+                        let result = self.session.format_result(result);
+                        match &self.session.last_stored {
+                            Some(name) => output.push_str(&format!("{} = {}\n", name, result)),
+                            None => output.push_str(&format!("{}\n", result)),
+                        }
+                        for note in &self.session.last_notes {
+                            output.push_str(&format!("linha {}: {}\n", number + 1, note));
+                        }
+                    }
+                    Err(errors) => {
+                        for error in errors {
+                            output.push_str(&format!("linha {}: {}\n", number + 1, error));
+                        }
+                    }
+                },
+            }
+        }
+        output
+    }
+
+    /// `errors`, as últimas linhas com erro, cada uma com as marcações e mensagens que foram
+    /// mostradas quando ela foi calculada.
+    fn recent_errors(&self) -> String {
+        if self.session.recent_errors.is_empty() {
+            return "nenhum erro recente\n".to_string();
+        }
+
+        let mut output = String::new();
+        for (i, entry) in self.session.recent_errors.iter().enumerate() {
+            let prefix = format!("[{}] ", i + 1);
+            output.push_str(&format!("{}{}\n", prefix, entry.input));
+            output.push_str(&format!(
+                "{}\n",
+                error_markers(&entry.errors, prefix.chars().count())
+            ));
+            for error in &entry.errors {
+                output.push_str(&format!("{}{}\n", " ".repeat(prefix.len()), error));
+            }
+        }
+        output
+    }
+
+    fn evaluate(&mut self, input: &str, leading_whitespace: usize) -> String {
+        match self.session.eval(input.trim()) {
+            Ok(calculation_result) => {
+                let literal = self.session.result_literal(calculation_result);
+                self.editor.set_last_result(literal);
+                let result = self.session.format_result(calculation_result);
+                let mut output = match &self.session.last_stored {
+                    Some(name) => format!("{} = {}\n", name, result),
+                    None => format!("{}\n", result),
+                };
+                if !self.session.last_notes.is_empty() {
+                    let notes = &self.session.last_notes;
+                    output.push_str(&format!(
+                        "{}\n",
+                        markers(
+                            notes.iter().map(|note| note.span),
+                            self.prompt_width + leading_whitespace
+                        )
+                    ));
+                    for note in notes {
+                        output.push_str(&format!("{}\n", note));
+                    }
+                }
+                if let (true, Some(timing)) = (self.show_timing, self.session.last_timing) {
+                    output.push_str(&format!(
+                        "leitura: {}, cálculo: {}\n",
+                        format_duration(timing.parse),
+                        format_duration(timing.evaluation)
+                    ));
+                }
+                output
+            }
+            Err(errors) => {
+                let mut output = format!(
+                    "{}\n",
+                    error_markers(&errors, self.prompt_width + leading_whitespace)
+                );
+                for error in errors {
+                    output.push_str(&format!("{}\n", error));
+                }
+                if let Some(correction) = self.session.correction(input.trim()) {
+                    output.push_str(&format!("você quis dizer `{}`?\n", correction));
+                    self.correction = Some(correction);
+                }
+                output
+            }
+        }
+    }
+}
+
+/// Mostra uma duração com no máximo três casas decimais na unidade mais adequada.
+pub fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos() as f64;
+    if nanos < 1e3 {
+        format!("{}ns", nanos)
+    } else if nanos < 1e6 {
+        format!("{:.3}µs", nanos / 1e3)
+    } else if nanos < 1e9 {
+        format!("{:.3}ms", nanos / 1e6)
+    } else {
+        format!("{:.3}s", nanos / 1e9)
+    }
+}
+
+#[test]
+fn repl_should_toggle_timing() {
+    let mut repl = Repl::new(Session::default());
+    assert_eq!(repl.respond("2 + 2\n"), "4\n");
+    assert_eq!(repl.respond(":time\n"), "tempos ligados\n");
+    let output = repl.respond("2 + 2\n");
+    assert!(output.starts_with("4\nleitura: "), "{}", output);
+    assert_eq!(repl.respond(":time\n"), "tempos desligados\n");
+}
+
+#[test]
+fn repl_should_show_the_last_result_as_a_fraction() {
+    let mut repl = Repl::new(Session::default());
+    assert_eq!(
+        repl.respond("as_fraction\n"),
+        "nenhum resultado para converter ainda\n"
+    );
+    assert_eq!(repl.respond("2 / 3\n"), "0.6666666666666666\n");
+    assert_eq!(repl.respond("as_fraction\n"), "2/3\n");
+    assert_eq!(repl.respond("pi\n"), "3.141592653589793\n");
+    assert_eq!(repl.respond("AS_FRACTION 10\n"), "22/7 (±1.3e-3)\n");
+    assert_eq!(
+        repl.respond("as_fraction 0\n"),
+        "denominador máximo inválido: 0\n"
+    );
+}
+
+#[test]
+fn repl_should_switch_result_notation() {
+    let mut repl = Repl::new(Session::default());
+    assert_eq!(repl.respond("format\n"), "formato: plain\n");
+    assert_eq!(repl.respond("FORMAT eng\n"), "formato: eng\n");
+    assert_eq!(repl.respond("12345\n"), "12.345e3\n");
+    assert_eq!(repl.respond("format auto 3\n"), "formato: auto 3\n");
+    assert_eq!(repl.respond("12345\n"), "1.2345e4\n");
+    assert_eq!(repl.respond("123\n"), "123\n");
+    assert!(repl
+        .respond("format octal\n")
+        .starts_with("formato desconhecido"));
+    assert_eq!(repl.respond("format\n"), "formato: auto 3\n");
+}
+
+#[test]
+fn repl_should_list_recent_errors() {
+    let mut repl = Repl::new(Session::default());
+    assert_eq!(repl.respond("errors\n"), "nenhum erro recente\n");
+    repl.respond("  1 + * 2\n");
+    repl.respond("3 * 3\n");
+    repl.respond("foo(1)\n");
+    let expected = [
+        "[1] 1 + * 2",
+        "      ^",
+        format!(
+            "    {}",
+            Error::ConsecutiveOperators(asdf_calc::Span::new(2, 3))
+        )
+        .as_str(),
+        "[2] foo(1)",
+        "    ^",
+        format!(
+            "    {}",
+            Error::UnknownFunction("foo".to_string(), asdf_calc::Span::new(0, 3))
+        )
+        .as_str(),
+        "",
+    ]
+    .join("\n");
+    assert_eq!(repl.respond("ERRORS\n"), expected);
+}
+
+#[test]
+fn repl_should_render_the_configured_prompt() {
+    let mut repl = Repl::new(Session::default());
+    assert_eq!(repl.prompt(), "> ");
+    assert_eq!(
+        repl.respond("PROMPT \"[{angle}|{base}|{mode}] {line}> \"\n"),
+        "prompt: \"[{angle}|{base}|{mode}] {line}> \"\n"
+    );
+    repl.respond("angle deg\n");
+    repl.lines = 11;
+    assert_eq!(repl.prompt(), "[deg|dec|plain] 12> ");
+    repl.respond("set base hex\n");
+    assert_eq!(repl.prompt(), "[deg|hex|plain] 12> ");
+    assert!(repl
+        .respond("prompt {hex}>\n")
+        .starts_with("marcador desconhecido"));
+    assert_eq!(repl.respond("prompt default\n"), "prompt: \"> \"\n");
+}
+
+#[test]
+fn repl_should_announce_auto_stored_results() {
+    let mut repl = Repl::new(Session::default());
+    assert_eq!(repl.respond("autostore on\n"), "autostore ligado\n");
+    assert_eq!(repl.respond("6 * 7\n"), "r1 = 42\n");
+    assert_eq!(repl.respond("r1 / 2\n"), "r2 = 21\n");
+    assert_eq!(repl.respond("y = 2\n"), "2\n");
+}
+
+#[test]
+fn repl_should_evaluate_pasted_blocks_at_the_end() {
+    let mut repl = Repl::new(Session::default());
+    assert!(repl.respond(":paste\n").starts_with("modo paste"));
+    assert_eq!(repl.respond("base = 10\n"), "");
+    assert_eq!(repl.respond("\n"), "");
+    assert_eq!(repl.respond("# altura\n"), "");
+    assert_eq!(repl.respond("area = base * height\r\n"), "");
+    assert_eq!(repl.respond("height = 2\n"), "");
+    assert_eq!(repl.respond("exit\n"), "");
+
+    let output = repl.respond(":end\n");
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", output);
+    assert_eq!(lines[0], "10");
+    assert!(lines[1].starts_with("linha 4: "), "{}", lines[1]);
+    assert_eq!(lines[2], "2");
+    assert!(lines[3].starts_with("linha 6: "), "{}", lines[3]);
+    assert_eq!(repl.respond("base * height\n"), "20\n");
+}
+
+#[test]
+fn repl_should_print_lint_notes_under_the_input() {
+    let mut repl = Repl::new(Session::default());
+    assert_eq!(repl.respond("-2^2\n"), "-4\n");
+    assert_eq!(repl.respond("lint on\n"), "avisos ligados\n");
+    assert_eq!(
+        repl.respond("1 + -2^2\n"),
+        "-3\n      ^\ncoluna 5: nota: `-2^2` é lido como `-(2^2)`\n"
+    );
+    assert_eq!(repl.respond("1 + 2\n"), "3\n");
+}
+
+#[test]
+fn repl_should_expand_aliases_before_reading_the_line() {
+    let mut repl = Repl::new(Session::default());
+    assert_eq!(repl.respond("alias k = * 1000\n"), "k = * 1000\n");
+    assert_eq!(repl.respond("3 k\n"), "3000\n");
+    assert_eq!(repl.respond("alias t = :time\n"), "t = :time\n");
+    assert_eq!(repl.respond("t\n"), "tempos ligados\n");
+}
+
+#[test]
+fn repl_should_prefer_variables_over_bare_commands() {
+    let mut repl = Repl::new(Session::default());
+    let help = repl.respond(":help\n");
+    assert!(help.starts_with("comandos"));
+    for command in [
+        ":show options",
+        ":workspace",
+        ":prompt",
+        ":format",
+        ":units",
+        ":to dms",
+    ] {
+        assert!(help.contains(command), "{}", command);
+    }
+    assert_eq!(repl.respond("clear = 2\n"), "2\n");
+    assert_eq!(repl.respond("clear * 3\n"), "6\n");
+    assert_eq!(repl.respond("clear\n"), "2\n");
+    assert_eq!(repl.respond(":clear\n"), terminal::clear_screen());
+    assert_eq!(repl.respond(":time\n"), "tempos ligados\n");
+    assert_eq!(repl.respond(":vars\n"), "clear = 2\n");
+    // os tempos do `:time` continuam ligados
+    assert!(repl.respond("show = 1\n").starts_with("1\n"));
+    assert!(repl.respond("show\n").starts_with("1\n"));
+    assert!(repl.respond(":show options\n").starts_with("angle"));
+}
+
+#[test]
+fn repl_should_offer_to_correct_typos() {
+    let mut repl = Repl::new(Session::default());
+    assert_eq!(
+        repl.respond("3 ++ 4\n"),
+        "    ^\ncoluna 3: síntaxe incorreta, operadores consecutivos\nvocê quis dizer `3 + 4`?\n"
+    );
+    assert_eq!(repl.respond("s\n"), "3 + 4\n7\n");
+    assert!(repl
+        .respond("1 000 + 2\n")
+        .ends_with("você quis dizer `1000 + 2`?\n"));
+    assert_eq!(repl.respond("\n"), "");
+    assert_eq!(repl.respond("1 000 + 2\n").lines().count(), 3);
+    assert_eq!(repl.respond("N\n"), "");
+    assert_eq!(
+        repl.respond("2 ** 10\n").lines().last(),
+        Some("você quis dizer `2 ^ 10`?")
+    );
+    assert_eq!(repl.respond("sim\n"), "2 ^ 10\n1024\n");
+    assert!(!repl.respond("3 )\n").contains("você quis dizer"));
+}
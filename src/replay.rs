@@ -0,0 +1,92 @@
+//! `asdf-calc replay <arquivo>`: mostra de novo, linha por linha, uma sessão salva com
+//! `export json`. Com `--interactive` cada input espera um Enter antes do resultado aparecer, o
+//! que ajuda a mostrar um cálculo em aulas e demonstrações.
+
+use std::{
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+use crate::{export::SessionExport, format::Notation};
+
+/// Escreve cada linha do histórico de `export` e o resultado dela. Se `pause` existir, uma linha
+/// é lida dele antes de cada resultado, e a reprodução para no fim do input ou se a linha lida
+/// for `q`.
+pub fn replay(
+    export: &SessionExport,
+    notation: Notation,
+    mut pause: Option<&mut dyn BufRead>,
+    output: &mut dyn Write,
+) -> io::Result<()> {
+    let total = export.history.len();
+    if pause.is_some() {
+        writeln!(output, "{} linhas, Enter mostra o resultado e q sai", total)?;
+    }
+    for (index, entry) in export.history.iter().enumerate() {
+        writeln!(output, "[{}/{}] {}", index + 1, total, entry.input)?;
+        if let Some(pause) = pause.as_deref_mut() {
+            output.flush()?;
+            let mut answer = String::new();
+            if pause.read_line(&mut answer)? == 0 || answer.trim().eq_ignore_ascii_case("q") {
+                return Ok(());
+            }
+        }
+        writeln!(output, "= {}", notation.format(entry.result))?;
+    }
+    Ok(())
+}
+
+pub fn run(file: &Path, interactive: bool, notation: Notation) -> Result<(), String> {
+    let export = SessionExport::read(file)?;
+    let stdin = io::stdin();
+    let mut stdin = stdin.lock();
+    let pause: Option<&mut dyn BufRead> = if interactive { Some(&mut stdin) } else { None };
+    replay(&export, notation, pause, &mut io::stdout().lock())
+        .map_err(|error| format!("erro ao mostrar {}: {}", file.display(), error))
+}
+
+#[cfg(test)]
+fn recorded() -> SessionExport {
+    use crate::session::HistoryEntry;
+
+    SessionExport {
+        history: vec![
+            HistoryEntry {
+                input: "rate = 0.05".to_string(),
+                result: 0.05,
+            },
+            HistoryEntry {
+                input: "1000 * rate".to_string(),
+                result: 50.0,
+            },
+        ],
+        ..SessionExport::default()
+    }
+}
+
+#[test]
+fn replay_should_show_every_line_with_its_result() {
+    let mut output = Vec::new();
+    replay(&recorded(), Notation::Plain, None, &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "[1/2] rate = 0.05\n= 0.05\n[2/2] 1000 * rate\n= 50\n"
+    );
+}
+
+#[test]
+fn interactive_replay_should_wait_before_each_result() {
+    let mut output = Vec::new();
+    let mut answers: &[u8] = b"\nq\n";
+    replay(
+        &recorded(),
+        Notation::Plain,
+        Some(&mut answers),
+        &mut output,
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "2 linhas, Enter mostra o resultado e q sai\n[1/2] rate = 0.05\n= 0.05\n[2/2] 1000 * rate\n"
+    );
+}
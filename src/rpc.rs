@@ -0,0 +1,227 @@
+//! Modo JSON-RPC 2.0 sobre stdin/stdout (`asdf-calc --rpc`), uma mensagem por linha, para que
+//! plugins de editores possam calcular trechos selecionados e completar nomes da sessão.
+//!
+//! Métodos:
+//! - `eval` com `{"expr": "x = 2 * 3"}` responde `{"value": 6}`;
+//! - `complete` com `{"prefix": "r"}` responde a lista de nomes (`{"name", "kind"}`) que começam
+//!   com o prefixo;
+//! - `defs` responde as variáveis definidas na sessão (`{"name", "kind", "value"}`).
+
+use std::io::{self, BufRead, Write};
+
+use asdf_calc::{EvalContext, Statement, BUILTINS, CONSTANTS};
+
+use crate::json::{errors_json, result_json, Json};
+
+const PARSE_ERROR: f64 = -32700.0;
+const INVALID_REQUEST: f64 = -32600.0;
+const METHOD_NOT_FOUND: f64 = -32601.0;
+const INVALID_PARAMS: f64 = -32602.0;
+/// Código usado quando a expressão em si tem erros, que vão detalhados em `data`.
+const EXPRESSION_ERROR: f64 = -32000.0;
+
+struct RpcError {
+    code: f64,
+    message: String,
+    data: Option<Json>,
+}
+
+impl RpcError {
+    fn new(code: f64, message: &str) -> Self {
+        RpcError {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+}
+
+fn string_param<'a>(params: Option<&'a Json>, name: &str) -> Result<&'a str, RpcError> {
+    params
+        .and_then(|params| params.get(name))
+        .and_then(Json::as_str)
+        .ok_or_else(|| {
+            RpcError::new(
+                INVALID_PARAMS,
+                &format!("o parâmetro \"{}\" precisa ser uma string", name),
+            )
+        })
+}
+
+fn name_json(name: &str, kind: &str) -> Json {
+    Json::object([("name", Json::from(name)), ("kind", Json::from(kind))])
+}
+
+fn call(context: &mut EvalContext, method: &str, params: Option<&Json>) -> Result<Json, RpcError> {
+    match method {
+        "eval" => {
+            let text = string_param(params, "expr")?;
+            Statement::new(text, context)
+                .and_then(|statement| statement.execute(context).map_err(|error| vec![error]))
+                .map(|value| Json::object([("value", result_json(value))]))
+                .map_err(|errors| RpcError {
+                    code: EXPRESSION_ERROR,
+                    message: "a expressão não pôde ser calculada".to_string(),
+                    data: Some(errors_json(&errors)),
+                })
+        }
+        "complete" => {
+            let prefix = string_param(params, "prefix")?;
+            let mut names: Vec<(&str, &str)> = context
+                .variables()
+                .map(|(name, _)| (name, "variable"))
+                .chain(context.constants().map(|(name, _)| (name, "constant")))
+                .chain(CONSTANTS.iter().map(|(name, _)| (*name, "constant")))
+                .chain(BUILTINS.iter().map(|builtin| (builtin.name(), "builtin")))
+                .filter(|(name, _)| name.starts_with(prefix))
+                .collect();
+            names.sort();
+            names.dedup_by_key(|(name, _)| *name);
+            Ok(Json::Array(
+                names
+                    .into_iter()
+                    .map(|(name, kind)| name_json(name, kind))
+                    .collect(),
+            ))
+        }
+        "defs" => Ok(Json::Array(
+            context
+                .variables()
+                .map(|(name, value)| {
+                    let mut json = name_json(name, "variable");
+                    if let Json::Object(pairs) = &mut json {
+                        pairs.push(("value".to_string(), result_json(value)));
+                    }
+                    json
+                })
+                .collect(),
+        )),
+        method => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            &format!("método desconhecido '{}'", method),
+        )),
+    }
+}
+
+/// Responde a uma linha recebida, retornando `None` para notificações (mensagens sem `id`).
+pub fn handle_line(context: &mut EvalContext, line: &str) -> Option<Json> {
+    let (id, outcome) = match Json::parse(line) {
+        Err(message) => (Json::Null, Err(RpcError::new(PARSE_ERROR, &message))),
+        Ok(request) => {
+            let id = request.get("id").cloned();
+            let outcome = match request.get("method").and_then(Json::as_str) {
+                Some(method) => call(context, method, request.get("params")),
+                None => Err(RpcError::new(
+                    INVALID_REQUEST,
+                    "o campo \"method\" precisa ser uma string",
+                )),
+            };
+            match id {
+                Some(id) => (id, outcome),
+                None => return None,
+            }
+        }
+    };
+
+    let response = match outcome {
+        Ok(result) => ("result", result),
+        Err(error) => {
+            let mut pairs = vec![
+                ("code".to_string(), Json::Number(error.code)),
+                ("message".to_string(), Json::from(error.message)),
+            ];
+            if let Some(data) = error.data {
+                pairs.push(("data".to_string(), data));
+            }
+            ("error", Json::Object(pairs))
+        }
+    };
+    Some(Json::object([
+        ("jsonrpc", Json::from("2.0")),
+        ("id", id),
+        response,
+    ]))
+}
+
+pub fn run(mut context: EvalContext) -> io::Result<()> {
+    let mut out_handle = io::stdout();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(&mut context, &line) {
+            writeln!(out_handle, "{}", response)?;
+            out_handle.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn rpc_should_evaluate_and_keep_definitions() {
+    let mut context = EvalContext::new();
+    let response = handle_line(
+        &mut context,
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "eval", "params": {"expr": "rate = 0.5"}}"#,
+    );
+    assert_eq!(
+        response.unwrap().to_string(),
+        r#"{"jsonrpc":"2.0","id":1,"result":{"value":0.5}}"#
+    );
+
+    let response = handle_line(
+        &mut context,
+        r#"{"jsonrpc": "2.0", "id": 2, "method": "defs"}"#,
+    );
+    assert_eq!(
+        response.unwrap().to_string(),
+        r#"{"jsonrpc":"2.0","id":2,"result":[{"name":"rate","kind":"variable","value":0.5}]}"#
+    );
+}
+
+#[test]
+fn rpc_should_complete_names() {
+    let mut context = EvalContext::new();
+    context.set_variable("pitch", 2.0);
+    let response = handle_line(
+        &mut context,
+        r#"{"jsonrpc": "2.0", "id": "a", "method": "complete", "params": {"prefix": "pi"}}"#,
+    );
+    assert_eq!(
+        response.unwrap().to_string(),
+        r#"{"jsonrpc":"2.0","id":"a","result":[{"name":"pi","kind":"constant"},{"name":"pitch","kind":"variable"}]}"#
+    );
+}
+
+#[test]
+fn rpc_should_report_errors() {
+    let mut context = EvalContext::new();
+    let response = handle_line(
+        &mut context,
+        r#"{"jsonrpc": "2.0", "id": 3, "method": "eval", "params": {"expr": "1 +"}}"#,
+    )
+    .unwrap();
+    let error = response.get("error").unwrap();
+    assert_eq!(error.get("code"), Some(&Json::Number(EXPRESSION_ERROR)));
+    assert!(error.get("data").is_some());
+
+    let response = handle_line(&mut context, "{").unwrap();
+    assert_eq!(
+        response.get("error").and_then(|error| error.get("code")),
+        Some(&Json::Number(PARSE_ERROR))
+    );
+
+    let response = handle_line(
+        &mut context,
+        r#"{"jsonrpc": "2.0", "id": 4, "method": "nope"}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        response.get("error").and_then(|error| error.get("code")),
+        Some(&Json::Number(METHOD_NOT_FOUND))
+    );
+
+    // notificações não têm resposta
+    assert!(handle_line(&mut context, r#"{"jsonrpc": "2.0", "method": "defs"}"#).is_none());
+}
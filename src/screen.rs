@@ -0,0 +1,65 @@
+//! `aspect(largura, altura)`, a proporção de uma tela ou imagem (`16:9`). Como `polar`, é um
+//! comando que ocupa a linha inteira, porque o resultado não é um número.
+
+/// As proporções com que as telas costumam ser vendidas, mesmo quando a resolução não dá
+/// exatamente nelas (2560×1080 é 64:27, mas é vendida como 21:9).
+const COMMON: [(u64, u64); 8] = [
+    (1, 1),
+    (5, 4),
+    (4, 3),
+    (3, 2),
+    (16, 10),
+    (16, 9),
+    (21, 9),
+    (32, 9),
+];
+
+/// Quão longe, relativamente, uma proporção pode estar de uma das de `COMMON` para ser mostrada
+/// como ela.
+const TOLERANCE: f64 = 0.02;
+
+/// `largura:altura` simplificada, ou como a proporção comum se for a mesma (1920×1200 é 16:10),
+/// com a comum mais próxima se ela não for nenhuma delas.
+pub fn aspect(width: f64, height: f64) -> Result<String, String> {
+    let is_size = |value: f64| value >= 1.0 && value.fract() == 0.0 && value < 2f64.powi(53);
+    if !is_size(width) || !is_size(height) {
+        return Err(format!(
+            "a largura e a altura precisam ser inteiros positivos: {} e {}",
+            width, height
+        ));
+    }
+    let (width, height) = (width as u64, height as u64);
+    let divisor = gcd(width, height);
+    let (width, height) = (width / divisor, height / divisor);
+    let distance =
+        |(w, h): (u64, u64)| (width as f64 * h as f64 / (height as f64 * w as f64) - 1.0).abs();
+    let closest = COMMON
+        .into_iter()
+        .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+        .filter(|common| distance(*common) <= TOLERANCE);
+    Ok(match closest {
+        Some((w, h)) if u128::from(w) * u128::from(height) == u128::from(h) * u128::from(width) => {
+            format!("{}:{}", w, h)
+        }
+        Some((w, h)) => format!("{}:{} (≈ {}:{})", width, height, w, h),
+        None => format!("{}:{}", width, height),
+    })
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[test]
+fn aspect_should_reduce_the_ratio() {
+    assert_eq!(aspect(3840.0, 2160.0).unwrap(), "16:9");
+    assert_eq!(aspect(1920.0, 1200.0).unwrap(), "16:10");
+    assert_eq!(aspect(2560.0, 1080.0).unwrap(), "64:27 (≈ 21:9)");
+    assert_eq!(aspect(1366.0, 768.0).unwrap(), "683:384 (≈ 16:9)");
+    assert_eq!(aspect(1000.0, 10.0).unwrap(), "100:1");
+    assert!(aspect(1920.5, 1080.0).is_err());
+    assert!(aspect(0.0, 1080.0).is_err());
+}
@@ -0,0 +1,316 @@
+//! Modo servidor (`asdf-calc serve`): uma API JSON mínima sobre HTTP/1.1, para que scripts e
+//! dashboards possam usar um único avaliador compartilhado.
+//!
+//! - `POST /eval` com `{"expr": "2+2"}` responde `{"result": 4}`. Com `"session": "<token>"`,
+//!   as variáveis atribuídas (`x = 2`) continuam disponíveis nas próximas requisições com o
+//!   mesmo token.
+//! - `POST /session` cria uma sessão e responde `{"session": "<token>"}`.
+//! - `DELETE /session/<token>` descarta a sessão.
+
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+};
+
+use asdf_calc::{EvalContext, Statement};
+
+#[cfg(test)]
+use asdf_calc::Error;
+
+use crate::{
+    json::{errors_json, result_json, Json},
+    pool::ThreadPool,
+};
+
+/// Tamanho máximo aceito para o corpo de uma requisição.
+const MAX_BODY_LEN: usize = 1024 * 1024;
+
+pub struct Response {
+    pub status: u16,
+    pub body: Json,
+}
+
+impl Response {
+    fn new(status: u16, body: Json) -> Self {
+        Response { status, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Response::new(status, Json::object([("error", Json::from(message))]))
+    }
+}
+
+fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Compartilhado entre as threads que atendem as conexões. Cálculos sem sessão não passam por
+/// nenhum lock, e cada sessão tem o seu, então só requisições da mesma sessão esperam umas
+/// pelas outras.
+#[derive(Default)]
+pub struct Server {
+    sessions: Mutex<HashMap<String, Arc<Mutex<EvalContext>>>>,
+    random_state: RandomState,
+    /// Nomes com que toda sessão (e todo cálculo sem sessão) começa.
+    defaults: EvalContext,
+}
+
+impl Server {
+    pub fn with_defaults(defaults: EvalContext) -> Self {
+        Server {
+            defaults,
+            ..Server::default()
+        }
+    }
+
+    fn new_token(&self) -> String {
+        // `RandomState` já vem com uma semente aleatória a cada execução, o contador só garante
+        // que dois tokens seguidos nunca sejam iguais
+        let sessions_count = self.sessions.lock().unwrap().len() as u64;
+        let mut token = String::new();
+        for part in 0..2u64 {
+            let mut hasher = self.random_state.build_hasher();
+            hasher.write_u64(sessions_count);
+            hasher.write_u64(part);
+            hasher.write_u128(
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|duration| duration.as_nanos())
+                    .unwrap_or_default(),
+            );
+            token.push_str(&format!("{:016x}", hasher.finish()));
+        }
+        token
+    }
+
+    fn eval(&self, body: &str) -> Response {
+        let request = match Json::parse(body) {
+            Ok(request) => request,
+            Err(message) => return Response::error(400, &message),
+        };
+        let Some(text) = request.get("expr").and_then(Json::as_str) else {
+            return Response::error(400, "o campo \"expr\" precisa ser uma string");
+        };
+
+        let session = match request.get("session") {
+            None | Some(Json::Null) => None,
+            Some(Json::String(token)) => match self.sessions.lock().unwrap().get(token) {
+                Some(session) => Some(Arc::clone(session)),
+                None => return Response::error(404, "sessão desconhecida"),
+            },
+            Some(_) => return Response::error(400, "o campo \"session\" precisa ser uma string"),
+        };
+        let evaluate = |context: &mut EvalContext| {
+            Statement::new(text, context)
+                .and_then(|statement| statement.execute(context).map_err(|error| vec![error]))
+        };
+        let result = match &session {
+            Some(session) => evaluate(&mut session.lock().unwrap()),
+            None => evaluate(&mut self.defaults.clone()),
+        };
+        match result {
+            Ok(result) => Response::new(200, Json::object([("result", result_json(result))])),
+            Err(errors) => Response::new(400, Json::object([("errors", errors_json(&errors))])),
+        }
+    }
+
+    /// Responde a uma requisição já lida, sem depender da conexão, para que possa ser testado.
+    pub fn handle(&self, method: &str, path: &str, body: &str) -> Response {
+        match (method, path) {
+            ("POST", "/eval") => self.eval(body),
+            ("POST", "/session") => {
+                let token = self.new_token();
+                self.sessions
+                    .lock()
+                    .unwrap()
+                    .insert(token.clone(), Arc::new(Mutex::new(self.defaults.clone())));
+                Response::new(201, Json::object([("session", Json::from(token))]))
+            }
+            ("DELETE", path) if path.starts_with("/session/") => {
+                let token = &path["/session/".len()..];
+                match self.sessions.lock().unwrap().remove(token) {
+                    Some(_) => Response::new(200, Json::object([("session", Json::from(token))])),
+                    None => Response::error(404, "sessão desconhecida"),
+                }
+            }
+            (_, "/eval") | (_, "/session") => Response::error(405, "método não suportado"),
+            _ => Response::error(404, "caminho desconhecido"),
+        }
+    }
+
+    fn handle_connection(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let path = parts.next().unwrap_or_default().to_string();
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+
+        let response = if content_length > MAX_BODY_LEN {
+            Response::error(413, "corpo da requisição grande demais")
+        } else {
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body)?;
+            self.handle(&method, &path, &String::from_utf8_lossy(&body))
+        };
+
+        let body = format!("{}\n", response.body);
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            response.status,
+            status_text(response.status),
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+}
+
+/// Escuta em `port` até o processo ser encerrado, atendendo as conexões com um `ThreadPool`.
+pub fn serve(port: u16, defaults: EvalContext) -> io::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    eprintln!("escutando em http://0.0.0.0:{}", port);
+
+    let server = Arc::new(Server::with_defaults(defaults));
+    let pool = ThreadPool::new(ThreadPool::default_size());
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("erro ao aceitar conexão: {}", error);
+                continue;
+            }
+        };
+
+        let server = Arc::clone(&server);
+        pool.execute(move || {
+            if let Err(error) = server.handle_connection(stream) {
+                eprintln!("erro ao responder requisição: {}", error);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+#[test]
+fn server_should_evaluate_expressions() {
+    let server = Server::default();
+    let response = server.handle("POST", "/eval", r#"{"expr": "2+2"}"#);
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body.to_string(), r#"{"result":4}"#);
+
+    let response = server.handle("POST", "/eval", r#"{"expr": "2+"}"#);
+    assert_eq!(response.status, 400);
+    assert_eq!(
+        response.body.get("errors"),
+        Some(&errors_json(&[Error::TrailingOperator(
+            asdf_calc::Span::new(1, 2)
+        )]))
+    );
+}
+
+#[test]
+fn server_should_keep_variables_in_sessions() {
+    let server = Server::default();
+    let response = server.handle("POST", "/session", "");
+    assert_eq!(response.status, 201);
+    let token = response
+        .body
+        .get("session")
+        .and_then(Json::as_str)
+        .unwrap()
+        .to_string();
+
+    let assign = format!(r#"{{"expr": "x = 21", "session": "{}"}}"#, token);
+    assert_eq!(server.handle("POST", "/eval", &assign).status, 200);
+
+    let read = format!(r#"{{"expr": "x * 2", "session": "{}"}}"#, token);
+    assert_eq!(
+        server.handle("POST", "/eval", &read).body.to_string(),
+        r#"{"result":42}"#
+    );
+
+    // sem a sessão, a variável não existe
+    assert_eq!(
+        server
+            .handle("POST", "/eval", r#"{"expr": "x * 2"}"#)
+            .status,
+        400
+    );
+
+    let path = format!("/session/{}", token);
+    assert_eq!(server.handle("DELETE", &path, "").status, 200);
+    assert_eq!(server.handle("POST", "/eval", &read).status, 404);
+}
+
+#[test]
+fn server_should_create_distinct_tokens() {
+    let server = Server::default();
+    let first = server.handle("POST", "/session", "").body;
+    let second = server.handle("POST", "/session", "").body;
+    assert_ne!(first, second);
+}
+
+#[test]
+fn server_should_answer_requests_from_many_threads() {
+    let server = Arc::new(Server::default());
+    let token = server
+        .handle("POST", "/session", "")
+        .body
+        .get("session")
+        .and_then(Json::as_str)
+        .unwrap()
+        .to_string();
+    let increment = format!(r#"{{"expr": "n = n + 1", "session": "{}"}}"#, token);
+    let start = format!(r#"{{"expr": "n = 0", "session": "{}"}}"#, token);
+    assert_eq!(server.handle("POST", "/eval", &start).status, 200);
+
+    let pool = ThreadPool::new(4);
+    for i in 0..200 {
+        let (server, increment) = (Arc::clone(&server), increment.clone());
+        pool.execute(move || {
+            let stateless = format!(r#"{{"expr": "{} * 2"}}"#, i);
+            let response = server.handle("POST", "/eval", &stateless);
+            assert_eq!(
+                response.body.to_string(),
+                format!(r#"{{"result":{}}}"#, i * 2)
+            );
+            assert_eq!(server.handle("POST", "/eval", &increment).status, 200);
+        });
+    }
+    drop(pool);
+
+    let read = format!(r#"{{"expr": "n", "session": "{}"}}"#, token);
+    assert_eq!(
+        server.handle("POST", "/eval", &read).body.to_string(),
+        r#"{"result":200}"#
+    );
+}
@@ -0,0 +1,3147 @@
+//! Estado de uma sessão da calculadora, compartilhado pelos modos que mantêm estado entre uma
+//! linha e outra (REPL, daemon...).
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use asdf_calc::{
+    lint, AngleMode, Dimension, Error, EvalContext, Expression, Function, Note, Span, Statement,
+    Summation, Unit, WordSize,
+};
+
+use crate::{
+    alias, autocorrect,
+    cache::Cache,
+    calendar::{self, Calendar},
+    color, coordinates,
+    date::{self, Date},
+    duration::DurationFormat,
+    editor::EditorOptions,
+    env::{self, Interpolated},
+    export::{FunctionDefinition, SessionExport},
+    finance,
+    format::{self, Base, Notation},
+    fraction, inspect,
+    interrupt::Watchdog,
+    log,
+    pager::PagerMode,
+    prompt, screen, table,
+    unit_system::{self, UnitSystem},
+    workspace::{self, Workspace},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub input: String,
+    pub result: f64,
+}
+
+/// Linha que não pôde ser calculada, com os erros e as posições deles no texto.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorEntry {
+    pub input: String,
+    pub errors: Vec<Error>,
+}
+
+/// Atribuição que pode ser desfeita com `undo`, com o valor que a variável tinha antes dela.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redefinition {
+    pub name: String,
+    /// `None` se a variável não existia, e deve ser removida ao desfazer.
+    pub previous: Option<f64>,
+    /// A unidade do valor anterior.
+    pub dimension: Dimension,
+}
+
+/// Quantas linhas com erro ficam guardadas para o comando `errors`.
+pub const RECENT_ERRORS: usize = 20;
+
+/// Quanto a última linha calculada demorou em cada etapa.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+    pub parse: Duration,
+    pub evaluation: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    pub context: EvalContext,
+    /// Só as linhas calculadas com sucesso entram no histórico.
+    pub history: Vec<HistoryEntry>,
+    /// As últimas `RECENT_ERRORS` linhas com erro, da mais antiga para a mais recente.
+    pub recent_errors: VecDeque<ErrorEntry>,
+    /// As atribuições feitas na sessão, da mais antiga para a mais recente, para o `undo`.
+    pub undo_journal: Vec<Redefinition>,
+    /// Troca `${NOME}` pelo valor da variável de ambiente antes de calcular (`--env`).
+    pub interpolate_env: bool,
+    pub last_timing: Option<Timing>,
+    /// Como os resultados são mostrados (`format sci`...). O histórico guarda o valor exato.
+    pub notation: Notation,
+    /// `autostore on`, guarda cada resultado que não veio de uma atribuição em `r1`, `r2`...
+    pub autostore: bool,
+    /// Variável em que o último resultado foi guardado pelo `autostore`.
+    pub last_stored: Option<String>,
+    /// `sigfigs on`, mostra os resultados com os algarismos significativos dos números escritos.
+    pub significant_figures: bool,
+    /// Algarismos significativos do último resultado, se ele tiver algum número medido.
+    pub last_figures: Option<u32>,
+    /// `lint on`, avisa sobre os trechos ambíguos das linhas calculadas (`-2^2`, `1/2 km`).
+    pub lint: bool,
+    /// Os avisos do `lint` sobre a última linha calculada, vazio com ele desligado.
+    pub last_notes: Vec<Note>,
+    /// Em que unidades os resultados com unidade são mostrados (`units imperial`).
+    pub units: UnitSystem,
+    /// A unidade em que o último resultado é mostrado e quanto ela vale no SI, se ele tiver
+    /// uma: a do `to` da linha ou a de `units`.
+    pub last_unit: Option<(String, f64)>,
+    /// Se o último resultado é um tempo sem `to`, mostrado como uma duração (`8h 53m 20s`).
+    pub last_duration: bool,
+    /// Se a última linha terminou com `to dms`, e o resultado é mostrado como um ângulo em
+    /// graus, minutos e segundos.
+    pub last_dms: bool,
+    /// Como os tempos são mostrados quando não há `to` (`format duration hh:mm:ss`).
+    pub duration_format: DurationFormat,
+    /// Os fins de semana e feriados de `workdays` e `add_workdays`.
+    pub calendar: Calendar,
+    /// Os apelidos de `alias`, pelo nome em minúsculas.
+    pub aliases: BTreeMap<String, String>,
+    /// `show all_bases`, mostra os resultados inteiros também em hexadecimal, binário e octal.
+    pub all_bases: bool,
+    /// `set precision <n>`, o máximo de algarismos significativos dos resultados, `None` para
+    /// mostrar todos.
+    pub precision: Option<u32>,
+    /// `set base hex`, a base em que os resultados inteiros sem unidade são mostrados.
+    pub base: Base,
+    /// Opções do editor de linha da calculadora interativa (`autoclose on`...).
+    pub editor: EditorOptions,
+    /// Se a saída longa da calculadora interativa é paginada (`pager auto|off`).
+    pub pager: PagerMode,
+    /// Modelo do prompt da calculadora interativa (`prompt "..."`), `None` para o padrão.
+    prompt: Option<String>,
+    /// Onde os workspaces são salvos, `None` se não houver um diretório para eles.
+    pub workspace_directory: Option<PathBuf>,
+    /// Workspace em uso, salvo a cada `save_workspace`. Sem um, nada é salvo.
+    workspace: Option<String>,
+    /// Arquivo do log dos cálculos (`log path <arquivo>`), `None` para o padrão.
+    log_path: Option<PathBuf>,
+    /// Se os cálculos estão sendo registrados no log (`log on`).
+    logging: bool,
+    /// Erro ao escrever no log, que o desliga até o próximo `log on`, esperando para ser
+    /// mostrado.
+    pub log_error: Option<String>,
+    /// As linhas já lidas, e os resultados das que não dependem de nenhum nome.
+    pub cache: Cache,
+    /// Tempo máximo de cada cálculo (`--timeout`), e a thread que o interrompe.
+    timeout: Option<(Duration, Watchdog)>,
+}
+
+impl Session {
+    /// Sessão que já começa com os nomes de `context` (vindos de `-D`, por exemplo).
+    pub fn with_context(context: EvalContext) -> Self {
+        Session {
+            context,
+            ..Session::default()
+        }
+    }
+
+    /// Interrompe com `Error::Interrupted` os cálculos que demorarem mais que `timeout`.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout.map(|timeout| {
+            let flag = self.context.interrupt_flag().clone();
+            (timeout, Watchdog::new(flag))
+        });
+    }
+
+    /// Comandos que não são expressões (`format sci`, `as_fraction`...), aceitos tanto pela
+    /// calculadora interativa quanto pelo daemon. Retorna `None` se `line` não for um deles.
+    ///
+    /// Com um `:` na frente (`:format sci`), a linha é sempre um comando. Sem ele, os nomes dos
+    /// comandos também podem ser usados como variáveis: veja `Session::is_calculation`.
+    pub fn command(&mut self, line: &str) -> Option<Result<String, String>> {
+        let line = line.trim();
+        if let Some(command) = line.strip_prefix(':') {
+            let command = command.trim_start();
+            return Some(self.run_command(command).unwrap_or_else(|| {
+                let name = command.split_whitespace().next().unwrap_or_default();
+                Err(format!("comando desconhecido ':{}'", name))
+            }));
+        }
+        if self.is_calculation(line) {
+            return None;
+        }
+        self.run_command(line)
+    }
+
+    /// Se a linha sem `:` é uma conta mesmo começando com o nome de um comando: uma atribuição
+    /// (`units = 3`) ou só o nome de uma variável que já existe (`format`, depois de
+    /// `format = 2`).
+    pub fn is_calculation(&self, line: &str) -> bool {
+        let line = line.trim();
+        let end = line
+            .find(|char: char| !(char.is_alphanumeric() || char == '_'))
+            .unwrap_or(line.len());
+        let (name, rest) = line.split_at(end);
+        if !is_name(name) {
+            return false;
+        }
+        let rest = rest.trim_start();
+        let name = name.to_lowercase();
+        let defined =
+            self.context.variable(&name).is_some() || self.context.constant(&name).is_some();
+        (rest.is_empty() && defined) || (rest.starts_with('=') && !rest.starts_with("=="))
+    }
+
+    /// `vars`, as variáveis da sessão com os valores (e as unidades) delas.
+    fn variables(&self) -> Result<String, String> {
+        let lines: Vec<String> = self
+            .context
+            .variables()
+            .map(|(name, value)| {
+                let quantity = Unit::new(value, self.context.dimension(name));
+                format!("{} = {}", name, self.format_quantity(quantity))
+            })
+            .collect();
+        if lines.is_empty() {
+            Ok("nenhuma variável definida".to_string())
+        } else {
+            Ok(lines.join("\n"))
+        }
+    }
+
+    fn run_command(&mut self, line: &str) -> Option<Result<String, String>> {
+        // o modelo do prompt, os nomes de arquivo, as expressões conferidas pelo `check` (em
+        // que `3.3M` é diferente de `3.3m`) e as unidades são os únicos argumentos em que as
+        // maiúsculas importam
+        let line = line.trim();
+        let (name, arguments) = line.split_once(' ').unwrap_or((line, ""));
+        match name.to_lowercase().as_str() {
+            "prompt" => return Some(self.set_prompt(arguments.trim())),
+            "log" => return Some(self.log_command(arguments)),
+            "export" => return Some(self.export_command(arguments)),
+            "import" => return Some(self.import_command(arguments)),
+            "check" => return Some(self.check_command(arguments)),
+            "compare" => return Some(self.compare(arguments)),
+            "unit" => return Some(self.unit_command(arguments)),
+            "holidays" => return Some(self.holidays_command(arguments)),
+            "alias" => return Some(self.define_alias(arguments)),
+            "unalias" => return Some(self.remove_alias(arguments)),
+            _ => {}
+        }
+
+        let line = line.to_lowercase();
+        // `amortize(...) table` também mostra a tabela com todos os meses
+        let (call, table) = match line.strip_suffix("table") {
+            Some(call) if call.trim_end().ends_with(')') => (call.trim_end(), true),
+            _ => (line.as_str(), false),
+        };
+        if let Some(arguments) = coordinates::call_arguments(call, "amortize") {
+            return Some(self.amortize(arguments, table));
+        }
+        for (name, convert) in [
+            (
+                "polar",
+                coordinates::polar as fn(f64, f64, AngleMode) -> (f64, f64),
+            ),
+            ("cart", coordinates::cart),
+        ] {
+            if let Some(arguments) = coordinates::call_arguments(&line, name) {
+                return Some(self.convert_coordinates(name, arguments, convert));
+            }
+        }
+        if let Some(arguments) = coordinates::call_arguments(&line, "aspect") {
+            return Some(self.aspect(arguments));
+        }
+        for name in ["unix", "from_unix", "now", "today"] {
+            if let Some(arguments) = coordinates::call_arguments(&line, name) {
+                return Some(self.timestamp(name, arguments));
+            }
+        }
+        if let Some(arguments) = coordinates::call_arguments(&line, "workdays") {
+            return Some(self.workdays(arguments));
+        }
+        if let Some(arguments) = coordinates::call_arguments(&line, "add_workdays") {
+            return Some(self.add_workdays(arguments));
+        }
+        for name in ["hex_to_rgb", "rgb_to_hex", "blend", "lighten", "darken"] {
+            if let Some(arguments) = coordinates::call_arguments(&line, name) {
+                return Some(self.color(name, arguments));
+            }
+        }
+
+        let (name, arguments) = line.split_once(' ').unwrap_or((&line, ""));
+        match name {
+            "format" => Some(self.set_format(arguments)),
+            "as_fraction" => Some(self.as_fraction(arguments)),
+            "inspect" => Some(self.inspect(arguments)),
+            "history" => Some(self.history_command(arguments)),
+            "which" => Some(self.which(arguments)),
+            "weekend" => Some(self.set_weekend(arguments)),
+            "angle" => Some(self.set_angle_mode(arguments)),
+            "word" => Some(self.set_word_size(arguments)),
+            "summation" => Some(self.set_summation(arguments)),
+            "units" => Some(self.set_unit_system(arguments)),
+            "to" if arguments.trim() == "dms" => Some(self.to_dms()),
+            "undo" if arguments.trim().is_empty() => Some(self.undo()),
+            "workspace" => Some(self.workspace_command(arguments)),
+            "autostore" => Some(self.set_autostore(arguments)),
+            "sigfigs" => Some(self.set_significant_figures(arguments)),
+            "lint" => Some(self.set_lint(arguments)),
+            "set" => Some(self.set_option(arguments)),
+            "show" => Some(self.show(arguments)),
+            "vars" if arguments.trim().is_empty() => Some(self.variables()),
+            "const" => Some(self.define_constant(arguments)),
+            "pager" => Some(self.set_pager(arguments)),
+            "cache" => Some(self.cache_command(arguments)),
+            "match_brackets" | "autoclose" => Some(self.set_editor_option(name, arguments)),
+            _ => None,
+        }
+    }
+
+    /// Aplica `format <notação>`, ou só mostra a atual se `arguments` estiver vazio.
+    pub fn set_format(&mut self, arguments: &str) -> Result<String, String> {
+        if let Some(format) = arguments.trim().strip_prefix("duration") {
+            if !format.trim().is_empty() {
+                self.duration_format = DurationFormat::parse(format)?;
+            }
+            return Ok(format!("formato das durações: {}", self.duration_format));
+        }
+        if !arguments.trim().is_empty() {
+            self.notation = Notation::parse(arguments)?;
+        }
+        Ok(format!("formato: {}", self.notation))
+    }
+
+    /// Aplica `prompt "<modelo>"` (as aspas permitem espaços no fim), ou `prompt default` para
+    /// voltar ao padrão, ou só mostra o modelo atual se `template` estiver vazio.
+    pub fn set_prompt(&mut self, template: &str) -> Result<String, String> {
+        match template {
+            "" => {}
+            "default" => self.prompt = None,
+            template => {
+                let template = template
+                    .strip_prefix('"')
+                    .and_then(|template| template.strip_suffix('"'))
+                    .unwrap_or(template);
+                prompt::validate(template)?;
+                self.prompt = Some(template.to_string());
+            }
+        }
+        Ok(format!("prompt: \"{}\"", self.prompt_template()))
+    }
+
+    /// `const nome = expressão`, pensado para o `init.calc`: define uma constante que não pode
+    /// ser alterada por atribuições e que não faz parte das variáveis (não é salva nos
+    /// workspaces, nem some ao trocar de workspace).
+    pub fn define_constant(&mut self, arguments: &str) -> Result<String, String> {
+        let (name, value) = arguments
+            .split_once('=')
+            .ok_or_else(|| "uso: const nome = expressão".to_string())?;
+        let name = name.trim();
+        if !is_name(name) {
+            return Err(format!("nome de constante inválido '{}'", name));
+        }
+
+        let value = self
+            .evaluate_argument(value)
+            .map_err(|message| format!("const {}: {}", name, message))?;
+        self.context.define_constant(name, value);
+        Ok(format!(
+            "{} = {} (constante)",
+            name,
+            self.notation.format(value)
+        ))
+    }
+
+    /// Aplica `autostore on` ou `autostore off`, ou só mostra se ele está ligado.
+    pub fn set_autostore(&mut self, arguments: &str) -> Result<String, String> {
+        match arguments.trim() {
+            "" => {}
+            "on" => self.autostore = true,
+            "off" => self.autostore = false,
+            other => return Err(format!("opção desconhecida para 'autostore': {}", other)),
+        }
+        Ok(format!(
+            "autostore {}",
+            if self.autostore {
+                "ligado"
+            } else {
+                "desligado"
+            }
+        ))
+    }
+
+    /// Aplica `sigfigs on` ou `sigfigs off`, ou só mostra se o modo está ligado.
+    pub fn set_significant_figures(&mut self, arguments: &str) -> Result<String, String> {
+        match arguments.trim() {
+            "" => {}
+            "on" => self.significant_figures = true,
+            "off" => self.significant_figures = false,
+            other => return Err(format!("opção desconhecida para 'sigfigs': {}", other)),
+        }
+        Ok(format!(
+            "algarismos significativos {}",
+            if self.significant_figures {
+                "ligados"
+            } else {
+                "desligados"
+            }
+        ))
+    }
+
+    /// `set <opção> <valor>`, que aplica qualquer uma das `OPTIONS` (`set angle deg`), ou só mostra
+    /// o valor atual sem o valor. Os comandos de cada uma (`angle deg`...) continuam valendo.
+    pub fn set_option(&mut self, arguments: &str) -> Result<String, String> {
+        let (option, value) = arguments
+            .trim()
+            .split_once(' ')
+            .unwrap_or((arguments.trim(), ""));
+        match option {
+            "angle" => self.set_angle_mode(value),
+            "format" => self.set_format(value),
+            "precision" => self.set_precision(value),
+            "base" => self.set_base(value),
+            "duration" => self.set_format(&format!("duration {}", value)),
+            "word" => self.set_word_size(value),
+            "summation" => self.set_summation(value),
+            "units" => self.set_unit_system(value),
+            "sigfigs" => self.set_significant_figures(value),
+            "autostore" => self.set_autostore(value),
+            "lint" => self.set_lint(value),
+            // `all-bases` é o nome antigo
+            "all_bases" | "all-bases" => self.set_all_bases(value),
+            "epsilon" | "approx" => self.set_tolerance(option, value),
+            "weekend" => self.set_weekend(value),
+            "pager" => self.set_pager(value),
+            "match_brackets" | "autoclose" => self.set_editor_option(option, value),
+            "" => Err("uso: set <opção> [valor], veja 'show options'".to_string()),
+            option => Err(format!(
+                "opção desconhecida '{}' (use {})",
+                option,
+                OPTIONS.join(", ")
+            )),
+        }
+    }
+
+    /// O valor atual de cada uma das `OPTIONS`, escrito como no `set`.
+    fn options(&self) -> String {
+        let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+        OPTIONS
+            .iter()
+            .map(|option| {
+                let value = match *option {
+                    "angle" => match self.context.angle_mode() {
+                        AngleMode::Degrees => "deg".to_string(),
+                        AngleMode::Radians => "rad".to_string(),
+                    },
+                    "format" => self.notation.to_string(),
+                    "precision" => self
+                        .precision
+                        .map_or("off".to_string(), |digits| digits.to_string()),
+                    "base" => self.base.to_string(),
+                    "duration" => self.duration_format.to_string(),
+                    "word" => format!("u{}", self.context.word_size().bits()),
+                    "summation" => self.context.summation().name().to_string(),
+                    "units" => self.units.to_string(),
+                    "sigfigs" => on_off(self.significant_figures).to_string(),
+                    "autostore" => on_off(self.autostore).to_string(),
+                    "lint" => on_off(self.lint).to_string(),
+                    "all_bases" => on_off(self.all_bases).to_string(),
+                    "epsilon" => format_tolerance(self.context.epsilon()),
+                    "approx" => format_tolerance(self.context.approx_tolerance()),
+                    "weekend" => self.calendar.describe_weekend(),
+                    "pager" => self.pager.to_string(),
+                    "match_brackets" => on_off(self.editor.match_brackets).to_string(),
+                    _ => on_off(self.editor.autoclose).to_string(),
+                };
+                format!("{:<15}{}", option, value)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// `set epsilon <valor>`, a tolerância absoluta de `==`, `!=`, `<`... (começa em zero), e
+    /// `set approx <valor>`, a relativa de `~=`.
+    fn set_tolerance(&mut self, option: &str, value: &str) -> Result<String, String> {
+        if !value.trim().is_empty() {
+            let tolerance = self.evaluate_argument(value)?;
+            if !(tolerance >= 0.0 && tolerance.is_finite()) {
+                return Err(format!(
+                    "tolerância inválida {} (precisa ser um número finito maior ou igual a zero)",
+                    tolerance
+                ));
+            }
+            if option == "epsilon" {
+                self.context.set_epsilon(tolerance);
+            } else {
+                self.context.set_approx_tolerance(tolerance);
+            }
+        }
+        Ok(if option == "epsilon" {
+            format!(
+                "epsilon: {} (absoluto, usado por ==, !=, <, <=, > e >=)",
+                format_tolerance(self.context.epsilon())
+            )
+        } else {
+            format!(
+                "approx: {} (relativo ao maior dos dois números, usado por ~=)",
+                format_tolerance(self.context.approx_tolerance())
+            )
+        })
+    }
+
+    /// Aplica `lint on` ou `lint off`, ou só mostra se os avisos estão ligados.
+    pub fn set_lint(&mut self, arguments: &str) -> Result<String, String> {
+        match arguments.trim() {
+            "" => {}
+            "on" => self.lint = true,
+            "off" => self.lint = false,
+            other => return Err(format!("opção desconhecida para 'lint': {}", other)),
+        }
+        Ok(format!(
+            "avisos {}",
+            if self.lint { "ligados" } else { "desligados" }
+        ))
+    }
+
+    /// `show options`, todas as opções com os valores atuais, `show <opção>`, só uma delas, e
+    /// `show all_bases`, que liga ou desliga a exibição dos resultados nas outras bases (como
+    /// `set all_bases on|off`).
+    pub fn show(&mut self, arguments: &str) -> Result<String, String> {
+        let (option, value) = arguments
+            .trim()
+            .split_once(' ')
+            .unwrap_or((arguments.trim(), ""));
+        match (option, value.trim()) {
+            ("options", "") => Ok(self.options()),
+            ("all_bases" | "all-bases", "") => {
+                self.set_all_bases(if self.all_bases { "off" } else { "on" })
+            }
+            ("all_bases" | "all-bases", value) => self.set_all_bases(value),
+            (option, "") if OPTIONS.contains(&option) => self.set_option(option),
+            _ => Err(format!(
+                "opção desconhecida para 'show': {}",
+                arguments.trim()
+            )),
+        }
+    }
+
+    /// Aplica `set precision <algarismos>`, o máximo de algarismos significativos dos
+    /// resultados, ou `set precision off` para mostrar todos, ou só mostra o atual.
+    pub fn set_precision(&mut self, arguments: &str) -> Result<String, String> {
+        match arguments.trim() {
+            "" => {}
+            "off" => self.precision = None,
+            digits => {
+                let digits = digits
+                    .parse()
+                    .ok()
+                    .filter(|digits| (1..=17).contains(digits))
+                    .ok_or_else(|| {
+                        format!(
+                            "precisão inválida '{}' (use de 1 a 17 algarismos, ou off)",
+                            digits
+                        )
+                    })?;
+                self.precision = Some(digits);
+            }
+        }
+        Ok(match self.precision {
+            Some(digits) => format!("precisão: {} algarismos", digits),
+            None => "precisão: todos os algarismos".to_string(),
+        })
+    }
+
+    /// Aplica `set base dec|hex|oct|bin`, ou só mostra a base atual.
+    pub fn set_base(&mut self, arguments: &str) -> Result<String, String> {
+        if !arguments.trim().is_empty() {
+            self.base = Base::parse(arguments)?;
+        }
+        Ok(format!("base: {}", self.base))
+    }
+
+    /// Aplica `set all_bases on|off`, ou só mostra se as outras bases são mostradas.
+    pub fn set_all_bases(&mut self, arguments: &str) -> Result<String, String> {
+        match arguments.trim() {
+            "" => {}
+            "on" => self.all_bases = true,
+            "off" => self.all_bases = false,
+            other => {
+                return Err(format!(
+                    "opção desconhecida para 'show all_bases': {}",
+                    other
+                ))
+            }
+        }
+        Ok(format!(
+            "todas as bases {}",
+            if self.all_bases {
+                "ligadas"
+            } else {
+                "desligadas"
+            }
+        ))
+    }
+
+    /// A correção de `autocorrect` para `input`, se a linha não pode ser lida como está e a
+    /// corrigida pode.
+    pub fn correction(&self, input: &str) -> Option<String> {
+        let parses = |line: &str| {
+            self.prepare(line).is_ok_and(|prepared| {
+                let (text, _) = split_conversion(&prepared.text);
+                Statement::new(text, &self.context).is_ok()
+            })
+        };
+        let corrected = autocorrect::correct(input)?;
+        (!parses(input) && parses(&corrected)).then_some(corrected)
+    }
+
+    /// O resultado `value` da última linha escrito de um jeito que pode ser lido de volta, para o
+    /// Alt+. do editor: com todos os dígitos do `f64` e na unidade em que ele foi mostrado, se
+    /// ela puder ser lida como está (`km`, mas não `m·s⁻¹`). Senão, é só o número, em unidades do
+    /// SI. Infinitos e NaN não têm como ser escritos.
+    pub fn result_literal(&self, value: f64) -> Option<String> {
+        if !value.is_finite() {
+            return None;
+        }
+        let unit = match &self.last_unit {
+            Some((symbol, factor)) => Some((symbol.as_str(), *factor)),
+            None if self.last_duration => Some(("s", 1.0)),
+            None => None,
+        };
+        if let Some((symbol, factor)) = unit {
+            let text = format!("{} {}", literal(value / factor), symbol);
+            // o valor lido de volta pode diferir nos últimos bits pela divisão
+            let read = self.evaluate_argument(&text);
+            if read.is_ok_and(|read| (read - value).abs() <= value.abs() * 1e-12) {
+                return Some(text);
+            }
+        }
+        Some(literal(value))
+    }
+
+    /// Escreve o último resultado na notação atual, arredondado para os seus algarismos
+    /// significativos se o `sigfigs` estiver ligado e na unidade dele, se tiver uma, ou seguido
+    /// das outras bases se o `show all_bases` estiver, com no máximo os algarismos do
+    /// `set precision` e na base do `set base`.
+    pub fn format_result(&self, value: f64) -> String {
+        if self.last_dms {
+            if let Ok(text) = format::dms(self.context.angle_mode().to_degrees(value)) {
+                return text;
+            }
+        }
+        if self.last_duration {
+            return self.duration_format.format(value, &self.notation);
+        }
+        let (value, unit) = match &self.last_unit {
+            Some((symbol, factor)) => (value / factor, Some(symbol)),
+            None => (value, None),
+        };
+        let figures = match (self.last_figures, self.precision) {
+            (Some(figures), Some(precision)) => Some(figures.min(precision)),
+            (figures, precision) => figures.or(precision),
+        };
+        let result = match figures {
+            Some(figures) => self.notation.format_significant(value, figures),
+            None => self.notation.format(value),
+        };
+        if let Some(symbol) = unit {
+            return format!("{} {}", result, symbol);
+        }
+        let result = self.base.format(value).unwrap_or(result);
+        match format::other_bases(value, self.context.word_size()).filter(|_| self.all_bases) {
+            Some(bases) => format!("{}  {}", result, bases),
+            None => result,
+        }
+    }
+
+    /// Aplica `pager auto` ou `pager off`, ou só mostra o modo atual.
+    pub fn set_pager(&mut self, arguments: &str) -> Result<String, String> {
+        if !arguments.trim().is_empty() {
+            self.pager = PagerMode::parse(arguments)?;
+        }
+        Ok(format!("pager: {}", self.pager))
+    }
+
+    /// Aplica `match_brackets on|off` ou `autoclose on|off`, ou só mostra se a opção está ligada.
+    pub fn set_editor_option(&mut self, name: &str, arguments: &str) -> Result<String, String> {
+        let option = match name {
+            "match_brackets" => &mut self.editor.match_brackets,
+            _ => &mut self.editor.autoclose,
+        };
+        match arguments.trim() {
+            "" => {}
+            "on" => *option = true,
+            "off" => *option = false,
+            other => return Err(format!("opção desconhecida para '{}': {}", name, other)),
+        }
+        Ok(format!("{} {}", name, if *option { "on" } else { "off" }))
+    }
+
+    /// Guarda `result` na variável `r<n>` seguinte à maior que já existe, para que a numeração
+    /// continue de onde parou depois de trocar de workspace.
+    fn store_result(&mut self, result: f64, dimension: Dimension) -> String {
+        let last = self
+            .context
+            .variables()
+            .filter_map(|(name, _)| name.strip_prefix('r')?.parse::<u64>().ok())
+            .max()
+            .unwrap_or(0);
+        let name = format!("r{}", last + 1);
+        self.context.set_quantity(name.clone(), result, dimension);
+        name
+    }
+
+    pub fn prompt_template(&self) -> &str {
+        self.prompt.as_deref().unwrap_or(prompt::DEFAULT_PROMPT)
+    }
+
+    pub fn workspace(&self) -> Option<&str> {
+        self.workspace.as_deref()
+    }
+
+    /// `log on|off` liga ou desliga o registro dos cálculos, `log path <arquivo>` troca o
+    /// arquivo, e `log` só mostra como ele está.
+    pub fn log_command(&mut self, arguments: &str) -> Result<String, String> {
+        let arguments = arguments.trim();
+        let (option, path) = arguments.split_once(' ').unwrap_or((arguments, ""));
+        match (option.to_lowercase().as_str(), path) {
+            ("", _) => {}
+            ("on", "") => {
+                self.log_file()?;
+                self.logging = true;
+            }
+            ("off", "") => self.logging = false,
+            ("path", path) if !path.trim().is_empty() => {
+                self.log_path = Some(PathBuf::from(path.trim()));
+            }
+            _ => return Err("uso: log on|off|path <arquivo>".to_string()),
+        }
+        let file = self.log_file()?;
+        Ok(if self.logging {
+            format!("log ligado: {}", file.display())
+        } else {
+            format!("log desligado ({})", file.display())
+        })
+    }
+
+    fn log_file(&self) -> Result<PathBuf, String> {
+        self.log_path
+            .clone()
+            .or_else(log::default_path)
+            .ok_or_else(|| "não há um diretório para o log, use 'log path <arquivo>'".to_string())
+    }
+
+    /// `cache stats` (ou só `cache`) mostra quanto o cache foi usado, e `cache clear` o esvazia.
+    fn cache_command(&mut self, arguments: &str) -> Result<String, String> {
+        match arguments.trim() {
+            "" | "stats" => Ok(self.cache.stats()),
+            "clear" => {
+                self.cache.clear();
+                Ok("cache limpo".to_string())
+            }
+            _ => Err("uso: cache [stats|clear]".to_string()),
+        }
+    }
+
+    /// `check <expressão>`: diz se a expressão é válida, ou quais são os erros dela, sem
+    /// calcular nada.
+    fn check_command(&self, arguments: &str) -> Result<String, String> {
+        if arguments.trim().is_empty() {
+            return Err("uso: check <expressão>".to_string());
+        }
+        self.check(arguments.trim())
+            .map(|()| "expressão válida".to_string())
+            .map_err(|errors| {
+                let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                messages.join("\n")
+            })
+    }
+
+    /// `export json <arquivo>`: salva o histórico, as variáveis e as funções da sessão no
+    /// formato descrito em `export`.
+    fn export_command(&self, arguments: &str) -> Result<String, String> {
+        let path = json_path(arguments, "export")?;
+        let mut variables = self.variables_with_dimensions();
+        variables.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+        let mut functions: Vec<FunctionDefinition> = self
+            .context
+            .functions()
+            .map(|(name, function)| FunctionDefinition {
+                name: name.to_string(),
+                parameters: function.parameters().to_vec(),
+                body: function.source().to_string(),
+            })
+            .collect();
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+        let export = SessionExport {
+            history: self.history.clone(),
+            variables,
+            functions,
+        };
+        export.write(&path)?;
+        Ok(format!(
+            "exportado para {}: {}",
+            path.display(),
+            summary(&export)
+        ))
+    }
+
+    /// `import json <arquivo>`: junta à sessão o que foi salvo com `export json`. As linhas vão
+    /// para o fim do histórico, e as variáveis e funções com o mesmo nome são substituídas (as
+    /// variáveis podem ser recuperadas com `undo`).
+    fn import_command(&mut self, arguments: &str) -> Result<String, String> {
+        let path = json_path(arguments, "import")?;
+        let export = SessionExport::read(&path)?;
+
+        // as funções são lidas antes de mudar qualquer coisa, para que um arquivo com erro não
+        // seja importado pela metade
+        if let Some((name, ..)) = export
+            .variables
+            .iter()
+            .find(|(name, ..)| self.context.constant(name).is_some())
+        {
+            return Err(format!("{}: {} é uma constante", path.display(), name));
+        }
+        let mut context = self.context.clone();
+        let mut functions = Vec::new();
+        for definition in &export.functions {
+            let function = Function::new(definition.parameters.clone(), &definition.body, &context)
+                .map_err(|errors| {
+                    format!(
+                        "{}: função {}: {}",
+                        path.display(),
+                        definition.name,
+                        join_errors(&errors)
+                    )
+                })?;
+            context.define_function(definition.name.clone(), function.clone());
+            functions.push((definition.name.clone(), function));
+        }
+        for (name, function) in functions {
+            self.context.define_function(name, function);
+        }
+        for (name, value, quantity) in &export.variables {
+            let dimension = self.context.dimension(name);
+            let previous = self.context.set_quantity(name.clone(), *value, *quantity);
+            self.undo_journal.push(Redefinition {
+                name: name.clone(),
+                previous,
+                dimension,
+            });
+        }
+        self.history.extend(export.history.iter().cloned());
+        Ok(format!(
+            "importado de {}: {}",
+            path.display(),
+            summary(&export)
+        ))
+    }
+
+    /// `which <expressão>`: as linhas do histórico cujo resultado é igual ao valor, ou quase
+    /// igual como no `~=` (marcadas com `≈`).
+    fn which(&self, arguments: &str) -> Result<String, String> {
+        if arguments.trim().is_empty() {
+            return Err("uso: which <valor>".to_string());
+        }
+        let value = self.evaluate_argument(arguments)?;
+        let lines: Vec<String> = self
+            .history
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| {
+                let scale = entry.result.abs().max(value.abs()).max(1.0);
+                if entry.result == value {
+                    Some(self.history_line(i, entry, "="))
+                } else if (entry.result - value).abs() <= self.context.approx_tolerance() * scale {
+                    Some(self.history_line(i, entry, "\u{2248}"))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if lines.is_empty() {
+            Err(format!(
+                "nenhum resultado do histórico é {}",
+                self.notation.format(value)
+            ))
+        } else {
+            Ok(lines.join("\n"))
+        }
+    }
+
+    /// `history` lista o histórico com a posição de cada linha, e `history search <texto>` só as
+    /// linhas que contêm o texto.
+    fn history_command(&self, arguments: &str) -> Result<String, String> {
+        let query = match arguments.trim().split_once(' ') {
+            _ if arguments.trim().is_empty() => "",
+            Some(("search", query)) => query.trim(),
+            _ => return Err("uso: history [search <texto>]".to_string()),
+        };
+        let lines: Vec<String> = self
+            .history
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.input.to_lowercase().contains(query))
+            .map(|(i, entry)| self.history_line(i, entry, "="))
+            .collect();
+        if lines.is_empty() {
+            Err(if query.is_empty() {
+                "o histórico está vazio".to_string()
+            } else {
+                format!("nenhuma linha do histórico contém '{}'", query)
+            })
+        } else {
+            Ok(lines.join("\n"))
+        }
+    }
+
+    /// Linha `n: input = resultado` do histórico, com a posição começando em 1.
+    fn history_line(&self, index: usize, entry: &HistoryEntry, relation: &str) -> String {
+        format!(
+            "{}: {} {} {}",
+            index + 1,
+            entry.input,
+            relation,
+            self.notation.format(entry.result)
+        )
+    }
+
+    /// Calcula a expressão passada para um comando, sem que ela entre no histórico.
+    fn evaluate_argument(&self, text: &str) -> Result<f64, String> {
+        Expression::with_context(text, &self.context)
+            .and_then(|expression| {
+                expression
+                    .evaluate_with(&self.context)
+                    .map_err(|error| vec![error])
+            })
+            .map_err(|errors| join_errors(&errors))
+    }
+
+    /// Calcula cada um dos argumentos separados por vírgulas de um comando como `polar(...)`.
+    fn evaluate_arguments(&self, arguments: &str) -> Result<Vec<f64>, String> {
+        split_arguments(arguments)
+            .into_iter()
+            .map(|argument| {
+                self.evaluate_argument(argument)
+                    .map_err(|message| format!("{}: {}", argument.trim(), message))
+            })
+            .collect()
+    }
+
+    /// `inspect <expressão>`, ou `inspect` para o último resultado: como o valor está guardado
+    /// no `f64`.
+    pub fn inspect(&self, arguments: &str) -> Result<String, String> {
+        let value = if arguments.trim().is_empty() {
+            self.history
+                .last()
+                .ok_or_else(|| "nenhum resultado para inspecionar ainda".to_string())?
+                .result
+        } else {
+            self.evaluate_argument(arguments)?
+        };
+        Ok(inspect::describe(value))
+    }
+
+    /// `as_fraction [denominador máximo]`, o último resultado como a fração mais próxima.
+    pub fn as_fraction(&self, arguments: &str) -> Result<String, String> {
+        let arguments = arguments.trim();
+        let max_denominator = if arguments.is_empty() {
+            fraction::DEFAULT_MAX_DENOMINATOR
+        } else {
+            arguments
+                .parse()
+                .ok()
+                .filter(|max_denominator| *max_denominator > 0)
+                .ok_or_else(|| format!("denominador máximo inválido: {}", arguments))?
+        };
+        let last = self
+            .history
+            .last()
+            .ok_or_else(|| "nenhum resultado para converter ainda".to_string())?;
+        fraction::describe(last.result, max_denominator)
+    }
+
+    /// Aplica `word u8|u16|u32|u64`, o tamanho da palavra das funções de bits, ou só mostra o
+    /// atual.
+    pub fn set_word_size(&mut self, arguments: &str) -> Result<String, String> {
+        let arguments = arguments.trim();
+        if !arguments.is_empty() {
+            let word_size = arguments
+                .strip_prefix('u')
+                .and_then(|bits| bits.parse().ok())
+                .and_then(WordSize::from_bits)
+                .ok_or_else(|| {
+                    format!(
+                        "tamanho de palavra desconhecido '{}' (use u8, u16, u32 ou u64)",
+                        arguments
+                    )
+                })?;
+            self.context.set_word_size(word_size);
+        }
+        Ok(format!("palavra: u{}", self.context.word_size().bits()))
+    }
+
+    /// `unit define <nome> = <expressão>` define uma unidade a partir das que já existem
+    /// (`unit define furlong = 201.168 m`), ou uma contagem sem unidade se a expressão for
+    /// omitida (`unit define rack`). `unit remove <nome>` remove uma, e `unit` lista as
+    /// definidas.
+    fn unit_command(&mut self, arguments: &str) -> Result<String, String> {
+        let usage = || "uso: unit define nome = expressão, ou unit remove nome".to_string();
+        let arguments = arguments.trim();
+        let (action, arguments) = arguments.split_once(' ').unwrap_or((arguments, ""));
+        match action.to_lowercase().as_str() {
+            "" => {
+                let lines: Vec<String> = self
+                    .context
+                    .units()
+                    .map(|(name, unit)| format!("1 {} = {}", name, self.format_quantity(unit)))
+                    .collect();
+                if lines.is_empty() {
+                    Ok("nenhuma unidade definida".to_string())
+                } else {
+                    Ok(lines.join("\n"))
+                }
+            }
+            "define" => {
+                let (name, value) = match arguments.split_once('=') {
+                    Some((name, value)) => (name.trim(), Some(value)),
+                    None => (arguments.trim(), None),
+                };
+                if !is_name(name) {
+                    return Err(format!("nome de unidade inválido '{}'", name));
+                }
+                let unit = match value {
+                    Some(value) => {
+                        let unit = self
+                            .evaluate_quantity(value)
+                            .map_err(|message| format!("unit {}: {}", name, message))?;
+                        if !unit.factor.is_finite() || unit.factor == 0.0 {
+                            return Err(format!(
+                                "unit {}: a unidade precisa valer um número finito e diferente de zero",
+                                name
+                            ));
+                        }
+                        unit
+                    }
+                    None => Unit::new(1.0, Dimension::NONE),
+                };
+                self.context.define_unit(name, unit);
+                Ok(format!("1 {} = {}", name, self.format_quantity(unit)))
+            }
+            "remove" => {
+                let name = arguments.trim();
+                match self.context.remove_unit(name) {
+                    Some(_) => Ok(format!("{} foi removida", name)),
+                    None => Err(format!("unidade do usuário desconhecida '{}'", name)),
+                }
+            }
+            _ => Err(usage()),
+        }
+    }
+
+    /// O valor de uma expressão com unidade passada para um comando, como `Unit` (`201.168 m`).
+    fn evaluate_quantity(&self, text: &str) -> Result<Unit, String> {
+        let text = self.lowercase_names(text);
+        Expression::with_context(&text, &self.context)
+            .and_then(|expression| {
+                let value = expression.dimension(&self.context).and_then(|dimension| {
+                    Ok(Unit::new(
+                        expression.evaluate_with(&self.context)?,
+                        dimension,
+                    ))
+                });
+                value.map_err(|error| vec![error])
+            })
+            .map_err(|errors| join_errors(&errors))
+    }
+
+    /// O valor na unidade do sistema de `units` para a dimensão dele.
+    fn format_quantity(&self, quantity: Unit) -> String {
+        if quantity.dimension.is_none() {
+            return self.notation.format(quantity.factor);
+        }
+        if quantity.dimension == Dimension::TIME {
+            return self.duration_format.format(quantity.factor, &self.notation);
+        }
+        let (symbol, factor) = if quantity.dimension == Dimension::INFORMATION {
+            unit_system::data_size(quantity.factor, false, &self.context)
+        } else {
+            self.units.unit(quantity.dimension, &self.context)
+        };
+        format!(
+            "{} {}",
+            self.notation.format(quantity.factor / factor),
+            symbol
+        )
+    }
+
+    /// Aplica `units si|imperial|cgs`, ou só mostra o sistema atual.
+    pub fn set_unit_system(&mut self, arguments: &str) -> Result<String, String> {
+        if !arguments.trim().is_empty() {
+            self.units = UnitSystem::parse(arguments)?;
+        }
+        Ok(format!("unidades: {}", self.units))
+    }
+
+    /// Aplica `summation naive|kahan|neumaier|pairwise`, o algoritmo das somas de `sum(...)` e
+    /// `mean(...)`, ou só mostra o atual.
+    pub fn set_summation(&mut self, arguments: &str) -> Result<String, String> {
+        let arguments = arguments.trim();
+        if !arguments.is_empty() {
+            let summation = Summation::from_name(arguments).ok_or_else(|| {
+                format!(
+                    "algoritmo de soma desconhecido '{}' (use naive, kahan, neumaier ou pairwise)",
+                    arguments
+                )
+            })?;
+            self.context.set_summation(summation);
+        }
+        Ok(format!("soma: {}", self.context.summation().name()))
+    }
+
+    /// Aplica `angle deg` ou `angle rad`, ou só mostra o modo atual se `arguments` estiver vazio.
+    pub fn set_angle_mode(&mut self, arguments: &str) -> Result<String, String> {
+        match arguments.trim() {
+            "" => {}
+            "deg" => self.context.set_angle_mode(AngleMode::Degrees),
+            "rad" => self.context.set_angle_mode(AngleMode::Radians),
+            other => {
+                return Err(format!(
+                    "modo de ângulo desconhecido '{}' (use deg ou rad)",
+                    other
+                ))
+            }
+        }
+        Ok(match self.context.angle_mode() {
+            AngleMode::Degrees => "ângulos em graus",
+            AngleMode::Radians => "ângulos em radianos",
+        }
+        .to_string())
+    }
+
+    /// `to dms`, o último resultado (no modo de ângulo atual) em graus, minutos e segundos.
+    pub fn to_dms(&self) -> Result<String, String> {
+        let last = self
+            .history
+            .last()
+            .ok_or_else(|| "nenhum resultado para converter ainda".to_string())?;
+        format::dms(self.context.angle_mode().to_degrees(last.result))
+    }
+
+    /// `undo`, volta a variável da última atribuição ao valor que tinha antes dela.
+    pub fn undo(&mut self) -> Result<String, String> {
+        let Redefinition {
+            name,
+            previous,
+            dimension,
+        } = self
+            .undo_journal
+            .pop()
+            .ok_or_else(|| "nenhuma atribuição para desfazer".to_string())?;
+        match previous {
+            Some(value) => {
+                self.context.set_quantity(name.clone(), value, dimension);
+                Ok(format!("{} = {}", name, self.notation.format(value)))
+            }
+            None => {
+                self.context.remove_variable(&name);
+                Ok(format!("{} foi removida", name))
+            }
+        }
+    }
+
+    /// `workspace`, que mostra o workspace em uso, e `workspace use <nome>`.
+    fn workspace_command(&mut self, arguments: &str) -> Result<String, String> {
+        let mut words = arguments.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (None, ..) => {}
+            (Some("use"), Some(name), None) => self.use_workspace(name)?,
+            _ => return Err("uso: workspace [use <nome>]".to_string()),
+        }
+        Ok(match &self.workspace {
+            Some(name) => format!("workspace: {}", name),
+            None => "nenhum workspace em uso".to_string(),
+        })
+    }
+
+    /// Salva o workspace em uso e troca as variáveis e o histórico da sessão pelos do workspace
+    /// `name`, que começa vazio se ainda não existir. As funções e configurações (modo de ângulo,
+    /// formato...) continuam as mesmas.
+    pub fn use_workspace(&mut self, name: &str) -> Result<(), String> {
+        workspace::validate_name(name)?;
+        let directory = self
+            .workspace_directory
+            .clone()
+            .ok_or_else(|| "não há um diretório para salvar os workspaces".to_string())?;
+        self.save_workspace()?;
+        let Workspace { variables, history } = Workspace::load(&directory, name)?;
+
+        let names: Vec<String> = self
+            .context
+            .variables()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        for name in names {
+            self.context.remove_variable(&name);
+        }
+        for (name, value, dimension) in variables {
+            self.context.set_quantity(name, value, dimension);
+        }
+        self.history = history;
+        // desfazer uma atribuição de outro workspace mudaria este
+        self.undo_journal.clear();
+        self.workspace = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Salva as variáveis e o histórico no arquivo do workspace em uso, se houver um.
+    pub fn save_workspace(&self) -> Result<(), String> {
+        let (Some(name), Some(directory)) = (&self.workspace, &self.workspace_directory) else {
+            return Ok(());
+        };
+        let mut variables = self.variables_with_dimensions();
+        // a ordem do mapa de variáveis muda de uma execução para outra
+        variables.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+        Workspace {
+            variables,
+            history: self.history.clone(),
+        }
+        .save(directory, name)
+    }
+
+    /// As variáveis do contexto com o valor em unidades do SI e a dimensão de cada uma, como
+    /// são salvas nos workspaces e no `export json`.
+    fn variables_with_dimensions(&self) -> Vec<(String, f64, Dimension)> {
+        self.context
+            .variables()
+            .map(|(name, value)| (name.to_string(), value, self.context.dimension(name)))
+            .collect()
+    }
+
+    /// `polar(x, y)` e `cart(r, θ)`, mostrando o par convertido no formato dos resultados.
+    fn convert_coordinates(
+        &self,
+        name: &str,
+        arguments: &str,
+        convert: fn(f64, f64, AngleMode) -> (f64, f64),
+    ) -> Result<String, String> {
+        let values = self.evaluate_arguments(arguments)?;
+        let [first, second] = values[..] else {
+            return Err(format!(
+                "a função '{}' recebe 2 argumento(s), mas {} foram passados",
+                name,
+                values.len()
+            ));
+        };
+
+        let (first, second) = convert(first, second, self.context.angle_mode());
+        Ok(format!(
+            "({}, {})",
+            self.notation.format(first),
+            self.notation.format(second)
+        ))
+    }
+
+    /// `aspect(largura, altura)`, a proporção simplificada de uma tela (`16:9`).
+    fn aspect(&self, arguments: &str) -> Result<String, String> {
+        let values = self.evaluate_arguments(arguments)?;
+        let [width, height] = values[..] else {
+            return Err(format!(
+                "a função 'aspect' recebe 2 argumento(s), mas {} foram passados",
+                values.len()
+            ));
+        };
+        screen::aspect(width, height)
+    }
+
+    /// `hex_to_rgb(cor)`, `rgb_to_hex(r, g, b)`, `blend(cor, cor, t)`, `lighten(cor, t)` e
+    /// `darken(cor, t)`, com frações `t` entre 0 e 1. As cores são escritas como `#336699` ou como qualquer expressão que dê
+    /// o número da cor (`0x336699`).
+    fn color(&self, name: &str, arguments: &str) -> Result<String, String> {
+        let arguments = split_arguments(arguments);
+        let arity = match name {
+            "hex_to_rgb" => 1,
+            "rgb_to_hex" | "blend" => 3,
+            _ => 2,
+        };
+        if arguments.len() != arity {
+            return Err(format!(
+                "a função '{}' recebe {} argumento(s), mas {} foram passados",
+                name,
+                arity,
+                arguments.len()
+            ));
+        }
+        let color = |argument: &str| match argument.trim() {
+            hex if hex.starts_with('#') => color::parse_hex(hex),
+            argument => color::from_value(self.evaluate_argument(argument)?),
+        };
+        let number = |argument: &str| {
+            self.evaluate_argument(argument)
+                .map_err(|message| format!("{}: {}", argument.trim(), message))
+        };
+        match name {
+            "hex_to_rgb" => {
+                let [red, green, blue] = color(arguments[0])?;
+                Ok(format!("({}, {}, {})", red, green, blue))
+            }
+            "rgb_to_hex" => {
+                let color = color::from_components(
+                    number(arguments[0])?,
+                    number(arguments[1])?,
+                    number(arguments[2])?,
+                )?;
+                Ok(color::to_hex(color))
+            }
+            "blend" => color::blend(
+                color(arguments[0])?,
+                color(arguments[1])?,
+                number(arguments[2])?,
+            )
+            .map(color::to_hex),
+            "lighten" => {
+                color::lighten(color(arguments[0])?, number(arguments[1])?).map(color::to_hex)
+            }
+            _ => color::darken(color(arguments[0])?, number(arguments[1])?).map(color::to_hex),
+        }
+    }
+
+    /// `unix(aaaa-mm-dd hh:mm)`, `from_unix(segundos)`, `now()` e `today()`, sempre em UTC.
+    fn timestamp(&self, name: &str, arguments: &str) -> Result<String, String> {
+        let arguments = arguments.trim();
+        match name {
+            "unix" => Ok(date::parse_timestamp(arguments)?.to_string()),
+            "from_unix" => {
+                let seconds = self
+                    .evaluate_argument(arguments)
+                    .map_err(|message| format!("{}: {}", arguments, message))?;
+                // de 0000-01-01 a 9999-12-31, as datas que `unix` consegue ler de volta
+                if seconds.fract() != 0.0
+                    || !(-62_167_219_200.0..=253_402_300_799.0).contains(&seconds)
+                {
+                    return Err(format!(
+                        "o timestamp precisa ser um número inteiro de segundos entre os anos 0 e 9999: {}",
+                        seconds
+                    ));
+                }
+                Ok(date::format_timestamp(seconds as i64))
+            }
+            _ if !arguments.is_empty() => Err(format!(
+                "a função '{}' recebe 0 argumento(s), mas 1 foram passados",
+                name
+            )),
+            "now" => Ok(now().to_string()),
+            _ => Ok(Date::from_timestamp(now()).to_string()),
+        }
+    }
+
+    /// `workdays(início, fim)`, os dias úteis entre as duas datas, contando as duas.
+    fn workdays(&self, arguments: &str) -> Result<String, String> {
+        let [start, end] = split_arguments(arguments)[..] else {
+            return Err("uso: workdays(aaaa-mm-dd, aaaa-mm-dd)".to_string());
+        };
+        let days = self
+            .calendar
+            .workdays(date_argument(start)?, date_argument(end)?);
+        Ok(days.to_string())
+    }
+
+    /// `add_workdays(data, dias)`, a data `dias` dias úteis depois.
+    fn add_workdays(&self, arguments: &str) -> Result<String, String> {
+        let [date, days] = split_arguments(arguments)[..] else {
+            return Err("uso: add_workdays(aaaa-mm-dd, dias)".to_string());
+        };
+        let date = date_argument(date)?;
+        let days = self
+            .evaluate_argument(days)
+            .map_err(|message| format!("{}: {}", days.trim(), message))?;
+        if days.fract() != 0.0 || days.abs() > 1e15 {
+            return Err(format!("o número de dias precisa ser inteiro: {}", days));
+        }
+        Ok(self.calendar.add_workdays(date, days as i64)?.to_string())
+    }
+
+    /// Aplica `weekend sat sun` (ou `none`), ou só mostra os dias do fim de semana.
+    pub fn set_weekend(&mut self, arguments: &str) -> Result<String, String> {
+        if !arguments.trim().is_empty() {
+            self.calendar.set_weekend(arguments)?;
+        }
+        Ok(format!(
+            "fim de semana: {}",
+            self.calendar.describe_weekend()
+        ))
+    }
+
+    /// `alias nome = texto` faz com que `nome` seja trocado por `texto` nas linhas seguintes
+    /// (veja `alias::expand`), e `alias` sozinho lista os que existem.
+    fn define_alias(&mut self, arguments: &str) -> Result<String, String> {
+        if arguments.trim().is_empty() {
+            if self.aliases.is_empty() {
+                return Ok("nenhum alias definido".to_string());
+            }
+            let lines: Vec<String> = self
+                .aliases
+                .iter()
+                .map(|(name, text)| format!("{} = {}", name, text))
+                .collect();
+            return Ok(lines.join("\n"));
+        }
+        let (name, text) = arguments
+            .split_once('=')
+            .ok_or_else(|| "uso: alias nome = texto".to_string())?;
+        let (name, text) = (name.trim().to_lowercase(), text.trim());
+        if !is_name(&name) {
+            return Err(format!("nome de alias inválido '{}'", name));
+        }
+        if text.is_empty() {
+            return Err(format!("o alias {} precisa de um texto", name));
+        }
+        self.aliases.insert(name.clone(), text.to_string());
+        Ok(format!("{} = {}", name, text))
+    }
+
+    /// `unalias nome`, que esquece o apelido.
+    fn remove_alias(&mut self, arguments: &str) -> Result<String, String> {
+        let name = arguments.trim().to_lowercase();
+        match self.aliases.remove(&name) {
+            Some(_) => Ok(format!("{} foi removido", name)),
+            None => Err(format!("alias desconhecido '{}'", name)),
+        }
+    }
+
+    /// `line` com os apelidos trocados pelo texto deles.
+    pub fn expand_aliases(&self, line: &str) -> String {
+        alias::expand(line, &self.aliases)
+    }
+
+    /// `holidays <arquivo>` lê os feriados de `workdays`, `holidays off` os esquece, e
+    /// `holidays` mostra quantos são.
+    fn holidays_command(&mut self, arguments: &str) -> Result<String, String> {
+        match arguments.trim() {
+            "" => {}
+            off if off.eq_ignore_ascii_case("off") => self.calendar.holidays.clear(),
+            path => self.calendar.holidays = calendar::read_holidays(Path::new(path))?,
+        }
+        Ok(match self.calendar.holidays.len() {
+            0 => "nenhum feriado".to_string(),
+            1 => "1 feriado".to_string(),
+            count => format!("{} feriados", count),
+        })
+    }
+
+    /// `compare <fórmula> with a=1, b=2; a=3, b=4`: calcula a fórmula com cada conjunto de
+    /// variáveis, definidas em ordem como as do `-D`, e mostra uma tabela com uma linha por
+    /// conjunto. As variáveis da sessão não mudam.
+    pub fn compare(&self, arguments: &str) -> Result<String, String> {
+        let usage = || "uso: compare <fórmula> with a=1, b=2; a=3, b=4".to_string();
+        let arguments = self.lowercase_names(arguments);
+        let (formula, sets) = arguments.split_once(" with ").ok_or_else(usage)?;
+        let formula = formula.trim();
+        if formula.is_empty() {
+            return Err(usage());
+        }
+
+        let mut names: Vec<String> = Vec::new();
+        let mut rows = Vec::new();
+        for (index, set) in sets.split(';').enumerate() {
+            if set.trim().is_empty() {
+                continue;
+            }
+            let definitions = split_arguments(set)
+                .into_iter()
+                .map(|definition| {
+                    let (name, value) = definition
+                        .split_once('=')
+                        .ok_or_else(|| format!("definição inválida '{}'", definition.trim()))?;
+                    let name = name.trim();
+                    if !is_name(name) {
+                        return Err(format!("nome inválido '{}'", name));
+                    }
+                    Ok((name.to_string(), value.trim().to_string()))
+                })
+                .collect::<Result<Vec<_>, String>>()
+                .and_then(|definitions| {
+                    let context = crate::define_variables(self.context.clone(), &definitions)?;
+                    Ok((definitions, context))
+                });
+            let (definitions, context) =
+                definitions.map_err(|message| format!("conjunto {}: {}", index + 1, message))?;
+            let result = Expression::with_context(formula, &context)
+                .and_then(|expression| {
+                    expression
+                        .evaluate_with(&context)
+                        .map_err(|error| vec![error])
+                })
+                .map_err(|errors| format!("conjunto {}: {}", index + 1, join_errors(&errors)))?;
+
+            for (name, _) in &definitions {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+            rows.push((context, result));
+        }
+
+        let header: Vec<String> = names.iter().cloned().chain([formula.to_string()]).collect();
+        let rows: Vec<Vec<String>> = rows
+            .iter()
+            .map(|(context, result)| {
+                names
+                    .iter()
+                    .map(|name| {
+                        context
+                            .variable(name)
+                            .map(|value| self.notation.format(value))
+                            .unwrap_or_default()
+                    })
+                    .chain([self.notation.format(*result)])
+                    .collect()
+            })
+            .collect();
+        Ok(table::render_to_fit(&header, &rows).trim_end().to_string())
+    }
+
+    /// `amortize(principal, taxa, anos)`, a parcela e os juros de um financiamento, com a tabela
+    /// de todos os meses se `table`.
+    fn amortize(&self, arguments: &str, table: bool) -> Result<String, String> {
+        let values = self.evaluate_arguments(arguments)?;
+        let [principal, rate, years] = values[..] else {
+            return Err(format!(
+                "a função 'amortize' recebe 3 argumento(s), mas {} foram passados",
+                values.len()
+            ));
+        };
+        finance::describe(principal, rate, years, table)
+    }
+
+    /// Calcula uma linha sem diferenciar maiúsculas de minúsculas nos nomes (`PI` é o mesmo que
+    /// `pi`), a não ser nos das variáveis de ambiente.
+    pub fn eval(&mut self, input: &str) -> Result<f64, Vec<Error>> {
+        let result = self.eval_statement(input);
+        if let Err(errors) = &result {
+            if self.recent_errors.len() == RECENT_ERRORS {
+                self.recent_errors.pop_front();
+            }
+            self.recent_errors.push_back(ErrorEntry {
+                input: input.to_string(),
+                errors: errors.clone(),
+            });
+        }
+        result
+    }
+
+    /// Lê `input` como o `eval` leria, sem calcular nada, para conferir se ele é válido.
+    pub fn check(&self, input: &str) -> Result<(), Vec<Error>> {
+        let prepared = self.prepare(input)?;
+        let (text, target) = split_conversion(&prepared.text);
+        target
+            .map(|(offset, target)| self.conversion(offset, target))
+            .transpose()
+            .and_then(|conversion| {
+                let statement = Statement::new(text, &self.context)?;
+                self.result_dimension(&statement, conversion.as_ref())
+                    .map(|_| ())
+            })
+            .map_err(|errors| prepared.restore(errors))
+    }
+
+    /// A dimensão do resultado de `statement`, conferindo que as unidades combinam e que ela é
+    /// a da unidade do `to`, se a linha tiver um.
+    fn result_dimension(
+        &self,
+        statement: &Statement,
+        conversion: Option<&(String, Unit, Span)>,
+    ) -> Result<Dimension, Vec<Error>> {
+        let dimension = statement
+            .expression()
+            .dimension(&self.context)
+            .map_err(|error| vec![error])?;
+        if let Some((_, unit, span)) = conversion {
+            if unit.dimension != dimension {
+                return Err(vec![Error::IncompatibleUnits(
+                    dimension,
+                    unit.dimension,
+                    *span,
+                )]);
+            }
+        }
+        Ok(dimension)
+    }
+
+    /// O texto que é de fato lido: com as variáveis de ambiente trocadas (`--env`) e os nomes em
+    /// minúsculas. Os erros encontrados nele precisam passar por `Interpolated::restore`.
+    fn prepare(&self, input: &str) -> Result<Interpolated, Vec<Error>> {
+        let mut prepared = if self.interpolate_env {
+            env::interpolate_env(input)?
+        } else {
+            Interpolated::unchanged(input)
+        };
+        prepared.text = self.lowercase_names(&prepared.text);
+        Ok(prepared)
+    }
+
+    /// `lowercase_names`, mantendo as unidades em que as maiúsculas importam (`N` é newton).
+    fn lowercase_names(&self, text: &str) -> String {
+        lowercase_names(text, |name| self.context.unit(name).is_some())
+    }
+
+    /// A unidade do `to <unidade>` que começa no caractere `offset` da linha, com o trecho dela
+    /// para os erros.
+    fn conversion(&self, offset: usize, target: &str) -> Result<(String, Unit, Span), Vec<Error>> {
+        let span = Span::new(offset, offset + target.chars().count());
+        // `to dms` só muda como o ângulo é mostrado
+        if target.trim() == "dms" {
+            return Ok(("dms".to_string(), Unit::new(1.0, Dimension::NONE), span));
+        }
+        // os espaços no lugar do resto da linha deixam as posições dos erros certas
+        let text = format!("{}{}", " ".repeat(offset), target);
+        let expression = Expression::with_context(&text, &self.context)?;
+        let dimension = expression
+            .dimension(&self.context)
+            .and_then(|dimension| {
+                let factor = expression.evaluate_with(&self.context)?;
+                Ok(Unit::new(factor, dimension))
+            })
+            .map_err(|error| vec![error])?;
+        Ok((target.trim().to_string(), dimension, span))
+    }
+
+    fn eval_statement(&mut self, input: &str) -> Result<f64, Vec<Error>> {
+        self.last_stored = None;
+        self.last_figures = None;
+        self.last_unit = None;
+        self.last_duration = false;
+        self.last_dms = false;
+        self.last_notes.clear();
+        let prepared = self.prepare(input)?;
+        self.eval_prepared(input, &prepared.text)
+            .map_err(|errors| prepared.restore(errors))
+    }
+
+    /// O resto de `eval_statement`, com `text` já preparado a partir de `input`.
+    fn eval_prepared(&mut self, input: &str, text: &str) -> Result<f64, Vec<Error>> {
+        let (text, target) = split_conversion(text);
+        let conversion = target
+            .map(|(offset, target)| self.conversion(offset, target))
+            .transpose()?;
+        let text = text.to_string();
+        let start = Instant::now();
+        let mut lookup = self.cache.lookup(&text, &self.context);
+        let statement = match lookup.statement.take() {
+            Some(statement) => statement,
+            None => Arc::new(Statement::new(&text, &self.context)?),
+        };
+        let parsed = Instant::now();
+        let redefinition = match statement.as_ref() {
+            Statement::Assignment(name, _) => Some(Redefinition {
+                name: name.clone(),
+                previous: self.context.variable(name),
+                dimension: self.context.dimension(name),
+            }),
+            Statement::Expression(_) => None,
+        };
+
+        // um Ctrl+C ou prazo que chegou depois do fim do cálculo anterior não vale para este
+        self.context.interrupt_flag().reset();
+        let dimension = self.result_dimension(&statement, conversion.as_ref())?;
+        if let Some((timeout, watchdog)) = &self.timeout {
+            watchdog.arm(*timeout);
+        }
+        if self.significant_figures {
+            // calculado antes da atribuição, que pode mudar as variáveis usadas na expressão
+            self.last_figures = statement
+                .expression()
+                .significant_figures(&self.context)
+                .ok()
+                .and_then(|(_, figures)| figures);
+        }
+        let result = match lookup.result {
+            Some(result) => Ok(result),
+            None => statement.execute(&mut self.context),
+        };
+        if let Some((_, watchdog)) = &self.timeout {
+            watchdog.disarm();
+        }
+        let result = result.map_err(|error| vec![error])?;
+        self.cache.insert(lookup, statement, result);
+        if self.autostore && redefinition.is_none() {
+            self.last_stored = Some(self.store_result(result, dimension));
+        }
+        self.last_unit = match conversion {
+            Some((symbol, ..)) if symbol == "dms" => {
+                self.last_dms = true;
+                None
+            }
+            Some((symbol, unit, _)) => Some((symbol, unit.factor)),
+            None if dimension.is_none() => None,
+            None if dimension == Dimension::TIME => {
+                self.last_duration = true;
+                None
+            }
+            None if dimension == Dimension::INFORMATION => {
+                let binary = unit_system::uses_binary_prefixes(&text);
+                Some(unit_system::data_size(result, binary, &self.context))
+            }
+            None => Some(self.units.unit(dimension, &self.context)),
+        };
+        if self.lint {
+            self.last_notes = lint(&text, &self.context);
+        }
+        self.undo_journal.extend(redefinition);
+        self.last_timing = Some(Timing {
+            parse: parsed - start,
+            evaluation: parsed.elapsed(),
+        });
+
+        if self.logging {
+            if let Err(message) = self
+                .log_file()
+                .and_then(|file| log::append(&file, SystemTime::now(), input, result))
+            {
+                self.logging = false;
+                self.log_error = Some(format!("{} (log desligado)", message));
+            }
+        }
+
+        self.history.push(HistoryEntry {
+            input: input.to_string(),
+            result,
+        });
+        Ok(result)
+    }
+}
+
+/// `value` com os dígitos que bastam para que ele seja lido de volta igual, com expoente só
+/// quando ele é muito grande ou muito pequeno.
+fn literal(value: f64) -> String {
+    if value == 0.0 || (1e-5..1e16).contains(&value.abs()) {
+        value.to_string()
+    } else {
+        format!("{:e}", value)
+    }
+}
+
+/// Se `name` pode ser usado como nome de uma variável, constante ou unidade.
+fn is_name(name: &str) -> bool {
+    name.starts_with(|char: char| char.is_alphabetic() || char == '_')
+        && name
+            .chars()
+            .all(|char| char.is_alphanumeric() || char == '_')
+}
+
+/// Os comandos de `Session::command`, com o uso e uma descrição curta, para o `:help`. As
+/// contas que ocupam a linha inteira (`polar(x, y)`) vêm no fim.
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("vars", "as variáveis e os valores delas"),
+    (
+        "history [search <texto>]",
+        "o histórico, ou só as linhas com o texto",
+    ),
+    ("undo", "volta a variável da última atribuição"),
+    ("set <opção> <valor>", "muda uma opção"),
+    ("show options", "todas as opções com os valores atuais"),
+    ("show <opção>", "o valor de uma opção"),
+    (
+        "format <notação>",
+        "a notação dos resultados (plain, sci, eng, human)",
+    ),
+    ("format duration <formato>", "o formato das durações"),
+    ("angle deg|rad", "o modo dos ângulos"),
+    (
+        "units si|imperial|cgs",
+        "o sistema das unidades dos resultados",
+    ),
+    ("to dms", "o último resultado em graus, minutos e segundos"),
+    (
+        "prompt \"<modelo>\"",
+        "muda o prompt ({angle}, {base}, {line}...)",
+    ),
+    (
+        "workspace [use <nome>]",
+        "o workspace em uso, ou troca de workspace",
+    ),
+    (
+        "export json <arquivo>",
+        "salva o histórico, as variáveis e as funções",
+    ),
+    (
+        "import json <arquivo>",
+        "junta à sessão o que foi salvo com 'export json'",
+    ),
+    ("log on|off|path <arquivo>", "o registro dos cálculos"),
+    ("check <expressão>", "confere a expressão sem calcular"),
+    (
+        "compare <fórmula> with ...",
+        "calcula a fórmula com cada conjunto de variáveis",
+    ),
+    ("inspect [<expressão>]", "como o valor está guardado no f64"),
+    ("as_fraction [máximo]", "o último resultado como fração"),
+    (
+        "which <expressão>",
+        "as linhas do histórico com o mesmo resultado",
+    ),
+    (
+        "unit define <nome> = ...",
+        "define uma unidade ('unit' lista as definidas)",
+    ),
+    ("const <nome> = <expr>", "define uma constante"),
+    ("alias <nome> = <texto>", "cria um apelido"),
+    ("unalias <nome>", "esquece um apelido"),
+    ("holidays <arquivo>|off", "os feriados do workdays"),
+    ("weekend <dias>|none", "os dias do fim de semana"),
+    (
+        "word u8|u16|u32|u64",
+        "o tamanho da palavra das funções de bits",
+    ),
+    (
+        "summation <algoritmo>",
+        "o algoritmo das somas (naive, kahan...)",
+    ),
+    ("autostore on|off", "guarda cada resultado em r1, r2..."),
+    (
+        "sigfigs on|off",
+        "os algarismos significativos dos números escritos",
+    ),
+    ("lint on|off", "avisa sobre os trechos ambíguos das linhas"),
+    ("pager auto|off", "o paginador das saídas longas"),
+    (
+        "cache [stats|clear]",
+        "quanto o cache foi usado, ou o esvazia",
+    ),
+    (
+        "match_brackets on|off",
+        "destaca o parêntese correspondente",
+    ),
+    ("autoclose on|off", "fecha os parênteses ao digitar"),
+    ("polar(x, y), cart(r, θ)", "converte as coordenadas"),
+    ("aspect(largura, altura)", "a proporção de uma tela"),
+    (
+        "unix(...), from_unix(...)",
+        "datas em segundos unix, e now() e today()",
+    ),
+    (
+        "workdays(início, fim)",
+        "os dias úteis, e add_workdays(data, dias)",
+    ),
+    (
+        "amortize(...) [table]",
+        "a parcela e os juros de um financiamento",
+    ),
+    (
+        "blend(cor, cor, t)",
+        "hex_to_rgb, rgb_to_hex, lighten e darken",
+    ),
+];
+
+/// As opções de `set` e `show`, na ordem de `show options`.
+pub const OPTIONS: &[&str] = &[
+    "angle",
+    "format",
+    "precision",
+    "base",
+    "duration",
+    "word",
+    "summation",
+    "units",
+    "sigfigs",
+    "autostore",
+    "lint",
+    "all_bases",
+    "epsilon",
+    "approx",
+    "weekend",
+    "pager",
+    "match_brackets",
+    "autoclose",
+];
+
+/// Os nomes que podem completar a última palavra de `line` quando ela é o nome da opção de um
+/// `set` ou `show` (`set an` tem `angle`), para o Tab do editor.
+pub fn complete(line: &str) -> Vec<&'static str> {
+    let Some((command, prefix)) = line.trim_start().split_once(' ') else {
+        return Vec::new();
+    };
+    let prefix = prefix.trim_start();
+    if prefix.contains(' ') {
+        return Vec::new();
+    }
+    let names: Vec<&'static str> = match command {
+        "set" => OPTIONS.to_vec(),
+        "show" => ["options"].iter().chain(OPTIONS).copied().collect(),
+        _ => return Vec::new(),
+    };
+    names
+        .into_iter()
+        .filter(|name| name.starts_with(prefix))
+        .collect()
+}
+
+/// Uma tolerância de `set`, em notação científica para que `1e-9` não vire `0.000000001`.
+fn format_tolerance(tolerance: f64) -> String {
+    if tolerance == 0.0 {
+        "0".to_string()
+    } else {
+        format!("{:e}", tolerance)
+    }
+}
+
+fn join_errors(errors: &[Error]) -> String {
+    let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    messages.join("; ")
+}
+
+/// O arquivo de `export json <arquivo>` e `import json <arquivo>`, o único formato por enquanto.
+fn json_path(arguments: &str, command: &str) -> Result<PathBuf, String> {
+    let arguments = arguments.trim();
+    match arguments.split_once(' ') {
+        Some((format, path)) if format.eq_ignore_ascii_case("json") && !path.trim().is_empty() => {
+            Ok(PathBuf::from(path.trim()))
+        }
+        _ => Err(format!("uso: {} json <arquivo>", command)),
+    }
+}
+
+fn summary(export: &SessionExport) -> String {
+    let count = |count: usize, singular: &str, plural: &str| {
+        format!("{} {}", count, if count == 1 { singular } else { plural })
+    };
+    format!(
+        "{}, {}, {}",
+        count(export.history.len(), "linha", "linhas"),
+        count(export.variables.len(), "variável", "variáveis"),
+        count(export.functions.len(), "função", "funções")
+    )
+}
+
+/// Separa argumentos pelas vírgulas que não estão dentro de parênteses, já que os argumentos
+/// podem ter chamadas de função.
+pub fn split_arguments(text: &str) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, char) in text.char_indices() {
+        match char {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                arguments.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    arguments.push(&text[start..]);
+    arguments
+}
+
+/// O timestamp Unix de agora, em segundos.
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+/// Uma data dos comandos de dias úteis: `aaaa-mm-dd` ou `today()`.
+fn date_argument(text: &str) -> Result<Date, String> {
+    match text.trim() {
+        "today()" => Ok(Date::from_timestamp(now())),
+        text => Date::parse(text),
+    }
+}
+
+/// Separa o `to <unidade>` do fim da linha (`5 km to mi`), que não pode estar dentro de
+/// parênteses, retornando também o caractere em que a unidade começa.
+fn split_conversion(text: &str) -> (&str, Option<(usize, &str)>) {
+    let conversion = text.rmatch_indices(" to ").find(|(index, _)| {
+        let before = &text[..*index];
+        before.matches('(').count() <= before.matches(')').count()
+    });
+    match conversion {
+        Some((index, separator)) => {
+            let target = &text[index + separator.len()..];
+            let offset = text[..index + separator.len()].chars().count();
+            (&text[..index], Some((offset, target)))
+        }
+        None => (text, None),
+    }
+}
+
+/// Passa o texto para minúsculas, menos as letras coladas num número, em que a diferença importa
+/// (`3.3M` é `3.3e6`, e `3.3m` é `3.3e-3`), e os nomes escritos logo depois de um número ou de
+/// um `to` para os quais `unit` retorna `true` (`5 N`, `to MB`), que são lidos como unidades.
+fn lowercase_names(text: &str, unit: impl Fn(&str) -> bool) -> String {
+    let mut lowercased = String::with_capacity(text.len());
+    let mut name = String::new();
+    let mut in_number = false;
+    // se o último token foi um número ou um `to`, sem contar os espaços
+    let mut before_unit = false;
+    let flush = |name: &mut String, lowercased: &mut String, before_unit: &mut bool| {
+        if name.is_empty() {
+            return;
+        }
+        if *before_unit && unit(name) {
+            lowercased.push_str(name);
+            *before_unit = false;
+        } else {
+            let start = lowercased.len();
+            lowercased.extend(name.chars().flat_map(char::to_lowercase));
+            *before_unit = &lowercased[start..] == "to";
+        }
+        name.clear();
+    };
+    for char in text.chars() {
+        let continues_token = char.is_alphanumeric() || char == '_' || char == '.';
+        if !continues_token {
+            flush(&mut name, &mut lowercased, &mut before_unit);
+            if in_number {
+                before_unit = true;
+            } else if !char.is_whitespace() {
+                before_unit = false;
+            }
+            in_number = false;
+            lowercased.push(char);
+        } else if in_number || (name.is_empty() && (char.is_ascii_digit() || char == '.')) {
+            in_number = true;
+            lowercased.push(char);
+        } else {
+            name.push(char);
+        }
+    }
+    flush(&mut name, &mut lowercased, &mut before_unit);
+    lowercased
+}
+
+#[test]
+fn lowercase_names_should_keep_number_suffixes() {
+    assert_eq!(
+        lowercase_names("PI * 3.3M + X1 / 2E3 - 10Mb", |_| false),
+        "pi * 3.3M + x1 / 2E3 - 10Mb"
+    );
+    assert_eq!(
+        lowercase_names("5 N + 2 KM + N * (3 N) to N", |name| name == "N"),
+        "5 N + 2 km + n * (3 N) to N"
+    );
+}
+
+#[test]
+fn session_should_convert_angles_with_the_angle_mode() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.eval("30\u{b0}15'50\""),
+        Ok((30.0f64 + 15.0 / 60.0 + 50.0 / 3600.0).to_radians())
+    );
+    assert_eq!(
+        session.command("to dms"),
+        Some(Ok("30\u{b0}15'50\"".to_string()))
+    );
+    assert_eq!(
+        session.command("ANGLE deg"),
+        Some(Ok("ângulos em graus".to_string()))
+    );
+    assert_eq!(session.eval("90\u{b0}30' / 2"), Ok(45.25));
+    assert_eq!(
+        session.command("to dms"),
+        Some(Ok("45\u{b0}15'0\"".to_string()))
+    );
+    // o `to dms` também pode vir no fim da linha
+    assert_eq!(session.command("x = 30.5 to dms"), None);
+    let result = session.eval("x = 30.5 to dms").unwrap();
+    assert_eq!(session.format_result(result), "30\u{b0}30'0\"");
+    let result = session.eval("x / 2 to dms").unwrap();
+    assert_eq!(session.format_result(result), "15\u{b0}15'0\"");
+    let result = session.eval("x / 2").unwrap();
+    assert_eq!(session.format_result(result), "15.25");
+    assert!(session.eval("5 km to dms").is_err());
+    assert!(matches!(session.command("angle grad"), Some(Err(_))));
+    assert_eq!(session.command("x + 1"), None);
+
+    assert_eq!(
+        session.command("polar(1, 1)"),
+        Some(Ok(format!("({}, 45)", 2f64.sqrt())))
+    );
+    session.context.set_variable("r", 2.0);
+    // cos(180°) é exatamente -1, mas sin(180°) só chega perto de 0
+    let Some(Ok(point)) = session.command("CART(r * 2, 180)") else {
+        panic!("cart deveria ter sido calculado");
+    };
+    assert!(point.starts_with("(-4, 0.0000000000000004"), "{}", point);
+    assert_eq!(
+        session.command("polar(1)"),
+        Some(Err(
+            "a função 'polar' recebe 2 argumento(s), mas 1 foram passados".to_string()
+        ))
+    );
+    assert!(matches!(session.command("polar(1, y)"), Some(Err(_))));
+}
+
+#[test]
+fn session_should_compare_parameter_sets() {
+    let mut session = Session::default();
+    session.eval("p = 1000").unwrap();
+    assert_eq!(
+        session.command("compare P * (1 + R/100)^N with r=5, n=10; r=6.5, n=2*5; r=0, n=1"),
+        Some(Ok([
+            "  r |  n |  p * (1 + r/100)^n",
+            "  5 | 10 |  1628.894626777442",
+            "6.5 | 10 | 1877.1374652693592",
+            "  0 |  1 |               1000",
+        ]
+        .join("\n")))
+    );
+    assert_eq!(session.context.variable("r"), None);
+    assert_eq!(
+        session.command("compare x * 2 with x=1; y=2"),
+        Some(Err(
+            "conjunto 2: coluna 1: nome desconhecido 'x'".to_string()
+        ))
+    );
+    assert!(session.command("compare x with 1x=2").unwrap().is_err());
+    assert!(session.command("compare x").unwrap().is_err());
+}
+
+#[test]
+fn session_should_show_screen_proportions() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.command("aspect(3840, 2160)"),
+        Some(Ok("16:9".to_string()))
+    );
+    assert!(session.command("aspect(3840)").unwrap().is_err());
+    assert!(session.command("aspect(3840, 2160) * 2").is_none());
+    let result = session.eval("diag(3840, 2160)").unwrap();
+    assert_eq!(session.format_result(result).get(..6), Some("4405.8"));
+    let result = session.eval("diag(60 cm, 34 cm) to in").unwrap();
+    assert_eq!(session.format_result(result).get(..5), Some("27.15"));
+    let result = session.eval("ppi(3840, 2160, 27)").unwrap();
+    assert_eq!(session.format_result(result).get(..6), Some("163.17"));
+}
+
+#[test]
+fn session_should_compute_colors() {
+    let mut session = Session::default();
+    let mut command = |line: &str| session.command(line).unwrap();
+    assert_eq!(
+        command("hex_to_rgb(#336699)"),
+        Ok("(51, 102, 153)".to_string())
+    );
+    assert_eq!(
+        command("HEX_TO_RGB(0x336699)"),
+        Ok("(51, 102, 153)".to_string())
+    );
+    assert_eq!(
+        command("rgb_to_hex(51, 102, 150 + 3)"),
+        Ok("#336699".to_string())
+    );
+    assert_eq!(
+        command("blend(#f00, #0000ff, 0.5)"),
+        Ok("#800080".to_string())
+    );
+    assert_eq!(command("lighten(#336699, 0.2)"), Ok("#5c85ad".to_string()));
+    assert_eq!(command("darken(#336699, 1)"), Ok("#000000".to_string()));
+    assert!(command("blend(#f00, #0000ff, 50)").is_err());
+    assert!(command("lighten(#336699, 20)").is_err());
+    assert!(command("rgb_to_hex(51, 102)").is_err());
+    assert!(command("hex_to_rgb(#33669)").is_err());
+    assert!(command("lighten(#336699, x)").is_err());
+}
+
+#[test]
+fn session_should_convert_unix_timestamps() {
+    let mut session = Session::default();
+    let mut command = |line: &str| session.command(line).unwrap();
+    assert_eq!(
+        command("unix(2024-05-01 12:00)"),
+        Ok("1714564800".to_string())
+    );
+    assert_eq!(
+        command("UNIX(2024-05-01T12:00Z)"),
+        Ok("1714564800".to_string())
+    );
+    assert_eq!(
+        command("from_unix(1714564800 + 90)"),
+        Ok("2024-05-01 12:01:30".to_string())
+    );
+    assert!(command("from_unix(0.5)").is_err());
+    assert!(command("unix(2024-05-01 25:00)").is_err());
+
+    let now: i64 = command("now()").unwrap().parse().unwrap();
+    assert!(now > 1_700_000_000);
+    let today = command("today()").unwrap();
+    assert_eq!(Date::parse(&today), Ok(Date::from_timestamp(now)));
+    assert!(command("now(1)").is_err());
+    assert_eq!(
+        command(&format!("workdays(today(), {})", today)),
+        Ok(i64::from(session.calendar.is_workday(Date::parse(&today).unwrap())).to_string())
+    );
+}
+
+#[test]
+fn session_should_count_workdays() {
+    let mut session = Session::default();
+    let mut command = |line: &str| session.command(line).unwrap();
+    assert_eq!(
+        command("workdays(2024-01-01, 2024-03-01)"),
+        Ok("45".to_string())
+    );
+    assert_eq!(
+        command("add_workdays(2024-01-01, 5 * 2)"),
+        Ok("2024-01-15".to_string())
+    );
+    assert!(command("workdays(2024-01-01)").is_err());
+    assert!(command("add_workdays(2024-01-01, 1.5)").is_err());
+    assert!(command("workdays(2024-01-01, 2024-02-30)").is_err());
+
+    assert_eq!(
+        command("weekend fri sat"),
+        Ok("fim de semana: fri sat".to_string())
+    );
+    assert_eq!(
+        command("workdays(2024-01-01, 2024-01-07)"),
+        Ok("5".to_string())
+    );
+    assert!(command("weekend sex").is_err());
+    assert_eq!(command("weekend"), Ok("fim de semana: fri sat".to_string()));
+
+    let path = std::env::temp_dir().join(format!("asdf-calc-holidays-{}", std::process::id()));
+    std::fs::write(&path, "# ano novo\n2024-01-01\n").unwrap();
+    assert_eq!(
+        command(&format!("holidays {}", path.display())),
+        Ok("1 feriado".to_string())
+    );
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        command("workdays(2024-01-01, 2024-01-07)"),
+        Ok("4".to_string())
+    );
+    assert_eq!(command("holidays off"), Ok("nenhum feriado".to_string()));
+    assert!(command("holidays /nao/existe").is_err());
+}
+
+#[test]
+fn session_should_amortize_loans() {
+    let mut session = Session::default();
+    session.eval("rate = 6.5").unwrap();
+    assert_eq!(
+        session.command("amortize(200000, rate, 30)"),
+        Some(Ok(
+            "parcela mensal: 1264.14\ntotal pago: 455088.98 em 360 meses (juros: 255088.98)"
+                .to_string()
+        ))
+    );
+    let table = session
+        .command("Amortize(1200, 0, 1) table")
+        .unwrap()
+        .unwrap();
+    assert_eq!(table.lines().count(), 2 + 1 + 12 + 1);
+    assert!(matches!(session.command("amortize(1200, 0)"), Some(Err(_))));
+    assert!(session.command("amortize(1200, 0, 1) + 1").is_none());
+}
+
+#[test]
+fn session_should_keep_variables_and_history() {
+    let mut session = Session::default();
+    assert_eq!(session.eval("x = 2"), Ok(2.0));
+    assert!(session.eval("x +").is_err());
+    assert_eq!(session.eval("x * 3"), Ok(6.0));
+
+    assert_eq!(
+        session.history,
+        vec![
+            HistoryEntry {
+                input: "x = 2".to_string(),
+                result: 2.0
+            },
+            HistoryEntry {
+                input: "x * 3".to_string(),
+                result: 6.0
+            },
+        ]
+    );
+}
+
+#[test]
+fn session_should_undo_assignments() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.command("undo"),
+        Some(Err("nenhuma atribuição para desfazer".to_string()))
+    );
+    assert_eq!(session.eval("x = 2"), Ok(2.0));
+    assert_eq!(session.eval("x = x * 10"), Ok(20.0));
+    assert!(session.eval("x = 1 / y").is_err());
+    assert_eq!(session.eval("x + 1"), Ok(21.0));
+
+    assert_eq!(session.command("UNDO"), Some(Ok("x = 2".to_string())));
+    assert_eq!(session.context.variable("x"), Some(2.0));
+    assert_eq!(
+        session.command("undo"),
+        Some(Ok("x foi removida".to_string()))
+    );
+    assert_eq!(session.context.variable("x"), None);
+    assert!(session.command("undo").unwrap().is_err());
+    assert_eq!(session.command("undo 2"), None);
+}
+
+#[test]
+fn session_should_keep_workspaces_apart() {
+    let directory =
+        std::env::temp_dir().join(format!("asdf-calc-test-workspaces-{}", std::process::id()));
+    let mut session = Session {
+        workspace_directory: Some(directory.clone()),
+        ..Session::default()
+    };
+    assert_eq!(
+        session.command("workspace"),
+        Some(Ok("nenhum workspace em uso".to_string()))
+    );
+    assert_eq!(
+        session.command("workspace use finance"),
+        Some(Ok("workspace: finance".to_string()))
+    );
+    assert_eq!(session.eval("rate = 0.05"), Ok(0.05));
+    assert_eq!(
+        session.command("workspace use work"),
+        Some(Ok("workspace: work".to_string()))
+    );
+    assert!(session.eval("rate").is_err());
+    assert!(session.history.is_empty());
+    assert_eq!(session.eval("load = 3"), Ok(3.0));
+    assert_eq!(session.save_workspace(), Ok(()));
+
+    // outra sessão, como se a calculadora tivesse sido aberta de novo
+    let mut session = Session {
+        workspace_directory: Some(directory.clone()),
+        ..Session::default()
+    };
+    session.eval("scratch = 1").unwrap();
+    session.command("workspace use finance").unwrap().unwrap();
+    assert_eq!(session.eval("rate * 2"), Ok(0.1));
+    assert!(session.eval("scratch").is_err());
+    assert_eq!(session.history.len(), 2);
+    assert_eq!(session.history[0].input, "rate = 0.05");
+    session.command("workspace use work").unwrap().unwrap();
+    assert_eq!(session.eval("load"), Ok(3.0));
+
+    // as unidades das variáveis também são salvas
+    session.eval("d = 5 km").unwrap();
+    session.command("workspace use finance").unwrap().unwrap();
+    session.command("workspace use work").unwrap().unwrap();
+    assert_eq!(session.eval("d + 1 m"), Ok(5001.0));
+    assert_eq!(session.format_result(5001.0), "5001 m");
+
+    assert!(session.command("workspace use ../x").unwrap().is_err());
+    assert!(session.command("workspace drop work").unwrap().is_err());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn session_should_store_results_in_sequential_variables() {
+    let mut session = Session::default();
+    assert_eq!(session.eval("2 + 2"), Ok(4.0));
+    assert_eq!(session.last_stored, None);
+    assert_eq!(
+        session.command("autostore on"),
+        Some(Ok("autostore ligado".to_string()))
+    );
+    assert_eq!(session.eval("2 + 2"), Ok(4.0));
+    assert_eq!(session.last_stored, Some("r1".to_string()));
+    assert_eq!(session.eval("x = r1 * 10"), Ok(40.0));
+    assert_eq!(session.last_stored, None);
+    assert_eq!(session.eval("r1 + x"), Ok(44.0));
+    assert_eq!(session.last_stored, Some("r2".to_string()));
+
+    session.context.set_variable("r9", 0.0);
+    assert_eq!(session.eval("1"), Ok(1.0));
+    assert_eq!(session.last_stored, Some("r10".to_string()));
+    assert!(session.eval("1 +").is_err());
+    assert_eq!(session.last_stored, None);
+    assert!(session.command("autostore maybe").unwrap().is_err());
+}
+
+#[test]
+fn session_should_configure_the_pager() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.command("pager"),
+        Some(Ok("pager: auto".to_string()))
+    );
+    assert_eq!(
+        session.command("PAGER off"),
+        Some(Ok("pager: off".to_string()))
+    );
+    assert_eq!(session.pager, PagerMode::Off);
+    assert!(session.command("pager more").unwrap().is_err());
+}
+
+#[test]
+fn session_should_configure_the_editor() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.command("match_brackets"),
+        Some(Ok("match_brackets on".to_string()))
+    );
+    assert_eq!(
+        session.command("autoclose on"),
+        Some(Ok("autoclose on".to_string()))
+    );
+    assert_eq!(
+        session.editor,
+        EditorOptions {
+            match_brackets: true,
+            autoclose: true
+        }
+    );
+    assert!(session.command("autoclose yes").unwrap().is_err());
+}
+
+#[test]
+fn session_should_define_read_only_constants() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.command("const rate = 0.25"),
+        Some(Ok("rate = 0.25 (constante)".to_string()))
+    );
+    assert_eq!(
+        session.command("const double_rate = rate * 2"),
+        Some(Ok("double_rate = 0.5 (constante)".to_string()))
+    );
+    assert_eq!(session.eval("RATE * 4"), Ok(1.0));
+    assert!(session.eval("rate = 1").is_err());
+    assert_eq!(session.eval("rate"), Ok(0.25));
+    assert!(session.command("const 2x = 1").unwrap().is_err());
+    assert!(session.command("const y").unwrap().is_err());
+    assert!(session.command("const y = z").unwrap().is_err());
+}
+
+#[test]
+fn session_should_show_significant_figures_when_enabled() {
+    let mut session = Session::default();
+    assert_eq!(session.eval("2.0 * 3.00"), Ok(6.0));
+    assert_eq!(session.format_result(6.0), "6");
+
+    assert_eq!(
+        session.command("sigfigs on"),
+        Some(Ok("algarismos significativos ligados".to_string()))
+    );
+    assert_eq!(session.eval("2.0 * 3.00"), Ok(6.0));
+    assert_eq!(session.format_result(6.0), "6.0");
+    assert_eq!(session.eval("x = 1.5 + 10.25"), Ok(11.75));
+    assert_eq!(session.format_result(11.75), "11.8");
+    // variáveis contam como exatas
+    assert_eq!(session.eval("x"), Ok(11.75));
+    assert_eq!(session.format_result(11.75), "11.75");
+    assert!(session.command("sigfigs 3").unwrap().is_err());
+}
+
+#[test]
+fn session_should_show_integers_in_all_bases() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.command("show all-bases"),
+        Some(Ok("todas as bases ligadas".to_string()))
+    );
+    assert_eq!(session.format_result(255.0), "255  0xff  0b11111111  0o377");
+    assert_eq!(session.format_result(0.5), "0.5");
+    assert_eq!(
+        session.command("SHOW ALL-BASES"),
+        Some(Ok("todas as bases desligadas".to_string()))
+    );
+    assert_eq!(session.format_result(255.0), "255");
+    assert_eq!(
+        session.command("show all-bases on"),
+        Some(Ok("todas as bases ligadas".to_string()))
+    );
+    assert!(session.command("show hex").unwrap().is_err());
+}
+
+#[test]
+fn session_should_use_the_word_size_in_bit_functions() {
+    let mut session = Session::default();
+    assert_eq!(session.eval("rotl(1, 1)"), Ok(2.0));
+    assert_eq!(session.eval("rotr(1, 1)"), Ok(2f64.powi(63)));
+    assert_eq!(
+        session.command("word u8"),
+        Some(Ok("palavra: u8".to_string()))
+    );
+    assert_eq!(session.eval("rotr(1, 1)"), Ok(128.0));
+    assert_eq!(session.eval("clz(16)"), Ok(3.0));
+    assert!(session.eval("popcount(256)").is_err());
+    assert!(session.command("word u12").unwrap().is_err());
+    assert_eq!(session.eval("signed(0xFF)"), Ok(-1.0));
+    assert_eq!(session.eval("unsigned(-2)"), Ok(254.0));
+
+    session.command("show all-bases on");
+    assert_eq!(
+        session.format_result(-1.0),
+        "-1  (u8: 255)  0xff  0b11111111  0o377"
+    );
+    assert_eq!(session.command("word"), Some(Ok("palavra: u8".to_string())));
+}
+
+#[test]
+fn session_should_sum_with_the_chosen_algorithm() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.command("summation"),
+        Some(Ok("soma: neumaier".to_string()))
+    );
+    assert_eq!(session.eval("sum(1, 1e100, 1, -1e100)"), Ok(2.0));
+    assert_eq!(
+        session.command("summation naive"),
+        Some(Ok("soma: naive".to_string()))
+    );
+    assert_eq!(session.context.summation(), Summation::Naive);
+    assert_eq!(session.eval("sum(1, 1e100, 1, -1e100)"), Ok(0.0));
+    assert!(session.command("summation fast").unwrap().is_err());
+    assert_eq!(session.eval("mean(1, 2, 3, 4)"), Ok(2.5));
+}
+
+#[test]
+fn session_should_show_results_in_the_chosen_units() {
+    let mut session = Session::default();
+    let result = session.eval("100 km / 2 h").unwrap();
+    assert_eq!(session.format_result(result), "13.88888888888889 m/s");
+    let result = session.eval("1 mi to km").unwrap();
+    assert_eq!(session.format_result(result), "1.609344 km");
+    let result = session.eval("d = 5 km + 500 m").unwrap();
+    assert_eq!(session.format_result(result), "5500 m");
+    let result = session.eval("2 kg * 9.8 m/s^2").unwrap();
+    assert_eq!(session.format_result(result), "19.6 N");
+    let result = session.eval("2 + 2").unwrap();
+    assert_eq!(session.format_result(result), "4");
+
+    assert_eq!(
+        session.command("units imperial"),
+        Some(Ok("unidades: imperial".to_string()))
+    );
+    let result = session.eval("d").unwrap();
+    assert_eq!(session.format_result(result), "18044.619422572177 ft");
+    let result = session.eval("60 mi / 1 h").unwrap();
+    assert_eq!(session.format_result(result), "60 mph");
+    let result = session.eval("d to m").unwrap();
+    assert_eq!(session.format_result(result), "5500 m");
+    assert!(session.command("units metric").unwrap().is_err());
+
+    assert_eq!(
+        session.eval("d + 1 s"),
+        Err(vec![Error::IncompatibleUnits(
+            Dimension::LENGTH,
+            Dimension::TIME,
+            Span::new(0, 7)
+        )])
+    );
+    assert_eq!(
+        session.eval("d to h"),
+        Err(vec![Error::IncompatibleUnits(
+            Dimension::LENGTH,
+            Dimension::TIME,
+            Span::new(5, 6)
+        )])
+    );
+    assert!(session.eval("d ^ 0.5").is_err());
+    let result = session.eval("(4 m^2) ^ 0.5 to cm").unwrap();
+    assert_eq!(session.format_result(result), "200 cm");
+}
+
+#[test]
+fn session_should_tell_binary_and_decimal_data_sizes_apart() {
+    let mut session = Session::default();
+    let result = session.eval("1.5 GiB + 300 MB to MiB").unwrap();
+    assert_eq!(session.format_result(result), "1822.102294921875 MiB");
+    let result = session.eval("1.5 GiB + 300 MB").unwrap();
+    assert_eq!(session.format_result(result), "1.910612736 GB");
+    let result = session.eval("1 KiB").unwrap();
+    assert_eq!(session.format_result(result), "1 KiB");
+    let result = session.eval("2 GiB").unwrap();
+    assert_eq!(session.format_result(result), "2 GiB");
+    let result = session.eval("1 GiB + 512 MiB").unwrap();
+    assert_eq!(session.format_result(result), "1.5 GiB");
+    let result = session.eval("1 KiB to kB").unwrap();
+    assert_eq!(session.format_result(result), "1.024 kB");
+    let result = session.eval("1 TB to GiB").unwrap();
+    assert_eq!(session.format_result(result), "931.3225746154785 GiB");
+    let result = session.eval("8 bit").unwrap();
+    assert_eq!(session.format_result(result), "1 B");
+    let result = session.eval("100 MB / 2 s").unwrap();
+    assert_eq!(session.format_result(result), "50000000 B/s");
+    assert!(session.eval("1 GB + 1 m").is_err());
+}
+
+#[test]
+fn session_should_show_transfer_times_as_durations() {
+    let mut session = Session::default();
+    let result = session.eval("transfer_time(4 TB, 1 Gbps)").unwrap();
+    assert_eq!(session.format_result(result), "8h 53m 20s");
+    let result = session
+        .eval("transfer_time(700 MiB, 10 MB/s) to min")
+        .unwrap();
+    assert_eq!(session.format_result(result), "1.2233386666666666 min");
+    let result = session.eval("90 min").unwrap();
+    assert_eq!(session.format_result(result), "1h 30m");
+    assert_eq!(
+        session.command("format duration hh:mm:ss"),
+        Some(Ok("formato das durações: hh:mm:ss".to_string()))
+    );
+    assert_eq!(session.format_result(result), "01:30:00");
+    session.command("format duration decimal").unwrap().unwrap();
+    assert_eq!(session.format_result(result), "1.5 h");
+    assert_eq!(
+        session.command("format duration"),
+        Some(Ok("formato das durações: decimal".to_string()))
+    );
+    assert!(session.command("format duration weeks").unwrap().is_err());
+    assert_eq!(
+        session.command("format"),
+        Some(Ok("formato: plain".to_string()))
+    );
+    assert!(session.eval("transfer_time(4, 1)").is_err());
+    assert!(session.eval("transfer_time(1 Gbps, 4 TB)").is_err());
+}
+
+#[test]
+fn session_should_measure_geodesic_distances_in_the_chosen_units() {
+    let mut session = Session::default();
+    let paris_london = "48.8566, 2.3522, 51.5074, -0.1278";
+    let result = session
+        .eval(&format!("haversine({}) to km", paris_london))
+        .unwrap();
+    assert_eq!(session.format_result(result).get(..6), Some("343.55"));
+    let result = session
+        .eval(&format!("vincenty({}) to km", paris_london))
+        .unwrap();
+    assert_eq!(session.format_result(result).get(..6), Some("343.92"));
+    session.command("units imperial").unwrap().unwrap();
+    let result = session
+        .eval(&format!("haversine({})", paris_london))
+        .unwrap();
+    assert!(session.format_result(result).ends_with(" ft"));
+    assert!(session.eval("haversine(100, 0, 0, 0)").is_err());
+}
+
+#[test]
+fn session_should_define_custom_units() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.command("unit define furlong = 201.168 m"),
+        Some(Ok("1 furlong = 201.168 m".to_string()))
+    );
+    let result = session.eval("2 furlong to m").unwrap();
+    assert_eq!(session.format_result(result), "402.336 m");
+    let result = session.eval("1 mi to furlong").unwrap();
+    assert_eq!(session.format_result(result), "8 furlong");
+
+    assert_eq!(
+        session.command("unit define point"),
+        Some(Ok("1 point = 1".to_string()))
+    );
+    let result = session.eval("30 point / 2 week to point/week").unwrap();
+    assert_eq!(session.format_result(result), "15 point/week");
+    assert_eq!(
+        session.command("unit"),
+        Some(Ok("1 furlong = 201.168 m\n1 point = 1".to_string()))
+    );
+
+    assert!(session.command("unit define 2x = 1 m").unwrap().is_err());
+    assert!(session.command("unit define nada = 0 m").unwrap().is_err());
+    assert!(session
+        .command("unit define erro = 1 m + 1 s")
+        .unwrap()
+        .is_err());
+    assert_eq!(
+        session.command("unit remove furlong"),
+        Some(Ok("furlong foi removida".to_string()))
+    );
+    assert!(session.command("unit remove furlong").unwrap().is_err());
+    assert!(session.eval("1 furlong").is_err());
+}
+
+#[test]
+fn session_should_search_the_history() {
+    let mut session = Session::default();
+    assert!(session.command("history").unwrap().is_err());
+    session.eval("rate = 0.05").unwrap();
+    session.eval("1000 * Rate").unwrap();
+    session.eval("2 + 2").unwrap();
+    assert_eq!(
+        session.command("history search rate"),
+        Some(Ok("1: rate = 0.05 = 0.05\n2: 1000 * Rate = 50".to_string()))
+    );
+    assert_eq!(
+        session.command("history").unwrap().unwrap().lines().count(),
+        3
+    );
+    assert!(session.command("history search xyz").unwrap().is_err());
+    assert!(session.command("history rate").unwrap().is_err());
+}
+
+#[test]
+fn session_should_find_the_lines_with_a_result() {
+    let mut session = Session::default();
+    session.eval("6 * 7").unwrap();
+    session.eval("1 + 1").unwrap();
+    session.eval("0.1 * 3").unwrap();
+    session.eval("84 / 2").unwrap();
+    assert_eq!(
+        session.command("which 42"),
+        Some(Ok("1: 6 * 7 = 42\n4: 84 / 2 = 42".to_string()))
+    );
+    assert_eq!(
+        session.command("which 0.3"),
+        Some(Ok("3: 0.1 * 3 \u{2248} 0.30000000000000004".to_string()))
+    );
+    assert!(session.command("which 43").unwrap().is_err());
+    assert!(session.command("which").unwrap().is_err());
+}
+
+#[test]
+fn session_should_log_calculations_when_enabled() {
+    let directory =
+        std::env::temp_dir().join(format!("asdf-calc-session-log-{}", std::process::id()));
+    let path = directory.join("calc.log");
+    let _ = std::fs::remove_dir_all(&directory);
+
+    let mut session = Session::default();
+    session.eval("1 + 1").unwrap();
+    assert_eq!(
+        session.command(&format!("log path {}", path.display())),
+        Some(Ok(format!("log desligado ({})", path.display())))
+    );
+    assert_eq!(
+        session.command("log on"),
+        Some(Ok(format!("log ligado: {}", path.display())))
+    );
+    session.eval("x = 6 * 7").unwrap();
+    assert!(session.eval("y").is_err());
+    session.command("log off");
+    session.eval("2 + 2").unwrap();
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(log.lines().count(), 1);
+    assert!(log.ends_with("Z\tx = 6 * 7\t42\n"), "{}", log);
+    assert!(session.command("log maybe").unwrap().is_err());
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    // um diretório no lugar do arquivo faz a escrita falhar
+    session.command(&format!("log path {}", std::env::temp_dir().display()));
+    session.command("log on");
+    assert_eq!(session.eval("3"), Ok(3.0));
+    assert!(session.log_error.take().is_some());
+    assert!(session
+        .command("log")
+        .unwrap()
+        .unwrap()
+        .starts_with("log desligado"));
+}
+
+#[test]
+fn session_should_export_and_import_json() {
+    let directory =
+        std::env::temp_dir().join(format!("asdf-calc-session-export-{}", std::process::id()));
+    let path = directory.join("Session.json");
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let mut session = Session::default();
+    session.eval("rate = 0.05").unwrap();
+    session.eval("1000 * rate").unwrap();
+    session.eval("d = 5 km").unwrap();
+    let double = Function::new(vec!["x".to_string()], "x * 2", &session.context).unwrap();
+    session.context.define_function("double", double);
+    assert_eq!(
+        session.command(&format!("export json {}", path.display())),
+        Some(Ok(format!(
+            "exportado para {}: 3 linhas, 2 variáveis, 1 função",
+            path.display()
+        )))
+    );
+
+    let mut other = Session::default();
+    other.eval("rate = 0.1").unwrap();
+    assert_eq!(
+        other.command(&format!("IMPORT json {}", path.display())),
+        Some(Ok(format!(
+            "importado de {}: 3 linhas, 2 variáveis, 1 função",
+            path.display()
+        )))
+    );
+    assert_eq!(other.history.len(), 4);
+    assert_eq!(other.history[2].input, "1000 * rate");
+    assert_eq!(other.eval("double(rate)"), Ok(0.1));
+    assert_eq!(other.eval("d + 1 m"), Ok(5001.0));
+    other.command("undo");
+    other.command("undo");
+    assert_eq!(other.context.variable("rate"), Some(0.1));
+
+    // um arquivo com erro não é importado pela metade
+    std::fs::write(
+        &path,
+        r#"{"version": 1, "history": [], "variables": {"y": 1}, "functions": [{"name": "f", "parameters": [], "body": "1 +"}]}"#,
+    )
+    .unwrap();
+    assert!(other
+        .command(&format!("import json {}", path.display()))
+        .unwrap()
+        .is_err());
+    assert_eq!(other.context.variable("y"), None);
+    assert!(other.command("export csv x").unwrap().is_err());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn session_should_check_expressions_without_evaluating_them() {
+    let mut session = Session::default();
+    session.eval("x = 1").unwrap();
+    assert_eq!(
+        session.command("check x = 1 / 0"),
+        Some(Ok("expressão válida".to_string()))
+    );
+    assert_eq!(session.context.variable("x"), Some(1.0));
+    assert_eq!(session.history.len(), 1);
+    session.context.set_si_suffixes(true);
+    assert!(session.command("CHECK 3.3M * 2").unwrap().is_ok());
+    assert_eq!(
+        session.command("check 2 + y"),
+        Some(Err("coluna 5: nome desconhecido 'y'".to_string()))
+    );
+    assert!(session.command("check").unwrap().is_err());
+    // as unidades são conferidas como no cálculo
+    assert_eq!(
+        session.check("1 m + 1 s"),
+        Err(vec![Error::IncompatibleUnits(
+            Dimension::LENGTH,
+            Dimension::TIME,
+            Span::new(2, 9)
+        )])
+    );
+    assert_eq!(
+        session.check("1 m to s"),
+        Err(vec![Error::IncompatibleUnits(
+            Dimension::LENGTH,
+            Dimension::TIME,
+            Span::new(7, 8)
+        )])
+    );
+    assert_eq!(session.check("1 m to km"), Ok(()));
+    assert!(session.recent_errors.is_empty());
+}
+
+#[test]
+fn session_should_reuse_cached_lines() {
+    let mut session = Session::default();
+    assert_eq!(session.eval("max(2, 3) ^ 2"), Ok(9.0));
+    assert_eq!(session.eval("max(2, 3) ^ 2"), Ok(9.0));
+    assert_eq!(session.cache.result_hits, 1);
+
+    session.eval("x = 1").unwrap();
+    session.eval("x = x + 1").unwrap();
+    assert_eq!(session.eval("x = x + 1"), Ok(3.0));
+    assert_eq!(session.eval("x * 10"), Ok(30.0));
+    assert_eq!(session.cache.parse_hits, 2);
+    assert_eq!(session.cache.result_hits, 1);
+
+    // resultados guardados respeitam as configurações com que foram calculados
+    session.eval("sin(90)").unwrap();
+    session.command("angle deg");
+    assert_eq!(session.eval("sin(90)"), Ok(1.0));
+    assert!(session
+        .command("cache stats")
+        .unwrap()
+        .unwrap()
+        .starts_with("cache: "));
+    assert_eq!(
+        session.command("cache clear"),
+        Some(Ok("cache limpo".to_string()))
+    );
+    assert_eq!(session.cache.parse_hits, 0);
+}
+
+#[test]
+fn session_should_inspect_floats() {
+    let mut session = Session::default();
+    assert!(session.command("inspect").unwrap().is_err());
+    let description = session.command("inspect 0.1 + 0.2").unwrap().unwrap();
+    assert!(description.starts_with("valor exato: 0.3000000000000000444"));
+    assert!(description.ends_with("próximo: 0.3000000000000001"));
+
+    session.eval("1 / 3").unwrap();
+    let description = session.command("inspect").unwrap().unwrap();
+    assert!(description.contains("bits: 0x3fd5555555555555"));
+    assert!(session.command("inspect 1 +").unwrap().is_err());
+}
+
+#[test]
+fn session_should_keep_only_the_most_recent_errors() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.eval("1 +"),
+        Err(vec![Error::TrailingOperator(asdf_calc::Span::new(2, 3))])
+    );
+    for i in 0..RECENT_ERRORS {
+        assert!(session.eval(&format!("{} +", i)).is_err());
+    }
+    assert_eq!(session.eval("1 + 1"), Ok(2.0));
+
+    assert_eq!(session.recent_errors.len(), RECENT_ERRORS);
+    assert_eq!(session.recent_errors[0].input, "0 +");
+    assert_eq!(
+        session
+            .recent_errors
+            .back()
+            .map(|entry| entry.input.as_str()),
+        Some("19 +")
+    );
+}
+
+#[test]
+fn session_should_interrupt_slow_evaluations() {
+    use asdf_calc::Function;
+
+    // cada função chama a anterior duas vezes, então `f40` faria 2^40 chamadas
+    let mut context = EvalContext::new();
+    let identity = Function::new(vec!["x".to_string()], "x", &context).unwrap();
+    context.define_function("f0", identity);
+    for i in 1..=40 {
+        let body = format!("f{0}(x) + f{0}(x)", i - 1);
+        let function = Function::new(vec!["x".to_string()], &body, &context).unwrap();
+        context.define_function(format!("f{}", i), function);
+    }
+
+    let mut session = Session::with_context(context);
+    session.set_timeout(Some(Duration::from_millis(50)));
+    assert_eq!(
+        session.eval("f40(1)"),
+        Err(vec![Error::Interrupted(asdf_calc::Span::new(0, 3))])
+    );
+    assert_eq!(session.eval("f3(1)"), Ok(8.0));
+}
+
+#[test]
+fn session_should_lint_only_when_enabled() {
+    let mut session = Session::default();
+    assert_eq!(session.eval("1/2 km"), Ok(0.0005));
+    assert!(session.last_notes.is_empty());
+    assert_eq!(
+        session.command("lint on"),
+        Some(Ok("avisos ligados".to_string()))
+    );
+    assert_eq!(session.eval("y = 1/2 km"), Ok(0.0005));
+    let messages: Vec<&str> = session
+        .last_notes
+        .iter()
+        .map(|note| note.message.as_str())
+        .collect();
+    assert_eq!(messages, ["`1/2 km` é lido como `1/(2 km)`"]);
+    assert_eq!(session.eval("2 * 3"), Ok(6.0));
+    assert!(session.last_notes.is_empty());
+    assert!(session.command("lint talvez").unwrap().is_err());
+}
+
+#[test]
+fn session_should_set_the_comparison_tolerances() {
+    let mut session = Session::default();
+    assert_eq!(session.eval("0.1 + 0.2 == 0.3"), Ok(0.0));
+    assert_eq!(
+        session.command("set epsilon 1e-9"),
+        Some(Ok(
+            "epsilon: 1e-9 (absoluto, usado por ==, !=, <, <=, > e >=)".to_string()
+        ))
+    );
+    assert_eq!(session.eval("0.1 + 0.2 == 0.3"), Ok(1.0));
+    assert_eq!(session.eval("1e-10 == 0"), Ok(1.0));
+
+    assert_eq!(
+        session.command("set approx"),
+        Some(Ok(
+            "approx: 1e-9 (relativo ao maior dos dois números, usado por ~=)".to_string()
+        ))
+    );
+    assert_eq!(session.eval("1000 ~= 1000.5"), Ok(0.0));
+    session.command("set approx 1e-3").unwrap().unwrap();
+    assert_eq!(session.eval("1000 ~= 1000.5"), Ok(1.0));
+    assert_eq!(session.eval("approx(1000, 1000.5, 1e-9)"), Ok(0.0));
+    assert_eq!(session.eval("equal(1000, 1000.5, 1)"), Ok(1.0));
+
+    session.command("set epsilon 0").unwrap().unwrap();
+    assert_eq!(session.eval("0.1 + 0.2 == 0.3"), Ok(0.0));
+    assert!(session.command("set epsilon -1").unwrap().is_err());
+    assert!(session.command("set tolerance 1").unwrap().is_err());
+    assert!(session.command("set").unwrap().is_err());
+}
+
+#[test]
+fn session_should_set_and_show_every_option() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.command("set angle deg"),
+        Some(Ok("ângulos em graus".to_string()))
+    );
+    assert_eq!(
+        session.command("set format sci"),
+        Some(Ok("formato: sci".to_string()))
+    );
+    assert_eq!(
+        session.command("set duration hh:mm:ss"),
+        Some(Ok("formato das durações: hh:mm:ss".to_string()))
+    );
+    session.command("set sigfigs on").unwrap().unwrap();
+    session.command("set all-bases on").unwrap().unwrap();
+    session.command("set weekend fri sat").unwrap().unwrap();
+    assert_eq!(
+        session.command("show word"),
+        Some(Ok("palavra: u64".to_string()))
+    );
+    assert_eq!(
+        session.command("show options"),
+        Some(Ok([
+            "angle          deg",
+            "format         sci",
+            "precision      off",
+            "base           dec",
+            "duration       hh:mm:ss",
+            "word           u64",
+            "summation      neumaier",
+            "units          si",
+            "sigfigs        on",
+            "autostore      off",
+            "lint           off",
+            "all_bases      on",
+            "epsilon        0",
+            "approx         1e-9",
+            "weekend        fri sat",
+            "pager          auto",
+            "match_brackets on",
+            "autoclose      off",
+        ]
+        .join("\n")))
+    );
+    // os comandos antigos continuam valendo
+    assert_eq!(
+        session.command("show all-bases"),
+        Some(Ok("todas as bases desligadas".to_string()))
+    );
+    assert!(session.command("set language pt").unwrap().is_err());
+
+    session.command("set format plain").unwrap().unwrap();
+    assert_eq!(
+        session.command("set precision 3"),
+        Some(Ok("precisão: 3 algarismos".to_string()))
+    );
+    assert_eq!(session.format_result(2.0 / 3.0), "0.667");
+    assert!(session.command("set precision 0").unwrap().is_err());
+    session.command("set precision off").unwrap().unwrap();
+    assert_eq!(
+        session.command("set base hex"),
+        Some(Ok("base: hex".to_string()))
+    );
+    assert_eq!(session.format_result(255.0), "0xff");
+    assert_eq!(session.format_result(0.5), "0.5");
+    assert!(session.command("set base b3").unwrap().is_err());
+    assert!(session.command("show angle deg").unwrap().is_err());
+}
+
+#[test]
+fn complete_should_suggest_option_names() {
+    assert_eq!(complete("set ep"), ["epsilon"]);
+    assert_eq!(complete("show op"), ["options"]);
+    assert_eq!(complete("set "), OPTIONS);
+    assert!(complete("set angle d").is_empty());
+    assert!(complete("angle").is_empty());
+}
+
+#[test]
+fn session_should_define_and_expand_aliases() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.command("alias"),
+        Some(Ok("nenhum alias definido".to_string()))
+    );
+    assert_eq!(
+        session.command("alias K = * 1000"),
+        Some(Ok("k = * 1000".to_string()))
+    );
+    session.command("alias deg = angle deg").unwrap().unwrap();
+    let line = session.expand_aliases("5 k");
+    assert_eq!(session.eval(&line), Ok(5000.0));
+    let line = session.expand_aliases("deg");
+    assert_eq!(
+        session.command(&line),
+        Some(Ok("ângulos em graus".to_string()))
+    );
+    assert_eq!(
+        session.command("alias"),
+        Some(Ok("deg = angle deg\nk = * 1000".to_string()))
+    );
+    assert_eq!(
+        session.command("unalias k"),
+        Some(Ok("k foi removido".to_string()))
+    );
+    assert_eq!(session.expand_aliases("5 k"), "5 k");
+    assert!(session.command("unalias k").unwrap().is_err());
+    assert!(session.command("alias 2x = 2 * x").unwrap().is_err());
+    assert!(session.command("alias x =").unwrap().is_err());
+}
+
+#[test]
+fn colon_commands_should_not_shadow_variables() {
+    let mut session = Session::default();
+    assert_eq!(
+        session.command(":angle deg"),
+        Some(Ok("ângulos em graus".to_string()))
+    );
+    assert_eq!(
+        session.command(":foo"),
+        Some(Err("comando desconhecido ':foo'".to_string()))
+    );
+    assert_eq!(
+        session.command("vars"),
+        Some(Ok("nenhuma variável definida".to_string()))
+    );
+    assert_eq!(session.command("units = 3"), None);
+    assert_eq!(session.eval("units = 3"), Ok(3.0));
+    assert_eq!(session.command("units"), None);
+    assert_eq!(session.eval("units"), Ok(3.0));
+    assert!(session.command(":units").unwrap().is_ok());
+    assert_eq!(session.command(":vars"), Some(Ok("units = 3".to_string())));
+}
+
+#[test]
+fn result_literal_should_read_back_as_the_result() {
+    let mut session = Session::default();
+    let mut literal = |input: &str| {
+        let result = session.eval(input).unwrap();
+        session.result_literal(result)
+    };
+    assert_eq!(
+        literal("0.1 + 0.2"),
+        Some("0.30000000000000004".to_string())
+    );
+    assert_eq!(literal("10^20 + 1"), Some("1e20".to_string()));
+    assert_eq!(literal("-3 / 10^7"), Some("-3e-7".to_string()));
+    assert_eq!(literal("2 km * 3"), Some("6000 m".to_string()));
+    assert_eq!(literal("2 km * 3 to km"), Some("6 km".to_string()));
+    assert_eq!(literal("1 mi to m"), Some("1609.344 m".to_string()));
+    assert_eq!(literal("90 s"), Some("90 s".to_string()));
+    assert_eq!(literal("1/0"), None);
+    assert_eq!(literal("3 m / 2 s"), Some("1.5 m/s".to_string()));
+    assert_eq!(literal("2 m * 3 s"), Some("6".to_string()));
+}
+
+#[test]
+fn session_should_know_every_command_of_the_help() {
+    let mut session = Session::default();
+    for (usage, _) in COMMANDS.iter().filter(|(usage, _)| !usage.contains('(')) {
+        let command: Vec<&str> = usage
+            .split_whitespace()
+            .take_while(|word| !word.starts_with(['<', '[', '"']) && !word.contains('|'))
+            .collect();
+        let response = session.command(&format!(":{}", command.join(" ")));
+        assert!(
+            !matches!(&response, Some(Err(message)) if message.starts_with("comando desconhecido")),
+            "{}",
+            usage
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Algarismos significativos dos números escritos no input, e como eles passam pelas operações:
+//! na multiplicação e na divisão o resultado fica com os algarismos do fator que tiver menos
+//! deles (`2.0 * 3.00` tem dois), e na soma e na subtração com a última casa decimal mais alta
+//! entre as parcelas (`1.5 + 10.25` vai até os décimos).
+
+use alloc::format;
+
+/// Precisão de um valor medido: quantos algarismos significativos ele tem, e a posição do último
+/// deles como potência de 10 (`0` nas unidades, `-1` nos décimos, `2` nas centenas).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Precision {
+    pub(crate) figures: u32,
+    pub(crate) place: i32,
+}
+
+impl Precision {
+    /// Precisão de um número escrito como `text`, que `exponent` multiplica por uma potência de
+    /// 10 (a de um sufixo do SI). Os zeros no fim de um número sem ponto não são significativos
+    /// (`1200` tem dois algarismos, `1200.` tem quatro).
+    pub(crate) fn of_literal(text: &str, exponent: i32) -> Precision {
+        let (mantissa, written_exponent) = match text.find(['e', 'E']) {
+            Some(index) => (&text[..index], text[index + 1..].parse().unwrap_or(0)),
+            None => (text, 0),
+        };
+        let exponent = exponent + written_exponent;
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let has_point = mantissa.contains('.');
+
+        let digits = format!("{}{}", integer, fraction).replace('_', "");
+        let digits = digits.trim_start_matches('0');
+        if digits.is_empty() {
+            return Precision {
+                figures: 1,
+                place: exponent - fraction.len() as i32,
+            };
+        }
+
+        if has_point {
+            Precision {
+                figures: digits.len() as u32,
+                place: exponent - fraction.len() as i32,
+            }
+        } else {
+            let significant = digits.trim_end_matches('0');
+            Precision {
+                figures: significant.len() as u32,
+                place: exponent + (digits.len() - significant.len()) as i32,
+            }
+        }
+    }
+
+    /// Precisão de `value` com `figures` algarismos significativos.
+    pub(crate) fn with_figures(value: f64, figures: u32) -> Precision {
+        let figures = figures.max(1);
+        Precision {
+            figures,
+            place: decimal_exponent(value) - figures as i32 + 1,
+        }
+    }
+
+    /// Precisão de `value` indo até a casa `place`, com pelo menos um algarismo.
+    pub(crate) fn with_place(value: f64, place: i32) -> Precision {
+        let figures = decimal_exponent(value) - place + 1;
+        if figures < 1 {
+            Precision::with_figures(value, 1)
+        } else {
+            Precision {
+                figures: figures as u32,
+                place,
+            }
+        }
+    }
+}
+
+/// Expoente de `value` na notação científica, ou 0 para o zero e os valores que não são finitos.
+fn decimal_exponent(value: f64) -> i32 {
+    if value == 0.0 || !value.is_finite() {
+        return 0;
+    }
+    let text = format!("{:e}", value);
+    text.split_once('e')
+        .and_then(|(_, exponent)| exponent.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Precisão de um produto ou quociente, em que os valores exatos (`None`) não limitam nada.
+pub(crate) fn product(
+    value: f64,
+    lhs: Option<Precision>,
+    rhs: Option<Precision>,
+) -> Option<Precision> {
+    let figures = match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => lhs.figures.min(rhs.figures),
+        (Some(precision), None) | (None, Some(precision)) => precision.figures,
+        (None, None) => return None,
+    };
+    Some(Precision::with_figures(value, figures))
+}
+
+/// Precisão de uma soma ou diferença.
+pub(crate) fn sum(value: f64, lhs: Option<Precision>, rhs: Option<Precision>) -> Option<Precision> {
+    let place = match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => lhs.place.max(rhs.place),
+        (Some(precision), None) | (None, Some(precision)) => precision.place,
+        (None, None) => return None,
+    };
+    Some(Precision::with_place(value, place))
+}
+
+#[test]
+fn precision_should_count_the_significant_figures_of_literals() {
+    let precision = |text: &str| {
+        let precision = Precision::of_literal(text, 0);
+        (precision.figures, precision.place)
+    };
+    assert_eq!(precision("2.0"), (2, -1));
+    assert_eq!(precision("3.00"), (3, -2));
+    assert_eq!(precision("1200"), (2, 2));
+    assert_eq!(precision("1200."), (4, 0));
+    assert_eq!(precision("0.0050"), (2, -4));
+    assert_eq!(precision("1.50e3"), (3, 1));
+    assert_eq!(precision("0"), (1, 0));
+    assert_eq!(Precision::of_literal("4.7", -6).place, -7);
+
+    let sum = sum(11.75, Some(Precision::of_literal("1.5", 0)), None);
+    assert_eq!(sum.map(|precision| precision.figures), Some(3));
+}
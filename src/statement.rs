@@ -0,0 +1,190 @@
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::{
+    context::EvalContext,
+    error::Error,
+    expression::Expression,
+    lexer::{tokenize_with, Token, TokenKind},
+};
+
+#[cfg(test)]
+use crate::{error::Span, units::Dimension};
+
+/// Uma linha completa de input: uma expressão, ou a atribuição do resultado de uma expressão a
+/// um nome (`x = 2 * 3`, ou `let x = 2 * 3` nos scripts), que passa a poder ser usado nas linhas
+/// seguintes.
+///
+/// ```
+/// use asdf_calc::{EvalContext, Statement};
+///
+/// let mut context = EvalContext::new();
+/// Statement::new("rate = 0.05", &context)
+///     .expect("atribuição válida")
+///     .execute(&mut context)
+///     .expect("cálculo válido");
+///
+/// let statement = Statement::new("1000 * rate", &context).expect("expressão válida");
+/// assert_eq!(statement.execute(&mut context), Ok(50.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Expression(Expression),
+    Assignment(String, Expression),
+}
+
+impl Statement {
+    pub fn new(text: &str, context: &EvalContext) -> Result<Self, Vec<Error>> {
+        let mut errors = Vec::new();
+        let mut tokens = tokenize_with(text, context.si_suffixes(), &mut errors);
+        let end = text.chars().count();
+
+        let assigned_name = match tokens.as_slice() {
+            [Token {
+                kind: TokenKind::Identifier(keyword),
+                ..
+            }, Token {
+                kind: TokenKind::Identifier(name),
+                span,
+            }, Token {
+                kind: TokenKind::Assign,
+                ..
+            }, ..]
+                if keyword == "let" =>
+            {
+                Some((name.clone(), *span, 3))
+            }
+            [Token {
+                kind: TokenKind::Identifier(name),
+                span,
+            }, Token {
+                kind: TokenKind::Assign,
+                ..
+            }, ..] => Some((name.clone(), *span, 2)),
+            _ => None,
+        };
+
+        match assigned_name {
+            Some((name, span, _)) if context.constant(&name).is_some() => {
+                Err(vec![Error::AssignmentToConstant(name, span)])
+            }
+            Some((name, _, length)) => {
+                tokens.drain(..length);
+                let expression = Expression::from_tokens(tokens, end, errors, context, &[])?;
+                Ok(Statement::Assignment(name, expression))
+            }
+            None => Ok(Statement::Expression(Expression::from_tokens(
+                tokens,
+                end,
+                errors,
+                context,
+                &[],
+            )?)),
+        }
+    }
+
+    /// A expressão calculada pela linha, a própria ou a do lado direito da atribuição.
+    pub fn expression(&self) -> &Expression {
+        match self {
+            Statement::Expression(expression) | Statement::Assignment(_, expression) => expression,
+        }
+    }
+
+    /// Calcula a linha, guardando o resultado em `context` no caso de uma atribuição, junto da
+    /// unidade dele. As unidades são conferidas antes do cálculo, com `Expression::dimension`.
+    pub fn execute(&self, context: &mut EvalContext) -> Result<f64, Error> {
+        let dimension = self.expression().dimension(context)?;
+        match self {
+            Statement::Expression(expression) => expression.evaluate_with(context),
+            Statement::Assignment(name, expression) => {
+                let value = expression.evaluate_with(context)?;
+                context.set_quantity(name.clone(), value, dimension);
+                Ok(value)
+            }
+        }
+    }
+}
+
+#[test]
+fn statement_should_assign_variables() {
+    let mut context = EvalContext::new();
+    let statement = Statement::new("x = 2 * 3", &context).expect("falha na criação do Statement");
+    assert_eq!(statement.execute(&mut context), Ok(6.0));
+    assert_eq!(context.variable("x"), Some(6.0));
+
+    let statement = Statement::new("x = x + 1", &context).expect("falha na criação do Statement");
+    assert_eq!(statement.execute(&mut context), Ok(7.0));
+    assert_eq!(context.variable("x"), Some(7.0));
+}
+
+#[test]
+fn statement_should_keep_the_unit_of_assigned_quantities() {
+    let mut context = EvalContext::new();
+    let statement = Statement::new("d = 5 km", &context).expect("falha na criação do Statement");
+    assert_eq!(statement.execute(&mut context), Ok(5000.0));
+    assert_eq!(context.dimension("d"), Dimension::LENGTH);
+
+    let statement = Statement::new("d / 2 h", &context).expect("falha na criação do Statement");
+    assert_eq!(statement.execute(&mut context), Ok(5000.0 / 7200.0));
+    let statement = Statement::new("d + 1 s", &context).expect("falha na criação do Statement");
+    assert_eq!(
+        statement.execute(&mut context),
+        Err(Error::IncompatibleUnits(
+            Dimension::LENGTH,
+            Dimension::TIME,
+            Span::new(0, 7)
+        ))
+    );
+
+    let statement = Statement::new("d = 2", &context).expect("falha na criação do Statement");
+    assert_eq!(statement.execute(&mut context), Ok(2.0));
+    assert_eq!(context.dimension("d"), Dimension::NONE);
+}
+
+#[test]
+fn statement_should_not_assign_to_constants() {
+    let mut context = EvalContext::new();
+    context.define_constant("rate", 0.0725);
+    assert_eq!(
+        Statement::new("let rate = 1", &context),
+        Err(vec![Error::AssignmentToConstant(
+            "rate".to_string(),
+            Span::new(4, 8)
+        )])
+    );
+    let statement = Statement::new("rate * 100", &context).expect("falha na criação do Statement");
+    assert_eq!(statement.execute(&mut context), Ok(7.249999999999999));
+}
+
+#[test]
+fn statement_should_accept_let_assignments() {
+    let mut context = EvalContext::new();
+    let statement =
+        Statement::new("let rate = 0.5", &context).expect("falha na criação do Statement");
+    assert_eq!(statement.execute(&mut context), Ok(0.5));
+    assert_eq!(context.variable("rate"), Some(0.5));
+
+    // `let` continua podendo ser usado como nome
+    let statement = Statement::new("let = 2", &context).expect("falha na criação do Statement");
+    assert_eq!(statement.execute(&mut context), Ok(2.0));
+    assert_eq!(context.variable("let"), Some(2.0));
+}
+
+#[test]
+fn statement_should_reject_misplaced_assignments() {
+    let context = EvalContext::new();
+    assert_eq!(
+        Statement::new("2 = 3", &context).unwrap_err(),
+        vec![Error::InvalidAssignment(Span::new(2, 3))]
+    );
+    assert_eq!(
+        Statement::new("x =", &context).unwrap_err(),
+        vec![Error::MissingOperand(Span::new(3, 4))]
+    );
+    assert_eq!(
+        Statement::new("x = y = 2", &context).unwrap_err(),
+        vec![
+            Error::UnknownIdentifier("y".into(), Span::new(4, 5)),
+            Error::InvalidAssignment(Span::new(6, 7)),
+        ]
+    );
+}
@@ -0,0 +1,131 @@
+//! Tabelas de texto com as colunas alinhadas à direita e separadas por `|`, usadas pelo `grid` e
+//! pelas tabelas de outros comandos.
+
+use crate::pager;
+
+/// O `" | "` entre as colunas.
+const SEPARATOR_WIDTH: usize = 3;
+
+/// Tabela com uma linha de cabeçalho e uma linha de texto para cada linha de `rows`. Células
+/// que faltam no fim de uma linha ficam vazias.
+pub fn render(header: &[String], rows: &[Vec<String>]) -> String {
+    let lines = || [header].into_iter().chain(rows.iter().map(Vec::as_slice));
+    let widths = widths(header, rows);
+
+    let mut output = String::new();
+    for line in lines() {
+        let cells: Vec<String> = widths
+            .iter()
+            .enumerate()
+            .map(|(column, width)| {
+                format!(
+                    "{:>width$}",
+                    line.get(column).map_or("", String::as_str),
+                    width = width
+                )
+            })
+            .collect();
+        output.push_str(&cells.join(" | "));
+        output.push('\n');
+    }
+    output
+}
+
+/// Como `render`, mas com as colunas que não cabem em `width` caracteres passadas para outras
+/// partes da tabela, uma embaixo da outra e separadas por uma linha vazia. Cada parte repete a
+/// primeira coluna, a que diz qual é cada linha. Sem `width`, é o mesmo que `render`.
+pub fn render_within(header: &[String], rows: &[Vec<String>], width: Option<usize>) -> String {
+    let widths = widths(header, rows);
+    let total = widths.iter().sum::<usize>() + SEPARATOR_WIDTH * widths.len().saturating_sub(1);
+    let Some(width) = width.filter(|width| total > *width && widths.len() > 2) else {
+        return render(header, rows);
+    };
+
+    // uma coluna mais larga que o terminal fica sozinha na parte dela
+    let mut parts: Vec<Vec<usize>> = Vec::new();
+    let mut used = 0;
+    for column in 1..widths.len() {
+        let extra = SEPARATOR_WIDTH + widths[column];
+        match parts.last_mut() {
+            Some(part) if used + extra <= width => {
+                part.push(column);
+                used += extra;
+            }
+            _ => {
+                parts.push(vec![column]);
+                used = widths[0] + extra;
+            }
+        }
+    }
+    let select = |line: &[String], part: &[usize]| -> Vec<String> {
+        [0].iter()
+            .chain(part)
+            .map(|&column| line.get(column).cloned().unwrap_or_default())
+            .collect()
+    };
+    let parts: Vec<String> = parts
+        .iter()
+        .map(|part| {
+            let rows: Vec<Vec<String>> = rows.iter().map(|row| select(row, part)).collect();
+            render(&select(header, part), &rows)
+        })
+        .collect();
+    parts.join("\n")
+}
+
+/// `render_within` na largura do terminal, lida a cada tabela para acompanhar o
+/// redimensionamento dele.
+pub fn render_to_fit(header: &[String], rows: &[Vec<String>]) -> String {
+    render_within(header, rows, pager::terminal_width())
+}
+
+/// A largura de cada coluna, a da célula mais larga dela.
+fn widths(header: &[String], rows: &[Vec<String>]) -> Vec<usize> {
+    let lines = || [header].into_iter().chain(rows.iter().map(Vec::as_slice));
+    let columns = lines().map(<[String]>::len).max().unwrap_or(0);
+    (0..columns)
+        .map(|column| {
+            lines()
+                .filter_map(|line| line.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+#[test]
+fn render_should_align_the_columns_to_the_right() {
+    let header = vec!["n".to_string(), "valor".to_string()];
+    let rows = vec![
+        vec!["1".to_string(), "2.5".to_string()],
+        vec!["10".to_string()],
+    ];
+    assert_eq!(
+        render(&header, &rows),
+        " n | valor\n 1 |   2.5\n10 |      \n"
+    );
+    assert_eq!(render(&[], &[]), "\n");
+}
+
+#[test]
+fn render_within_should_split_wide_tables() {
+    let header: Vec<String> = ["", "A", "B", "C"].map(String::from).to_vec();
+    let rows = vec![
+        ["1", "100", "7", "12345"].map(String::from).to_vec(),
+        ["2", "2.5"].map(String::from).to_vec(),
+    ];
+    assert_eq!(
+        render_within(&header, &rows, Some(14)),
+        "  |   A | B\n1 | 100 | 7\n2 | 2.5 |  \n\n  |     C\n1 | 12345\n2 |      \n"
+    );
+    assert_eq!(
+        render_within(&header, &rows, Some(3)),
+        "  |   A\n1 | 100\n2 | 2.5\n\n  | B\n1 | 7\n2 |  \n\n  |     C\n1 | 12345\n2 |      \n"
+    );
+    assert_eq!(
+        render_within(&header, &rows, Some(80)),
+        render(&header, &rows)
+    );
+    assert_eq!(render_within(&header, &rows, None), render(&header, &rows));
+}
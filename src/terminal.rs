@@ -0,0 +1,195 @@
+//! Modo "raw" do terminal, em que cada tecla chega assim que é apertada e nada é ecoado, usado
+//! pelo editor de linha da calculadora interativa. Só está disponível no Linux e no macOS, e nos
+//! outros sistemas a calculadora lê linhas inteiras como antes. Também decide se as sequências
+//! ANSI (`clear`, o destaque dos parênteses) podem ser usadas, ligando-as no console do Windows.
+
+use std::{
+    io::{self, IsTerminal},
+    process::Command,
+    sync::OnceLock,
+};
+
+use crate::pager;
+
+/// Limpa a tela e volta o cursor para o começo.
+const CLEAR: &str = "\x1B[2J\x1B[1;1H";
+
+/// Se o stdout entende as sequências ANSI. No console do Windows, o processamento delas precisa
+/// ser ligado, o que os consoles antigos (anteriores ao Windows 10) não permitem. Nos outros
+/// sistemas, só o `TERM=dumb` não as entende.
+pub fn ansi() -> bool {
+    static ANSI: OnceLock<bool> = OnceLock::new();
+    *ANSI.get_or_init(|| {
+        !std::env::var("TERM").is_ok_and(|term| term == "dumb") && enable_virtual_terminal()
+    })
+}
+
+/// Se as cores e os destaques podem ser usados: com as sequências ANSI e sem o `NO_COLOR`
+/// (<https://no-color.org>).
+pub fn colors() -> bool {
+    ansi() && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
+
+/// O texto que limpa a tela. Sem as sequências ANSI, o `cls` do Windows limpa a tela ele mesmo
+/// e o texto fica vazio; nos outros terminais, as linhas em branco empurram o que estava na
+/// tela para cima.
+pub fn clear_screen() -> String {
+    if ansi() {
+        return CLEAR.to_string();
+    }
+    if cfg!(windows) && Command::new("cmd").args(["/C", "cls"]).status().is_ok() {
+        return String::new();
+    }
+    "\n".repeat(pager::terminal_height().unwrap_or(24))
+}
+
+#[cfg(windows)]
+fn enable_virtual_terminal() -> bool {
+    use std::os::raw::c_void;
+
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(handle: u32) -> *mut c_void;
+        fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+    }
+
+    if !io::stdout().is_terminal() {
+        // redirecionada, a saída vai para um arquivo ou outro programa, que lê as sequências
+        return true;
+    }
+    // SAFETY: o handle vem do próprio `GetStdHandle`, e `mode` é escrito por `GetConsoleMode`
+    unsafe {
+        let console = GetStdHandle(STD_OUTPUT_HANDLE);
+        let mut mode = 0;
+        GetConsoleMode(console, &mut mode) != 0
+            && (mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                || SetConsoleMode(console, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0)
+    }
+}
+
+#[cfg(not(windows))]
+fn enable_virtual_terminal() -> bool {
+    true
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod sys {
+    use std::os::raw::c_int;
+
+    #[cfg(target_os = "linux")]
+    mod consts {
+        pub type Flag = std::os::raw::c_uint;
+        pub type Speed = std::os::raw::c_uint;
+        pub const NCCS: usize = 32;
+        pub const ISIG: Flag = 0o1;
+        pub const ICANON: Flag = 0o2;
+        pub const ECHO: Flag = 0o10;
+        pub const IEXTEN: Flag = 0o100000;
+        pub const ICRNL: Flag = 0o400;
+        pub const IXON: Flag = 0o2000;
+        pub const VTIME: usize = 5;
+        pub const VMIN: usize = 6;
+    }
+
+    #[cfg(target_os = "macos")]
+    mod consts {
+        pub type Flag = std::os::raw::c_ulong;
+        pub type Speed = std::os::raw::c_ulong;
+        pub const NCCS: usize = 20;
+        pub const ISIG: Flag = 0x80;
+        pub const ICANON: Flag = 0x100;
+        pub const ECHO: Flag = 0x8;
+        pub const IEXTEN: Flag = 0x400;
+        pub const ICRNL: Flag = 0x100;
+        pub const IXON: Flag = 0x200;
+        pub const VMIN: usize = 16;
+        pub const VTIME: usize = 17;
+    }
+
+    pub use consts::*;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct Termios {
+        pub iflag: Flag,
+        pub oflag: Flag,
+        pub cflag: Flag,
+        pub lflag: Flag,
+        #[cfg(target_os = "linux")]
+        pub line: u8,
+        pub cc: [u8; NCCS],
+        pub ispeed: Speed,
+        pub ospeed: Speed,
+    }
+
+    extern "C" {
+        pub fn tcgetattr(fd: c_int, termios: *mut Termios) -> c_int;
+        pub fn tcsetattr(fd: c_int, action: c_int, termios: *const Termios) -> c_int;
+    }
+
+    pub const TCSANOW: c_int = 0;
+}
+
+/// Enquanto existir, o stdin fica em modo raw. O modo anterior volta quando ele é descartado.
+pub struct RawMode {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    original: sys::Termios,
+}
+
+impl RawMode {
+    /// Liga o modo raw, ou retorna `None` se o stdin ou o stdout não forem um terminal (ou o
+    /// sistema não for suportado).
+    pub fn enable() -> Option<RawMode> {
+        if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            return None;
+        }
+        if !ansi() {
+            return None;
+        }
+        Self::enable_on_stdin()
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn enable_on_stdin() -> Option<RawMode> {
+        use sys::*;
+
+        let mut termios = std::mem::MaybeUninit::<Termios>::uninit();
+        // SAFETY: `tcgetattr` preenche o `termios` inteiro quando retorna 0
+        let original = unsafe {
+            if tcgetattr(0, termios.as_mut_ptr()) != 0 {
+                return None;
+            }
+            termios.assume_init()
+        };
+
+        let mut raw = original;
+        // o Ctrl+C também vira uma tecla, que o editor usa para descartar a linha
+        raw.lflag &= !(ICANON | ECHO | ISIG | IEXTEN);
+        raw.iflag &= !(ICRNL | IXON);
+        raw.cc[VMIN] = 1;
+        raw.cc[VTIME] = 0;
+        // SAFETY: `raw` é um `termios` válido, lido por `tcgetattr`
+        if unsafe { tcsetattr(0, TCSANOW, &raw) } != 0 {
+            return None;
+        }
+        Some(RawMode { original })
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    fn enable_on_stdin() -> Option<RawMode> {
+        None
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        // SAFETY: `original` foi lido por `tcgetattr`
+        unsafe {
+            sys::tcsetattr(0, sys::TCSANOW, &self.original);
+        }
+    }
+}
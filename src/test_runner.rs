@@ -0,0 +1,155 @@
+//! `asdf-calc test <arquivo>...`: executa scripts de cálculo linha por linha, conferindo as
+//! linhas `assert 2 + 2 == 4` (ou qualquer outra condição) e `assert_approx x, 3.14, 1e-2`,
+//! para que arquivos de fórmulas possam ter testes de regressão.
+//!
+//! Linhas vazias e começando com `#` são ignoradas, e as outras são calculadas normalmente
+//! (`taxa = 0.05`), ficando disponíveis para as linhas seguintes.
+
+use std::{fs, path::PathBuf};
+
+use crate::session::{split_arguments, Session};
+
+/// Resultado da execução de um script.
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    pub asserts: usize,
+    /// Mensagens já com o arquivo e a linha (`budget.calc:3: ...`).
+    pub failures: Vec<String>,
+}
+
+fn evaluate(session: &mut Session, text: &str) -> Result<f64, String> {
+    session.eval(text.trim()).map_err(|errors| {
+        errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    })
+}
+
+/// `assert condição` confere que a condição (`a == b`, `x < 10 and y > 0`...) não é zero. Numa
+/// comparação `a == b` que falhar, a mensagem mostra o valor de cada lado.
+fn check_assert(session: &mut Session, condition: &str) -> Result<(), String> {
+    let value = evaluate(session, condition)?;
+    if value != 0.0 && !value.is_nan() {
+        return Ok(());
+    }
+
+    let sides = condition
+        .split_once("==")
+        .and_then(|(lhs, rhs)| Some((evaluate(session, lhs).ok()?, evaluate(session, rhs).ok()?)));
+    match sides {
+        Some((lhs_value, rhs_value)) => Err(format!(
+            "assert falhou: {} ({} != {})",
+            condition.trim(),
+            lhs_value,
+            rhs_value
+        )),
+        None => Err(format!("assert falhou: {} ({})", condition.trim(), value)),
+    }
+}
+
+fn check_assert_approx(session: &mut Session, arguments: &str) -> Result<(), String> {
+    let [actual, expected, tolerance] = split_arguments(arguments)[..] else {
+        return Err(
+            "assert_approx precisa de 3 argumentos: valor, esperado, tolerância".to_string(),
+        );
+    };
+    let actual = evaluate(session, actual)?;
+    let expected = evaluate(session, expected)?;
+    let tolerance = evaluate(session, tolerance)?;
+
+    let difference = (actual - expected).abs();
+    if difference <= tolerance {
+        Ok(())
+    } else {
+        Err(format!(
+            "assert_approx falhou: |{} - {}| = {} > {}",
+            actual, expected, difference, tolerance
+        ))
+    }
+}
+
+/// Executa o texto de um script numa sessão, usando `source` nas mensagens de erro.
+pub fn run_script(text: &str, source: &str, session: &mut Session) -> Report {
+    let mut report = Report::default();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let result = if let Some(arguments) = line.strip_prefix("assert_approx ") {
+            report.asserts += 1;
+            check_assert_approx(session, arguments)
+        } else if let Some(condition) = line.strip_prefix("assert ") {
+            report.asserts += 1;
+            check_assert(session, condition)
+        } else {
+            evaluate(session, line).map(|_| ())
+        };
+
+        if let Err(message) = result {
+            report
+                .failures
+                .push(format!("{}:{}: {}", source, number + 1, message));
+        }
+    }
+    report
+}
+
+/// Executa cada arquivo numa sessão nova, criada a partir de `template`. Retorna o código de
+/// saída do processo, que só é 0 se não houver nenhuma falha.
+pub fn run(files: &[PathBuf], template: &Session) -> i32 {
+    let mut failed = false;
+    for file in files {
+        let source = file.display().to_string();
+        let text = match fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("{}: não foi possível ler: {}", source, error);
+                failed = true;
+                continue;
+            }
+        };
+
+        let report = run_script(&text, &source, &mut template.clone());
+        for failure in &report.failures {
+            eprintln!("{}", failure);
+        }
+        if report.failures.is_empty() {
+            println!("{}: {} assert(s), todos passaram", source, report.asserts);
+        } else {
+            println!("{}: {} falha(s)", source, report.failures.len());
+            failed = true;
+        }
+    }
+
+    if failed {
+        1
+    } else {
+        0
+    }
+}
+
+#[test]
+fn run_script_should_report_failing_lines() {
+    let script = "# taxas\ntaxa = 0.05\nassert 2 + 2 == 4\nassert 100 * taxa == 6\n\nassert_approx pi, 3.14, 1e-2\nassert_approx pi, 3, 0.1\nassert x\nassert 1 < 2 and 2 <= 2\nassert taxa > 1\n";
+    let report = run_script(script, "budget.calc", &mut Session::default());
+    assert_eq!(
+        report,
+        Report {
+            asserts: 7,
+            failures: vec![
+                "budget.calc:4: assert falhou: 100 * taxa == 6 (5 != 6)".to_string(),
+                format!(
+                    "budget.calc:7: assert_approx falhou: |{} - 3| = {} > 0.1",
+                    std::f64::consts::PI,
+                    std::f64::consts::PI - 3.0
+                ),
+                "budget.calc:8: coluna 1: nome desconhecido 'x'".to_string(),
+                "budget.calc:10: assert falhou: taxa > 1 (0)".to_string(),
+            ],
+        }
+    );
+}
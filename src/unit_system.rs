@@ -0,0 +1,160 @@
+//! Em que unidades os resultados com unidade são mostrados quando a linha não pede uma com
+//! `to` (`units si|imperial|cgs`). Cada sistema tem uma unidade para cada grandeza de base, com
+//! as quais as outras dimensões são escritas (`ft/s²`), e algumas unidades com nome próprio
+//! para as dimensões compostas mais comuns (`N`, `psi`).
+
+use asdf_calc::{Dimension, EvalContext, BASES};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnitSystem {
+    #[default]
+    Si,
+    /// As medidas americanas: pés, libras, galões...
+    Imperial,
+    /// Centímetro, grama e segundo, comum na física.
+    Cgs,
+}
+
+impl UnitSystem {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.trim() {
+            "si" => Ok(UnitSystem::Si),
+            "imperial" => Ok(UnitSystem::Imperial),
+            "cgs" => Ok(UnitSystem::Cgs),
+            other => Err(format!(
+                "sistema de unidades desconhecido: {} (use si, imperial ou cgs)",
+                other
+            )),
+        }
+    }
+
+    /// As unidades de comprimento, massa, tempo, corrente, temperatura e informação, na ordem
+    /// das grandezas de `Dimension`.
+    fn bases(self) -> [&'static str; BASES] {
+        match self {
+            UnitSystem::Si => ["m", "kg", "s", "A", "K", "B"],
+            UnitSystem::Imperial => ["ft", "lb", "s", "A", "K", "B"],
+            UnitSystem::Cgs => ["cm", "g", "s", "A", "K", "B"],
+        }
+    }
+
+    /// Unidades usadas no lugar das de base para as dimensões delas.
+    fn named(self) -> &'static [&'static str] {
+        match self {
+            UnitSystem::Si => &["N", "J", "W", "Pa"],
+            UnitSystem::Imperial => &["mph", "gal", "lbf", "BTU", "hp", "psi"],
+            UnitSystem::Cgs => &["dyn", "erg"],
+        }
+    }
+
+    /// O símbolo da unidade em que grandezas de `dimension` são mostradas, e quanto ela vale no
+    /// SI.
+    pub fn unit(self, dimension: Dimension, context: &EvalContext) -> (String, f64) {
+        let named = self.named().iter().find_map(|name| {
+            let unit = context.unit(name)?;
+            (unit.dimension == dimension).then(|| (name.to_string(), unit.factor))
+        });
+        if let Some(named) = named {
+            return named;
+        }
+
+        let bases = self.bases();
+        let factor = bases
+            .iter()
+            .zip(dimension.exponents())
+            .map(|(name, exponent)| {
+                let factor = context.unit(name).map_or(1.0, |unit| unit.factor);
+                factor.powi(i32::from(exponent))
+            })
+            .product();
+        (dimension.format_with(&bases), factor)
+    }
+}
+
+/// A unidade em que `bytes` é mostrado quando a linha não pede uma com `to`: a maior das de
+/// prefixo decimal que não passa do valor (`1.2 GB`), ou das de prefixo binário (`1 KiB`) se
+/// `binary`. Quem misturou `GiB` e `MB` fica com os decimais, e os binários continuam disponíveis
+/// com `to GiB`.
+pub fn data_size(bytes: f64, binary: bool, context: &EvalContext) -> (String, f64) {
+    let names = if binary {
+        ["PiB", "TiB", "GiB", "MiB", "KiB"]
+    } else {
+        ["PB", "TB", "GB", "MB", "kB"]
+    };
+    names
+        .iter()
+        .find_map(|name| {
+            let unit = context.unit(name)?;
+            (bytes.abs() >= unit.factor).then(|| (name.to_string(), unit.factor))
+        })
+        .unwrap_or_else(|| ("B".to_string(), 1.0))
+}
+
+/// Se `text` escreve tamanhos de dados só com os prefixos binários (`2 GiB + 512 MiB`), para o
+/// resultado ser mostrado com eles também.
+pub fn uses_binary_prefixes(text: &str) -> bool {
+    let mut binary = false;
+    for word in text.split(|char: char| !char.is_alphanumeric() && char != '_') {
+        // `1.5GiB` escreve a unidade colada no número
+        match word.trim_start_matches(|char: char| char.is_ascii_digit()) {
+            "KiB" | "MiB" | "GiB" | "TiB" | "PiB" => binary = true,
+            "kB" | "MB" | "GB" | "TB" | "PB" => return false,
+            _ => {}
+        }
+    }
+    binary
+}
+
+impl std::fmt::Display for UnitSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnitSystem::Si => write!(f, "si"),
+            UnitSystem::Imperial => write!(f, "imperial"),
+            UnitSystem::Cgs => write!(f, "cgs"),
+        }
+    }
+}
+
+#[test]
+fn unit_should_write_the_dimension_in_the_system() {
+    let context = EvalContext::new();
+    let [length, time] = [Dimension::LENGTH, Dimension::TIME];
+    let acceleration = length.divide(time.multiply(time).unwrap()).unwrap();
+    assert_eq!(
+        UnitSystem::Si.unit(acceleration, &context),
+        ("m/s²".to_string(), 1.0)
+    );
+    let (symbol, factor) = UnitSystem::Imperial.unit(acceleration, &context);
+    assert_eq!((symbol.as_str(), factor), ("ft/s²", 0.3048));
+    let (symbol, factor) = UnitSystem::Cgs.unit(length.multiply(length).unwrap(), &context);
+    assert_eq!(symbol, "cm²");
+    assert!((factor - 1e-4).abs() < 1e-18);
+
+    let force = acceleration.multiply(Dimension::MASS).unwrap();
+    assert_eq!(UnitSystem::Si.unit(force, &context).0, "N");
+    assert_eq!(UnitSystem::Imperial.unit(force, &context).0, "lbf");
+    assert!(UnitSystem::parse("metric").is_err());
+}
+
+#[test]
+fn data_size_should_use_the_largest_decimal_prefix() {
+    let context = EvalContext::new();
+    assert_eq!(data_size(1.5e9, false, &context), ("GB".to_string(), 1e9));
+    assert_eq!(data_size(-2048.0, false, &context), ("kB".to_string(), 1e3));
+    assert_eq!(data_size(512.0, false, &context), ("B".to_string(), 1.0));
+    assert_eq!(data_size(0.0, false, &context), ("B".to_string(), 1.0));
+}
+
+#[test]
+fn data_size_should_keep_the_binary_prefixes_of_the_line() {
+    let context = EvalContext::new();
+    assert_eq!(
+        data_size(2048.0, true, &context),
+        ("KiB".to_string(), 1024.0)
+    );
+    assert_eq!(data_size(512.0, true, &context), ("B".to_string(), 1.0));
+    assert!(uses_binary_prefixes("2 GiB + 512 MiB"));
+    assert!(uses_binary_prefixes("1.5GiB * 2"));
+    assert!(!uses_binary_prefixes("1.5 GiB + 300 MB"));
+    assert!(!uses_binary_prefixes("1536 B"));
+}
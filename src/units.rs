@@ -0,0 +1,255 @@
+//! Unidades de medida escritas depois dos números (`5 km`, `9.8 m/s^2`). As grandezas são
+//! calculadas sempre no SI, e cada unidade é só o fator até ele, junto da dimensão que permite
+//! conferir que as contas fazem sentido (`1 m + 1 s` não faz).
+
+use alloc::string::String;
+use core::fmt::{Display, Formatter, Write};
+
+use crate::math;
+
+/// Quantas grandezas de base uma `Dimension` tem.
+pub const BASES: usize = 6;
+
+/// Os símbolos das unidades de base do SI, na ordem das grandezas de cada `Dimension`. A
+/// informação não faz parte do SI, e é medida em bytes.
+pub const SI_BASES: [&str; BASES] = ["m", "kg", "s", "A", "K", "B"];
+
+/// O expoente de cada grandeza de base (comprimento, massa, tempo, corrente elétrica,
+/// temperatura e informação) numa unidade: a força, `m·kg/s²`, tem 1, 1 e -2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Dimension([i8; BASES]);
+
+impl Dimension {
+    /// Números sem unidade.
+    pub const NONE: Dimension = Dimension([0; BASES]);
+    pub const LENGTH: Dimension = Dimension([1, 0, 0, 0, 0, 0]);
+    pub const MASS: Dimension = Dimension([0, 1, 0, 0, 0, 0]);
+    pub const TIME: Dimension = Dimension([0, 0, 1, 0, 0, 0]);
+    pub const CURRENT: Dimension = Dimension([0, 0, 0, 1, 0, 0]);
+    pub const TEMPERATURE: Dimension = Dimension([0, 0, 0, 0, 1, 0]);
+    pub const INFORMATION: Dimension = Dimension([0, 0, 0, 0, 0, 1]);
+
+    pub const fn new(exponents: [i8; BASES]) -> Self {
+        Dimension(exponents)
+    }
+
+    pub fn exponents(self) -> [i8; BASES] {
+        self.0
+    }
+
+    pub fn is_none(self) -> bool {
+        self == Dimension::NONE
+    }
+
+    /// A dimensão do produto, se os expoentes couberem num `i8`.
+    pub fn multiply(self, other: Dimension) -> Option<Dimension> {
+        self.combine(other, i8::checked_add)
+    }
+
+    pub fn divide(self, other: Dimension) -> Option<Dimension> {
+        self.combine(other, i8::checked_sub)
+    }
+
+    /// A dimensão de `self ^ exponent`, que só existe se todos os expoentes continuarem inteiros
+    /// (`(m^2)^0.5` é `m`, mas `m^0.5` não tem dimensão).
+    pub fn power(self, exponent: f64) -> Option<Dimension> {
+        let mut exponents = [0; BASES];
+        for (result, base) in exponents.iter_mut().zip(self.0) {
+            let value = f64::from(base) * exponent;
+            if math::trunc(value) != value || value.abs() > f64::from(i8::MAX) {
+                return None;
+            }
+            *result = value as i8;
+        }
+        Some(Dimension(exponents))
+    }
+
+    fn combine(self, other: Dimension, operation: fn(i8, i8) -> Option<i8>) -> Option<Dimension> {
+        let mut exponents = [0; BASES];
+        for (i, result) in exponents.iter_mut().enumerate() {
+            *result = operation(self.0[i], other.0[i])?;
+        }
+        Some(Dimension(exponents))
+    }
+
+    /// Escreve a dimensão com os símbolos de `bases` para cada grandeza, os expoentes
+    /// positivos antes da barra e os negativos depois (`m·kg/s²`), ou vazia se ela for
+    /// `Dimension::NONE`.
+    pub fn format_with(self, bases: &[&str; BASES]) -> String {
+        let mut numerator = String::new();
+        let mut denominator = String::new();
+        for (exponent, symbol) in self.0.iter().zip(bases) {
+            let part = match exponent {
+                0 => continue,
+                exponent if *exponent > 0 => &mut numerator,
+                _ => &mut denominator,
+            };
+            if !part.is_empty() {
+                part.push('·');
+            }
+            part.push_str(symbol);
+            let exponent = exponent.unsigned_abs();
+            if exponent != 1 {
+                let _ = write!(part, "{}", Superscript(exponent));
+            }
+        }
+        match (numerator.is_empty(), denominator.is_empty()) {
+            (_, true) => numerator,
+            (true, false) => alloc::format!("1/{}", denominator),
+            (false, false) => alloc::format!("{}/{}", numerator, denominator),
+        }
+    }
+}
+
+/// Com os símbolos do SI (`m/s²`).
+impl Display for Dimension {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.format_with(&SI_BASES))
+    }
+}
+
+/// Expoente escrito com os dígitos sobrescritos (`²`, `¹⁰`).
+struct Superscript(u8);
+
+impl Display for Superscript {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        const DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+        for digit in alloc::format!("{}", self.0).bytes() {
+            f.write_char(DIGITS[usize::from(digit - b'0')])?;
+        }
+        Ok(())
+    }
+}
+
+/// Uma unidade: quantas unidades do SI ela vale, e de que grandeza.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unit {
+    pub factor: f64,
+    pub dimension: Dimension,
+}
+
+impl Unit {
+    pub const fn new(factor: f64, dimension: Dimension) -> Self {
+        Unit { factor, dimension }
+    }
+}
+
+const AREA: Dimension = Dimension::new([2, 0, 0, 0, 0, 0]);
+const VOLUME: Dimension = Dimension::new([3, 0, 0, 0, 0, 0]);
+const SPEED: Dimension = Dimension::new([1, 0, -1, 0, 0, 0]);
+const FORCE: Dimension = Dimension::new([1, 1, -2, 0, 0, 0]);
+const ENERGY: Dimension = Dimension::new([2, 1, -2, 0, 0, 0]);
+const POWER: Dimension = Dimension::new([2, 1, -3, 0, 0, 0]);
+const PRESSURE: Dimension = Dimension::new([-1, 1, -2, 0, 0, 0]);
+/// Bytes por segundo.
+pub(crate) const DATA_RATE: Dimension = Dimension::new([0, 0, -1, 0, 0, 1]);
+
+/// As unidades que podem ser escritas nas expressões. Os nomes diferenciam maiúsculas de
+/// minúsculas (`N` é newton), e os das medidas americanas são os do sistema internacional de
+/// 1959 (a polegada tem exatamente 2,54 cm).
+pub const UNITS: &[(&str, Unit)] = &[
+    ("m", Unit::new(1.0, Dimension::LENGTH)),
+    ("km", Unit::new(1e3, Dimension::LENGTH)),
+    ("cm", Unit::new(1e-2, Dimension::LENGTH)),
+    ("mm", Unit::new(1e-3, Dimension::LENGTH)),
+    ("in", Unit::new(0.0254, Dimension::LENGTH)),
+    ("ft", Unit::new(0.3048, Dimension::LENGTH)),
+    ("yd", Unit::new(0.9144, Dimension::LENGTH)),
+    ("mi", Unit::new(1609.344, Dimension::LENGTH)),
+    ("nmi", Unit::new(1852.0, Dimension::LENGTH)),
+    ("kg", Unit::new(1.0, Dimension::MASS)),
+    ("g", Unit::new(1e-3, Dimension::MASS)),
+    ("mg", Unit::new(1e-6, Dimension::MASS)),
+    ("t", Unit::new(1e3, Dimension::MASS)),
+    ("lb", Unit::new(0.45359237, Dimension::MASS)),
+    ("oz", Unit::new(0.028349523125, Dimension::MASS)),
+    ("s", Unit::new(1.0, Dimension::TIME)),
+    ("ms", Unit::new(1e-3, Dimension::TIME)),
+    ("min", Unit::new(60.0, Dimension::TIME)),
+    ("h", Unit::new(3600.0, Dimension::TIME)),
+    ("day", Unit::new(86400.0, Dimension::TIME)),
+    ("week", Unit::new(604800.0, Dimension::TIME)),
+    ("A", Unit::new(1.0, Dimension::CURRENT)),
+    ("mA", Unit::new(1e-3, Dimension::CURRENT)),
+    ("K", Unit::new(1.0, Dimension::TEMPERATURE)),
+    ("ha", Unit::new(1e4, AREA)),
+    ("acre", Unit::new(4046.8564224, AREA)),
+    ("L", Unit::new(1e-3, VOLUME)),
+    ("mL", Unit::new(1e-6, VOLUME)),
+    ("gal", Unit::new(0.003785411784, VOLUME)),
+    ("mph", Unit::new(0.44704, SPEED)),
+    ("kn", Unit::new(1852.0 / 3600.0, SPEED)),
+    ("N", Unit::new(1.0, FORCE)),
+    ("kN", Unit::new(1e3, FORCE)),
+    ("dyn", Unit::new(1e-5, FORCE)),
+    ("lbf", Unit::new(4.4482216152605, FORCE)),
+    ("J", Unit::new(1.0, ENERGY)),
+    ("kJ", Unit::new(1e3, ENERGY)),
+    ("erg", Unit::new(1e-7, ENERGY)),
+    ("cal", Unit::new(4.184, ENERGY)),
+    ("kcal", Unit::new(4184.0, ENERGY)),
+    ("Wh", Unit::new(3600.0, ENERGY)),
+    ("kWh", Unit::new(3.6e6, ENERGY)),
+    ("BTU", Unit::new(1055.05585262, ENERGY)),
+    ("W", Unit::new(1.0, POWER)),
+    ("kW", Unit::new(1e3, POWER)),
+    ("hp", Unit::new(745.699871582270, POWER)),
+    ("Pa", Unit::new(1.0, PRESSURE)),
+    ("kPa", Unit::new(1e3, PRESSURE)),
+    ("bar", Unit::new(1e5, PRESSURE)),
+    ("atm", Unit::new(101325.0, PRESSURE)),
+    ("psi", Unit::new(6894.757293168361, PRESSURE)),
+    // os prefixos do SI são potências de 1000 também para bytes, e os binários (`KiB`, `MiB`)
+    // são os de 1024
+    ("B", Unit::new(1.0, Dimension::INFORMATION)),
+    ("bit", Unit::new(0.125, Dimension::INFORMATION)),
+    ("kB", Unit::new(1e3, Dimension::INFORMATION)),
+    ("MB", Unit::new(1e6, Dimension::INFORMATION)),
+    ("GB", Unit::new(1e9, Dimension::INFORMATION)),
+    ("TB", Unit::new(1e12, Dimension::INFORMATION)),
+    ("PB", Unit::new(1e15, Dimension::INFORMATION)),
+    ("KiB", Unit::new(1024.0, Dimension::INFORMATION)),
+    ("MiB", Unit::new(1048576.0, Dimension::INFORMATION)),
+    ("GiB", Unit::new(1073741824.0, Dimension::INFORMATION)),
+    ("TiB", Unit::new(1099511627776.0, Dimension::INFORMATION)),
+    ("PiB", Unit::new(1125899906842624.0, Dimension::INFORMATION)),
+    // as velocidades de rede são em bits por segundo, com os prefixos decimais
+    ("bps", Unit::new(0.125, DATA_RATE)),
+    ("kbps", Unit::new(125.0, DATA_RATE)),
+    ("Mbps", Unit::new(1.25e5, DATA_RATE)),
+    ("Gbps", Unit::new(1.25e8, DATA_RATE)),
+    ("Tbps", Unit::new(1.25e11, DATA_RATE)),
+];
+
+pub(crate) fn unit(name: &str) -> Option<Unit> {
+    UNITS
+        .iter()
+        .find(|(unit_name, _)| *unit_name == name)
+        .map(|(_, unit)| *unit)
+}
+
+#[test]
+fn dimension_should_combine_exponents() {
+    let speed = Dimension::LENGTH.divide(Dimension::TIME).unwrap();
+    assert_eq!(speed, SPEED);
+    assert_eq!(speed.multiply(Dimension::TIME), Some(Dimension::LENGTH));
+    assert_eq!(AREA.power(0.5), Some(Dimension::LENGTH));
+    assert_eq!(Dimension::LENGTH.power(0.5), None);
+    assert_eq!(Dimension::LENGTH.power(200.0), None);
+    assert_eq!(Dimension::NONE.power(0.5), Some(Dimension::NONE));
+}
+
+#[test]
+fn dimension_should_be_written_with_si_symbols() {
+    assert_eq!(alloc::format!("{}", FORCE), "m·kg/s²");
+    assert_eq!(alloc::format!("{}", AREA), "m²");
+    assert_eq!(
+        alloc::format!("{}", Dimension::NONE.divide(Dimension::TIME).unwrap()),
+        "1/s"
+    );
+    assert_eq!(
+        Dimension::new([12, 0, 0, 0, 0, 0]).format_with(&["ft", "lb", "s", "A", "K", "B"]),
+        "ft¹²"
+    );
+    assert_eq!(Dimension::NONE.to_string(), "");
+}
@@ -0,0 +1,83 @@
+//! `asdf-calc watch <arquivo>`: calcula de novo todas as linhas do arquivo sempre que ele muda,
+//! para que a calculadora funcione como um caderno ao lado do editor.
+
+use std::{
+    fs, io,
+    path::Path,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{session::Session, terminal};
+
+/// De quanto em quanto tempo a data de modificação do arquivo é conferida.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Calcula cada linha do arquivo numa mesma sessão, mostrando o resultado alinhado ao lado
+/// dela, ou os erros logo abaixo. Linhas vazias são mantidas, para que o resultado fique
+/// parecido com o arquivo.
+pub fn render(text: &str, template: &Session) -> String {
+    let width = text
+        .lines()
+        .map(|line| line.trim_end().chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut session = template.clone();
+    let mut output = String::new();
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            output.push('\n');
+            continue;
+        }
+
+        match session.eval(line.trim()) {
+            Ok(result) => {
+                let padding = width - line.chars().count();
+                output.push_str(&format!("{}{} = {}\n", line, " ".repeat(padding), result));
+            }
+            Err(errors) => {
+                output.push_str(&format!("{}\n", line));
+                for error in errors {
+                    output.push_str(&format!("    {}\n", error));
+                }
+            }
+        }
+    }
+    output
+}
+
+fn modified(path: &Path) -> io::Result<SystemTime> {
+    fs::metadata(path)?.modified()
+}
+
+/// Mostra o arquivo calculado e fica esperando por mudanças nele, até o processo ser
+/// interrompido.
+pub fn run(path: &Path, template: &Session) -> io::Result<()> {
+    let mut last_modified = None;
+    let mut first = true;
+    loop {
+        let current = modified(path).ok();
+        if first || current != last_modified {
+            print!("{}", terminal::clear_screen());
+            match fs::read_to_string(path) {
+                Ok(text) => print!("{}\n{}", path.display(), render(&text, template)),
+                Err(error) => println!("não foi possível ler {}: {}", path.display(), error),
+            }
+            io::Write::flush(&mut io::stdout())?;
+            last_modified = current;
+            first = false;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[test]
+fn render_should_align_results_and_show_errors() {
+    let text = "x = 2\n\nx * 10\n1 +\n";
+    assert_eq!(
+        render(text, &Session::default()),
+        "x = 2  = 2\n\nx * 10 = 20\n1 +\n    coluna 3: síntaxe incorreta, operador sem um número à sua direita\n"
+    );
+}
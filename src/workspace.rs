@@ -0,0 +1,180 @@
+//! Workspaces (`workspace use <nome>`), conjuntos separados de variáveis e histórico para que
+//! cálculos sem relação entre si (finanças pessoais e contas do trabalho, por exemplo) não se
+//! misturem. Cada um é salvo num arquivo próprio em `$XDG_DATA_HOME/asdf-calc/workspaces`.
+//!
+//! O arquivo tem uma linha por variável (`var<TAB>nome<TAB>valor`) e por linha do histórico
+//! (`hist<TAB>valor<TAB>input`), com os valores escritos como o Rust escreve o `f64`, que é lido
+//! de volta sem perder precisão. As variáveis com unidade têm o valor em unidades do SI e mais um
+//! campo com os expoentes da dimensão, na ordem de `SI_BASES` (`var<TAB>d<TAB>5000<TAB>1 0 0 0 0
+//! 0` para `d = 5 km`).
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use asdf_calc::{Dimension, BASES};
+
+use crate::session::HistoryEntry;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Workspace {
+    pub variables: Vec<(String, f64, Dimension)>,
+    pub history: Vec<HistoryEntry>,
+}
+
+/// `$XDG_DATA_HOME/asdf-calc/workspaces`, ou `~/.local/share/asdf-calc/workspaces` se a variável
+/// não estiver definida.
+pub fn default_directory() -> Option<PathBuf> {
+    let data = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => Path::new(&env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(data.join("asdf-calc/workspaces"))
+}
+
+/// Os nomes viram nomes de arquivo, então só são aceitas letras, números, `-` e `_`.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|char| char.is_alphanumeric() || char == '-' || char == '_')
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "nome de workspace inválido '{}' (use letras, números, '-' e '_')",
+            name
+        ))
+    }
+}
+
+fn path(directory: &Path, name: &str) -> PathBuf {
+    directory.join(format!("{}.calc", name))
+}
+
+impl Workspace {
+    /// Lê o workspace salvo com o nome `name`, ou um vazio se ele ainda não existir.
+    pub fn load(directory: &Path, name: &str) -> Result<Self, String> {
+        let path = path(directory, name);
+        match fs::read_to_string(&path) {
+            Ok(text) => Workspace::parse(&text)
+                .map_err(|message| format!("{}: {}", path.display(), message)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Workspace::default()),
+            Err(error) => Err(format!(
+                "não foi possível ler {}: {}",
+                path.display(),
+                error
+            )),
+        }
+    }
+
+    pub fn save(&self, directory: &Path, name: &str) -> Result<(), String> {
+        let path = path(directory, name);
+        fs::create_dir_all(directory)
+            .and_then(|()| fs::write(&path, self.to_text()))
+            .map_err(|error| format!("não foi possível salvar {}: {}", path.display(), error))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut workspace = Workspace::default();
+        for (number, line) in text.lines().enumerate() {
+            let invalid = || format!("linha {} inválida", number + 1);
+            let mut fields = line.splitn(3, '\t');
+            let (kind, first, rest) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(kind), Some(first), Some(rest)) => (kind, first, rest),
+                _ if line.is_empty() => continue,
+                _ => return Err(invalid()),
+            };
+            match kind {
+                "var" => {
+                    let (value, dimension) = match rest.split_once('\t') {
+                        Some((value, exponents)) => {
+                            (value, parse_dimension(exponents).ok_or_else(invalid)?)
+                        }
+                        None => (rest, Dimension::NONE),
+                    };
+                    let value = value.parse().map_err(|_| invalid())?;
+                    workspace
+                        .variables
+                        .push((first.to_string(), value, dimension));
+                }
+                "hist" => workspace.history.push(HistoryEntry {
+                    input: rest.to_string(),
+                    result: first.parse().map_err(|_| invalid())?,
+                }),
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(workspace)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (name, value, dimension) in &self.variables {
+            text.push_str(&format!("var\t{}\t{}", name, value));
+            if !dimension.is_none() {
+                let exponents: Vec<String> = dimension
+                    .exponents()
+                    .iter()
+                    .map(|exponent| exponent.to_string())
+                    .collect();
+                text.push_str(&format!("\t{}", exponents.join(" ")));
+            }
+            text.push('\n');
+        }
+        for entry in &self.history {
+            // uma linha do histórico nunca tem quebras de linha, mas pode ter tabs
+            text.push_str(&format!("hist\t{}\t{}\n", entry.result, entry.input));
+        }
+        text
+    }
+}
+
+/// Os expoentes separados por espaço de uma linha `var`, um para cada grandeza de base.
+fn parse_dimension(text: &str) -> Option<Dimension> {
+    let exponents = text
+        .split(' ')
+        .map(|exponent| exponent.parse().ok())
+        .collect::<Option<Vec<i8>>>()?;
+    let exponents: [i8; BASES] = exponents.try_into().ok()?;
+    Some(Dimension::new(exponents))
+}
+
+#[test]
+fn workspace_should_be_written_and_read_back() {
+    let workspace = Workspace {
+        variables: vec![
+            ("rate".to_string(), 0.1 + 0.2, Dimension::NONE),
+            ("big".to_string(), f64::INFINITY, Dimension::NONE),
+            (
+                "speed".to_string(),
+                2.5,
+                Dimension::new([1, 0, -1, 0, 0, 0]),
+            ),
+        ],
+        history: vec![HistoryEntry {
+            input: "rate\t* 2".to_string(),
+            result: -1e-300,
+        }],
+    };
+    let text = workspace.to_text();
+    assert!(text.contains("var\tspeed\t2.5\t1 0 -1 0 0 0\n"));
+    assert_eq!(Workspace::parse(&text), Ok(workspace));
+
+    assert_eq!(Workspace::parse("\n"), Ok(Workspace::default()));
+    assert_eq!(
+        Workspace::parse("var\tx\t1\nvar\ty"),
+        Err("linha 2 inválida".to_string())
+    );
+    assert!(Workspace::parse("hist\tum\tx").is_err());
+    assert!(Workspace::parse("var\tx\t1\t1 0").is_err());
+}
+
+#[test]
+fn workspace_names_should_be_valid_file_names() {
+    assert_eq!(validate_name("project-x_2"), Ok(()));
+    assert!(validate_name("../etc").is_err());
+    assert!(validate_name("a b").is_err());
+    assert!(validate_name("").is_err());
+}